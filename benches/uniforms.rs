@@ -0,0 +1,89 @@
+//! Per-frame cost of setting uniforms, measured on the mock backend so only the CPU side is counted.
+//!
+//! Run with `cargo bench --bench uniforms`.
+
+use std::{hint::black_box, rc::Rc};
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use glam::Vec3;
+use suricato::{
+    backend::mock::MockContext, camera::PerspectiveCamera, geometry::Geometry, material::Material, mesh::Mesh, renderer::Renderer,
+    uniforms::Uniform,
+};
+
+const VERTEX_SHADER: &str = "#version 300 es
+in vec3 position;
+uniform mat4 transform;
+uniform mat4 projection_matrix;
+uniform mat4 camera_inverse_matrix;
+
+void main() {
+    gl_Position = projection_matrix * camera_inverse_matrix * transform * vec4(position, 1.0);
+}
+";
+
+const FRAGMENT_SHADER: &str = "#version 300 es
+precision mediump float;
+uniform vec3 color;
+uniform float opacity;
+out vec4 fragment_color;
+
+void main() {
+    fragment_color = vec4(color, opacity);
+}
+";
+
+fn scene(mesh_count: usize) -> Vec<Mesh> {
+    (0..mesh_count)
+        .map(|index| {
+            let mut material = Material::new(VERTEX_SHADER, FRAGMENT_SHADER);
+            material.set_uniform("color", Uniform::Vec3([1.0, 0.5, 0.0]));
+            material.set_uniform("opacity", Uniform::Float(1.0));
+
+            let mut mesh = Mesh::new(Geometry::box_geometry(), material);
+            mesh.transform.translation = Vec3::new((index % 100) as f32, (index / 100) as f32, -10.0);
+            mesh.frustum_culled = false;
+            mesh
+        })
+        .collect()
+}
+
+fn render_scene(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render_scene");
+
+    for mesh_count in [1_000, 5_000] {
+        let mock = Rc::new(MockContext::new(800, 600));
+        let mut renderer = Renderer::with_context(mock.context());
+        let mut camera = PerspectiveCamera::new(1.0, 800.0 / 600.0, 0.1, 1000.0);
+        camera.update_projection_matrix();
+        let mut scene = scene(mesh_count);
+
+        group.bench_with_input(BenchmarkId::from_parameter(mesh_count), &mesh_count, |b, _| {
+            b.iter(|| {
+                renderer.render_scene(&mut scene, &mut camera);
+                mock.clear_calls();
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn set_uniform(c: &mut Criterion) {
+    let mut group = c.benchmark_group("set_uniform");
+    let mut material = Material::new(VERTEX_SHADER, FRAGMENT_SHADER);
+
+    group.bench_function("name", |b| {
+        b.iter(|| material.set_uniform(black_box("color"), Uniform::Vec3([1.0, 0.5, 0.0])))
+    });
+
+    let handle = material.get_uniform_handle("color");
+    group.bench_function("handle", |b| {
+        b.iter(|| material.set_uniform(black_box(handle), Uniform::Vec3([1.0, 0.5, 0.0])))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, render_scene, set_uniform);
+criterion_main!(benches);
@@ -0,0 +1,31 @@
+//! Derive macros of `suricato`, use them through `suricato::std140::Std140` and `suricato::vertex_buffer::Vertex`.
+
+mod std140;
+mod vertex;
+
+use proc_macro::TokenStream;
+use syn::{DeriveInput, parse_macro_input};
+
+/// Implements `suricato::std140::Std140` for a struct with named fields, laid out like the GLSL
+/// struct or `layout(std140)` block with the same members in the same order.
+///
+/// Also adds `STD140_FIELDS`, the offset of every field, to set them one by one.
+#[proc_macro_derive(Std140)]
+pub fn derive_std140(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    std140::expand(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// Implements `suricato::vertex_buffer::Vertex` for a `#[repr(C)]` struct with named fields, every field
+/// being an attribute named after it.
+///
+/// Fields take `#[vertex(name = "...")]` to use another attribute name, `#[vertex(normalize)]` for
+/// integers read as floats in `[0, 1]` or `[-1, 1]`, `#[vertex(divisor = N)]` for instanced attributes
+/// and `#[vertex(skip)]` for padding, which must itself be padding free like `[u8; 3]`.
+///
+/// Also adds `VERTEX_FIELDS`, the offset of every attribute, to set them one by one.
+#[proc_macro_derive(Vertex, attributes(vertex))]
+pub fn derive_vertex(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    vertex::expand(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
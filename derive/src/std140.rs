@@ -0,0 +1,73 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DataStruct, DeriveInput, Error, Fields};
+
+pub fn expand(input: &DeriveInput) -> Result<TokenStream, Error> {
+    let name = &input.ident;
+    let visibility = &input.vis;
+
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "`Std140` can not be derived for generic structs",
+        ));
+    }
+
+    let Data::Struct(DataStruct {
+        fields: Fields::Named(fields),
+        ..
+    }) = &input.data
+    else {
+        return Err(Error::new_spanned(
+            name,
+            "`Std140` can only be derived for structs with named fields",
+        ));
+    };
+
+    let Some(last) = fields.named.last().and_then(|field| field.ident.as_ref()) else {
+        return Err(Error::new_spanned(name, "`Std140` can not be derived for structs without fields"));
+    };
+
+    let idents: Vec<_> = fields.named.iter().filter_map(|field| field.ident.as_ref()).collect();
+    let types: Vec<_> = fields.named.iter().map(|field| &field.ty).collect();
+    let visibilities: Vec<_> = fields.named.iter().map(|field| &field.vis).collect();
+    let fields_name = format_ident!("{name}Std140Fields");
+    let fields_doc = format!("Fields of [`{name}`] in its std140 layout.");
+
+    // Every field starts at the end of the previous one, aligned
+    let mut end = quote!(0);
+    let offsets = idents.iter().zip(&types).map(|(ident, field_type)| {
+        let offset = quote! {
+            let #ident = ::suricato::std140::Std140Field::<#field_type>::after(#end);
+        };
+        end = quote!(#ident.end());
+        offset
+    });
+    let offsets: Vec<_> = offsets.collect();
+
+    Ok(quote! {
+        #[doc = #fields_doc]
+        #[derive(Clone, Copy, Debug)]
+        #visibility struct #fields_name {
+            #(#visibilities #idents: ::suricato::std140::Std140Field<#types>,)*
+        }
+
+        impl #name {
+            /// Offset of every field, see `UniformBufferObject::set_field`.
+            #visibility const STD140_FIELDS: #fields_name = {
+                #(#offsets)*
+                #fields_name { #(#idents),* }
+            };
+        }
+
+        impl ::suricato::std140::Std140 for #name {
+            const ALIGNMENT: usize =
+                ::suricato::std140::struct_alignment(&[#(<#types as ::suricato::std140::Std140>::ALIGNMENT),*]);
+            const SIZE: usize = ::suricato::std140::align_to(Self::STD140_FIELDS.#last.end(), Self::ALIGNMENT);
+
+            fn write_std140(&self, bytes: &mut [u8]) {
+                #(::suricato::std140::Std140::write_std140(&self.#idents, &mut bytes[Self::STD140_FIELDS.#idents.offset()..]);)*
+            }
+        }
+    })
+}
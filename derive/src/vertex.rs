@@ -0,0 +1,181 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, quote_spanned};
+use syn::{Attribute, Data, DataStruct, DeriveInput, Error, Field, Fields, LitInt, LitStr, spanned::Spanned};
+
+/// Options of a field given by `#[vertex(...)]`.
+struct FieldOptions {
+    name:      Option<String>,
+    normalize: bool,
+    divisor:   u32,
+    skip:      bool,
+}
+
+impl FieldOptions {
+    fn parse(field: &Field) -> Result<FieldOptions, Error> {
+        let mut options = FieldOptions {
+            name:      None,
+            normalize: false,
+            divisor:   0,
+            skip:      false,
+        };
+
+        for attribute in field.attrs.iter().filter(|attribute| attribute.path().is_ident("vertex")) {
+            attribute.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    options.name = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("normalize") {
+                    options.normalize = true;
+                } else if meta.path.is_ident("divisor") {
+                    options.divisor = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+                } else if meta.path.is_ident("skip") {
+                    options.skip = true;
+                } else {
+                    return Err(meta.error("expected `name = \"...\"`, `normalize`, `divisor = N` or `skip`"));
+                }
+
+                Ok(())
+            })?;
+        }
+
+        Ok(options)
+    }
+}
+
+fn is_repr_c(attributes: &[Attribute]) -> Result<bool, Error> {
+    let mut repr_c = false;
+
+    for attribute in attributes.iter().filter(|attribute| attribute.path().is_ident("repr")) {
+        attribute.parse_nested_meta(|meta| {
+            repr_c |= meta.path.is_ident("C");
+
+            // Skips the arguments of `align(N)` and `packed(N)`
+            if meta.input.peek(syn::token::Paren) {
+                meta.input.parse::<TokenStream>()?;
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(repr_c)
+}
+
+pub fn expand(input: &DeriveInput) -> Result<TokenStream, Error> {
+    let name = &input.ident;
+    let visibility = &input.vis;
+
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "`Vertex` can not be derived for generic structs",
+        ));
+    }
+
+    let Data::Struct(DataStruct {
+        fields: Fields::Named(fields),
+        ..
+    }) = &input.data
+    else {
+        return Err(Error::new_spanned(
+            name,
+            "`Vertex` can only be derived for structs with named fields",
+        ));
+    };
+
+    // The buffer holds the bytes of the struct, the fields must stay in the declared order
+    if !is_repr_c(&input.attrs)? {
+        return Err(Error::new_spanned(name, "`Vertex` can only be derived for `#[repr(C)]` structs"));
+    }
+
+    let mut attributes = Vec::new();
+    let mut skipped_types = Vec::new();
+    for field in &fields.named {
+        let options = FieldOptions::parse(field)?;
+        if options.skip {
+            skipped_types.push(&field.ty);
+        } else {
+            attributes.push((field, options));
+        }
+    }
+
+    if attributes.is_empty() {
+        return Err(Error::new_spanned(
+            name,
+            "`Vertex` can not be derived for structs without attributes",
+        ));
+    }
+
+    let all_types: Vec<_> = fields.named.iter().map(|field| &field.ty).collect();
+    let padding_error = format!("`{name}` has padding between its fields, reorder them or add `#[vertex(skip)]` fields");
+
+    let idents: Vec<_> = attributes.iter().filter_map(|(field, _)| field.ident.as_ref()).collect();
+    let types: Vec<_> = attributes.iter().map(|(field, _)| &field.ty).collect();
+    let visibilities: Vec<_> = attributes.iter().map(|(field, _)| &field.vis).collect();
+    let attribute_names: Vec<_> = attributes
+        .iter()
+        .zip(&idents)
+        .map(|((_, options), ident)| options.name.clone().unwrap_or_else(|| ident.to_string()))
+        .collect();
+    let normalizes: Vec<_> = attributes.iter().map(|(_, options)| options.normalize).collect();
+    let divisors: Vec<_> = attributes.iter().map(|(_, options)| options.divisor).collect();
+
+    let fields_name = format_ident!("{name}VertexFields");
+    let fields_doc = format!("Attributes of [`{name}`] in a vertex buffer.");
+
+    // Only padding free types implement `VertexAttribute`, so the sizes of the fields add up to the bytes they hold
+    let skipped_checks = (!skipped_types.is_empty()).then(|| {
+        let checks = skipped_types.iter().map(|skipped_type| {
+            quote_spanned! {skipped_type.span()=>
+                padding_free::<#skipped_type>();
+            }
+        });
+
+        quote! {
+            const _: fn() = || {
+                fn padding_free<T: ::suricato::vertex_buffer::VertexAttribute>() {}
+                #(#checks)*
+            };
+        }
+    });
+
+    Ok(quote! {
+        // Padding bytes are uninitialized and can not be uploaded
+        const _: () = assert!(
+            ::core::mem::size_of::<#name>() == 0 #(+ ::core::mem::size_of::<#all_types>())*,
+            #padding_error
+        );
+
+        #skipped_checks
+
+        #[doc = #fields_doc]
+        #[derive(Clone, Copy, Debug)]
+        #visibility struct #fields_name {
+            #(#visibilities #idents: ::suricato::vertex_buffer::VertexField<#types>,)*
+        }
+
+        impl #name {
+            /// Offset of every attribute, see `InterleavedVertexBuffer::set_vertex_field`.
+            #visibility const VERTEX_FIELDS: #fields_name = #fields_name {
+                #(#idents: ::suricato::vertex_buffer::VertexField::at(::core::mem::offset_of!(#name, #idents)),)*
+            };
+        }
+
+        // Safe as every field is padding free and the assert above rejects padding between them
+        unsafe impl ::suricato::vertex_buffer::Vertex for #name {
+            fn layouts() -> ::std::vec::Vec<::suricato::vertex_buffer::VertexLayout> {
+                ::std::vec![
+                    #(::suricato::vertex_buffer::VertexLayout {
+                        name:              ::std::string::String::from(#attribute_names),
+                        component_count:   <#types as ::suricato::vertex_buffer::VertexAttribute>::COMPONENT_COUNT,
+                        component_type:    <#types as ::suricato::vertex_buffer::VertexAttribute>::COMPONENT_TYPE,
+                        normalize:         #normalizes,
+                        stride:            ::core::mem::size_of::<#name>(),
+                        offset:            ::core::mem::offset_of!(#name, #idents),
+                        divisor:           #divisors,
+                        number_of_columns: <#types as ::suricato::vertex_buffer::VertexAttribute>::NUMBER_OF_COLUMNS,
+                    },)*
+                ]
+            }
+        }
+    })
+}
@@ -0,0 +1,180 @@
+use super::Context;
+
+/// Values from the WebGL 2 specification, shared by every backend.
+/// https://registry.khronos.org/webgl/specs/latest/2.0/#3.7
+impl Context {
+    pub const ACTIVE_ATTRIBUTES: u32 = 0x8B89;
+    pub const ACTIVE_UNIFORMS: u32 = 0x8B86;
+    pub const ACTIVE_UNIFORM_BLOCKS: u32 = 0x8A36;
+    pub const ALPHA: u32 = 0x1906;
+    pub const ALREADY_SIGNALED: u32 = 0x911A;
+    pub const ALWAYS: u32 = 0x0207;
+    pub const ARRAY_BUFFER: u32 = 0x8892;
+    pub const BACK: u32 = 0x0405;
+    pub const BLEND: u32 = 0x0BE2;
+    pub const BOOL: u32 = 0x8B56;
+    pub const BOOL_VEC2: u32 = 0x8B57;
+    pub const BOOL_VEC3: u32 = 0x8B58;
+    pub const BOOL_VEC4: u32 = 0x8B59;
+    pub const BYTE: u32 = 0x1400;
+    pub const CLAMP_TO_EDGE: u32 = 0x812F;
+    pub const COLOR: u32 = 0x1800;
+    pub const COLOR_ATTACHMENT0: u32 = 0x8CE0;
+    pub const COLOR_ATTACHMENT1: u32 = 0x8CE1;
+    pub const COLOR_BUFFER_BIT: u32 = 0x4000;
+    pub const COMPILE_STATUS: u32 = 0x8B81;
+    pub const CONDITION_SATISFIED: u32 = 0x911C;
+    pub const CONSTANT_ALPHA: u32 = 0x8003;
+    pub const CONSTANT_COLOR: u32 = 0x8001;
+    pub const CULL_FACE: u32 = 0x0B44;
+    pub const DECR: u32 = 0x1E03;
+    pub const DECR_WRAP: u32 = 0x8508;
+    pub const DEPTH: u32 = 0x1801;
+    pub const DEPTH24_STENCIL8: u32 = 0x88F0;
+    pub const DEPTH_ATTACHMENT: u32 = 0x8D00;
+    pub const DEPTH_BUFFER_BIT: u32 = 0x0100;
+    pub const DEPTH_COMPONENT24: u32 = 0x81A6;
+    pub const DEPTH_STENCIL_ATTACHMENT: u32 = 0x821A;
+    pub const DEPTH_TEST: u32 = 0x0B71;
+    pub const DRAW_FRAMEBUFFER: u32 = 0x8CA9;
+    pub const DST_ALPHA: u32 = 0x0304;
+    pub const DST_COLOR: u32 = 0x0306;
+    pub const DYNAMIC_DRAW: u32 = 0x88E8;
+    pub const ELEMENT_ARRAY_BUFFER: u32 = 0x8893;
+    pub const EQUAL: u32 = 0x0202;
+    pub const FLOAT: u32 = 0x1406;
+    pub const FLOAT_MAT2: u32 = 0x8B5A;
+    pub const FLOAT_MAT2X3: u32 = 0x8B65;
+    pub const FLOAT_MAT2X4: u32 = 0x8B66;
+    pub const FLOAT_MAT3: u32 = 0x8B5B;
+    pub const FLOAT_MAT3X2: u32 = 0x8B67;
+    pub const FLOAT_MAT3X4: u32 = 0x8B68;
+    pub const FLOAT_MAT4: u32 = 0x8B5C;
+    pub const FLOAT_MAT4X2: u32 = 0x8B69;
+    pub const FLOAT_MAT4X3: u32 = 0x8B6A;
+    pub const FLOAT_VEC2: u32 = 0x8B50;
+    pub const FLOAT_VEC3: u32 = 0x8B51;
+    pub const FLOAT_VEC4: u32 = 0x8B52;
+    pub const FRAGMENT_SHADER: u32 = 0x8B30;
+    pub const FRAMEBUFFER: u32 = 0x8D40;
+    pub const FRAMEBUFFER_COMPLETE: u32 = 0x8CD5;
+    pub const FRAMEBUFFER_UNSUPPORTED: u32 = 0x8CDD;
+    pub const FRONT: u32 = 0x0404;
+    pub const FRONT_AND_BACK: u32 = 0x0408;
+    pub const FUNC_ADD: u32 = 0x8006;
+    pub const FUNC_REVERSE_SUBTRACT: u32 = 0x800B;
+    pub const FUNC_SUBTRACT: u32 = 0x800A;
+    pub const GEQUAL: u32 = 0x0206;
+    pub const GREATER: u32 = 0x0204;
+    pub const INCR: u32 = 0x1E02;
+    pub const INCR_WRAP: u32 = 0x8507;
+    pub const INT: u32 = 0x1404;
+    pub const INT_SAMPLER_2D: u32 = 0x8DCA;
+    pub const INT_SAMPLER_2D_ARRAY: u32 = 0x8DCF;
+    pub const INT_SAMPLER_3D: u32 = 0x8DCB;
+    pub const INT_SAMPLER_CUBE: u32 = 0x8DCC;
+    pub const INT_VEC2: u32 = 0x8B53;
+    pub const INT_VEC3: u32 = 0x8B54;
+    pub const INT_VEC4: u32 = 0x8B55;
+    pub const INVERT: u32 = 0x150A;
+    pub const KEEP: u32 = 0x1E00;
+    pub const LEQUAL: u32 = 0x0203;
+    pub const LESS: u32 = 0x0201;
+    pub const LINEAR: u32 = 0x2601;
+    pub const LINEAR_MIPMAP_LINEAR: u32 = 0x2703;
+    pub const LINEAR_MIPMAP_NEAREST: u32 = 0x2701;
+    pub const LINES: u32 = 0x0001;
+    pub const LINE_LOOP: u32 = 0x0002;
+    pub const LINE_STRIP: u32 = 0x0003;
+    pub const LINK_STATUS: u32 = 0x8B82;
+    pub const LUMINANCE: u32 = 0x1909;
+    pub const LUMINANCE_ALPHA: u32 = 0x190A;
+    pub const MAX: u32 = 0x8008;
+    pub const MAX_COMBINED_TEXTURE_IMAGE_UNITS: u32 = 0x8B4D;
+    pub const MAX_UNIFORM_BUFFER_BINDINGS: u32 = 0x8A2F;
+    pub const MIN: u32 = 0x8007;
+    pub const MIRRORED_REPEAT: u32 = 0x8370;
+    pub const NEAREST: u32 = 0x2600;
+    pub const NEAREST_MIPMAP_LINEAR: u32 = 0x2702;
+    pub const NEAREST_MIPMAP_NEAREST: u32 = 0x2700;
+    pub const NEVER: u32 = 0x0200;
+    pub const NOTEQUAL: u32 = 0x0205;
+    pub const ONE: u32 = 0x0001;
+    pub const ONE_MINUS_CONSTANT_ALPHA: u32 = 0x8004;
+    pub const ONE_MINUS_CONSTANT_COLOR: u32 = 0x8002;
+    pub const ONE_MINUS_DST_ALPHA: u32 = 0x0305;
+    pub const ONE_MINUS_DST_COLOR: u32 = 0x0307;
+    pub const ONE_MINUS_SRC_ALPHA: u32 = 0x0303;
+    pub const ONE_MINUS_SRC_COLOR: u32 = 0x0301;
+    pub const PIXEL_PACK_BUFFER: u32 = 0x88EB;
+    pub const POINTS: u32 = 0x0000;
+    pub const POLYGON_OFFSET_FILL: u32 = 0x8037;
+    pub const R32UI: u32 = 0x8236;
+    pub const READ_FRAMEBUFFER: u32 = 0x8CA8;
+    pub const RED: u32 = 0x1903;
+    pub const RED_INTEGER: u32 = 0x8D94;
+    pub const RENDERBUFFER: u32 = 0x8D41;
+    pub const REPEAT: u32 = 0x2901;
+    pub const REPLACE: u32 = 0x1E01;
+    pub const RG: u32 = 0x8227;
+    pub const RGB: u32 = 0x1907;
+    pub const RGBA: u32 = 0x1908;
+    pub const RGBA16F: u32 = 0x881A;
+    pub const RGBA32F: u32 = 0x8814;
+    pub const RGBA8: u32 = 0x8058;
+    pub const RGBA_INTEGER: u32 = 0x8D99;
+    pub const SAMPLER_2D: u32 = 0x8B5E;
+    pub const SAMPLER_2D_ARRAY: u32 = 0x8DC1;
+    pub const SAMPLER_2D_ARRAY_SHADOW: u32 = 0x8DC4;
+    pub const SAMPLER_2D_SHADOW: u32 = 0x8B62;
+    pub const SAMPLER_3D: u32 = 0x8B5F;
+    pub const SAMPLER_CUBE: u32 = 0x8B60;
+    pub const SAMPLER_CUBE_SHADOW: u32 = 0x8DC5;
+    pub const SCISSOR_TEST: u32 = 0x0C11;
+    pub const SHORT: u32 = 0x1402;
+    pub const SRC_ALPHA: u32 = 0x0302;
+    pub const SRC_ALPHA_SATURATE: u32 = 0x0308;
+    pub const SRC_COLOR: u32 = 0x0300;
+    pub const STATIC_DRAW: u32 = 0x88E4;
+    pub const STENCIL_ATTACHMENT: u32 = 0x8D20;
+    pub const STENCIL_BUFFER_BIT: u32 = 0x0400;
+    pub const STENCIL_TEST: u32 = 0x0B90;
+    pub const STREAM_DRAW: u32 = 0x88E0;
+    pub const STREAM_READ: u32 = 0x88E1;
+    pub const SYNC_GPU_COMMANDS_COMPLETE: u32 = 0x9117;
+    pub const TEXTURE0: u32 = 0x84C0;
+    pub const TEXTURE1: u32 = 0x84C1;
+    pub const TEXTURE_2D: u32 = 0x0DE1;
+    pub const TEXTURE_2D_ARRAY: u32 = 0x8C1A;
+    pub const TEXTURE_3D: u32 = 0x806F;
+    pub const TEXTURE_CUBE_MAP: u32 = 0x8513;
+    pub const TEXTURE_MAG_FILTER: u32 = 0x2800;
+    pub const TEXTURE_MIN_FILTER: u32 = 0x2801;
+    pub const TEXTURE_WRAP_S: u32 = 0x2802;
+    pub const TEXTURE_WRAP_T: u32 = 0x2803;
+    pub const TIMEOUT_EXPIRED: u32 = 0x911B;
+    pub const TRIANGLES: u32 = 0x0004;
+    pub const TRIANGLE_FAN: u32 = 0x0006;
+    pub const TRIANGLE_STRIP: u32 = 0x0005;
+    pub const UNIFORM_BLOCK_BINDING: u32 = 0x8A3F;
+    pub const UNIFORM_BLOCK_DATA_SIZE: u32 = 0x8A40;
+    pub const UNIFORM_BLOCK_INDEX: u32 = 0x8A3A;
+    pub const UNIFORM_BUFFER: u32 = 0x8A11;
+    pub const UNIFORM_OFFSET: u32 = 0x8A3B;
+    pub const UNSIGNED_BYTE: u32 = 0x1401;
+    pub const UNSIGNED_INT: u32 = 0x1405;
+    pub const UNSIGNED_INT_SAMPLER_2D: u32 = 0x8DD2;
+    pub const UNSIGNED_INT_SAMPLER_2D_ARRAY: u32 = 0x8DD7;
+    pub const UNSIGNED_INT_SAMPLER_3D: u32 = 0x8DD3;
+    pub const UNSIGNED_INT_SAMPLER_CUBE: u32 = 0x8DD4;
+    pub const UNSIGNED_INT_VEC2: u32 = 0x8DC6;
+    pub const UNSIGNED_INT_VEC3: u32 = 0x8DC7;
+    pub const UNSIGNED_INT_VEC4: u32 = 0x8DC8;
+    pub const UNSIGNED_SHORT: u32 = 0x1403;
+    pub const UNSIGNED_SHORT_4_4_4_4: u32 = 0x8033;
+    pub const UNSIGNED_SHORT_5_5_5_1: u32 = 0x8034;
+    pub const UNSIGNED_SHORT_5_6_5: u32 = 0x8363;
+    pub const VERTEX_SHADER: u32 = 0x8B31;
+    pub const WAIT_FAILED: u32 = 0x911D;
+    pub const ZERO: u32 = 0x0000;
+}
@@ -0,0 +1,1274 @@
+use std::{
+    cell::{Ref, RefCell},
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+#[cfg(feature = "web")]
+use web_sys::HtmlImageElement;
+
+use super::{
+    ActiveInfo, BackendError, BufferHandle, Context as GL, FramebufferHandle, GraphicsContext, ProgramHandle, RenderbufferHandle,
+    SamplerHandle, ShaderHandle, SyncHandle, TextureHandle, UniformLocation, VertexArrayHandle,
+};
+use crate::shader::reflection::GlslType;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MockBuffer {
+    pub data:         Vec<u8>,
+    pub usage:        u32,
+    /// Number of `bufferData` and `bufferSubData` calls targeting the buffer.
+    pub upload_count: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MockTexture {
+    pub width:           i32,
+    pub height:          i32,
+    pub internal_format: u32,
+    pub format:          u32,
+    pub data_type:       u32,
+    /// Pixels of the last `texImage2D` upload, rows from bottom to top.
+    pub data:            Vec<u8>,
+    pub parameters:      HashMap<u32, i32>,
+    pub upload_count:    usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockShader {
+    pub shader_type: u32,
+    pub source:      String,
+    pub compiled:    bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MockProgram {
+    pub shaders:                Vec<u32>,
+    pub linked:                 bool,
+    pub attributes:             Vec<ActiveInfo>,
+    pub uniforms:               Vec<ActiveInfo>,
+    pub uniform_blocks:         Vec<String>,
+    pub attribute_bindings:     HashMap<String, u32>,
+    pub attribute_locations:    HashMap<String, u32>,
+    pub uniform_block_bindings: HashMap<u32, u32>,
+    uniform_locations:          HashMap<String, u32>,
+}
+
+/// Source of one attribute, as set by `vertexAttribPointer` and `vertexAttribDivisor`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MockVertexAttribute {
+    pub enabled:    bool,
+    pub buffer:     Option<u32>,
+    pub size:       i32,
+    pub data_type:  u32,
+    pub normalized: bool,
+    pub stride:     i32,
+    pub offset:     i32,
+    pub divisor:    u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MockVertexArray {
+    pub attributes:     HashMap<u32, MockVertexAttribute>,
+    pub element_buffer: Option<u32>,
+}
+
+/// Fixed function state, starting with the GL defaults.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockPipelineState {
+    pub clear_color:    [f32; 4],
+    pub blend_equation: [u32; 2],
+    /// Source and destination factors for the color, then for the alpha.
+    pub blend_func:     [u32; 4],
+    pub blend_color:    [f32; 4],
+    pub depth_func:     u32,
+    pub depth_mask:     bool,
+    pub cull_face:      u32,
+    pub color_mask:     [bool; 4],
+    pub scissor:        [i32; 4],
+}
+
+impl Default for MockPipelineState {
+    fn default() -> Self {
+        MockPipelineState {
+            clear_color:    [0.0; 4],
+            blend_equation: [GL::FUNC_ADD; 2],
+            blend_func:     [GL::ONE, GL::ZERO, GL::ONE, GL::ZERO],
+            blend_color:    [0.0; 4],
+            depth_func:     GL::LESS,
+            depth_mask:     true,
+            cull_face:      GL::BACK,
+            color_mask:     [true; 4],
+            scissor:        [0; 4],
+        }
+    }
+}
+
+/// Value last set through one of the `uniform*` calls.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MockUniformValue {
+    Float(Vec<f32>),
+    Int(Vec<i32>),
+    UnsignedInt(Vec<u32>),
+    Matrix(Vec<f32>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockDrawCall {
+    pub mode:           u32,
+    pub count:          i32,
+    /// `None` for `drawArrays`.
+    pub index_type:     Option<u32>,
+    pub instance_count: Option<i32>,
+    pub program:        Option<u32>,
+    pub vertex_array:   Option<u32>,
+}
+
+/// Everything the mock knows about the objects and state of the context.
+#[derive(Debug, Default)]
+pub struct MockState {
+    /// Name of every method called, in order.
+    pub calls: Vec<&'static str>,
+
+    pub buffers:       HashMap<u32, MockBuffer>,
+    pub textures:      HashMap<u32, MockTexture>,
+    /// Parameters of every sampler.
+    pub samplers:      HashMap<u32, HashMap<u32, i32>>,
+    pub shaders:       HashMap<u32, MockShader>,
+    pub programs:      HashMap<u32, MockProgram>,
+    /// Vertex array `0` holds the attributes set while no vertex array is bound.
+    pub vertex_arrays: HashMap<u32, MockVertexArray>,
+    pub framebuffers:  HashSet<u32>,
+    pub renderbuffers: HashSet<u32>,
+    pub syncs:         HashSet<u32>,
+
+    pub enabled:                 HashSet<u32>,
+    pub pipeline:                MockPipelineState,
+    pub viewport:                [i32; 4],
+    pub bound_buffers:           HashMap<u32, u32>,
+    /// Buffer bound to every uniform buffer binding point.
+    pub uniform_buffer_bindings: HashMap<u32, u32>,
+    /// Texture bound to every `(unit, target)` pair.
+    pub bound_textures:          HashMap<(u32, u32), u32>,
+    pub active_texture:          u32,
+    /// Sampler bound to every texture unit.
+    pub bound_samplers:          HashMap<u32, u32>,
+    pub bound_vertex_array:      Option<u32>,
+    pub bound_framebuffer:       Option<u32>,
+    pub bound_read_framebuffer:  Option<u32>,
+    pub bound_renderbuffer:      Option<u32>,
+    pub current_program:         Option<u32>,
+    pub uniform_values:          HashMap<u32, MockUniformValue>,
+    /// Limits returned by `get_parameter_i32`, the WebGL 2 minimums by default.
+    pub parameters:              HashMap<u32, i32>,
+    pub draws:                   Vec<MockDrawCall>,
+    /// Returned by `check_framebuffer_status`, `FRAMEBUFFER_COMPLETE` when `None`.
+    pub framebuffer_status:      Option<u32>,
+
+    next_id:                   u32,
+    /// Program owning each uniform location.
+    uniform_location_programs: HashMap<u32, u32>,
+}
+
+impl MockState {
+    fn next_id(&mut self) -> u32 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    pub fn call_count(&self, name: &str) -> usize {
+        self.calls.iter().filter(|call| **call == name).count()
+    }
+
+    /// Buffer currently bound to `target`.
+    pub fn bound_buffer(&self, target: u32) -> Option<&MockBuffer> {
+        self.bound_buffers.get(&target).and_then(|buffer| self.buffers.get(buffer))
+    }
+
+    /// Value of the uniform `name` of `program`, by the name used in the shader.
+    pub fn uniform_value(&self, program: u32, name: &str) -> Option<&MockUniformValue> {
+        let location = self.programs.get(&program)?.uniform_locations.get(name)?;
+        self.uniform_values.get(location)
+    }
+
+    pub(super) fn bound_buffer_mut(&mut self, target: u32) -> Option<&mut MockBuffer> {
+        let buffer = self.bound_buffers.get(&target)?;
+        self.buffers.get_mut(buffer)
+    }
+
+    /// Vertex array whose attributes are used by draw calls.
+    pub fn current_vertex_array(&self) -> Option<&MockVertexArray> {
+        self.vertex_arrays.get(&self.bound_vertex_array.unwrap_or(0))
+    }
+
+    fn current_vertex_array_mut(&mut self) -> &mut MockVertexArray {
+        self.vertex_arrays.entry(self.bound_vertex_array.unwrap_or(0)).or_default()
+    }
+
+    fn current_attribute_mut(&mut self, index: u32) -> &mut MockVertexAttribute {
+        self.current_vertex_array_mut().attributes.entry(index).or_default()
+    }
+
+    /// Texture bound to `target` on the active texture unit.
+    fn bound_texture_mut(&mut self, target: u32) -> Option<&mut MockTexture> {
+        let texture = self.bound_textures.get(&(self.active_texture, target))?;
+        self.textures.get_mut(texture)
+    }
+
+    fn upload(&mut self, target: u32, data: Option<&[u8]>, size: usize, usage: Option<u32>, offset: usize) {
+        let Some(buffer) = self.bound_buffers.get(&target).and_then(|buffer| self.buffers.get_mut(buffer)) else {
+            return;
+        };
+
+        if let Some(usage) = usage {
+            buffer.usage = usage;
+            buffer.data = vec![0; size];
+        }
+
+        if let Some(data) = data
+            && offset + data.len() <= buffer.data.len()
+        {
+            buffer.data[offset..offset + data.len()].copy_from_slice(data);
+        }
+
+        buffer.upload_count += 1;
+    }
+
+    fn set_uniform(&mut self, location: Option<UniformLocation>, value: MockUniformValue) {
+        if let Some(location) = location {
+            self.uniform_values.insert(location.0, value);
+        }
+    }
+}
+
+/// [`GraphicsContext`] that runs anywhere by recording calls instead of drawing.
+///
+/// Shaders always compile and programs always link. Attributes, uniforms and uniform blocks
+/// are found by scanning the shader sources for their declarations, so materials get the same
+/// locations they would in a browser.
+///
+/// ```ignore
+/// let mock = Rc::new(MockContext::new(800, 600));
+/// let gl = Context::new(mock.clone());
+/// // ... use `gl` ...
+/// assert_eq!(mock.state().draws.len(), 1);
+/// ```
+#[derive(Debug, Default)]
+pub struct MockContext {
+    width:  i32,
+    height: i32,
+    state:  RefCell<MockState>,
+}
+
+impl MockContext {
+    pub fn new(width: i32, height: i32) -> MockContext {
+        let state = MockState {
+            viewport: [0, 0, width, height],
+            pipeline: MockPipelineState {
+                scissor: [0, 0, width, height],
+                ..Default::default()
+            },
+            parameters: HashMap::from([(GL::MAX_COMBINED_TEXTURE_IMAGE_UNITS, 32), (GL::MAX_UNIFORM_BUFFER_BINDINGS, 24)]),
+            ..Default::default()
+        };
+
+        MockContext {
+            width,
+            height,
+            state: RefCell::new(state),
+        }
+    }
+
+    pub fn state(&self) -> Ref<'_, MockState> {
+        self.state.borrow()
+    }
+
+    pub(super) fn state_mut(&self) -> std::cell::RefMut<'_, MockState> {
+        self.state.borrow_mut()
+    }
+
+    /// Overrides a limit returned by `get_parameter_i32`.
+    pub fn set_parameter(&self, parameter: u32, value: i32) {
+        self.state.borrow_mut().parameters.insert(parameter, value);
+    }
+
+    /// Makes `check_framebuffer_status` return `status`, to test incomplete framebuffers.
+    pub fn set_framebuffer_status(&self, status: u32) {
+        self.state.borrow_mut().framebuffer_status = Some(status);
+    }
+
+    /// Forgets the recorded calls and draws, keeping objects and bound state.
+    pub fn clear_calls(&self) {
+        let mut state = self.state.borrow_mut();
+        state.calls.clear();
+        state.draws.clear();
+    }
+
+    /// Context sharing this mock, for the renderer.
+    pub fn context(self: &Rc<Self>) -> GL {
+        GL::new(self.clone())
+    }
+
+    fn record(&self, name: &'static str) -> std::cell::RefMut<'_, MockState> {
+        let mut state = self.state.borrow_mut();
+        state.calls.push(name);
+        state
+    }
+
+    fn link(state: &mut MockState, program_id: u32) {
+        let Some(program) = state.programs.get(&program_id) else {
+            return;
+        };
+
+        let mut attributes = Vec::new();
+        let mut uniforms = Vec::new();
+        let mut uniform_blocks = Vec::new();
+
+        for shader in &program.shaders {
+            let Some(shader) = state.shaders.get(shader) else {
+                continue;
+            };
+
+            for declaration in parse_declarations(&shader.source) {
+                match declaration {
+                    Declaration::Input(info) if shader.shader_type == GL::VERTEX_SHADER => attributes.push(info),
+                    Declaration::Uniform(info) if !uniforms.contains(&info) => uniforms.push(info),
+                    Declaration::UniformBlock(name) if !uniform_blocks.contains(&name) => uniform_blocks.push(name),
+                    _ => {}
+                }
+            }
+        }
+
+        // Explicitly bound attributes keep their location, the others take the next free ones
+        let bindings = program.attribute_bindings.clone();
+        let mut attribute_locations = HashMap::new();
+        let mut used_locations: HashSet<u32> = bindings.values().copied().collect();
+        let mut next_location = 0;
+
+        for attribute in &attributes {
+            let location = match bindings.get(&attribute.name) {
+                Some(location) => *location,
+                None => {
+                    while used_locations.contains(&next_location) {
+                        next_location += 1;
+                    }
+
+                    next_location
+                }
+            };
+
+            // Matrices take one location per column
+            let columns = match attribute.kind {
+                GL::FLOAT_MAT2 => 2,
+                GL::FLOAT_MAT3 => 3,
+                GL::FLOAT_MAT4 => 4,
+                _ => 1,
+            };
+
+            for column in 0..columns {
+                used_locations.insert(location + column);
+            }
+
+            attribute_locations.insert(attribute.name.clone(), location);
+        }
+
+        let mut uniform_locations = HashMap::new();
+
+        for uniform in &uniforms {
+            let location = state.next_id();
+            state.uniform_location_programs.insert(location, program_id);
+            uniform_locations.insert(uniform.name.clone(), location);
+
+            // Arrays are reported as `name[0]` but can be queried without the index
+            if let Some(base_name) = uniform.name.strip_suffix("[0]") {
+                uniform_locations.insert(base_name.to_string(), location);
+
+                // Every other element has a location of its own
+                for element in 1..uniform.size {
+                    let location = state.next_id();
+                    state.uniform_location_programs.insert(location, program_id);
+                    uniform_locations.insert(format!("{base_name}[{element}]"), location);
+                }
+            }
+        }
+
+        let program = state.programs.get_mut(&program_id).unwrap();
+        program.linked = true;
+        program.attributes = attributes;
+        program.uniforms = uniforms;
+        program.uniform_blocks = uniform_blocks;
+        program.attribute_locations = attribute_locations;
+        program.uniform_locations = uniform_locations;
+    }
+}
+
+enum Declaration {
+    Input(ActiveInfo),
+    Uniform(ActiveInfo),
+    UniformBlock(String),
+}
+
+/// Finds the `in` and `uniform` declarations of a GLSL ES 3.00 shader.
+fn parse_declarations(source: &str) -> Vec<Declaration> {
+    let without_comments: String = source
+        .lines()
+        .map(|line| line.split("//").next().unwrap_or(""))
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut declarations = Vec::new();
+    let mut depth = 0;
+
+    for statement in without_comments.split(';') {
+        let statement_depth = depth;
+        depth += statement.matches('{').count() as i32 - statement.matches('}').count() as i32;
+
+        // Only global declarations, the `{` of a uniform block is part of its first statement
+        if statement_depth != 0 {
+            continue;
+        }
+
+        let statement = strip_layout(statement);
+        let mut tokens = statement
+            .split_whitespace()
+            .filter(|token| !matches!(*token, "flat" | "smooth" | "centroid" | "lowp" | "mediump" | "highp"));
+
+        let qualifier = tokens.next();
+        let (Some(kind), Some(name)) = (tokens.next(), tokens.next()) else {
+            continue;
+        };
+
+        match qualifier {
+            Some("uniform") if name.starts_with('{') || statement.contains('{') => {
+                declarations.push(Declaration::UniformBlock(kind.trim_end_matches('{').to_string()));
+            }
+            Some("uniform") => declarations.push(Declaration::Uniform(active_info(kind, name))),
+            Some("in") => declarations.push(Declaration::Input(active_info(kind, name))),
+            _ => {}
+        }
+    }
+
+    declarations
+}
+
+fn strip_layout(statement: &str) -> String {
+    let Some(start) = statement.find("layout") else {
+        return statement.to_string();
+    };
+
+    let Some(end) = statement[start..].find(')') else {
+        return statement.to_string();
+    };
+
+    format!("{}{}", &statement[..start], &statement[start + end + 1..])
+}
+
+fn active_info(kind: &str, name: &str) -> ActiveInfo {
+    let (name, size) = match name.split_once('[') {
+        Some((name, size)) => (format!("{name}[0]"), size.trim_end_matches(']').parse().unwrap_or(1)),
+        None => (name.to_string(), 1),
+    };
+
+    let kind = GlslType::from_glsl_name(kind).map_or(0, |kind| kind as u32);
+
+    ActiveInfo { name, size, kind }
+}
+
+impl GraphicsContext for MockContext {
+    fn enable(&self, capability: u32) {
+        self.record("enable").enabled.insert(capability);
+    }
+
+    fn disable(&self, capability: u32) {
+        self.record("disable").enabled.remove(&capability);
+    }
+
+    fn viewport(&self, x: i32, y: i32, width: i32, height: i32) {
+        self.record("viewport").viewport = [x, y, width, height];
+    }
+
+    fn scissor(&self, x: i32, y: i32, width: i32, height: i32) {
+        self.record("scissor").pipeline.scissor = [x, y, width, height];
+    }
+
+    fn flush(&self) {
+        self.record("flush");
+    }
+
+    fn drawing_buffer_width(&self) -> i32 {
+        self.width
+    }
+
+    fn drawing_buffer_height(&self) -> i32 {
+        self.height
+    }
+
+    fn enable_extension(&self, _name: &str) -> bool {
+        self.record("enable_extension");
+        true
+    }
+
+    fn get_parameter_i32(&self, parameter: u32) -> i32 {
+        self.record("get_parameter").parameters.get(&parameter).copied().unwrap_or(0)
+    }
+
+    fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+        self.record("clear_color").pipeline.clear_color = [red, green, blue, alpha];
+    }
+
+    fn clear(&self, _mask: u32) {
+        self.record("clear");
+    }
+
+    fn clear_buffer_u32(&self, _buffer: u32, _draw_buffer: i32, _values: &[u32]) {
+        self.record("clear_buffer_u32");
+    }
+
+    fn clear_buffer_f32(&self, _buffer: u32, _draw_buffer: i32, _values: &[f32]) {
+        self.record("clear_buffer_f32");
+    }
+
+    fn blend_equation_separate(&self, mode_rgb: u32, mode_alpha: u32) {
+        self.record("blend_equation_separate").pipeline.blend_equation = [mode_rgb, mode_alpha];
+    }
+
+    fn blend_func_separate(&self, source_rgb: u32, destination_rgb: u32, source_alpha: u32, destination_alpha: u32) {
+        self.record("blend_func_separate").pipeline.blend_func = [source_rgb, destination_rgb, source_alpha, destination_alpha];
+    }
+
+    fn blend_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+        self.record("blend_color").pipeline.blend_color = [red, green, blue, alpha];
+    }
+
+    fn depth_func(&self, function: u32) {
+        self.record("depth_func").pipeline.depth_func = function;
+    }
+
+    fn depth_mask(&self, flag: bool) {
+        self.record("depth_mask").pipeline.depth_mask = flag;
+    }
+
+    fn cull_face(&self, mode: u32) {
+        self.record("cull_face").pipeline.cull_face = mode;
+    }
+
+    fn color_mask(&self, red: bool, green: bool, blue: bool, alpha: bool) {
+        self.record("color_mask").pipeline.color_mask = [red, green, blue, alpha];
+    }
+
+    fn polygon_offset(&self, _factor: f32, _units: f32) {
+        self.record("polygon_offset");
+    }
+
+    fn stencil_func_separate(&self, _face: u32, _function: u32, _reference: i32, _mask: u32) {
+        self.record("stencil_func_separate");
+    }
+
+    fn stencil_op_separate(&self, _face: u32, _fail: u32, _depth_fail: u32, _pass: u32) {
+        self.record("stencil_op_separate");
+    }
+
+    fn stencil_mask(&self, _mask: u32) {
+        self.record("stencil_mask");
+    }
+
+    fn stencil_mask_separate(&self, _face: u32, _mask: u32) {
+        self.record("stencil_mask_separate");
+    }
+
+    fn create_buffer(&self) -> Option<BufferHandle> {
+        let mut state = self.record("create_buffer");
+        let id = state.next_id();
+        state.buffers.insert(id, MockBuffer::default());
+        Some(BufferHandle(id))
+    }
+
+    fn delete_buffer(&self, buffer: Option<BufferHandle>) {
+        let mut state = self.record("delete_buffer");
+
+        if let Some(buffer) = buffer {
+            state.buffers.remove(&buffer.0);
+            state.bound_buffers.retain(|_, bound| *bound != buffer.0);
+        }
+    }
+
+    fn bind_buffer(&self, target: u32, buffer: Option<BufferHandle>) {
+        let mut state = self.record("bind_buffer");
+
+        // The element array buffer binding is part of the vertex array
+        if target == GL::ELEMENT_ARRAY_BUFFER {
+            state.current_vertex_array_mut().element_buffer = buffer.map(|buffer| buffer.0);
+        }
+
+        match buffer {
+            Some(buffer) => state.bound_buffers.insert(target, buffer.0),
+            None => state.bound_buffers.remove(&target),
+        };
+    }
+
+    fn bind_buffer_base(&self, target: u32, index: u32, buffer: Option<BufferHandle>) {
+        let mut state = self.record("bind_buffer_base");
+
+        if target == GL::UNIFORM_BUFFER {
+            match buffer {
+                Some(buffer) => state.uniform_buffer_bindings.insert(index, buffer.0),
+                None => state.uniform_buffer_bindings.remove(&index),
+            };
+        }
+
+        // Also binds to the generic binding point, as in GL
+        match buffer {
+            Some(buffer) => state.bound_buffers.insert(target, buffer.0),
+            None => state.bound_buffers.remove(&target),
+        };
+    }
+
+    fn buffer_data_size(&self, target: u32, size: i32, usage: u32) {
+        self.record("buffer_data_size").upload(target, None, size as usize, Some(usage), 0);
+    }
+
+    fn buffer_data(&self, target: u32, data: &[u8], usage: u32) {
+        self.record("buffer_data").upload(target, Some(data), data.len(), Some(usage), 0);
+    }
+
+    fn buffer_sub_data(&self, target: u32, offset: i32, data: &[u8]) {
+        self.record("buffer_sub_data")
+            .upload(target, Some(data), data.len(), None, offset as usize);
+    }
+
+    fn get_buffer_sub_data(&self, target: u32, offset: i32, destination: &mut [u8]) {
+        let state = self.record("get_buffer_sub_data");
+
+        if let Some(buffer) = state.bound_buffer(target) {
+            let offset = offset as usize;
+            let end = (offset + destination.len()).min(buffer.data.len());
+
+            if offset < end {
+                destination[..end - offset].copy_from_slice(&buffer.data[offset..end]);
+            }
+        }
+    }
+
+    fn create_vertex_array(&self) -> Option<VertexArrayHandle> {
+        let mut state = self.record("create_vertex_array");
+        let id = state.next_id();
+        state.vertex_arrays.insert(id, MockVertexArray::default());
+        Some(VertexArrayHandle(id))
+    }
+
+    fn delete_vertex_array(&self, vertex_array: Option<VertexArrayHandle>) {
+        let mut state = self.record("delete_vertex_array");
+
+        if let Some(vertex_array) = vertex_array {
+            state.vertex_arrays.remove(&vertex_array.0);
+        }
+    }
+
+    fn bind_vertex_array(&self, vertex_array: Option<VertexArrayHandle>) {
+        let mut state = self.record("bind_vertex_array");
+        state.bound_vertex_array = vertex_array.map(|vertex_array| vertex_array.0);
+
+        match state.current_vertex_array_mut().element_buffer {
+            Some(buffer) => state.bound_buffers.insert(GL::ELEMENT_ARRAY_BUFFER, buffer),
+            None => state.bound_buffers.remove(&GL::ELEMENT_ARRAY_BUFFER),
+        };
+    }
+
+    fn enable_vertex_attrib_array(&self, index: u32) {
+        self.record("enable_vertex_attrib_array").current_attribute_mut(index).enabled = true;
+    }
+
+    fn vertex_attrib_pointer(&self, index: u32, size: i32, data_type: u32, normalized: bool, stride: i32, offset: i32) {
+        let mut state = self.record("vertex_attrib_pointer");
+        let buffer = state.bound_buffers.get(&GL::ARRAY_BUFFER).copied();
+
+        let attribute = state.current_attribute_mut(index);
+        attribute.buffer = buffer;
+        attribute.size = size;
+        attribute.data_type = data_type;
+        attribute.normalized = normalized;
+        attribute.stride = stride;
+        attribute.offset = offset;
+    }
+
+    fn vertex_attrib_divisor(&self, index: u32, divisor: u32) {
+        self.record("vertex_attrib_divisor").current_attribute_mut(index).divisor = divisor;
+    }
+
+    fn draw_arrays(&self, mode: u32, _first: i32, count: i32) {
+        let mut state = self.record("draw_arrays");
+
+        let draw = MockDrawCall {
+            mode,
+            count,
+            index_type: None,
+            instance_count: None,
+            program: state.current_program,
+            vertex_array: state.bound_vertex_array,
+        };
+
+        state.draws.push(draw);
+    }
+
+    fn draw_elements(&self, mode: u32, count: i32, index_type: u32, _offset: i32) {
+        let mut state = self.record("draw_elements");
+
+        let draw = MockDrawCall {
+            mode,
+            count,
+            index_type: Some(index_type),
+            instance_count: None,
+            program: state.current_program,
+            vertex_array: state.bound_vertex_array,
+        };
+
+        state.draws.push(draw);
+    }
+
+    fn draw_elements_instanced(&self, mode: u32, count: i32, index_type: u32, _offset: i32, instance_count: i32) {
+        let mut state = self.record("draw_elements_instanced");
+
+        let draw = MockDrawCall {
+            mode,
+            count,
+            index_type: Some(index_type),
+            instance_count: Some(instance_count),
+            program: state.current_program,
+            vertex_array: state.bound_vertex_array,
+        };
+
+        state.draws.push(draw);
+    }
+
+    fn create_shader(&self, shader_type: u32) -> Option<ShaderHandle> {
+        let mut state = self.record("create_shader");
+        let id = state.next_id();
+
+        state.shaders.insert(
+            id,
+            MockShader {
+                shader_type,
+                source: String::new(),
+                compiled: false,
+            },
+        );
+
+        Some(ShaderHandle(id))
+    }
+
+    fn delete_shader(&self, shader: Option<ShaderHandle>) {
+        let mut state = self.record("delete_shader");
+
+        if let Some(shader) = shader {
+            state.shaders.remove(&shader.0);
+        }
+    }
+
+    fn shader_source(&self, shader: ShaderHandle, source: &str) {
+        if let Some(shader) = self.record("shader_source").shaders.get_mut(&shader.0) {
+            shader.source = source.to_string();
+        }
+    }
+
+    fn compile_shader(&self, shader: ShaderHandle) {
+        if let Some(shader) = self.record("compile_shader").shaders.get_mut(&shader.0) {
+            shader.compiled = true;
+        }
+    }
+
+    fn get_shader_compile_status(&self, shader: ShaderHandle) -> bool {
+        self.state().shaders.get(&shader.0).is_some_and(|shader| shader.compiled)
+    }
+
+    fn get_shader_info_log(&self, _shader: ShaderHandle) -> Option<String> {
+        Some(String::new())
+    }
+
+    fn create_program(&self) -> Option<ProgramHandle> {
+        let mut state = self.record("create_program");
+        let id = state.next_id();
+        state.programs.insert(id, MockProgram::default());
+        Some(ProgramHandle(id))
+    }
+
+    fn delete_program(&self, program: Option<ProgramHandle>) {
+        let mut state = self.record("delete_program");
+
+        if let Some(program) = program {
+            state.programs.remove(&program.0);
+        }
+    }
+
+    fn attach_shader(&self, program: ProgramHandle, shader: ShaderHandle) {
+        if let Some(program) = self.record("attach_shader").programs.get_mut(&program.0) {
+            program.shaders.push(shader.0);
+        }
+    }
+
+    fn bind_attrib_location(&self, program: ProgramHandle, index: u32, name: &str) {
+        if let Some(program) = self.record("bind_attrib_location").programs.get_mut(&program.0) {
+            program.attribute_bindings.insert(name.to_string(), index);
+        }
+    }
+
+    fn link_program(&self, program: ProgramHandle) {
+        MockContext::link(&mut self.record("link_program"), program.0);
+    }
+
+    fn get_program_link_status(&self, program: ProgramHandle) -> bool {
+        self.state().programs.get(&program.0).is_some_and(|program| program.linked)
+    }
+
+    fn get_program_info_log(&self, _program: ProgramHandle) -> Option<String> {
+        Some(String::new())
+    }
+
+    fn get_program_parameter(&self, program: ProgramHandle, parameter: u32) -> i32 {
+        let state = self.state();
+
+        let Some(program) = state.programs.get(&program.0) else {
+            return 0;
+        };
+
+        match parameter {
+            GL::LINK_STATUS => program.linked as i32,
+            GL::ACTIVE_ATTRIBUTES => program.attributes.len() as i32,
+            GL::ACTIVE_UNIFORMS => program.uniforms.len() as i32,
+            GL::ACTIVE_UNIFORM_BLOCKS => program.uniform_blocks.len() as i32,
+            _ => 0,
+        }
+    }
+
+    fn use_program(&self, program: Option<ProgramHandle>) {
+        self.record("use_program").current_program = program.map(|program| program.0);
+    }
+
+    fn get_active_attrib(&self, program: ProgramHandle, index: u32) -> Option<ActiveInfo> {
+        self.state().programs.get(&program.0)?.attributes.get(index as usize).cloned()
+    }
+
+    fn get_active_uniform(&self, program: ProgramHandle, index: u32) -> Option<ActiveInfo> {
+        self.state().programs.get(&program.0)?.uniforms.get(index as usize).cloned()
+    }
+
+    fn get_attrib_location(&self, program: ProgramHandle, name: &str) -> i32 {
+        let state = self.state();
+
+        state
+            .programs
+            .get(&program.0)
+            .and_then(|program| program.attribute_locations.get(name))
+            .map_or(-1, |location| *location as i32)
+    }
+
+    fn get_uniform_location(&self, program: ProgramHandle, name: &str) -> Option<UniformLocation> {
+        let state = self.state();
+        let location = state.programs.get(&program.0)?.uniform_locations.get(name)?;
+        Some(UniformLocation(*location))
+    }
+
+    /// Uniform blocks are parsed without their members, so every uniform is outside of any block.
+    fn get_active_uniforms(&self, _program: ProgramHandle, indices: &[u32], parameter: u32) -> Vec<i32> {
+        let value = match parameter {
+            GL::UNIFORM_BLOCK_INDEX | GL::UNIFORM_OFFSET => -1,
+            _ => 0,
+        };
+
+        vec![value; indices.len()]
+    }
+
+    fn get_active_uniform_block_name(&self, program: ProgramHandle, index: u32) -> Option<String> {
+        self.state().programs.get(&program.0)?.uniform_blocks.get(index as usize).cloned()
+    }
+
+    fn get_active_uniform_block_parameter(&self, program: ProgramHandle, index: u32, parameter: u32) -> i32 {
+        let state = self.state();
+
+        let Some(program) = state.programs.get(&program.0) else {
+            return 0;
+        };
+
+        match parameter {
+            GL::UNIFORM_BLOCK_BINDING => program.uniform_block_bindings.get(&index).copied().unwrap_or(0) as i32,
+            _ => 0,
+        }
+    }
+
+    fn uniform_block_binding(&self, program: ProgramHandle, block_index: u32, binding: u32) {
+        if let Some(program) = self.record("uniform_block_binding").programs.get_mut(&program.0) {
+            program.uniform_block_bindings.insert(block_index, binding);
+        }
+    }
+
+    fn uniform1f(&self, location: Option<UniformLocation>, value: f32) {
+        self.record("uniform1f").set_uniform(location, MockUniformValue::Float(vec![value]));
+    }
+
+    fn uniform1fv(&self, location: Option<UniformLocation>, values: &[f32]) {
+        self.record("uniform1fv")
+            .set_uniform(location, MockUniformValue::Float(values.to_vec()));
+    }
+
+    fn uniform2fv(&self, location: Option<UniformLocation>, values: &[f32]) {
+        self.record("uniform2fv")
+            .set_uniform(location, MockUniformValue::Float(values.to_vec()));
+    }
+
+    fn uniform3fv(&self, location: Option<UniformLocation>, values: &[f32]) {
+        self.record("uniform3fv")
+            .set_uniform(location, MockUniformValue::Float(values.to_vec()));
+    }
+
+    fn uniform4fv(&self, location: Option<UniformLocation>, values: &[f32]) {
+        self.record("uniform4fv")
+            .set_uniform(location, MockUniformValue::Float(values.to_vec()));
+    }
+
+    fn uniform1i(&self, location: Option<UniformLocation>, value: i32) {
+        self.record("uniform1i").set_uniform(location, MockUniformValue::Int(vec![value]));
+    }
+
+    fn uniform1iv(&self, location: Option<UniformLocation>, values: &[i32]) {
+        self.record("uniform1iv")
+            .set_uniform(location, MockUniformValue::Int(values.to_vec()));
+    }
+
+    fn uniform2iv(&self, location: Option<UniformLocation>, values: &[i32]) {
+        self.record("uniform2iv")
+            .set_uniform(location, MockUniformValue::Int(values.to_vec()));
+    }
+
+    fn uniform3iv(&self, location: Option<UniformLocation>, values: &[i32]) {
+        self.record("uniform3iv")
+            .set_uniform(location, MockUniformValue::Int(values.to_vec()));
+    }
+
+    fn uniform4iv(&self, location: Option<UniformLocation>, values: &[i32]) {
+        self.record("uniform4iv")
+            .set_uniform(location, MockUniformValue::Int(values.to_vec()));
+    }
+
+    fn uniform1ui(&self, location: Option<UniformLocation>, value: u32) {
+        self.record("uniform1ui")
+            .set_uniform(location, MockUniformValue::UnsignedInt(vec![value]));
+    }
+
+    fn uniform1uiv(&self, location: Option<UniformLocation>, values: &[u32]) {
+        self.record("uniform1uiv")
+            .set_uniform(location, MockUniformValue::UnsignedInt(values.to_vec()));
+    }
+
+    fn uniform2uiv(&self, location: Option<UniformLocation>, values: &[u32]) {
+        self.record("uniform2uiv")
+            .set_uniform(location, MockUniformValue::UnsignedInt(values.to_vec()));
+    }
+
+    fn uniform3uiv(&self, location: Option<UniformLocation>, values: &[u32]) {
+        self.record("uniform3uiv")
+            .set_uniform(location, MockUniformValue::UnsignedInt(values.to_vec()));
+    }
+
+    fn uniform4uiv(&self, location: Option<UniformLocation>, values: &[u32]) {
+        self.record("uniform4uiv")
+            .set_uniform(location, MockUniformValue::UnsignedInt(values.to_vec()));
+    }
+
+    fn uniform_matrix2fv(&self, location: Option<UniformLocation>, _transpose: bool, values: &[f32]) {
+        self.record("uniform_matrix2fv")
+            .set_uniform(location, MockUniformValue::Matrix(values.to_vec()));
+    }
+
+    fn uniform_matrix3fv(&self, location: Option<UniformLocation>, _transpose: bool, values: &[f32]) {
+        self.record("uniform_matrix3fv")
+            .set_uniform(location, MockUniformValue::Matrix(values.to_vec()));
+    }
+
+    fn uniform_matrix4fv(&self, location: Option<UniformLocation>, _transpose: bool, values: &[f32]) {
+        self.record("uniform_matrix4fv")
+            .set_uniform(location, MockUniformValue::Matrix(values.to_vec()));
+    }
+
+    fn create_texture(&self) -> Option<TextureHandle> {
+        let mut state = self.record("create_texture");
+        let id = state.next_id();
+        state.textures.insert(id, MockTexture::default());
+        Some(TextureHandle(id))
+    }
+
+    fn delete_texture(&self, texture: Option<TextureHandle>) {
+        let mut state = self.record("delete_texture");
+
+        if let Some(texture) = texture {
+            state.textures.remove(&texture.0);
+            state.bound_textures.retain(|_, bound| *bound != texture.0);
+        }
+    }
+
+    fn active_texture(&self, unit: u32) {
+        self.record("active_texture").active_texture = unit - GL::TEXTURE0;
+    }
+
+    fn bind_texture(&self, target: u32, texture: Option<TextureHandle>) {
+        let mut state = self.record("bind_texture");
+        let unit = state.active_texture;
+
+        match texture {
+            Some(texture) => state.bound_textures.insert((unit, target), texture.0),
+            None => state.bound_textures.remove(&(unit, target)),
+        };
+    }
+
+    fn tex_parameteri(&self, target: u32, parameter: u32, value: i32) {
+        if let Some(texture) = self.record("tex_parameteri").bound_texture_mut(target) {
+            texture.parameters.insert(parameter, value);
+        }
+    }
+
+    fn tex_storage_2d(&self, target: u32, _levels: i32, internal_format: u32, width: i32, height: i32) {
+        if let Some(texture) = self.record("tex_storage_2d").bound_texture_mut(target) {
+            texture.width = width;
+            texture.height = height;
+            texture.internal_format = internal_format;
+        }
+    }
+
+    fn tex_image_2d(
+        &self,
+        target: u32,
+        _level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        data_type: u32,
+        pixels: Option<&[u8]>,
+    ) -> Result<(), BackendError> {
+        let mut state = self.record("tex_image_2d");
+
+        let Some(texture) = state.bound_texture_mut(target) else {
+            return Err(BackendError(String::from("No texture bound")));
+        };
+
+        texture.width = width;
+        texture.height = height;
+        texture.internal_format = internal_format as u32;
+        texture.format = format;
+        texture.data_type = data_type;
+        texture.data = pixels.map(<[u8]>::to_vec).unwrap_or_default();
+        texture.upload_count += 1;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "web")]
+    fn tex_image_2d_with_html_image_element(
+        &self,
+        _target: u32,
+        _level: i32,
+        _internal_format: i32,
+        _format: u32,
+        _data_type: u32,
+        _image: &HtmlImageElement,
+    ) -> Result<(), BackendError> {
+        self.record("tex_image_2d_with_html_image_element");
+        Err(BackendError(String::from("Images are not available outside of the browser")))
+    }
+
+    fn create_sampler(&self) -> Option<SamplerHandle> {
+        let mut state = self.record("create_sampler");
+        let id = state.next_id();
+        state.samplers.insert(id, HashMap::new());
+        Some(SamplerHandle(id))
+    }
+
+    fn delete_sampler(&self, sampler: Option<SamplerHandle>) {
+        let mut state = self.record("delete_sampler");
+
+        if let Some(sampler) = sampler {
+            state.samplers.remove(&sampler.0);
+            state.bound_samplers.retain(|_, bound| *bound != sampler.0);
+        }
+    }
+
+    fn bind_sampler(&self, unit: u32, sampler: Option<SamplerHandle>) {
+        let mut state = self.record("bind_sampler");
+
+        match sampler {
+            Some(sampler) => state.bound_samplers.insert(unit, sampler.0),
+            None => state.bound_samplers.remove(&unit),
+        };
+    }
+
+    fn sampler_parameteri(&self, sampler: SamplerHandle, parameter: u32, value: i32) {
+        if let Some(parameters) = self.record("sampler_parameteri").samplers.get_mut(&sampler.0) {
+            parameters.insert(parameter, value);
+        }
+    }
+
+    fn create_framebuffer(&self) -> Option<FramebufferHandle> {
+        let mut state = self.record("create_framebuffer");
+        let id = state.next_id();
+        state.framebuffers.insert(id);
+        Some(FramebufferHandle(id))
+    }
+
+    fn delete_framebuffer(&self, framebuffer: Option<FramebufferHandle>) {
+        let mut state = self.record("delete_framebuffer");
+
+        if let Some(framebuffer) = framebuffer {
+            state.framebuffers.remove(&framebuffer.0);
+        }
+    }
+
+    fn bind_framebuffer(&self, target: u32, framebuffer: Option<FramebufferHandle>) {
+        let mut state = self.record("bind_framebuffer");
+        let framebuffer = framebuffer.map(|framebuffer| framebuffer.0);
+
+        if target != GL::READ_FRAMEBUFFER {
+            state.bound_framebuffer = framebuffer;
+        }
+
+        if target != GL::DRAW_FRAMEBUFFER {
+            state.bound_read_framebuffer = framebuffer;
+        }
+    }
+
+    fn framebuffer_texture_2d(&self, _target: u32, _attachment: u32, _texture_target: u32, _texture: Option<TextureHandle>, _level: i32) {
+        self.record("framebuffer_texture_2d");
+    }
+
+    fn framebuffer_renderbuffer(
+        &self,
+        _target: u32,
+        _attachment: u32,
+        _renderbuffer_target: u32,
+        _renderbuffer: Option<RenderbufferHandle>,
+    ) {
+        self.record("framebuffer_renderbuffer");
+    }
+
+    fn check_framebuffer_status(&self, _target: u32) -> u32 {
+        self.record("check_framebuffer_status")
+            .framebuffer_status
+            .unwrap_or(GL::FRAMEBUFFER_COMPLETE)
+    }
+
+    fn draw_buffers(&self, _buffers: &[u32]) {
+        self.record("draw_buffers");
+    }
+
+    fn read_buffer(&self, _source: u32) {
+        self.record("read_buffer");
+    }
+
+    fn create_renderbuffer(&self) -> Option<RenderbufferHandle> {
+        let mut state = self.record("create_renderbuffer");
+        let id = state.next_id();
+        state.renderbuffers.insert(id);
+        Some(RenderbufferHandle(id))
+    }
+
+    fn delete_renderbuffer(&self, renderbuffer: Option<RenderbufferHandle>) {
+        let mut state = self.record("delete_renderbuffer");
+
+        if let Some(renderbuffer) = renderbuffer {
+            state.renderbuffers.remove(&renderbuffer.0);
+        }
+    }
+
+    fn bind_renderbuffer(&self, _target: u32, renderbuffer: Option<RenderbufferHandle>) {
+        self.record("bind_renderbuffer").bound_renderbuffer = renderbuffer.map(|renderbuffer| renderbuffer.0);
+    }
+
+    fn renderbuffer_storage(&self, _target: u32, _internal_format: u32, _width: i32, _height: i32) {
+        self.record("renderbuffer_storage");
+    }
+
+    /// Reads zeros, nothing is ever drawn.
+    fn read_pixels(
+        &self,
+        _x: i32,
+        _y: i32,
+        _width: i32,
+        _height: i32,
+        _format: u32,
+        _data_type: u32,
+        destination: &mut [u8],
+    ) -> Result<(), BackendError> {
+        self.record("read_pixels");
+        destination.fill(0);
+        Ok(())
+    }
+
+    fn read_pixels_to_pixel_buffer(
+        &self,
+        _x: i32,
+        _y: i32,
+        _width: i32,
+        _height: i32,
+        _format: u32,
+        _data_type: u32,
+        _offset: i32,
+    ) -> Result<(), BackendError> {
+        self.record("read_pixels_to_pixel_buffer");
+        Ok(())
+    }
+
+    fn fence_sync(&self, _condition: u32, _flags: u32) -> Option<SyncHandle> {
+        let mut state = self.record("fence_sync");
+        let id = state.next_id();
+        state.syncs.insert(id);
+        Some(SyncHandle(id))
+    }
+
+    /// Fences are signaled immediately.
+    fn client_wait_sync(&self, _sync: SyncHandle, _flags: u32, _timeout: u32) -> u32 {
+        self.record("client_wait_sync");
+        GL::ALREADY_SIGNALED
+    }
+
+    fn delete_sync(&self, sync: Option<SyncHandle>) {
+        let mut state = self.record("delete_sync");
+
+        if let Some(sync) = sync {
+            state.syncs.remove(&sync.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_shader_declarations() {
+        let source = r#"#version 300 es
+layout(location = 0) in vec3 position;
+in mat4 transform; // instanced
+uniform highp float time;
+uniform vec3 lights[4];
+uniform Camera {
+    mat4 view;
+    mat4 projection;
+};
+void main() {}
+"#;
+
+        let mock = Rc::new(MockContext::new(1, 1));
+        let program = mock.create_program().unwrap();
+        let shader = mock.create_shader(GL::VERTEX_SHADER).unwrap();
+        mock.shader_source(shader, source);
+        mock.attach_shader(program, shader);
+        mock.bind_attrib_location(program, 5, "position");
+        mock.link_program(program);
+
+        assert_eq!(mock.get_attrib_location(program, "position"), 5);
+        assert_eq!(mock.get_attrib_location(program, "transform"), 0);
+        assert_eq!(mock.get_program_parameter(program, GL::ACTIVE_UNIFORMS), 2);
+        assert_eq!(mock.get_active_uniform(program, 1).unwrap().size, 4);
+        assert_eq!(mock.get_active_uniform_block_name(program, 0).as_deref(), Some("Camera"));
+        assert!(mock.get_uniform_location(program, "lights").is_some());
+        assert!(mock.get_uniform_location(program, "view").is_none());
+    }
+}
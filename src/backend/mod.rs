@@ -0,0 +1,284 @@
+mod constants;
+pub mod mock;
+#[cfg(feature = "native")]
+pub mod native;
+pub mod software;
+#[cfg(feature = "web")]
+pub mod webgl;
+
+use std::{fmt, ops::Deref, rc::Rc};
+
+#[cfg(feature = "web")]
+use web_sys::HtmlImageElement;
+
+macro_rules! handle {
+    ($(#[$attribute:meta])* $name:ident) => {
+        $(#[$attribute])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub struct $name(pub u32);
+    };
+}
+
+handle!(BufferHandle);
+handle!(TextureHandle);
+handle!(SamplerHandle);
+handle!(ShaderHandle);
+handle!(ProgramHandle);
+handle!(VertexArrayHandle);
+handle!(FramebufferHandle);
+handle!(RenderbufferHandle);
+handle!(SyncHandle);
+handle!(
+    /// Only valid for the program it was queried from.
+    UniformLocation
+);
+
+/// Error reported by the backend for calls that can fail, like uploads with mismatched sizes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackendError(pub String);
+
+impl fmt::Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for BackendError {}
+
+/// Active attribute or uniform of a linked program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActiveInfo {
+    pub name: String,
+    /// Number of elements for arrays, `1` otherwise.
+    pub size: i32,
+    /// GL type enum, for example [`Context::FLOAT_VEC3`].
+    pub kind: u32,
+}
+
+/// Every graphics call made by the renderer goes through this trait, following the WebGL 2 API.
+///
+/// Objects are referred to by handles so the same code drives the browser, native GL or the
+/// [`MockContext`](mock::MockContext) used by tests.
+pub trait GraphicsContext {
+    // State
+    fn enable(&self, capability: u32);
+    fn disable(&self, capability: u32);
+    fn viewport(&self, x: i32, y: i32, width: i32, height: i32);
+    fn scissor(&self, x: i32, y: i32, width: i32, height: i32);
+    fn flush(&self);
+    fn drawing_buffer_width(&self) -> i32;
+    fn drawing_buffer_height(&self) -> i32;
+    /// Returns `false` when the extension is not supported.
+    fn enable_extension(&self, name: &str) -> bool;
+    /// Integer limits such as [`Context::MAX_COMBINED_TEXTURE_IMAGE_UNITS`].
+    fn get_parameter_i32(&self, parameter: u32) -> i32;
+
+    // Clearing
+    fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32);
+    fn clear(&self, mask: u32);
+    fn clear_buffer_u32(&self, buffer: u32, draw_buffer: i32, values: &[u32]);
+    fn clear_buffer_f32(&self, buffer: u32, draw_buffer: i32, values: &[f32]);
+
+    // Fixed function
+    fn blend_equation_separate(&self, mode_rgb: u32, mode_alpha: u32);
+    fn blend_func_separate(&self, source_rgb: u32, destination_rgb: u32, source_alpha: u32, destination_alpha: u32);
+    fn blend_color(&self, red: f32, green: f32, blue: f32, alpha: f32);
+    fn depth_func(&self, function: u32);
+    fn depth_mask(&self, flag: bool);
+    fn cull_face(&self, mode: u32);
+    fn color_mask(&self, red: bool, green: bool, blue: bool, alpha: bool);
+    fn polygon_offset(&self, factor: f32, units: f32);
+    fn stencil_func_separate(&self, face: u32, function: u32, reference: i32, mask: u32);
+    fn stencil_op_separate(&self, face: u32, fail: u32, depth_fail: u32, pass: u32);
+    fn stencil_mask(&self, mask: u32);
+    fn stencil_mask_separate(&self, face: u32, mask: u32);
+
+    // Buffers
+    fn create_buffer(&self) -> Option<BufferHandle>;
+    fn delete_buffer(&self, buffer: Option<BufferHandle>);
+    fn bind_buffer(&self, target: u32, buffer: Option<BufferHandle>);
+    fn bind_buffer_base(&self, target: u32, index: u32, buffer: Option<BufferHandle>);
+    /// Allocates `size` bytes without initializing them.
+    fn buffer_data_size(&self, target: u32, size: i32, usage: u32);
+    fn buffer_data(&self, target: u32, data: &[u8], usage: u32);
+    fn buffer_sub_data(&self, target: u32, offset: i32, data: &[u8]);
+    fn get_buffer_sub_data(&self, target: u32, offset: i32, destination: &mut [u8]);
+
+    // Vertex arrays
+    fn create_vertex_array(&self) -> Option<VertexArrayHandle>;
+    fn delete_vertex_array(&self, vertex_array: Option<VertexArrayHandle>);
+    fn bind_vertex_array(&self, vertex_array: Option<VertexArrayHandle>);
+    fn enable_vertex_attrib_array(&self, index: u32);
+    fn vertex_attrib_pointer(&self, index: u32, size: i32, data_type: u32, normalized: bool, stride: i32, offset: i32);
+    fn vertex_attrib_divisor(&self, index: u32, divisor: u32);
+
+    // Drawing
+    fn draw_arrays(&self, mode: u32, first: i32, count: i32);
+    fn draw_elements(&self, mode: u32, count: i32, index_type: u32, offset: i32);
+    fn draw_elements_instanced(&self, mode: u32, count: i32, index_type: u32, offset: i32, instance_count: i32);
+
+    // Shaders
+    fn create_shader(&self, shader_type: u32) -> Option<ShaderHandle>;
+    fn delete_shader(&self, shader: Option<ShaderHandle>);
+    fn shader_source(&self, shader: ShaderHandle, source: &str);
+    fn compile_shader(&self, shader: ShaderHandle);
+    fn get_shader_compile_status(&self, shader: ShaderHandle) -> bool;
+    fn get_shader_info_log(&self, shader: ShaderHandle) -> Option<String>;
+
+    // Programs
+    fn create_program(&self) -> Option<ProgramHandle>;
+    fn delete_program(&self, program: Option<ProgramHandle>);
+    fn attach_shader(&self, program: ProgramHandle, shader: ShaderHandle);
+    fn bind_attrib_location(&self, program: ProgramHandle, index: u32, name: &str);
+    fn link_program(&self, program: ProgramHandle);
+    fn get_program_link_status(&self, program: ProgramHandle) -> bool;
+    fn get_program_info_log(&self, program: ProgramHandle) -> Option<String>;
+    /// Integer parameters such as [`Context::ACTIVE_UNIFORMS`].
+    fn get_program_parameter(&self, program: ProgramHandle, parameter: u32) -> i32;
+    fn use_program(&self, program: Option<ProgramHandle>);
+
+    // Reflection
+    fn get_active_attrib(&self, program: ProgramHandle, index: u32) -> Option<ActiveInfo>;
+    fn get_active_uniform(&self, program: ProgramHandle, index: u32) -> Option<ActiveInfo>;
+    fn get_attrib_location(&self, program: ProgramHandle, name: &str) -> i32;
+    fn get_uniform_location(&self, program: ProgramHandle, name: &str) -> Option<UniformLocation>;
+    /// One value per uniform index, `parameter` being for example [`Context::UNIFORM_OFFSET`].
+    fn get_active_uniforms(&self, program: ProgramHandle, indices: &[u32], parameter: u32) -> Vec<i32>;
+    fn get_active_uniform_block_name(&self, program: ProgramHandle, index: u32) -> Option<String>;
+    /// Integer parameters such as [`Context::UNIFORM_BLOCK_DATA_SIZE`].
+    fn get_active_uniform_block_parameter(&self, program: ProgramHandle, index: u32, parameter: u32) -> i32;
+    fn uniform_block_binding(&self, program: ProgramHandle, block_index: u32, binding: u32);
+
+    // Uniforms
+    fn uniform1f(&self, location: Option<UniformLocation>, value: f32);
+    fn uniform1fv(&self, location: Option<UniformLocation>, values: &[f32]);
+    fn uniform2fv(&self, location: Option<UniformLocation>, values: &[f32]);
+    fn uniform3fv(&self, location: Option<UniformLocation>, values: &[f32]);
+    fn uniform4fv(&self, location: Option<UniformLocation>, values: &[f32]);
+    fn uniform1i(&self, location: Option<UniformLocation>, value: i32);
+    fn uniform1iv(&self, location: Option<UniformLocation>, values: &[i32]);
+    fn uniform2iv(&self, location: Option<UniformLocation>, values: &[i32]);
+    fn uniform3iv(&self, location: Option<UniformLocation>, values: &[i32]);
+    fn uniform4iv(&self, location: Option<UniformLocation>, values: &[i32]);
+    fn uniform1ui(&self, location: Option<UniformLocation>, value: u32);
+    fn uniform1uiv(&self, location: Option<UniformLocation>, values: &[u32]);
+    fn uniform2uiv(&self, location: Option<UniformLocation>, values: &[u32]);
+    fn uniform3uiv(&self, location: Option<UniformLocation>, values: &[u32]);
+    fn uniform4uiv(&self, location: Option<UniformLocation>, values: &[u32]);
+    fn uniform_matrix2fv(&self, location: Option<UniformLocation>, transpose: bool, values: &[f32]);
+    fn uniform_matrix3fv(&self, location: Option<UniformLocation>, transpose: bool, values: &[f32]);
+    fn uniform_matrix4fv(&self, location: Option<UniformLocation>, transpose: bool, values: &[f32]);
+
+    // Textures
+    fn create_texture(&self) -> Option<TextureHandle>;
+    fn delete_texture(&self, texture: Option<TextureHandle>);
+    fn active_texture(&self, unit: u32);
+    fn bind_texture(&self, target: u32, texture: Option<TextureHandle>);
+    fn tex_parameteri(&self, target: u32, parameter: u32, value: i32);
+    fn tex_storage_2d(&self, target: u32, levels: i32, internal_format: u32, width: i32, height: i32);
+    #[allow(clippy::too_many_arguments)]
+    fn tex_image_2d(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        data_type: u32,
+        pixels: Option<&[u8]>,
+    ) -> Result<(), BackendError>;
+    #[cfg(feature = "web")]
+    fn tex_image_2d_with_html_image_element(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        format: u32,
+        data_type: u32,
+        image: &HtmlImageElement,
+    ) -> Result<(), BackendError>;
+
+    // Samplers
+    fn create_sampler(&self) -> Option<SamplerHandle>;
+    fn delete_sampler(&self, sampler: Option<SamplerHandle>);
+    /// `unit` is the index of the texture unit, not `TEXTURE0 + index` as for `active_texture`.
+    fn bind_sampler(&self, unit: u32, sampler: Option<SamplerHandle>);
+    fn sampler_parameteri(&self, sampler: SamplerHandle, parameter: u32, value: i32);
+
+    // Framebuffers
+    fn create_framebuffer(&self) -> Option<FramebufferHandle>;
+    fn delete_framebuffer(&self, framebuffer: Option<FramebufferHandle>);
+    fn bind_framebuffer(&self, target: u32, framebuffer: Option<FramebufferHandle>);
+    fn framebuffer_texture_2d(&self, target: u32, attachment: u32, texture_target: u32, texture: Option<TextureHandle>, level: i32);
+    fn framebuffer_renderbuffer(&self, target: u32, attachment: u32, renderbuffer_target: u32, renderbuffer: Option<RenderbufferHandle>);
+    fn check_framebuffer_status(&self, target: u32) -> u32;
+    fn draw_buffers(&self, buffers: &[u32]);
+    fn read_buffer(&self, source: u32);
+
+    // Renderbuffers
+    fn create_renderbuffer(&self) -> Option<RenderbufferHandle>;
+    fn delete_renderbuffer(&self, renderbuffer: Option<RenderbufferHandle>);
+    fn bind_renderbuffer(&self, target: u32, renderbuffer: Option<RenderbufferHandle>);
+    fn renderbuffer_storage(&self, target: u32, internal_format: u32, width: i32, height: i32);
+
+    // Readback
+    /// Reads into `destination`, whose bytes are interpreted according to `data_type`.
+    #[allow(clippy::too_many_arguments)]
+    fn read_pixels(
+        &self,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        data_type: u32,
+        destination: &mut [u8],
+    ) -> Result<(), BackendError>;
+    /// Reads into the bound [`Context::PIXEL_PACK_BUFFER`] at `offset` bytes.
+    #[allow(clippy::too_many_arguments)]
+    fn read_pixels_to_pixel_buffer(
+        &self,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        data_type: u32,
+        offset: i32,
+    ) -> Result<(), BackendError>;
+
+    // Synchronization
+    fn fence_sync(&self, condition: u32, flags: u32) -> Option<SyncHandle>;
+    fn client_wait_sync(&self, sync: SyncHandle, flags: u32, timeout: u32) -> u32;
+    fn delete_sync(&self, sync: Option<SyncHandle>);
+}
+
+/// Shared handle to a [`GraphicsContext`], cheap to clone.
+///
+/// Dereferences to the context so calls read like the GL API, `gl.bind_buffer(GL::ARRAY_BUFFER, ...)`,
+/// and holds the GL constants.
+#[derive(Clone)]
+pub struct Context {
+    backend: Rc<dyn GraphicsContext>,
+}
+
+impl Context {
+    pub fn new<T: GraphicsContext + 'static>(backend: Rc<T>) -> Context {
+        Context { backend }
+    }
+}
+
+impl Deref for Context {
+    type Target = dyn GraphicsContext;
+
+    fn deref(&self) -> &Self::Target {
+        self.backend.as_ref()
+    }
+}
+
+impl fmt::Debug for Context {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("Context")
+    }
+}
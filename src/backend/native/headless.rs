@@ -0,0 +1,76 @@
+use std::{ffi::c_void, ptr};
+
+use khronos_egl as egl;
+
+use super::NativeError;
+
+/// `EGL_MESA_platform_surfaceless`, a display that needs neither a window system nor a GPU.
+const PLATFORM_SURFACELESS_MESA: egl::Enum = 0x31DD;
+
+/// OpenGL ES 3.0 context current on this thread without any surface, destroyed on drop.
+pub(super) struct EglContext {
+    egl:     egl::DynamicInstance<egl::EGL1_4>,
+    display: egl::Display,
+    context: egl::Context,
+}
+
+impl EglContext {
+    pub(super) fn new() -> Result<EglContext, NativeError> {
+        let egl = unsafe { egl::DynamicInstance::<egl::EGL1_4>::load_required() }
+            .map_err(|error| NativeError::EglUnavailable(error.to_string()))?;
+
+        // Falls back to the default display when the surfaceless platform is missing
+        let surfaceless = egl.upcast::<egl::EGL1_5>().and_then(|egl| unsafe {
+            egl.get_platform_display(PLATFORM_SURFACELESS_MESA, egl::DEFAULT_DISPLAY, &[egl::ATTRIB_NONE])
+                .ok()
+        });
+        let display = surfaceless
+            .or_else(|| unsafe { egl.get_display(egl::DEFAULT_DISPLAY) })
+            .ok_or_else(|| NativeError::EglUnavailable(String::from("No EGL display")))?;
+
+        let creation_failed = |error: egl::Error| NativeError::ContextCreationFailed(error.to_string());
+
+        egl.initialize(display).map_err(creation_failed)?;
+        egl.bind_api(egl::OPENGL_ES_API).map_err(creation_failed)?;
+
+        let config_attributes = [
+            egl::RENDERABLE_TYPE,
+            egl::OPENGL_ES3_BIT,
+            egl::SURFACE_TYPE,
+            egl::PBUFFER_BIT,
+            egl::NONE,
+        ];
+        let config = egl
+            .choose_first_config(display, &config_attributes)
+            .map_err(creation_failed)?
+            .ok_or_else(|| NativeError::ContextCreationFailed(String::from("No OpenGL ES 3.0 config")))?;
+
+        let context_attributes = [egl::CONTEXT_CLIENT_VERSION, 3, egl::NONE];
+        let context = egl
+            .create_context(display, config, None, &context_attributes)
+            .map_err(creation_failed)?;
+
+        let context = EglContext { egl, display, context };
+        context
+            .egl
+            .make_current(display, None, None, Some(context.context))
+            .map_err(creation_failed)?;
+
+        Ok(context)
+    }
+
+    pub(super) fn get_proc_address(&self, name: &str) -> *const c_void {
+        self.egl
+            .get_proc_address(name)
+            .map_or(ptr::null(), |function| function as *const c_void)
+    }
+}
+
+impl Drop for EglContext {
+    fn drop(&mut self) {
+        // Errors are ignored, nothing can be done about them while dropping
+        let _ = self.egl.make_current(self.display, None, None, None);
+        let _ = self.egl.destroy_context(self.display, self.context);
+        let _ = self.egl.terminate(self.display);
+    }
+}
@@ -0,0 +1,864 @@
+mod headless;
+mod window;
+
+pub use window::NativeWindow;
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    num::NonZeroU32,
+};
+
+use glow::{HasContext, PixelPackData, PixelUnpackData};
+#[cfg(feature = "web")]
+use web_sys::HtmlImageElement;
+
+use super::{
+    ActiveInfo, BackendError, BufferHandle, Context as GL, FramebufferHandle, GraphicsContext, ProgramHandle, RenderbufferHandle,
+    SamplerHandle, ShaderHandle, SyncHandle, TextureHandle, UniformLocation, VertexArrayHandle,
+};
+use headless::EglContext;
+
+#[derive(Debug)]
+pub enum NativeError {
+    /// `libEGL` could not be loaded or lacks a required entry point.
+    EglUnavailable(String),
+    ContextCreationFailed(String),
+    WindowCreationFailed(String),
+    SwapFailed(String),
+    FramebufferIncomplete(u32),
+}
+
+macro_rules! native_handles {
+    ($($function:ident: $handle:ident => $native:ident),* $(,)?) => {
+        $(
+            fn $function(handle: $handle) -> Option<glow::$native> {
+                NonZeroU32::new(handle.0).map(glow::$native)
+            }
+        )*
+    };
+}
+
+// GL object names are used as handles directly
+native_handles! {
+    native_buffer: BufferHandle => NativeBuffer,
+    native_texture: TextureHandle => NativeTexture,
+    native_sampler: SamplerHandle => NativeSampler,
+    native_shader: ShaderHandle => NativeShader,
+    native_program: ProgramHandle => NativeProgram,
+    native_vertex_array: VertexArrayHandle => NativeVertexArray,
+    native_framebuffer: FramebufferHandle => NativeFramebuffer,
+    native_renderbuffer: RenderbufferHandle => NativeRenderbuffer,
+}
+
+fn native_uniform_location(location: Option<UniformLocation>) -> Option<glow::NativeUniformLocation> {
+    location.map(|location| glow::NativeUniformLocation(location.0))
+}
+
+/// Fences are pointers rather than names, so they get handles of their own starting at `1`.
+#[derive(Default)]
+struct Syncs {
+    next_id: u32,
+    fences:  HashMap<u32, glow::NativeFence>,
+}
+
+/// Framebuffer drawn to in place of the default one when the context has no surface.
+struct OffscreenFramebuffer {
+    framebuffer:   glow::NativeFramebuffer,
+    color:         glow::NativeRenderbuffer,
+    depth_stencil: glow::NativeRenderbuffer,
+}
+
+/// [`GraphicsContext`] driving OpenGL ES 3.0 through `glow`, in a window or headless.
+///
+/// Behaves like WebGL 2 so the renderer does not need to know where it runs, for example primitive
+/// restart is always enabled and pixel uploads are checked against the buffer size.
+pub struct GlowContext {
+    gl:        glow::Context,
+    size:      Cell<(i32, i32)>,
+    offscreen: Option<OffscreenFramebuffer>,
+    syncs:     RefCell<Syncs>,
+
+    // Dropped after `gl` so the context outlives the objects deleted in `drop`
+    _egl: Option<EglContext>,
+}
+
+impl GlowContext {
+    /// Wraps a context already current on this thread, whose default framebuffer is `width` × `height`.
+    ///
+    /// # Safety
+    ///
+    /// `gl` must stay current on this thread for as long as the returned context is used.
+    pub unsafe fn from_glow(gl: glow::Context, width: u32, height: u32) -> GlowContext {
+        unsafe { gl.enable(glow::PRIMITIVE_RESTART_FIXED_INDEX) };
+
+        GlowContext {
+            gl,
+            size: Cell::new((width as i32, height as i32)),
+            offscreen: None,
+            syncs: RefCell::new(Syncs::default()),
+            _egl: None,
+        }
+    }
+
+    /// Surfaceless EGL context rendering into an offscreen framebuffer, works without a GPU on Mesa llvmpipe.
+    ///
+    /// The framebuffer has an `RGBA8` color and a `DEPTH24_STENCIL8` attachment, read it back with
+    /// [`Renderer::read_pixels`](crate::renderer::Renderer::read_pixels).
+    pub fn headless(width: u32, height: u32) -> Result<GlowContext, NativeError> {
+        let egl = EglContext::new()?;
+        let gl = unsafe { glow::Context::from_loader_function(|name| egl.get_proc_address(name)) };
+
+        let mut context = unsafe { GlowContext::from_glow(gl, width, height) };
+        context._egl = Some(egl);
+        context.offscreen = Some(context.create_offscreen_framebuffer()?);
+
+        Ok(context)
+    }
+
+    /// The underlying context, for calls the [`GraphicsContext`] trait does not cover.
+    pub fn raw(&self) -> &glow::Context {
+        &self.gl
+    }
+
+    /// Updates the drawing buffer size, reallocating the offscreen framebuffer of headless contexts.
+    pub fn resize(&self, width: u32, height: u32) {
+        self.size.set((width as i32, height as i32));
+
+        if let Some(offscreen) = &self.offscreen {
+            self.allocate_offscreen_storage(offscreen);
+        }
+    }
+
+    fn create_offscreen_framebuffer(&self) -> Result<OffscreenFramebuffer, NativeError> {
+        let creation_failed = |error: String| NativeError::ContextCreationFailed(error);
+
+        let offscreen = unsafe {
+            OffscreenFramebuffer {
+                framebuffer:   self.gl.create_framebuffer().map_err(creation_failed)?,
+                color:         self.gl.create_renderbuffer().map_err(creation_failed)?,
+                depth_stencil: self.gl.create_renderbuffer().map_err(creation_failed)?,
+            }
+        };
+
+        self.allocate_offscreen_storage(&offscreen);
+
+        unsafe {
+            self.gl.bind_framebuffer(glow::FRAMEBUFFER, Some(offscreen.framebuffer));
+            self.gl.framebuffer_renderbuffer(
+                glow::FRAMEBUFFER,
+                glow::COLOR_ATTACHMENT0,
+                glow::RENDERBUFFER,
+                Some(offscreen.color),
+            );
+            self.gl.framebuffer_renderbuffer(
+                glow::FRAMEBUFFER,
+                glow::DEPTH_STENCIL_ATTACHMENT,
+                glow::RENDERBUFFER,
+                Some(offscreen.depth_stencil),
+            );
+
+            let status = self.gl.check_framebuffer_status(glow::FRAMEBUFFER);
+            if status != glow::FRAMEBUFFER_COMPLETE {
+                return Err(NativeError::FramebufferIncomplete(status));
+            }
+
+            let (width, height) = self.size.get();
+            self.gl.viewport(0, 0, width, height);
+            self.gl.scissor(0, 0, width, height);
+        }
+
+        Ok(offscreen)
+    }
+
+    fn allocate_offscreen_storage(&self, offscreen: &OffscreenFramebuffer) {
+        let (width, height) = self.size.get();
+
+        unsafe {
+            self.gl.bind_renderbuffer(glow::RENDERBUFFER, Some(offscreen.color));
+            self.gl.renderbuffer_storage(glow::RENDERBUFFER, glow::RGBA8, width, height);
+            self.gl.bind_renderbuffer(glow::RENDERBUFFER, Some(offscreen.depth_stencil));
+            self.gl
+                .renderbuffer_storage(glow::RENDERBUFFER, glow::DEPTH24_STENCIL8, width, height);
+            self.gl.bind_renderbuffer(glow::RENDERBUFFER, None);
+        }
+    }
+}
+
+impl Drop for GlowContext {
+    fn drop(&mut self) {
+        if let Some(offscreen) = self.offscreen.take() {
+            unsafe {
+                self.gl.delete_framebuffer(offscreen.framebuffer);
+                self.gl.delete_renderbuffer(offscreen.color);
+                self.gl.delete_renderbuffer(offscreen.depth_stencil);
+            }
+        }
+    }
+}
+
+/// Bytes read or written by a pixel transfer of `width` × `height` with the default alignment of 4.
+fn pixel_transfer_size(width: i32, height: i32, format: u32, data_type: u32) -> usize {
+    let components = match format {
+        GL::RED | GL::RED_INTEGER | GL::ALPHA | GL::LUMINANCE => 1,
+        GL::RG | GL::LUMINANCE_ALPHA => 2,
+        GL::RGB => 3,
+        _ => 4,
+    };
+
+    let pixel_size = match data_type {
+        GL::BYTE | GL::UNSIGNED_BYTE => components,
+        GL::UNSIGNED_SHORT_5_6_5 | GL::UNSIGNED_SHORT_4_4_4_4 | GL::UNSIGNED_SHORT_5_5_5_1 => 2,
+        GL::SHORT | GL::UNSIGNED_SHORT | glow::HALF_FLOAT => components * 2,
+        _ => components * 4,
+    };
+
+    let (width, height) = (width.max(0) as usize, height.max(0) as usize);
+    if width == 0 || height == 0 {
+        return 0;
+    }
+
+    let row_size = width * pixel_size;
+    row_size.next_multiple_of(4) * (height - 1) + row_size
+}
+
+impl GraphicsContext for GlowContext {
+    fn enable(&self, capability: u32) {
+        unsafe { self.gl.enable(capability) };
+    }
+
+    fn disable(&self, capability: u32) {
+        unsafe { self.gl.disable(capability) };
+    }
+
+    fn viewport(&self, x: i32, y: i32, width: i32, height: i32) {
+        unsafe { self.gl.viewport(x, y, width, height) };
+    }
+
+    fn scissor(&self, x: i32, y: i32, width: i32, height: i32) {
+        unsafe { self.gl.scissor(x, y, width, height) };
+    }
+
+    fn flush(&self) {
+        unsafe { self.gl.flush() };
+    }
+
+    fn drawing_buffer_width(&self) -> i32 {
+        self.size.get().0
+    }
+
+    fn drawing_buffer_height(&self) -> i32 {
+        self.size.get().1
+    }
+
+    fn enable_extension(&self, name: &str) -> bool {
+        // Extensions are always enabled natively, and reported with a `GL_` prefix
+        let extensions = self.gl.supported_extensions();
+        extensions.contains(name) || extensions.contains(&format!("GL_{name}"))
+    }
+
+    fn get_parameter_i32(&self, parameter: u32) -> i32 {
+        unsafe { self.gl.get_parameter_i32(parameter) }
+    }
+
+    fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+        unsafe { self.gl.clear_color(red, green, blue, alpha) };
+    }
+
+    fn clear(&self, mask: u32) {
+        unsafe { self.gl.clear(mask) };
+    }
+
+    fn clear_buffer_u32(&self, buffer: u32, draw_buffer: i32, values: &[u32]) {
+        unsafe { self.gl.clear_buffer_u32_slice(buffer, draw_buffer as u32, values) };
+    }
+
+    fn clear_buffer_f32(&self, buffer: u32, draw_buffer: i32, values: &[f32]) {
+        unsafe { self.gl.clear_buffer_f32_slice(buffer, draw_buffer as u32, values) };
+    }
+
+    fn blend_equation_separate(&self, mode_rgb: u32, mode_alpha: u32) {
+        unsafe { self.gl.blend_equation_separate(mode_rgb, mode_alpha) };
+    }
+
+    fn blend_func_separate(&self, source_rgb: u32, destination_rgb: u32, source_alpha: u32, destination_alpha: u32) {
+        unsafe {
+            self.gl
+                .blend_func_separate(source_rgb, destination_rgb, source_alpha, destination_alpha)
+        };
+    }
+
+    fn blend_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+        unsafe { self.gl.blend_color(red, green, blue, alpha) };
+    }
+
+    fn depth_func(&self, function: u32) {
+        unsafe { self.gl.depth_func(function) };
+    }
+
+    fn depth_mask(&self, flag: bool) {
+        unsafe { self.gl.depth_mask(flag) };
+    }
+
+    fn cull_face(&self, mode: u32) {
+        unsafe { self.gl.cull_face(mode) };
+    }
+
+    fn color_mask(&self, red: bool, green: bool, blue: bool, alpha: bool) {
+        unsafe { self.gl.color_mask(red, green, blue, alpha) };
+    }
+
+    fn polygon_offset(&self, factor: f32, units: f32) {
+        unsafe { self.gl.polygon_offset(factor, units) };
+    }
+
+    fn stencil_func_separate(&self, face: u32, function: u32, reference: i32, mask: u32) {
+        unsafe { self.gl.stencil_func_separate(face, function, reference, mask) };
+    }
+
+    fn stencil_op_separate(&self, face: u32, fail: u32, depth_fail: u32, pass: u32) {
+        unsafe { self.gl.stencil_op_separate(face, fail, depth_fail, pass) };
+    }
+
+    fn stencil_mask(&self, mask: u32) {
+        unsafe { self.gl.stencil_mask(mask) };
+    }
+
+    fn stencil_mask_separate(&self, face: u32, mask: u32) {
+        unsafe { self.gl.stencil_mask_separate(face, mask) };
+    }
+
+    fn create_buffer(&self) -> Option<BufferHandle> {
+        let buffer = unsafe { self.gl.create_buffer() }.ok()?;
+        Some(BufferHandle(buffer.0.get()))
+    }
+
+    fn delete_buffer(&self, buffer: Option<BufferHandle>) {
+        if let Some(buffer) = buffer.and_then(native_buffer) {
+            unsafe { self.gl.delete_buffer(buffer) };
+        }
+    }
+
+    fn bind_buffer(&self, target: u32, buffer: Option<BufferHandle>) {
+        unsafe { self.gl.bind_buffer(target, buffer.and_then(native_buffer)) };
+    }
+
+    fn bind_buffer_base(&self, target: u32, index: u32, buffer: Option<BufferHandle>) {
+        unsafe { self.gl.bind_buffer_base(target, index, buffer.and_then(native_buffer)) };
+    }
+
+    fn buffer_data_size(&self, target: u32, size: i32, usage: u32) {
+        unsafe { self.gl.buffer_data_size(target, size, usage) };
+    }
+
+    fn buffer_data(&self, target: u32, data: &[u8], usage: u32) {
+        unsafe { self.gl.buffer_data_u8_slice(target, data, usage) };
+    }
+
+    fn buffer_sub_data(&self, target: u32, offset: i32, data: &[u8]) {
+        unsafe { self.gl.buffer_sub_data_u8_slice(target, offset, data) };
+    }
+
+    fn get_buffer_sub_data(&self, target: u32, offset: i32, destination: &mut [u8]) {
+        // OpenGL ES has no `glGetBufferSubData`, the buffer is mapped instead
+        unsafe {
+            let mapped = self
+                .gl
+                .map_buffer_range(target, offset, destination.len() as i32, glow::MAP_READ_BIT);
+
+            if !mapped.is_null() {
+                destination.copy_from_slice(std::slice::from_raw_parts(mapped, destination.len()));
+                self.gl.unmap_buffer(target);
+            }
+        }
+    }
+
+    fn create_vertex_array(&self) -> Option<VertexArrayHandle> {
+        let vertex_array = unsafe { self.gl.create_vertex_array() }.ok()?;
+        Some(VertexArrayHandle(vertex_array.0.get()))
+    }
+
+    fn delete_vertex_array(&self, vertex_array: Option<VertexArrayHandle>) {
+        if let Some(vertex_array) = vertex_array.and_then(native_vertex_array) {
+            unsafe { self.gl.delete_vertex_array(vertex_array) };
+        }
+    }
+
+    fn bind_vertex_array(&self, vertex_array: Option<VertexArrayHandle>) {
+        unsafe { self.gl.bind_vertex_array(vertex_array.and_then(native_vertex_array)) };
+    }
+
+    fn enable_vertex_attrib_array(&self, index: u32) {
+        unsafe { self.gl.enable_vertex_attrib_array(index) };
+    }
+
+    fn vertex_attrib_pointer(&self, index: u32, size: i32, data_type: u32, normalized: bool, stride: i32, offset: i32) {
+        unsafe {
+            self.gl
+                .vertex_attrib_pointer_f32(index, size, data_type, normalized, stride, offset)
+        };
+    }
+
+    fn vertex_attrib_divisor(&self, index: u32, divisor: u32) {
+        unsafe { self.gl.vertex_attrib_divisor(index, divisor) };
+    }
+
+    fn draw_arrays(&self, mode: u32, first: i32, count: i32) {
+        unsafe { self.gl.draw_arrays(mode, first, count) };
+    }
+
+    fn draw_elements(&self, mode: u32, count: i32, index_type: u32, offset: i32) {
+        unsafe { self.gl.draw_elements(mode, count, index_type, offset) };
+    }
+
+    fn draw_elements_instanced(&self, mode: u32, count: i32, index_type: u32, offset: i32, instance_count: i32) {
+        unsafe { self.gl.draw_elements_instanced(mode, count, index_type, offset, instance_count) };
+    }
+
+    fn create_shader(&self, shader_type: u32) -> Option<ShaderHandle> {
+        let shader = unsafe { self.gl.create_shader(shader_type) }.ok()?;
+        Some(ShaderHandle(shader.0.get()))
+    }
+
+    fn delete_shader(&self, shader: Option<ShaderHandle>) {
+        if let Some(shader) = shader.and_then(native_shader) {
+            unsafe { self.gl.delete_shader(shader) };
+        }
+    }
+
+    fn shader_source(&self, shader: ShaderHandle, source: &str) {
+        if let Some(shader) = native_shader(shader) {
+            unsafe { self.gl.shader_source(shader, source) };
+        }
+    }
+
+    fn compile_shader(&self, shader: ShaderHandle) {
+        if let Some(shader) = native_shader(shader) {
+            unsafe { self.gl.compile_shader(shader) };
+        }
+    }
+
+    fn get_shader_compile_status(&self, shader: ShaderHandle) -> bool {
+        native_shader(shader).is_some_and(|shader| unsafe { self.gl.get_shader_compile_status(shader) })
+    }
+
+    fn get_shader_info_log(&self, shader: ShaderHandle) -> Option<String> {
+        native_shader(shader).map(|shader| unsafe { self.gl.get_shader_info_log(shader) })
+    }
+
+    fn create_program(&self) -> Option<ProgramHandle> {
+        let program = unsafe { self.gl.create_program() }.ok()?;
+        Some(ProgramHandle(program.0.get()))
+    }
+
+    fn delete_program(&self, program: Option<ProgramHandle>) {
+        if let Some(program) = program.and_then(native_program) {
+            unsafe { self.gl.delete_program(program) };
+        }
+    }
+
+    fn attach_shader(&self, program: ProgramHandle, shader: ShaderHandle) {
+        if let (Some(program), Some(shader)) = (native_program(program), native_shader(shader)) {
+            unsafe { self.gl.attach_shader(program, shader) };
+        }
+    }
+
+    fn bind_attrib_location(&self, program: ProgramHandle, index: u32, name: &str) {
+        if let Some(program) = native_program(program) {
+            unsafe { self.gl.bind_attrib_location(program, index, name) };
+        }
+    }
+
+    fn link_program(&self, program: ProgramHandle) {
+        if let Some(program) = native_program(program) {
+            unsafe { self.gl.link_program(program) };
+        }
+    }
+
+    fn get_program_link_status(&self, program: ProgramHandle) -> bool {
+        native_program(program).is_some_and(|program| unsafe { self.gl.get_program_link_status(program) })
+    }
+
+    fn get_program_info_log(&self, program: ProgramHandle) -> Option<String> {
+        native_program(program).map(|program| unsafe { self.gl.get_program_info_log(program) })
+    }
+
+    fn get_program_parameter(&self, program: ProgramHandle, parameter: u32) -> i32 {
+        native_program(program).map_or(0, |program| unsafe { self.gl.get_program_parameter_i32(program, parameter) })
+    }
+
+    fn use_program(&self, program: Option<ProgramHandle>) {
+        unsafe { self.gl.use_program(program.and_then(native_program)) };
+    }
+
+    fn get_active_attrib(&self, program: ProgramHandle, index: u32) -> Option<ActiveInfo> {
+        let info = unsafe { self.gl.get_active_attribute(native_program(program)?, index) }?;
+
+        Some(ActiveInfo {
+            name: info.name,
+            size: info.size,
+            kind: info.atype,
+        })
+    }
+
+    fn get_active_uniform(&self, program: ProgramHandle, index: u32) -> Option<ActiveInfo> {
+        let info = unsafe { self.gl.get_active_uniform(native_program(program)?, index) }?;
+
+        Some(ActiveInfo {
+            name: info.name,
+            size: info.size,
+            kind: info.utype,
+        })
+    }
+
+    fn get_attrib_location(&self, program: ProgramHandle, name: &str) -> i32 {
+        native_program(program)
+            .and_then(|program| unsafe { self.gl.get_attrib_location(program, name) })
+            .map_or(-1, |location| location as i32)
+    }
+
+    fn get_uniform_location(&self, program: ProgramHandle, name: &str) -> Option<UniformLocation> {
+        let location = unsafe { self.gl.get_uniform_location(native_program(program)?, name) }?;
+        Some(UniformLocation(location.0))
+    }
+
+    fn get_active_uniforms(&self, program: ProgramHandle, indices: &[u32], parameter: u32) -> Vec<i32> {
+        native_program(program).map_or_else(
+            || vec![0; indices.len()],
+            |program| unsafe { self.gl.get_active_uniforms_parameter(program, indices, parameter) },
+        )
+    }
+
+    fn get_active_uniform_block_name(&self, program: ProgramHandle, index: u32) -> Option<String> {
+        native_program(program).map(|program| unsafe { self.gl.get_active_uniform_block_name(program, index) })
+    }
+
+    fn get_active_uniform_block_parameter(&self, program: ProgramHandle, index: u32, parameter: u32) -> i32 {
+        native_program(program).map_or(0, |program| unsafe {
+            self.gl.get_active_uniform_block_parameter_i32(program, index, parameter)
+        })
+    }
+
+    fn uniform_block_binding(&self, program: ProgramHandle, block_index: u32, binding: u32) {
+        if let Some(program) = native_program(program) {
+            unsafe { self.gl.uniform_block_binding(program, block_index, binding) };
+        }
+    }
+
+    fn uniform1f(&self, location: Option<UniformLocation>, value: f32) {
+        unsafe { self.gl.uniform_1_f32(native_uniform_location(location).as_ref(), value) };
+    }
+
+    fn uniform1fv(&self, location: Option<UniformLocation>, values: &[f32]) {
+        unsafe { self.gl.uniform_1_f32_slice(native_uniform_location(location).as_ref(), values) };
+    }
+
+    fn uniform2fv(&self, location: Option<UniformLocation>, values: &[f32]) {
+        unsafe { self.gl.uniform_2_f32_slice(native_uniform_location(location).as_ref(), values) };
+    }
+
+    fn uniform3fv(&self, location: Option<UniformLocation>, values: &[f32]) {
+        unsafe { self.gl.uniform_3_f32_slice(native_uniform_location(location).as_ref(), values) };
+    }
+
+    fn uniform4fv(&self, location: Option<UniformLocation>, values: &[f32]) {
+        unsafe { self.gl.uniform_4_f32_slice(native_uniform_location(location).as_ref(), values) };
+    }
+
+    fn uniform1i(&self, location: Option<UniformLocation>, value: i32) {
+        unsafe { self.gl.uniform_1_i32(native_uniform_location(location).as_ref(), value) };
+    }
+
+    fn uniform1iv(&self, location: Option<UniformLocation>, values: &[i32]) {
+        unsafe { self.gl.uniform_1_i32_slice(native_uniform_location(location).as_ref(), values) };
+    }
+
+    fn uniform2iv(&self, location: Option<UniformLocation>, values: &[i32]) {
+        unsafe { self.gl.uniform_2_i32_slice(native_uniform_location(location).as_ref(), values) };
+    }
+
+    fn uniform3iv(&self, location: Option<UniformLocation>, values: &[i32]) {
+        unsafe { self.gl.uniform_3_i32_slice(native_uniform_location(location).as_ref(), values) };
+    }
+
+    fn uniform4iv(&self, location: Option<UniformLocation>, values: &[i32]) {
+        unsafe { self.gl.uniform_4_i32_slice(native_uniform_location(location).as_ref(), values) };
+    }
+
+    fn uniform1ui(&self, location: Option<UniformLocation>, value: u32) {
+        unsafe { self.gl.uniform_1_u32(native_uniform_location(location).as_ref(), value) };
+    }
+
+    fn uniform1uiv(&self, location: Option<UniformLocation>, values: &[u32]) {
+        unsafe { self.gl.uniform_1_u32_slice(native_uniform_location(location).as_ref(), values) };
+    }
+
+    fn uniform2uiv(&self, location: Option<UniformLocation>, values: &[u32]) {
+        unsafe { self.gl.uniform_2_u32_slice(native_uniform_location(location).as_ref(), values) };
+    }
+
+    fn uniform3uiv(&self, location: Option<UniformLocation>, values: &[u32]) {
+        unsafe { self.gl.uniform_3_u32_slice(native_uniform_location(location).as_ref(), values) };
+    }
+
+    fn uniform4uiv(&self, location: Option<UniformLocation>, values: &[u32]) {
+        unsafe { self.gl.uniform_4_u32_slice(native_uniform_location(location).as_ref(), values) };
+    }
+
+    fn uniform_matrix2fv(&self, location: Option<UniformLocation>, transpose: bool, values: &[f32]) {
+        unsafe {
+            self.gl
+                .uniform_matrix_2_f32_slice(native_uniform_location(location).as_ref(), transpose, values)
+        };
+    }
+
+    fn uniform_matrix3fv(&self, location: Option<UniformLocation>, transpose: bool, values: &[f32]) {
+        unsafe {
+            self.gl
+                .uniform_matrix_3_f32_slice(native_uniform_location(location).as_ref(), transpose, values)
+        };
+    }
+
+    fn uniform_matrix4fv(&self, location: Option<UniformLocation>, transpose: bool, values: &[f32]) {
+        unsafe {
+            self.gl
+                .uniform_matrix_4_f32_slice(native_uniform_location(location).as_ref(), transpose, values)
+        };
+    }
+
+    fn create_texture(&self) -> Option<TextureHandle> {
+        let texture = unsafe { self.gl.create_texture() }.ok()?;
+        Some(TextureHandle(texture.0.get()))
+    }
+
+    fn delete_texture(&self, texture: Option<TextureHandle>) {
+        if let Some(texture) = texture.and_then(native_texture) {
+            unsafe { self.gl.delete_texture(texture) };
+        }
+    }
+
+    fn active_texture(&self, unit: u32) {
+        unsafe { self.gl.active_texture(unit) };
+    }
+
+    fn bind_texture(&self, target: u32, texture: Option<TextureHandle>) {
+        unsafe { self.gl.bind_texture(target, texture.and_then(native_texture)) };
+    }
+
+    fn tex_parameteri(&self, target: u32, parameter: u32, value: i32) {
+        unsafe { self.gl.tex_parameter_i32(target, parameter, value) };
+    }
+
+    fn tex_storage_2d(&self, target: u32, levels: i32, internal_format: u32, width: i32, height: i32) {
+        unsafe { self.gl.tex_storage_2d(target, levels, internal_format, width, height) };
+    }
+
+    fn tex_image_2d(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        data_type: u32,
+        pixels: Option<&[u8]>,
+    ) -> Result<(), BackendError> {
+        // WebGL rejects short uploads, GL would read past the end of the slice
+        let size = pixel_transfer_size(width, height, format, data_type);
+        if pixels.is_some_and(|pixels| pixels.len() < size) {
+            return Err(BackendError(format!("Texture upload needs {size} bytes")));
+        }
+
+        unsafe {
+            self.gl.tex_image_2d(
+                target,
+                level,
+                internal_format,
+                width,
+                height,
+                0,
+                format,
+                data_type,
+                PixelUnpackData::Slice(pixels),
+            )
+        };
+
+        Ok(())
+    }
+
+    #[cfg(feature = "web")]
+    fn tex_image_2d_with_html_image_element(
+        &self,
+        _target: u32,
+        _level: i32,
+        _internal_format: i32,
+        _format: u32,
+        _data_type: u32,
+        _image: &HtmlImageElement,
+    ) -> Result<(), BackendError> {
+        Err(BackendError(String::from("Images are not available outside of the browser")))
+    }
+
+    fn create_sampler(&self) -> Option<SamplerHandle> {
+        let sampler = unsafe { self.gl.create_sampler() }.ok()?;
+        Some(SamplerHandle(sampler.0.get()))
+    }
+
+    fn delete_sampler(&self, sampler: Option<SamplerHandle>) {
+        if let Some(sampler) = sampler.and_then(native_sampler) {
+            unsafe { self.gl.delete_sampler(sampler) };
+        }
+    }
+
+    fn bind_sampler(&self, unit: u32, sampler: Option<SamplerHandle>) {
+        unsafe { self.gl.bind_sampler(unit, sampler.and_then(native_sampler)) };
+    }
+
+    fn sampler_parameteri(&self, sampler: SamplerHandle, parameter: u32, value: i32) {
+        if let Some(sampler) = native_sampler(sampler) {
+            unsafe { self.gl.sampler_parameter_i32(sampler, parameter, value) };
+        }
+    }
+
+    fn create_framebuffer(&self) -> Option<FramebufferHandle> {
+        let framebuffer = unsafe { self.gl.create_framebuffer() }.ok()?;
+        Some(FramebufferHandle(framebuffer.0.get()))
+    }
+
+    fn delete_framebuffer(&self, framebuffer: Option<FramebufferHandle>) {
+        if let Some(framebuffer) = framebuffer.and_then(native_framebuffer) {
+            unsafe { self.gl.delete_framebuffer(framebuffer) };
+        }
+    }
+
+    fn bind_framebuffer(&self, target: u32, framebuffer: Option<FramebufferHandle>) {
+        let framebuffer = framebuffer
+            .and_then(native_framebuffer)
+            .or(self.offscreen.as_ref().map(|offscreen| offscreen.framebuffer));
+
+        unsafe { self.gl.bind_framebuffer(target, framebuffer) };
+    }
+
+    fn framebuffer_texture_2d(&self, target: u32, attachment: u32, texture_target: u32, texture: Option<TextureHandle>, level: i32) {
+        unsafe {
+            self.gl
+                .framebuffer_texture_2d(target, attachment, texture_target, texture.and_then(native_texture), level)
+        };
+    }
+
+    fn framebuffer_renderbuffer(&self, target: u32, attachment: u32, renderbuffer_target: u32, renderbuffer: Option<RenderbufferHandle>) {
+        unsafe {
+            self.gl
+                .framebuffer_renderbuffer(target, attachment, renderbuffer_target, renderbuffer.and_then(native_renderbuffer))
+        };
+    }
+
+    fn check_framebuffer_status(&self, target: u32) -> u32 {
+        unsafe { self.gl.check_framebuffer_status(target) }
+    }
+
+    fn draw_buffers(&self, buffers: &[u32]) {
+        // `BACK` names the default framebuffer, which is a color attachment when headless
+        match &self.offscreen {
+            Some(_) if buffers == [GL::BACK] => unsafe { self.gl.draw_buffers(&[GL::COLOR_ATTACHMENT0]) },
+            _ => unsafe { self.gl.draw_buffers(buffers) },
+        }
+    }
+
+    fn read_buffer(&self, source: u32) {
+        let source = match &self.offscreen {
+            Some(_) if source == GL::BACK => GL::COLOR_ATTACHMENT0,
+            _ => source,
+        };
+
+        unsafe { self.gl.read_buffer(source) };
+    }
+
+    fn create_renderbuffer(&self) -> Option<RenderbufferHandle> {
+        let renderbuffer = unsafe { self.gl.create_renderbuffer() }.ok()?;
+        Some(RenderbufferHandle(renderbuffer.0.get()))
+    }
+
+    fn delete_renderbuffer(&self, renderbuffer: Option<RenderbufferHandle>) {
+        if let Some(renderbuffer) = renderbuffer.and_then(native_renderbuffer) {
+            unsafe { self.gl.delete_renderbuffer(renderbuffer) };
+        }
+    }
+
+    fn bind_renderbuffer(&self, target: u32, renderbuffer: Option<RenderbufferHandle>) {
+        unsafe { self.gl.bind_renderbuffer(target, renderbuffer.and_then(native_renderbuffer)) };
+    }
+
+    fn renderbuffer_storage(&self, target: u32, internal_format: u32, width: i32, height: i32) {
+        unsafe { self.gl.renderbuffer_storage(target, internal_format, width, height) };
+    }
+
+    fn read_pixels(
+        &self,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        data_type: u32,
+        destination: &mut [u8],
+    ) -> Result<(), BackendError> {
+        let size = pixel_transfer_size(width, height, format, data_type);
+        if destination.len() < size {
+            return Err(BackendError(format!("Reading the pixels needs {size} bytes")));
+        }
+
+        unsafe {
+            self.gl
+                .read_pixels(x, y, width, height, format, data_type, PixelPackData::Slice(Some(destination)))
+        };
+
+        Ok(())
+    }
+
+    fn read_pixels_to_pixel_buffer(
+        &self,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        data_type: u32,
+        offset: i32,
+    ) -> Result<(), BackendError> {
+        unsafe {
+            self.gl
+                .read_pixels(x, y, width, height, format, data_type, PixelPackData::BufferOffset(offset as u32))
+        };
+
+        match unsafe { self.gl.get_error() } {
+            glow::NO_ERROR => Ok(()),
+            error => Err(BackendError(format!("Reading the pixels failed with error {error:#x}"))),
+        }
+    }
+
+    fn fence_sync(&self, condition: u32, flags: u32) -> Option<SyncHandle> {
+        let fence = unsafe { self.gl.fence_sync(condition, flags) }.ok()?;
+
+        let mut syncs = self.syncs.borrow_mut();
+        syncs.next_id += 1;
+        let id = syncs.next_id;
+        syncs.fences.insert(id, fence);
+
+        Some(SyncHandle(id))
+    }
+
+    fn client_wait_sync(&self, sync: SyncHandle, flags: u32, timeout: u32) -> u32 {
+        match self.syncs.borrow().fences.get(&sync.0) {
+            Some(fence) => unsafe { self.gl.client_wait_sync(*fence, flags, timeout.min(i32::MAX as u32) as i32) },
+            None => GL::WAIT_FAILED,
+        }
+    }
+
+    fn delete_sync(&self, sync: Option<SyncHandle>) {
+        if let Some(fence) = sync.and_then(|sync| self.syncs.borrow_mut().fences.remove(&sync.0)) {
+            unsafe { self.gl.delete_sync(fence) };
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::{ffi::CString, num::NonZeroU32, rc::Rc};
+
+use glutin::{
+    config::{Api, ConfigTemplateBuilder},
+    context::{ContextApi, ContextAttributesBuilder, NotCurrentGlContext, PossiblyCurrentContext, Version},
+    display::{GetGlDisplay, GlDisplay},
+    surface::{GlSurface, Surface, WindowSurface},
+};
+use glutin_winit::{DisplayBuilder, GlWindow};
+use raw_window_handle::HasWindowHandle;
+use winit::{
+    event_loop::ActiveEventLoop,
+    window::{Window, WindowAttributes},
+};
+
+use super::{GlowContext, NativeError};
+use crate::backend::Context as GL;
+
+/// winit window with an OpenGL ES 3.0 context drawing to it.
+///
+/// Create it once the event loop is resumed, then pass [`NativeWindow::context`] to
+/// [`Renderer::with_context`](crate::renderer::Renderer::with_context).
+pub struct NativeWindow {
+    context:    Rc<GlowContext>,
+    surface:    Surface<WindowSurface>,
+    gl_context: PossiblyCurrentContext,
+    window:     Window,
+}
+
+impl NativeWindow {
+    pub fn new(event_loop: &ActiveEventLoop, attributes: WindowAttributes) -> Result<NativeWindow, NativeError> {
+        let template = ConfigTemplateBuilder::new()
+            .with_api(Api::GLES3)
+            .with_depth_size(24)
+            .with_stencil_size(8);
+
+        let (window, config) = DisplayBuilder::new()
+            .with_window_attributes(Some(attributes))
+            .build(event_loop, template, |mut configs| configs.next().unwrap())
+            .map_err(|error| NativeError::WindowCreationFailed(error.to_string()))?;
+
+        let window = window.ok_or_else(|| NativeError::WindowCreationFailed(String::from("No window was created")))?;
+        let window_handle = window
+            .window_handle()
+            .map_err(|error| NativeError::WindowCreationFailed(error.to_string()))?;
+
+        let creation_failed = |error: glutin::error::Error| NativeError::ContextCreationFailed(error.to_string());
+
+        let display = config.display();
+        let context_attributes = ContextAttributesBuilder::new()
+            .with_context_api(ContextApi::Gles(Some(Version::new(3, 0))))
+            .build(Some(window_handle.as_raw()));
+        let surface_attributes = window
+            .build_surface_attributes(Default::default())
+            .map_err(|error| NativeError::WindowCreationFailed(error.to_string()))?;
+
+        let (surface, gl_context) = unsafe {
+            let surface = display
+                .create_window_surface(&config, &surface_attributes)
+                .map_err(creation_failed)?;
+            let gl_context = display
+                .create_context(&config, &context_attributes)
+                .map_err(creation_failed)?
+                .make_current(&surface)
+                .map_err(creation_failed)?;
+
+            (surface, gl_context)
+        };
+
+        let gl = unsafe {
+            glow::Context::from_loader_function(|name| {
+                let name = CString::new(name).unwrap();
+                display.get_proc_address(&name)
+            })
+        };
+
+        let size = window.inner_size();
+        let context = unsafe { GlowContext::from_glow(gl, size.width, size.height) };
+
+        Ok(NativeWindow {
+            context: Rc::new(context),
+            surface,
+            gl_context,
+            window,
+        })
+    }
+
+    pub fn window(&self) -> &Window {
+        &self.window
+    }
+
+    /// Context for [`Renderer::with_context`](crate::renderer::Renderer::with_context).
+    pub fn context(&self) -> GL {
+        GL::new(self.context.clone())
+    }
+
+    /// Resizes the surface to the window, call it on `WindowEvent::Resized` before
+    /// [`Renderer::handle_window_resize`](crate::renderer::Renderer::handle_window_resize).
+    pub fn resize(&self) {
+        let size = self.window.inner_size();
+
+        if let (Some(width), Some(height)) = (NonZeroU32::new(size.width), NonZeroU32::new(size.height)) {
+            self.surface.resize(&self.gl_context, width, height);
+            self.context.resize(size.width, size.height);
+        }
+    }
+
+    /// Presents the frame drawn since the last call.
+    pub fn swap_buffers(&self) -> Result<(), NativeError> {
+        self.surface
+            .swap_buffers(&self.gl_context)
+            .map_err(|error| NativeError::SwapFailed(error.to_string()))
+    }
+}
@@ -0,0 +1,1504 @@
+use std::{
+    cell::{Ref, RefCell},
+    collections::{BTreeMap, HashMap},
+    rc::Rc,
+};
+
+use glam::{Mat3, Mat4, Vec2, Vec3, Vec4, Vec4Swizzles};
+#[cfg(feature = "web")]
+use web_sys::HtmlImageElement;
+
+use super::{
+    ActiveInfo, BackendError, BufferHandle, Context as GL, FramebufferHandle, GraphicsContext, ProgramHandle, RenderbufferHandle,
+    SamplerHandle, ShaderHandle, SyncHandle, TextureHandle, UniformLocation, VertexArrayHandle,
+    mock::{MockContext, MockState, MockTexture, MockUniformValue, MockVertexArray},
+};
+use crate::shader::{ShaderStage, chunks::with_shader_chunks, preprocessor::preprocess};
+
+/// Attribute locations read for every vertex, the WebGL 2 minimum for `MAX_VERTEX_ATTRIBS`.
+const MAX_VERTEX_ATTRIBUTES: usize = 16;
+
+/// Clip space planes, a position is inside when its dot product with all of them is positive.
+const CLIP_PLANES: [Vec4; 6] = [
+    Vec4::new(1.0, 0.0, 0.0, 1.0),
+    Vec4::new(-1.0, 0.0, 0.0, 1.0),
+    Vec4::new(0.0, 1.0, 0.0, 1.0),
+    Vec4::new(0.0, -1.0, 0.0, 1.0),
+    Vec4::new(0.0, 0.0, 1.0, 1.0),
+    Vec4::new(0.0, 0.0, -1.0, 1.0),
+];
+
+pub type VertexShader = dyn Fn(&VertexInput) -> VertexOutput;
+
+/// Returns the fragment color, or `None` to discard the fragment.
+pub type FragmentShader = dyn Fn(&FragmentInput) -> Option<Vec4>;
+
+/// Uniform values of the program being drawn, unset uniforms read as zero like in GL.
+#[derive(Clone, Copy)]
+pub struct Uniforms<'a> {
+    state:   &'a MockState,
+    program: u32,
+}
+
+impl Uniforms<'_> {
+    fn floats(&self, name: &str) -> &[f32] {
+        match self.state.uniform_value(self.program, name) {
+            Some(MockUniformValue::Float(values) | MockUniformValue::Matrix(values)) => values,
+            _ => &[],
+        }
+    }
+
+    fn float_array<const N: usize>(&self, name: &str) -> [f32; N] {
+        let mut array = [0.0; N];
+        let values = self.floats(name);
+        let count = values.len().min(N);
+        array[..count].copy_from_slice(&values[..count]);
+        array
+    }
+
+    pub fn float(&self, name: &str) -> f32 {
+        self.float_array::<1>(name)[0]
+    }
+
+    pub fn vec2(&self, name: &str) -> Vec2 {
+        Vec2::from_array(self.float_array(name))
+    }
+
+    pub fn vec3(&self, name: &str) -> Vec3 {
+        Vec3::from_array(self.float_array(name))
+    }
+
+    pub fn vec4(&self, name: &str) -> Vec4 {
+        Vec4::from_array(self.float_array(name))
+    }
+
+    pub fn mat3(&self, name: &str) -> Mat3 {
+        Mat3::from_cols_array(&self.float_array(name))
+    }
+
+    pub fn mat4(&self, name: &str) -> Mat4 {
+        Mat4::from_cols_array(&self.float_array(name))
+    }
+
+    pub fn int(&self, name: &str) -> i32 {
+        match self.state.uniform_value(self.program, name) {
+            Some(MockUniformValue::Int(values)) => values.first().copied().unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    pub fn uint(&self, name: &str) -> u32 {
+        match self.state.uniform_value(self.program, name) {
+            Some(MockUniformValue::UnsignedInt(values)) => values.first().copied().unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    /// Samples the 2D texture bound to the unit of the sampler uniform `name`, like `texture(name, uv)`.
+    ///
+    /// There are no derivatives to choose between the filters, so the magnification filter is always used.
+    pub fn texture(&self, name: &str, uv: Vec2) -> Vec4 {
+        let unit = self.int(name) as u32;
+        let sampler = self
+            .state
+            .bound_samplers
+            .get(&unit)
+            .and_then(|sampler| self.state.samplers.get(sampler));
+
+        self.state
+            .bound_textures
+            .get(&(unit, GL::TEXTURE_2D))
+            .and_then(|texture| self.state.textures.get(texture))
+            .map_or(Vec4::new(0.0, 0.0, 0.0, 1.0), |texture| sample(texture, sampler, uv))
+    }
+}
+
+pub struct VertexInput<'a> {
+    /// Index of the vertex, `gl_VertexID`.
+    pub vertex_id:   u32,
+    /// `gl_InstanceID`.
+    pub instance_id: u32,
+    pub uniforms:    Uniforms<'a>,
+
+    locations:  &'a HashMap<String, u32>,
+    attributes: &'a [Vec4; MAX_VERTEX_ATTRIBUTES],
+}
+
+impl VertexInput<'_> {
+    /// Value of the attribute `name`, components missing from the buffer default to `(0, 0, 0, 1)`.
+    pub fn attribute(&self, name: &str) -> Vec4 {
+        self.locations
+            .get(name)
+            .and_then(|location| self.attributes.get(*location as usize))
+            .copied()
+            .unwrap_or(Vec4::W)
+    }
+
+    /// Matrix attribute, whose columns take consecutive locations.
+    pub fn attribute_mat4(&self, name: &str) -> Mat4 {
+        let Some(location) = self.locations.get(name).map(|location| *location as usize) else {
+            return Mat4::IDENTITY;
+        };
+
+        let column = |index: usize| self.attributes.get(location + index).copied().unwrap_or(Vec4::W);
+        Mat4::from_cols(column(0), column(1), column(2), column(3))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VertexOutput {
+    /// Clip space position, `gl_Position`.
+    pub position:   Vec4,
+    /// Interpolated with perspective correction and passed to the fragment shader.
+    pub varyings:   Vec<Vec4>,
+    pub point_size: f32,
+}
+
+impl VertexOutput {
+    pub fn new(position: Vec4, varyings: Vec<Vec4>) -> VertexOutput {
+        VertexOutput {
+            position,
+            varyings,
+            point_size: 1.0,
+        }
+    }
+
+    fn interpolate(&self, other: &VertexOutput, t: f32) -> VertexOutput {
+        VertexOutput {
+            position:   self.position.lerp(other.position, t),
+            varyings:   self.varyings.iter().zip(&other.varyings).map(|(a, b)| a.lerp(*b, t)).collect(),
+            point_size: self.point_size,
+        }
+    }
+}
+
+pub struct FragmentInput<'a> {
+    /// Window coordinates of the pixel center, depth and `1 / w`, as `gl_FragCoord`.
+    pub frag_coord:   Vec4,
+    pub front_facing: bool,
+    pub varyings:     &'a [Vec4],
+    pub uniforms:     Uniforms<'a>,
+}
+
+struct SoftwareProgram {
+    vertex_source:   String,
+    fragment_source: String,
+    vertex_shader:   Rc<VertexShader>,
+    fragment_shader: Rc<FragmentShader>,
+}
+
+/// 8 bit RGBA color buffer and depth buffer of the default framebuffer, rows from bottom to top.
+struct Framebuffer {
+    width:  usize,
+    height: usize,
+    color:  Vec<[u8; 4]>,
+    depth:  Vec<f32>,
+}
+
+impl Framebuffer {
+    fn write_color(&mut self, index: usize, color: [u8; 4], mask: [bool; 4]) {
+        for ((stored, component), write) in self.color[index].iter_mut().zip(color).zip(mask) {
+            if write {
+                *stored = component;
+            }
+        }
+    }
+}
+
+/// [`GraphicsContext`] drawing on the CPU, to test rendered images without a browser or GPU.
+///
+/// Shaders can not be compiled, instead Rust closures are registered for the GLSL sources with
+/// [`SoftwareContext::register_shaders`]. Everything else is kept by a [`MockContext`], whose
+/// state can be inspected through [`SoftwareContext::state`].
+///
+/// Implements triangles, lines and points, clipping, face culling, the depth test, blending,
+/// the color mask, the scissor test and 2D texture sampling, only into the default framebuffer.
+/// Stencil, polygon offset and multisampling are ignored.
+pub struct SoftwareContext {
+    recorder:    MockContext,
+    framebuffer: RefCell<Framebuffer>,
+    programs:    RefCell<Vec<SoftwareProgram>>,
+}
+
+impl SoftwareContext {
+    pub fn new(width: i32, height: i32) -> SoftwareContext {
+        let pixel_count = width.max(0) as usize * height.max(0) as usize;
+
+        SoftwareContext {
+            recorder:    MockContext::new(width, height),
+            framebuffer: RefCell::new(Framebuffer {
+                width:  width.max(0) as usize,
+                height: height.max(0) as usize,
+                color:  vec![[0; 4]; pixel_count],
+                depth:  vec![1.0; pixel_count],
+            }),
+            programs:    RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Context sharing this software context, for the renderer.
+    pub fn context(self: &Rc<Self>) -> GL {
+        GL::new(self.clone())
+    }
+
+    pub fn state(&self) -> Ref<'_, MockState> {
+        self.recorder.state()
+    }
+
+    /// Runs `vertex_shader` and `fragment_shader` for programs linked from exactly these sources,
+    /// as given to [`Material::new`](crate::material::Material::new) for a material without defines.
+    pub fn register_shaders(
+        &self,
+        vertex_source: &str,
+        fragment_source: &str,
+        vertex_shader: impl Fn(&VertexInput) -> VertexOutput + 'static,
+        fragment_shader: impl Fn(&FragmentInput) -> Option<Vec4> + 'static,
+    ) {
+        // Compared to the sources the material compiles, after preprocessing
+        let (vertex_source, fragment_source) = with_shader_chunks(|chunks| {
+            let preprocess = |source, stage| preprocess(source, stage, &BTreeMap::new(), chunks).unwrap().source;
+            (
+                preprocess(vertex_source, ShaderStage::Vertex),
+                preprocess(fragment_source, ShaderStage::Fragment),
+            )
+        });
+
+        self.programs.borrow_mut().push(SoftwareProgram {
+            vertex_source,
+            fragment_source,
+            vertex_shader: Rc::new(vertex_shader),
+            fragment_shader: Rc::new(fragment_shader),
+        });
+    }
+
+    fn find_shaders(&self, state: &MockState, program: u32) -> (Rc<VertexShader>, Rc<FragmentShader>) {
+        let sources: Vec<&str> = state.programs[&program]
+            .shaders
+            .iter()
+            .filter_map(|shader| state.shaders.get(shader))
+            .map(|shader| shader.source.as_str())
+            .collect();
+
+        let programs = self.programs.borrow();
+        let Some(software_program) = programs.iter().find(|software_program| {
+            sources.contains(&software_program.vertex_source.as_str()) && sources.contains(&software_program.fragment_source.as_str())
+        }) else {
+            panic!("No software shaders registered for program {program}, see SoftwareContext::register_shaders");
+        };
+
+        (software_program.vertex_shader.clone(), software_program.fragment_shader.clone())
+    }
+
+    fn draw(&self, mode: u32, indices: &[u32], instance_count: u32) {
+        let state = self.recorder.state();
+
+        // Only the default framebuffer is drawn
+        if state.bound_framebuffer.is_some() {
+            return;
+        }
+
+        let Some(program) = state.current_program else {
+            return;
+        };
+
+        let (vertex_shader, fragment_shader) = self.find_shaders(&state, program);
+        let default_vertex_array = MockVertexArray::default();
+        let vertex_array = state.current_vertex_array().unwrap_or(&default_vertex_array);
+        let uniforms = Uniforms { state: &state, program };
+        let locations = &state.programs[&program].attribute_locations;
+
+        let mut framebuffer = self.framebuffer.borrow_mut();
+        let mut rasterizer = Rasterizer::new(&state, &mut framebuffer, uniforms, fragment_shader.as_ref());
+
+        for instance_id in 0..instance_count {
+            let mut vertices: HashMap<u32, VertexOutput> = HashMap::new();
+
+            for &vertex_id in indices {
+                vertices.entry(vertex_id).or_insert_with(|| {
+                    let attributes = fetch_attributes(&state, vertex_array, vertex_id, instance_id);
+                    let input = VertexInput {
+                        vertex_id,
+                        instance_id,
+                        uniforms,
+                        locations,
+                        attributes: &attributes,
+                    };
+
+                    vertex_shader(&input)
+                });
+            }
+
+            let vertex = |index: usize| &vertices[&indices[index]];
+            let count = indices.len();
+
+            match mode {
+                GL::POINTS => (0..count).for_each(|index| rasterizer.point(vertex(index))),
+                GL::LINES => (0..count / 2).for_each(|line| rasterizer.line(vertex(2 * line), vertex(2 * line + 1))),
+                GL::LINE_STRIP | GL::LINE_LOOP => {
+                    for index in 1..count {
+                        rasterizer.line(vertex(index - 1), vertex(index));
+                    }
+
+                    if mode == GL::LINE_LOOP && count > 2 {
+                        rasterizer.line(vertex(count - 1), vertex(0));
+                    }
+                }
+                GL::TRIANGLES => {
+                    for triangle in 0..count / 3 {
+                        rasterizer.triangle([vertex(3 * triangle), vertex(3 * triangle + 1), vertex(3 * triangle + 2)]);
+                    }
+                }
+                GL::TRIANGLE_STRIP => {
+                    // Every other triangle is flipped to keep the winding of the first
+                    for index in 0..count.saturating_sub(2) {
+                        match index % 2 {
+                            0 => rasterizer.triangle([vertex(index), vertex(index + 1), vertex(index + 2)]),
+                            _ => rasterizer.triangle([vertex(index + 1), vertex(index), vertex(index + 2)]),
+                        }
+                    }
+                }
+                GL::TRIANGLE_FAN => {
+                    for index in 1..count.saturating_sub(1) {
+                        rasterizer.triangle([vertex(0), vertex(index), vertex(index + 1)]);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn draw_indexed(&self, mode: u32, count: i32, index_type: u32, offset: i32, instance_count: i32) {
+        let indices = {
+            let state = self.recorder.state();
+            let index_size = match index_type {
+                GL::UNSIGNED_BYTE => 1,
+                GL::UNSIGNED_SHORT => 2,
+                _ => 4,
+            };
+
+            let Some(buffer) = state.bound_buffer(GL::ELEMENT_ARRAY_BUFFER) else {
+                return;
+            };
+
+            buffer.data[offset.max(0) as usize..]
+                .chunks_exact(index_size)
+                .take(count.max(0) as usize)
+                .map(|bytes| {
+                    match index_size {
+                        1 => bytes[0] as u32,
+                        2 => u16::from_ne_bytes([bytes[0], bytes[1]]) as u32,
+                        _ => u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+                    }
+                })
+                .collect::<Vec<u32>>()
+        };
+
+        self.draw(mode, &indices, instance_count.max(0) as u32);
+    }
+
+    /// Reads RGBA pixels of the default framebuffer, as `UNSIGNED_BYTE` or `FLOAT` components.
+    #[allow(clippy::too_many_arguments)]
+    fn read_framebuffer(
+        &self,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        data_type: u32,
+        destination: &mut [u8],
+    ) -> Result<(), BackendError> {
+        if self.recorder.state().bound_read_framebuffer.is_some() {
+            return Err(BackendError(String::from("Only the default framebuffer can be read")));
+        }
+
+        let component_size = match (format, data_type) {
+            (GL::RGBA, GL::UNSIGNED_BYTE) => 1,
+            (GL::RGBA, GL::FLOAT) => 4,
+            _ => return Err(BackendError(format!("Unsupported read format {format:#x} and type {data_type:#x}"))),
+        };
+
+        let (width, height) = (width.max(0) as usize, height.max(0) as usize);
+        let pixel_size = 4 * component_size;
+
+        if destination.len() < width * height * pixel_size {
+            return Err(BackendError(String::from("Destination too small for the pixels")));
+        }
+
+        let framebuffer = self.framebuffer.borrow();
+
+        for row in 0..height {
+            for column in 0..width {
+                let (source_x, source_y) = (x + column as i32, y + row as i32);
+
+                // Pixels outside of the framebuffer are left untouched
+                if source_x < 0 || source_y < 0 || source_x as usize >= framebuffer.width || source_y as usize >= framebuffer.height {
+                    continue;
+                }
+
+                let color = framebuffer.color[source_y as usize * framebuffer.width + source_x as usize];
+                let start = (row * width + column) * pixel_size;
+                let pixel = &mut destination[start..start + pixel_size];
+
+                match component_size {
+                    1 => pixel.copy_from_slice(&color),
+                    _ => {
+                        for (bytes, component) in pixel.chunks_exact_mut(4).zip(color) {
+                            bytes.copy_from_slice(&(component as f32 / 255.0).to_ne_bytes());
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Values of every attribute location for one vertex of one instance.
+fn fetch_attributes(state: &MockState, vertex_array: &MockVertexArray, vertex_id: u32, instance_id: u32) -> [Vec4; MAX_VERTEX_ATTRIBUTES] {
+    let mut attributes = [Vec4::W; MAX_VERTEX_ATTRIBUTES];
+
+    for (location, attribute) in &vertex_array.attributes {
+        let Some(value) = attributes.get_mut(*location as usize) else {
+            continue;
+        };
+
+        if !attribute.enabled {
+            continue;
+        }
+
+        let Some(buffer) = attribute.buffer.and_then(|buffer| state.buffers.get(&buffer)) else {
+            continue;
+        };
+
+        let component_size = component_size(attribute.data_type);
+        let stride = match attribute.stride {
+            0 => attribute.size as usize * component_size,
+            stride => stride as usize,
+        };
+
+        let element = match attribute.divisor {
+            0 => vertex_id,
+            divisor => instance_id / divisor,
+        };
+
+        let start = attribute.offset as usize + element as usize * stride;
+
+        for component in 0..attribute.size.clamp(0, 4) as usize {
+            let offset = start + component * component_size;
+
+            if let Some(bytes) = buffer.data.get(offset..offset + component_size) {
+                value[component] = read_component(bytes, attribute.data_type, attribute.normalized);
+            }
+        }
+    }
+
+    attributes
+}
+
+fn component_size(data_type: u32) -> usize {
+    match data_type {
+        GL::BYTE | GL::UNSIGNED_BYTE => 1,
+        GL::SHORT | GL::UNSIGNED_SHORT => 2,
+        _ => 4,
+    }
+}
+
+fn read_component(bytes: &[u8], data_type: u32, normalized: bool) -> f32 {
+    let (value, max) = match data_type {
+        GL::FLOAT => return f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        GL::BYTE => (bytes[0] as i8 as f32, i8::MAX as f32),
+        GL::UNSIGNED_BYTE => (bytes[0] as f32, u8::MAX as f32),
+        GL::SHORT => (i16::from_ne_bytes([bytes[0], bytes[1]]) as f32, i16::MAX as f32),
+        GL::UNSIGNED_SHORT => (u16::from_ne_bytes([bytes[0], bytes[1]]) as f32, u16::MAX as f32),
+        GL::INT => (i32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32, i32::MAX as f32),
+        _ => (u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32, u32::MAX as f32),
+    };
+
+    if normalized { (value / max).max(-1.0) } else { value }
+}
+
+/// A bound sampler replaces every parameter of the texture, the ones it does not set keep their defaults.
+fn sample(texture: &MockTexture, sampler: Option<&HashMap<u32, i32>>, uv: Vec2) -> Vec4 {
+    if texture.width <= 0 || texture.height <= 0 {
+        return Vec4::new(0.0, 0.0, 0.0, 1.0);
+    }
+
+    let size = Vec2::new(texture.width as f32, texture.height as f32);
+    let parameters = sampler.unwrap_or(&texture.parameters);
+    let parameter = |name: u32, default: u32| parameters.get(&name).map_or(default, |value| *value as u32);
+    let wrap = [parameter(GL::TEXTURE_WRAP_S, GL::REPEAT), parameter(GL::TEXTURE_WRAP_T, GL::REPEAT)];
+
+    let texel = |x: i32, y: i32| {
+        let x = wrap_coordinate(x, texture.width, wrap[0]);
+        let y = wrap_coordinate(y, texture.height, wrap[1]);
+        fetch_texel(texture, (y * texture.width + x) as usize)
+    };
+
+    match parameter(GL::TEXTURE_MAG_FILTER, GL::LINEAR) {
+        GL::NEAREST => {
+            let position = (uv * size).floor();
+            texel(position.x as i32, position.y as i32)
+        }
+        _ => {
+            let position = uv * size - 0.5;
+            let base = position.floor();
+            let fraction = position - base;
+            let (x, y) = (base.x as i32, base.y as i32);
+
+            let bottom = texel(x, y).lerp(texel(x + 1, y), fraction.x);
+            let top = texel(x, y + 1).lerp(texel(x + 1, y + 1), fraction.x);
+            bottom.lerp(top, fraction.y)
+        }
+    }
+}
+
+fn wrap_coordinate(coordinate: i32, size: i32, wrap: u32) -> i32 {
+    match wrap {
+        GL::CLAMP_TO_EDGE => coordinate.clamp(0, size - 1),
+        GL::MIRRORED_REPEAT => {
+            let period = coordinate.rem_euclid(2 * size);
+            if period < size { period } else { 2 * size - 1 - period }
+        }
+        _ => coordinate.rem_euclid(size),
+    }
+}
+
+fn fetch_texel(texture: &MockTexture, index: usize) -> Vec4 {
+    let channels = match texture.format {
+        GL::RED | GL::ALPHA | GL::LUMINANCE => 1,
+        GL::RG | GL::LUMINANCE_ALPHA => 2,
+        GL::RGB => 3,
+        _ => 4,
+    };
+
+    let mut components = [0.0; 4];
+
+    for (channel, component) in components.iter_mut().enumerate().take(channels) {
+        *component = match texture.data_type {
+            GL::FLOAT => {
+                let offset = (index * channels + channel) * 4;
+                texture
+                    .data
+                    .get(offset..offset + 4)
+                    .map_or(0.0, |bytes| f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            }
+            _ => {
+                texture
+                    .data
+                    .get(index * channels + channel)
+                    .map_or(0.0, |byte| *byte as f32 / 255.0)
+            }
+        };
+    }
+
+    let [a, b, c, d] = components;
+
+    match texture.format {
+        GL::RED => Vec4::new(a, 0.0, 0.0, 1.0),
+        GL::RG => Vec4::new(a, b, 0.0, 1.0),
+        GL::RGB => Vec4::new(a, b, c, 1.0),
+        GL::ALPHA => Vec4::new(0.0, 0.0, 0.0, a),
+        GL::LUMINANCE => Vec4::new(a, a, a, 1.0),
+        GL::LUMINANCE_ALPHA => Vec4::new(a, a, a, b),
+        _ => Vec4::new(a, b, c, d),
+    }
+}
+
+fn compare(function: u32, incoming: f32, stored: f32) -> bool {
+    match function {
+        GL::NEVER => false,
+        GL::LESS => incoming < stored,
+        GL::EQUAL => incoming == stored,
+        GL::LEQUAL => incoming <= stored,
+        GL::GREATER => incoming > stored,
+        GL::NOTEQUAL => incoming != stored,
+        GL::GEQUAL => incoming >= stored,
+        _ => true,
+    }
+}
+
+fn blend_factor(factor: u32, source: Vec4, destination: Vec4, constant: Vec4) -> Vec4 {
+    match factor {
+        GL::ZERO => Vec4::ZERO,
+        GL::SRC_COLOR => source,
+        GL::ONE_MINUS_SRC_COLOR => 1.0 - source,
+        GL::DST_COLOR => destination,
+        GL::ONE_MINUS_DST_COLOR => 1.0 - destination,
+        GL::SRC_ALPHA => Vec4::splat(source.w),
+        GL::ONE_MINUS_SRC_ALPHA => Vec4::splat(1.0 - source.w),
+        GL::DST_ALPHA => Vec4::splat(destination.w),
+        GL::ONE_MINUS_DST_ALPHA => Vec4::splat(1.0 - destination.w),
+        GL::CONSTANT_COLOR => constant,
+        GL::ONE_MINUS_CONSTANT_COLOR => 1.0 - constant,
+        GL::CONSTANT_ALPHA => Vec4::splat(constant.w),
+        GL::ONE_MINUS_CONSTANT_ALPHA => Vec4::splat(1.0 - constant.w),
+        GL::SRC_ALPHA_SATURATE => {
+            let factor = source.w.min(1.0 - destination.w);
+            Vec4::new(factor, factor, factor, 1.0)
+        }
+        _ => Vec4::ONE,
+    }
+}
+
+fn blend_equation(equation: u32, source: Vec4, source_factor: Vec4, destination: Vec4, destination_factor: Vec4) -> Vec4 {
+    match equation {
+        GL::FUNC_SUBTRACT => source * source_factor - destination * destination_factor,
+        GL::FUNC_REVERSE_SUBTRACT => destination * destination_factor - source * source_factor,
+        GL::MIN => source.min(destination),
+        GL::MAX => source.max(destination),
+        _ => source * source_factor + destination * destination_factor,
+    }
+}
+
+/// Signed area of the parallelogram spanned by `a -> b` and `a -> p`, positive when `p` is left of `a -> b`.
+fn edge_function(a: Vec2, b: Vec2, p: Vec2) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+/// Pixels on an edge are drawn only for top and left edges, so triangles sharing the edge do not overlap.
+fn is_top_left(a: Vec2, b: Vec2) -> bool {
+    let edge = b - a;
+    edge.y < 0.0 || (edge.y == 0.0 && edge.x < 0.0)
+}
+
+/// Rasterizes the primitives of one draw call into the framebuffer.
+struct Rasterizer<'a> {
+    state:           &'a MockState,
+    framebuffer:     &'a mut Framebuffer,
+    uniforms:        Uniforms<'a>,
+    fragment_shader: &'a FragmentShader,
+    /// Pixels that can be written, the viewport clipped by the scissor box and the framebuffer.
+    bounds:          [i32; 4],
+}
+
+impl<'a> Rasterizer<'a> {
+    fn new(state: &'a MockState, framebuffer: &'a mut Framebuffer, uniforms: Uniforms<'a>, fragment_shader: &'a FragmentShader) -> Self {
+        let [x, y, width, height] = state.viewport;
+        let mut bounds = [
+            x.max(0),
+            y.max(0),
+            (x + width).min(framebuffer.width as i32),
+            (y + height).min(framebuffer.height as i32),
+        ];
+
+        if state.enabled.contains(&GL::SCISSOR_TEST) {
+            let [x, y, width, height] = state.pipeline.scissor;
+            bounds = [
+                bounds[0].max(x),
+                bounds[1].max(y),
+                bounds[2].min(x + width),
+                bounds[3].min(y + height),
+            ];
+        }
+
+        Rasterizer {
+            state,
+            framebuffer,
+            uniforms,
+            fragment_shader,
+            bounds,
+        }
+    }
+
+    /// Window coordinates and depth, with `1 / w` kept for perspective correct interpolation.
+    fn to_window(&self, position: Vec4) -> Vec4 {
+        let [x, y, width, height] = self.state.viewport;
+        let inverse_w = 1.0 / position.w;
+        let ndc = position.xyz() * inverse_w;
+
+        Vec4::new(
+            x as f32 + (ndc.x + 1.0) * 0.5 * width as f32,
+            y as f32 + (ndc.y + 1.0) * 0.5 * height as f32,
+            ((ndc.z + 1.0) * 0.5).clamp(0.0, 1.0),
+            inverse_w,
+        )
+    }
+
+    fn triangle(&mut self, vertices: [&VertexOutput; 3]) {
+        let mut polygon: Vec<VertexOutput> = vertices.into_iter().cloned().collect();
+
+        // Sutherland-Hodgman against each plane, in clip space where attributes interpolate linearly
+        for plane in CLIP_PLANES {
+            let mut clipped = Vec::with_capacity(polygon.len() + 1);
+
+            for (index, current) in polygon.iter().enumerate() {
+                let next = &polygon[(index + 1) % polygon.len()];
+                let (current_distance, next_distance) = (plane.dot(current.position), plane.dot(next.position));
+
+                if current_distance >= 0.0 {
+                    clipped.push(current.clone());
+                }
+
+                if (current_distance >= 0.0) != (next_distance >= 0.0) {
+                    clipped.push(current.interpolate(next, current_distance / (current_distance - next_distance)));
+                }
+            }
+
+            polygon = clipped;
+
+            if polygon.len() < 3 {
+                return;
+            }
+        }
+
+        for index in 1..polygon.len() - 1 {
+            self.clipped_triangle([&polygon[0], &polygon[index], &polygon[index + 1]]);
+        }
+    }
+
+    fn clipped_triangle(&mut self, mut vertices: [&VertexOutput; 3]) {
+        let mut window = vertices.map(|vertex| self.to_window(vertex.position));
+        let area = edge_function(window[0].xy(), window[1].xy(), window[2].xy());
+
+        if area == 0.0 || area.is_nan() {
+            return;
+        }
+
+        // Counter clockwise triangles face the camera
+        let front_facing = area > 0.0;
+
+        if self.state.enabled.contains(&GL::CULL_FACE) {
+            let culled = match self.state.pipeline.cull_face {
+                GL::FRONT => front_facing,
+                GL::BACK => !front_facing,
+                _ => true,
+            };
+
+            if culled {
+                return;
+            }
+        }
+
+        // Rasterize with a counter clockwise winding so the inside of every edge is on its left
+        if !front_facing {
+            vertices.swap(1, 2);
+            window.swap(1, 2);
+        }
+
+        let area = area.abs();
+        let points = window.map(|vertex| vertex.xy());
+        let minimum = points[0].min(points[1]).min(points[2]).floor();
+        let maximum = points[0].max(points[1]).max(points[2]).ceil();
+
+        let edges = [(points[1], points[2]), (points[2], points[0]), (points[0], points[1])];
+        let top_left = edges.map(|(a, b)| is_top_left(a, b));
+
+        for y in (minimum.y as i32).max(self.bounds[1])..(maximum.y as i32).min(self.bounds[3]) {
+            for x in (minimum.x as i32).max(self.bounds[0])..(maximum.x as i32).min(self.bounds[2]) {
+                let center = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+                let weights = edges.map(|(a, b)| edge_function(a, b, center));
+
+                let inside = weights
+                    .iter()
+                    .zip(top_left)
+                    .all(|(weight, top_left)| *weight > 0.0 || (*weight == 0.0 && top_left));
+
+                if !inside {
+                    continue;
+                }
+
+                let barycentric = Vec3::from_array(weights) / area;
+                let depth = barycentric.dot(Vec3::new(window[0].z, window[1].z, window[2].z));
+                let inverse_w = barycentric.dot(Vec3::new(window[0].w, window[1].w, window[2].w));
+
+                let perspective = [0, 1, 2].map(|index| barycentric[index] * window[index].w / inverse_w);
+                let varyings: Vec<Vec4> = (0..vertices[0].varyings.len())
+                    .map(|varying| {
+                        (0..3)
+                            .map(|index| vertices[index].varyings.get(varying).copied().unwrap_or(Vec4::ZERO) * perspective[index])
+                            .sum()
+                    })
+                    .collect();
+
+                self.fragment(x, y, Vec4::new(center.x, center.y, depth, inverse_w), front_facing, &varyings);
+            }
+        }
+    }
+
+    fn line(&mut self, start: &VertexOutput, end: &VertexOutput) {
+        // Liang-Barsky clipping of the segment parameter
+        let (mut t_start, mut t_end) = (0.0_f32, 1.0_f32);
+
+        for plane in CLIP_PLANES {
+            let (start_distance, end_distance) = (plane.dot(start.position), plane.dot(end.position));
+
+            if start_distance < 0.0 && end_distance < 0.0 {
+                return;
+            }
+
+            if start_distance < 0.0 {
+                t_start = t_start.max(start_distance / (start_distance - end_distance));
+            } else if end_distance < 0.0 {
+                t_end = t_end.min(start_distance / (start_distance - end_distance));
+            }
+        }
+
+        if t_start > t_end {
+            return;
+        }
+
+        let (start, end) = (start.interpolate(end, t_start), start.interpolate(end, t_end));
+        let (window_start, window_end) = (self.to_window(start.position), self.to_window(end.position));
+
+        let delta = window_end.xy() - window_start.xy();
+        let steps = delta.abs().max_element().ceil().max(1.0) as i32;
+
+        // One fragment per pixel along the major axis, the last pixel is left for the next segment
+        for step in 0..steps {
+            let t = (step as f32 + 0.5) / steps as f32;
+            let position = window_start.lerp(window_end, t);
+            let (x, y) = (position.x.floor() as i32, position.y.floor() as i32);
+
+            if x < self.bounds[0] || x >= self.bounds[2] || y < self.bounds[1] || y >= self.bounds[3] {
+                continue;
+            }
+
+            let inverse_w = position.w;
+            let end_weight = t * window_end.w / inverse_w;
+            let varyings: Vec<Vec4> = start
+                .varyings
+                .iter()
+                .zip(&end.varyings)
+                .map(|(a, b)| a.lerp(*b, end_weight))
+                .collect();
+
+            let frag_coord = Vec4::new(x as f32 + 0.5, y as f32 + 0.5, position.z, inverse_w);
+            self.fragment(x, y, frag_coord, true, &varyings);
+        }
+    }
+
+    fn point(&mut self, vertex: &VertexOutput) {
+        // Points are clipped by their center
+        if CLIP_PLANES.iter().any(|plane| plane.dot(vertex.position) < 0.0) {
+            return;
+        }
+
+        let window = self.to_window(vertex.position);
+        let size = vertex.point_size.max(1.0);
+        let minimum = window.xy() - size * 0.5;
+
+        // Pixels whose center is inside the point square
+        let start = (minimum - 0.5).ceil();
+        let end = (minimum + size - 0.5).ceil();
+
+        for y in (start.y as i32).max(self.bounds[1])..(end.y as i32).min(self.bounds[3]) {
+            for x in (start.x as i32).max(self.bounds[0])..(end.x as i32).min(self.bounds[2]) {
+                let frag_coord = Vec4::new(x as f32 + 0.5, y as f32 + 0.5, window.z, window.w);
+                self.fragment(x, y, frag_coord, true, &vertex.varyings);
+            }
+        }
+    }
+
+    /// Depth test, fragment shader, blending and color mask for one pixel.
+    fn fragment(&mut self, x: i32, y: i32, frag_coord: Vec4, front_facing: bool, varyings: &[Vec4]) {
+        let index = y as usize * self.framebuffer.width + x as usize;
+        let pipeline = &self.state.pipeline;
+        let depth_test = self.state.enabled.contains(&GL::DEPTH_TEST);
+
+        if depth_test && !compare(pipeline.depth_func, frag_coord.z, self.framebuffer.depth[index]) {
+            return;
+        }
+
+        let input = FragmentInput {
+            frag_coord,
+            front_facing,
+            varyings,
+            uniforms: self.uniforms,
+        };
+
+        let Some(color) = (self.fragment_shader)(&input) else {
+            return;
+        };
+
+        if depth_test && pipeline.depth_mask {
+            self.framebuffer.depth[index] = frag_coord.z;
+        }
+
+        // The color buffer stores normalized values, so colors are clamped before blending
+        let source = color.clamp(Vec4::ZERO, Vec4::ONE);
+        let stored = self.framebuffer.color[index];
+
+        let color = if self.state.enabled.contains(&GL::BLEND) {
+            let destination = Vec4::from_array(stored.map(|component| component as f32 / 255.0));
+            let constant = Vec4::from_array(pipeline.blend_color);
+            let [source_rgb, destination_rgb, source_alpha, destination_alpha] = pipeline.blend_func;
+
+            let rgb = blend_equation(
+                pipeline.blend_equation[0],
+                source,
+                blend_factor(source_rgb, source, destination, constant),
+                destination,
+                blend_factor(destination_rgb, source, destination, constant),
+            );
+
+            let alpha = blend_equation(
+                pipeline.blend_equation[1],
+                source,
+                blend_factor(source_alpha, source, destination, constant),
+                destination,
+                blend_factor(destination_alpha, source, destination, constant),
+            );
+
+            rgb.xyz().extend(alpha.w).clamp(Vec4::ZERO, Vec4::ONE)
+        } else {
+            source
+        };
+
+        let color = color.to_array().map(|component| (component * 255.0).round() as u8);
+        self.framebuffer.write_color(index, color, pipeline.color_mask);
+    }
+}
+
+impl GraphicsContext for SoftwareContext {
+    fn enable(&self, capability: u32) {
+        self.recorder.enable(capability)
+    }
+
+    fn disable(&self, capability: u32) {
+        self.recorder.disable(capability)
+    }
+
+    fn viewport(&self, x: i32, y: i32, width: i32, height: i32) {
+        self.recorder.viewport(x, y, width, height)
+    }
+
+    fn scissor(&self, x: i32, y: i32, width: i32, height: i32) {
+        self.recorder.scissor(x, y, width, height)
+    }
+
+    fn flush(&self) {
+        self.recorder.flush()
+    }
+
+    fn drawing_buffer_width(&self) -> i32 {
+        self.recorder.drawing_buffer_width()
+    }
+
+    fn drawing_buffer_height(&self) -> i32 {
+        self.recorder.drawing_buffer_height()
+    }
+
+    fn enable_extension(&self, name: &str) -> bool {
+        self.recorder.enable_extension(name)
+    }
+
+    fn get_parameter_i32(&self, parameter: u32) -> i32 {
+        self.recorder.get_parameter_i32(parameter)
+    }
+
+    fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+        self.recorder.clear_color(red, green, blue, alpha)
+    }
+
+    fn clear_buffer_u32(&self, buffer: u32, draw_buffer: i32, values: &[u32]) {
+        self.recorder.clear_buffer_u32(buffer, draw_buffer, values)
+    }
+
+    fn clear_buffer_f32(&self, buffer: u32, draw_buffer: i32, values: &[f32]) {
+        self.recorder.clear_buffer_f32(buffer, draw_buffer, values)
+    }
+
+    fn blend_equation_separate(&self, mode_rgb: u32, mode_alpha: u32) {
+        self.recorder.blend_equation_separate(mode_rgb, mode_alpha)
+    }
+
+    fn blend_func_separate(&self, source_rgb: u32, destination_rgb: u32, source_alpha: u32, destination_alpha: u32) {
+        self.recorder
+            .blend_func_separate(source_rgb, destination_rgb, source_alpha, destination_alpha)
+    }
+
+    fn blend_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+        self.recorder.blend_color(red, green, blue, alpha)
+    }
+
+    fn depth_func(&self, function: u32) {
+        self.recorder.depth_func(function)
+    }
+
+    fn depth_mask(&self, flag: bool) {
+        self.recorder.depth_mask(flag)
+    }
+
+    fn cull_face(&self, mode: u32) {
+        self.recorder.cull_face(mode)
+    }
+
+    fn color_mask(&self, red: bool, green: bool, blue: bool, alpha: bool) {
+        self.recorder.color_mask(red, green, blue, alpha)
+    }
+
+    fn polygon_offset(&self, factor: f32, units: f32) {
+        self.recorder.polygon_offset(factor, units)
+    }
+
+    fn stencil_func_separate(&self, face: u32, function: u32, reference: i32, mask: u32) {
+        self.recorder.stencil_func_separate(face, function, reference, mask)
+    }
+
+    fn stencil_op_separate(&self, face: u32, fail: u32, depth_fail: u32, pass: u32) {
+        self.recorder.stencil_op_separate(face, fail, depth_fail, pass)
+    }
+
+    fn stencil_mask(&self, mask: u32) {
+        self.recorder.stencil_mask(mask)
+    }
+
+    fn stencil_mask_separate(&self, face: u32, mask: u32) {
+        self.recorder.stencil_mask_separate(face, mask)
+    }
+
+    fn create_buffer(&self) -> Option<BufferHandle> {
+        self.recorder.create_buffer()
+    }
+
+    fn delete_buffer(&self, buffer: Option<BufferHandle>) {
+        self.recorder.delete_buffer(buffer)
+    }
+
+    fn bind_buffer(&self, target: u32, buffer: Option<BufferHandle>) {
+        self.recorder.bind_buffer(target, buffer)
+    }
+
+    fn bind_buffer_base(&self, target: u32, index: u32, buffer: Option<BufferHandle>) {
+        self.recorder.bind_buffer_base(target, index, buffer)
+    }
+
+    fn buffer_data_size(&self, target: u32, size: i32, usage: u32) {
+        self.recorder.buffer_data_size(target, size, usage)
+    }
+
+    fn buffer_data(&self, target: u32, data: &[u8], usage: u32) {
+        self.recorder.buffer_data(target, data, usage)
+    }
+
+    fn buffer_sub_data(&self, target: u32, offset: i32, data: &[u8]) {
+        self.recorder.buffer_sub_data(target, offset, data)
+    }
+
+    fn get_buffer_sub_data(&self, target: u32, offset: i32, destination: &mut [u8]) {
+        self.recorder.get_buffer_sub_data(target, offset, destination)
+    }
+
+    fn create_vertex_array(&self) -> Option<VertexArrayHandle> {
+        self.recorder.create_vertex_array()
+    }
+
+    fn delete_vertex_array(&self, vertex_array: Option<VertexArrayHandle>) {
+        self.recorder.delete_vertex_array(vertex_array)
+    }
+
+    fn bind_vertex_array(&self, vertex_array: Option<VertexArrayHandle>) {
+        self.recorder.bind_vertex_array(vertex_array)
+    }
+
+    fn enable_vertex_attrib_array(&self, index: u32) {
+        self.recorder.enable_vertex_attrib_array(index)
+    }
+
+    fn vertex_attrib_pointer(&self, index: u32, size: i32, data_type: u32, normalized: bool, stride: i32, offset: i32) {
+        self.recorder
+            .vertex_attrib_pointer(index, size, data_type, normalized, stride, offset)
+    }
+
+    fn vertex_attrib_divisor(&self, index: u32, divisor: u32) {
+        self.recorder.vertex_attrib_divisor(index, divisor)
+    }
+
+    fn create_shader(&self, shader_type: u32) -> Option<ShaderHandle> {
+        self.recorder.create_shader(shader_type)
+    }
+
+    fn delete_shader(&self, shader: Option<ShaderHandle>) {
+        self.recorder.delete_shader(shader)
+    }
+
+    fn shader_source(&self, shader: ShaderHandle, source: &str) {
+        self.recorder.shader_source(shader, source)
+    }
+
+    fn compile_shader(&self, shader: ShaderHandle) {
+        self.recorder.compile_shader(shader)
+    }
+
+    fn get_shader_compile_status(&self, shader: ShaderHandle) -> bool {
+        self.recorder.get_shader_compile_status(shader)
+    }
+
+    fn get_shader_info_log(&self, shader: ShaderHandle) -> Option<String> {
+        self.recorder.get_shader_info_log(shader)
+    }
+
+    fn create_program(&self) -> Option<ProgramHandle> {
+        self.recorder.create_program()
+    }
+
+    fn delete_program(&self, program: Option<ProgramHandle>) {
+        self.recorder.delete_program(program)
+    }
+
+    fn attach_shader(&self, program: ProgramHandle, shader: ShaderHandle) {
+        self.recorder.attach_shader(program, shader)
+    }
+
+    fn bind_attrib_location(&self, program: ProgramHandle, index: u32, name: &str) {
+        self.recorder.bind_attrib_location(program, index, name)
+    }
+
+    fn link_program(&self, program: ProgramHandle) {
+        self.recorder.link_program(program)
+    }
+
+    fn get_program_link_status(&self, program: ProgramHandle) -> bool {
+        self.recorder.get_program_link_status(program)
+    }
+
+    fn get_program_info_log(&self, program: ProgramHandle) -> Option<String> {
+        self.recorder.get_program_info_log(program)
+    }
+
+    fn get_program_parameter(&self, program: ProgramHandle, parameter: u32) -> i32 {
+        self.recorder.get_program_parameter(program, parameter)
+    }
+
+    fn use_program(&self, program: Option<ProgramHandle>) {
+        self.recorder.use_program(program)
+    }
+
+    fn get_active_attrib(&self, program: ProgramHandle, index: u32) -> Option<ActiveInfo> {
+        self.recorder.get_active_attrib(program, index)
+    }
+
+    fn get_active_uniform(&self, program: ProgramHandle, index: u32) -> Option<ActiveInfo> {
+        self.recorder.get_active_uniform(program, index)
+    }
+
+    fn get_attrib_location(&self, program: ProgramHandle, name: &str) -> i32 {
+        self.recorder.get_attrib_location(program, name)
+    }
+
+    fn get_uniform_location(&self, program: ProgramHandle, name: &str) -> Option<UniformLocation> {
+        self.recorder.get_uniform_location(program, name)
+    }
+
+    fn get_active_uniforms(&self, program: ProgramHandle, indices: &[u32], parameter: u32) -> Vec<i32> {
+        self.recorder.get_active_uniforms(program, indices, parameter)
+    }
+
+    fn get_active_uniform_block_name(&self, program: ProgramHandle, index: u32) -> Option<String> {
+        self.recorder.get_active_uniform_block_name(program, index)
+    }
+
+    fn get_active_uniform_block_parameter(&self, program: ProgramHandle, index: u32, parameter: u32) -> i32 {
+        self.recorder.get_active_uniform_block_parameter(program, index, parameter)
+    }
+
+    fn uniform_block_binding(&self, program: ProgramHandle, block_index: u32, binding: u32) {
+        self.recorder.uniform_block_binding(program, block_index, binding)
+    }
+
+    fn uniform1f(&self, location: Option<UniformLocation>, value: f32) {
+        self.recorder.uniform1f(location, value)
+    }
+
+    fn uniform1fv(&self, location: Option<UniformLocation>, values: &[f32]) {
+        self.recorder.uniform1fv(location, values)
+    }
+
+    fn uniform2fv(&self, location: Option<UniformLocation>, values: &[f32]) {
+        self.recorder.uniform2fv(location, values)
+    }
+
+    fn uniform3fv(&self, location: Option<UniformLocation>, values: &[f32]) {
+        self.recorder.uniform3fv(location, values)
+    }
+
+    fn uniform4fv(&self, location: Option<UniformLocation>, values: &[f32]) {
+        self.recorder.uniform4fv(location, values)
+    }
+
+    fn uniform1i(&self, location: Option<UniformLocation>, value: i32) {
+        self.recorder.uniform1i(location, value)
+    }
+
+    fn uniform1iv(&self, location: Option<UniformLocation>, values: &[i32]) {
+        self.recorder.uniform1iv(location, values)
+    }
+
+    fn uniform2iv(&self, location: Option<UniformLocation>, values: &[i32]) {
+        self.recorder.uniform2iv(location, values)
+    }
+
+    fn uniform3iv(&self, location: Option<UniformLocation>, values: &[i32]) {
+        self.recorder.uniform3iv(location, values)
+    }
+
+    fn uniform4iv(&self, location: Option<UniformLocation>, values: &[i32]) {
+        self.recorder.uniform4iv(location, values)
+    }
+
+    fn uniform1ui(&self, location: Option<UniformLocation>, value: u32) {
+        self.recorder.uniform1ui(location, value)
+    }
+
+    fn uniform1uiv(&self, location: Option<UniformLocation>, values: &[u32]) {
+        self.recorder.uniform1uiv(location, values)
+    }
+
+    fn uniform2uiv(&self, location: Option<UniformLocation>, values: &[u32]) {
+        self.recorder.uniform2uiv(location, values)
+    }
+
+    fn uniform3uiv(&self, location: Option<UniformLocation>, values: &[u32]) {
+        self.recorder.uniform3uiv(location, values)
+    }
+
+    fn uniform4uiv(&self, location: Option<UniformLocation>, values: &[u32]) {
+        self.recorder.uniform4uiv(location, values)
+    }
+
+    fn uniform_matrix2fv(&self, location: Option<UniformLocation>, transpose: bool, values: &[f32]) {
+        self.recorder.uniform_matrix2fv(location, transpose, values)
+    }
+
+    fn uniform_matrix3fv(&self, location: Option<UniformLocation>, transpose: bool, values: &[f32]) {
+        self.recorder.uniform_matrix3fv(location, transpose, values)
+    }
+
+    fn uniform_matrix4fv(&self, location: Option<UniformLocation>, transpose: bool, values: &[f32]) {
+        self.recorder.uniform_matrix4fv(location, transpose, values)
+    }
+
+    fn create_texture(&self) -> Option<TextureHandle> {
+        self.recorder.create_texture()
+    }
+
+    fn delete_texture(&self, texture: Option<TextureHandle>) {
+        self.recorder.delete_texture(texture)
+    }
+
+    fn active_texture(&self, unit: u32) {
+        self.recorder.active_texture(unit)
+    }
+
+    fn bind_texture(&self, target: u32, texture: Option<TextureHandle>) {
+        self.recorder.bind_texture(target, texture)
+    }
+
+    fn tex_parameteri(&self, target: u32, parameter: u32, value: i32) {
+        self.recorder.tex_parameteri(target, parameter, value)
+    }
+
+    fn tex_storage_2d(&self, target: u32, levels: i32, internal_format: u32, width: i32, height: i32) {
+        self.recorder.tex_storage_2d(target, levels, internal_format, width, height)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn tex_image_2d(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        data_type: u32,
+        pixels: Option<&[u8]>,
+    ) -> Result<(), BackendError> {
+        self.recorder
+            .tex_image_2d(target, level, internal_format, width, height, format, data_type, pixels)
+    }
+
+    #[cfg(feature = "web")]
+    fn tex_image_2d_with_html_image_element(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        format: u32,
+        data_type: u32,
+        image: &HtmlImageElement,
+    ) -> Result<(), BackendError> {
+        self.recorder
+            .tex_image_2d_with_html_image_element(target, level, internal_format, format, data_type, image)
+    }
+
+    fn create_sampler(&self) -> Option<SamplerHandle> {
+        self.recorder.create_sampler()
+    }
+
+    fn delete_sampler(&self, sampler: Option<SamplerHandle>) {
+        self.recorder.delete_sampler(sampler)
+    }
+
+    fn bind_sampler(&self, unit: u32, sampler: Option<SamplerHandle>) {
+        self.recorder.bind_sampler(unit, sampler)
+    }
+
+    fn sampler_parameteri(&self, sampler: SamplerHandle, parameter: u32, value: i32) {
+        self.recorder.sampler_parameteri(sampler, parameter, value)
+    }
+
+    fn create_framebuffer(&self) -> Option<FramebufferHandle> {
+        self.recorder.create_framebuffer()
+    }
+
+    fn delete_framebuffer(&self, framebuffer: Option<FramebufferHandle>) {
+        self.recorder.delete_framebuffer(framebuffer)
+    }
+
+    fn bind_framebuffer(&self, target: u32, framebuffer: Option<FramebufferHandle>) {
+        self.recorder.bind_framebuffer(target, framebuffer)
+    }
+
+    fn framebuffer_texture_2d(&self, target: u32, attachment: u32, texture_target: u32, texture: Option<TextureHandle>, level: i32) {
+        self.recorder
+            .framebuffer_texture_2d(target, attachment, texture_target, texture, level)
+    }
+
+    fn framebuffer_renderbuffer(&self, target: u32, attachment: u32, renderbuffer_target: u32, renderbuffer: Option<RenderbufferHandle>) {
+        self.recorder
+            .framebuffer_renderbuffer(target, attachment, renderbuffer_target, renderbuffer)
+    }
+
+    fn check_framebuffer_status(&self, target: u32) -> u32 {
+        self.recorder.check_framebuffer_status(target)
+    }
+
+    fn draw_buffers(&self, buffers: &[u32]) {
+        self.recorder.draw_buffers(buffers)
+    }
+
+    fn read_buffer(&self, source: u32) {
+        self.recorder.read_buffer(source)
+    }
+
+    fn create_renderbuffer(&self) -> Option<RenderbufferHandle> {
+        self.recorder.create_renderbuffer()
+    }
+
+    fn delete_renderbuffer(&self, renderbuffer: Option<RenderbufferHandle>) {
+        self.recorder.delete_renderbuffer(renderbuffer)
+    }
+
+    fn bind_renderbuffer(&self, target: u32, renderbuffer: Option<RenderbufferHandle>) {
+        self.recorder.bind_renderbuffer(target, renderbuffer)
+    }
+
+    fn renderbuffer_storage(&self, target: u32, internal_format: u32, width: i32, height: i32) {
+        self.recorder.renderbuffer_storage(target, internal_format, width, height)
+    }
+
+    fn fence_sync(&self, condition: u32, flags: u32) -> Option<SyncHandle> {
+        self.recorder.fence_sync(condition, flags)
+    }
+
+    fn client_wait_sync(&self, sync: SyncHandle, flags: u32, timeout: u32) -> u32 {
+        self.recorder.client_wait_sync(sync, flags, timeout)
+    }
+
+    fn delete_sync(&self, sync: Option<SyncHandle>) {
+        self.recorder.delete_sync(sync)
+    }
+
+    fn clear(&self, mask: u32) {
+        self.recorder.clear(mask);
+
+        let state = self.recorder.state();
+
+        if state.bound_framebuffer.is_some() {
+            return;
+        }
+
+        let mut framebuffer = self.framebuffer.borrow_mut();
+        let [mut x0, mut y0, mut x1, mut y1] = [0, 0, framebuffer.width as i32, framebuffer.height as i32];
+
+        if state.enabled.contains(&GL::SCISSOR_TEST) {
+            let [x, y, width, height] = state.pipeline.scissor;
+            [x0, y0, x1, y1] = [x0.max(x), y0.max(y), x1.min(x + width), y1.min(y + height)];
+        }
+
+        let clear_color = state
+            .pipeline
+            .clear_color
+            .map(|component| (component.clamp(0.0, 1.0) * 255.0).round() as u8);
+
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let index = y as usize * framebuffer.width + x as usize;
+
+                if mask & GL::COLOR_BUFFER_BIT != 0 {
+                    framebuffer.write_color(index, clear_color, state.pipeline.color_mask);
+                }
+
+                if mask & GL::DEPTH_BUFFER_BIT != 0 && state.pipeline.depth_mask {
+                    framebuffer.depth[index] = 1.0;
+                }
+            }
+        }
+    }
+
+    fn draw_arrays(&self, mode: u32, first: i32, count: i32) {
+        self.recorder.draw_arrays(mode, first, count);
+
+        let indices: Vec<u32> = (first.max(0) as u32..(first + count).max(0) as u32).collect();
+        self.draw(mode, &indices, 1);
+    }
+
+    fn draw_elements(&self, mode: u32, count: i32, index_type: u32, offset: i32) {
+        self.recorder.draw_elements(mode, count, index_type, offset);
+        self.draw_indexed(mode, count, index_type, offset, 1);
+    }
+
+    fn draw_elements_instanced(&self, mode: u32, count: i32, index_type: u32, offset: i32, instance_count: i32) {
+        self.recorder
+            .draw_elements_instanced(mode, count, index_type, offset, instance_count);
+        self.draw_indexed(mode, count, index_type, offset, instance_count);
+    }
+
+    fn read_pixels(
+        &self,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        data_type: u32,
+        destination: &mut [u8],
+    ) -> Result<(), BackendError> {
+        self.recorder.read_pixels(x, y, width, height, format, data_type, destination)?;
+        self.read_framebuffer(x, y, width, height, format, data_type, destination)
+    }
+
+    fn read_pixels_to_pixel_buffer(
+        &self,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        data_type: u32,
+        offset: i32,
+    ) -> Result<(), BackendError> {
+        self.recorder
+            .read_pixels_to_pixel_buffer(x, y, width, height, format, data_type, offset)?;
+
+        let component_size = if data_type == GL::FLOAT { 4 } else { 1 };
+        let mut pixels = vec![0; width.max(0) as usize * height.max(0) as usize * 4 * component_size];
+        self.read_framebuffer(x, y, width, height, format, data_type, &mut pixels)?;
+
+        let mut state = self.recorder.state_mut();
+        let Some(buffer) = state.bound_buffer_mut(GL::PIXEL_PACK_BUFFER) else {
+            return Err(BackendError(String::from("No pixel pack buffer bound")));
+        };
+
+        let offset = offset.max(0) as usize;
+        let Some(destination) = buffer.data.get_mut(offset..offset + pixels.len()) else {
+            return Err(BackendError(String::from("Pixel pack buffer too small for the pixels")));
+        };
+
+        destination.copy_from_slice(&pixels);
+        Ok(())
+    }
+}
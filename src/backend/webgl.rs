@@ -0,0 +1,741 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use web_sys::js_sys::{ArrayBuffer, Float32Array, Object, Uint8Array, Uint16Array, Uint32Array};
+use web_sys::wasm_bindgen::JsValue;
+use web_sys::{
+    HtmlImageElement, WebGl2RenderingContext, WebGlBuffer, WebGlFramebuffer, WebGlProgram, WebGlRenderbuffer, WebGlSampler, WebGlShader,
+    WebGlSync, WebGlTexture, WebGlUniformLocation, WebGlVertexArrayObject,
+};
+
+use super::{
+    ActiveInfo, BackendError, BufferHandle, FramebufferHandle, GraphicsContext, ProgramHandle, RenderbufferHandle, SamplerHandle,
+    ShaderHandle, SyncHandle, TextureHandle, UniformLocation, VertexArrayHandle,
+};
+
+/// WebGL objects by handle. Handles start at `1` and are never reused.
+struct ObjectTable<T> {
+    next_id: u32,
+    objects: HashMap<u32, T>,
+}
+
+impl<T: Clone> ObjectTable<T> {
+    fn insert(&mut self, object: T) -> u32 {
+        self.next_id += 1;
+        self.objects.insert(self.next_id, object);
+        self.next_id
+    }
+
+    fn get(&self, id: u32) -> Option<T> {
+        self.objects.get(&id).cloned()
+    }
+
+    fn remove(&mut self, id: u32) -> Option<T> {
+        self.objects.remove(&id)
+    }
+}
+
+impl<T> Default for ObjectTable<T> {
+    fn default() -> Self {
+        ObjectTable {
+            next_id: 0,
+            objects: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Objects {
+    buffers:           ObjectTable<WebGlBuffer>,
+    textures:          ObjectTable<WebGlTexture>,
+    samplers:          ObjectTable<WebGlSampler>,
+    shaders:           ObjectTable<WebGlShader>,
+    programs:          ObjectTable<WebGlProgram>,
+    vertex_arrays:     ObjectTable<WebGlVertexArrayObject>,
+    framebuffers:      ObjectTable<WebGlFramebuffer>,
+    renderbuffers:     ObjectTable<WebGlRenderbuffer>,
+    syncs:             ObjectTable<WebGlSync>,
+    uniform_locations: ObjectTable<WebGlUniformLocation>,
+
+    /// Locations queried for each program, released with it.
+    program_uniform_locations: HashMap<u32, Vec<u32>>,
+}
+
+/// [`GraphicsContext`] backed by the browser `WebGL2RenderingContext`.
+pub struct WebGlContext {
+    gl:      WebGl2RenderingContext,
+    objects: RefCell<Objects>,
+}
+
+impl WebGlContext {
+    pub fn new(gl: WebGl2RenderingContext) -> WebGlContext {
+        WebGlContext {
+            gl,
+            objects: RefCell::new(Objects::default()),
+        }
+    }
+
+    /// The underlying context, for calls the [`GraphicsContext`] trait does not cover.
+    pub fn raw(&self) -> &WebGl2RenderingContext {
+        &self.gl
+    }
+
+    fn uniform_location(&self, location: Option<UniformLocation>) -> Option<WebGlUniformLocation> {
+        location.and_then(|location| self.objects.borrow().uniform_locations.get(location.0))
+    }
+
+    fn program(&self, program: ProgramHandle) -> Option<WebGlProgram> {
+        self.objects.borrow().programs.get(program.0)
+    }
+
+    fn shader(&self, shader: ShaderHandle) -> Option<WebGlShader> {
+        self.objects.borrow().shaders.get(shader.0)
+    }
+}
+
+/// View matching the type read by `readPixels`, which WebGL requires.
+fn pixel_array_view(buffer: &ArrayBuffer, data_type: u32) -> Object {
+    match data_type {
+        WebGl2RenderingContext::FLOAT => Float32Array::new(buffer).into(),
+        WebGl2RenderingContext::UNSIGNED_INT | WebGl2RenderingContext::INT => Uint32Array::new(buffer).into(),
+        WebGl2RenderingContext::UNSIGNED_SHORT
+        | WebGl2RenderingContext::UNSIGNED_SHORT_5_6_5
+        | WebGl2RenderingContext::UNSIGNED_SHORT_4_4_4_4
+        | WebGl2RenderingContext::UNSIGNED_SHORT_5_5_5_1 => Uint16Array::new(buffer).into(),
+        _ => Uint8Array::new(buffer).into(),
+    }
+}
+
+fn backend_error(value: JsValue) -> BackendError {
+    BackendError(value.as_string().unwrap_or_else(|| format!("{value:?}")))
+}
+
+impl GraphicsContext for WebGlContext {
+    fn enable(&self, capability: u32) {
+        self.gl.enable(capability);
+    }
+
+    fn disable(&self, capability: u32) {
+        self.gl.disable(capability);
+    }
+
+    fn viewport(&self, x: i32, y: i32, width: i32, height: i32) {
+        self.gl.viewport(x, y, width, height);
+    }
+
+    fn scissor(&self, x: i32, y: i32, width: i32, height: i32) {
+        self.gl.scissor(x, y, width, height);
+    }
+
+    fn flush(&self) {
+        self.gl.flush();
+    }
+
+    fn drawing_buffer_width(&self) -> i32 {
+        self.gl.drawing_buffer_width()
+    }
+
+    fn drawing_buffer_height(&self) -> i32 {
+        self.gl.drawing_buffer_height()
+    }
+
+    fn enable_extension(&self, name: &str) -> bool {
+        self.gl.get_extension(name).ok().flatten().is_some()
+    }
+
+    fn get_parameter_i32(&self, parameter: u32) -> i32 {
+        self.gl
+            .get_parameter(parameter)
+            .ok()
+            .and_then(|value| value.as_f64())
+            .map_or(0, |value| value as i32)
+    }
+
+    fn clear_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+        self.gl.clear_color(red, green, blue, alpha);
+    }
+
+    fn clear(&self, mask: u32) {
+        self.gl.clear(mask);
+    }
+
+    fn clear_buffer_u32(&self, buffer: u32, draw_buffer: i32, values: &[u32]) {
+        self.gl.clear_bufferuiv_with_u32_array(buffer, draw_buffer, values);
+    }
+
+    fn clear_buffer_f32(&self, buffer: u32, draw_buffer: i32, values: &[f32]) {
+        self.gl.clear_bufferfv_with_f32_array(buffer, draw_buffer, values);
+    }
+
+    fn blend_equation_separate(&self, mode_rgb: u32, mode_alpha: u32) {
+        self.gl.blend_equation_separate(mode_rgb, mode_alpha);
+    }
+
+    fn blend_func_separate(&self, source_rgb: u32, destination_rgb: u32, source_alpha: u32, destination_alpha: u32) {
+        self.gl
+            .blend_func_separate(source_rgb, destination_rgb, source_alpha, destination_alpha);
+    }
+
+    fn blend_color(&self, red: f32, green: f32, blue: f32, alpha: f32) {
+        self.gl.blend_color(red, green, blue, alpha);
+    }
+
+    fn depth_func(&self, function: u32) {
+        self.gl.depth_func(function);
+    }
+
+    fn depth_mask(&self, flag: bool) {
+        self.gl.depth_mask(flag);
+    }
+
+    fn cull_face(&self, mode: u32) {
+        self.gl.cull_face(mode);
+    }
+
+    fn color_mask(&self, red: bool, green: bool, blue: bool, alpha: bool) {
+        self.gl.color_mask(red, green, blue, alpha);
+    }
+
+    fn polygon_offset(&self, factor: f32, units: f32) {
+        self.gl.polygon_offset(factor, units);
+    }
+
+    fn stencil_func_separate(&self, face: u32, function: u32, reference: i32, mask: u32) {
+        self.gl.stencil_func_separate(face, function, reference, mask);
+    }
+
+    fn stencil_op_separate(&self, face: u32, fail: u32, depth_fail: u32, pass: u32) {
+        self.gl.stencil_op_separate(face, fail, depth_fail, pass);
+    }
+
+    fn stencil_mask(&self, mask: u32) {
+        self.gl.stencil_mask(mask);
+    }
+
+    fn stencil_mask_separate(&self, face: u32, mask: u32) {
+        self.gl.stencil_mask_separate(face, mask);
+    }
+
+    fn create_buffer(&self) -> Option<BufferHandle> {
+        let buffer = self.gl.create_buffer()?;
+        Some(BufferHandle(self.objects.borrow_mut().buffers.insert(buffer)))
+    }
+
+    fn delete_buffer(&self, buffer: Option<BufferHandle>) {
+        let buffer = buffer.and_then(|buffer| self.objects.borrow_mut().buffers.remove(buffer.0));
+        self.gl.delete_buffer(buffer.as_ref());
+    }
+
+    fn bind_buffer(&self, target: u32, buffer: Option<BufferHandle>) {
+        let buffer = buffer.and_then(|buffer| self.objects.borrow().buffers.get(buffer.0));
+        self.gl.bind_buffer(target, buffer.as_ref());
+    }
+
+    fn bind_buffer_base(&self, target: u32, index: u32, buffer: Option<BufferHandle>) {
+        let buffer = buffer.and_then(|buffer| self.objects.borrow().buffers.get(buffer.0));
+        self.gl.bind_buffer_base(target, index, buffer.as_ref());
+    }
+
+    fn buffer_data_size(&self, target: u32, size: i32, usage: u32) {
+        self.gl.buffer_data_with_i32(target, size, usage);
+    }
+
+    fn buffer_data(&self, target: u32, data: &[u8], usage: u32) {
+        self.gl.buffer_data_with_u8_array(target, data, usage);
+    }
+
+    fn buffer_sub_data(&self, target: u32, offset: i32, data: &[u8]) {
+        self.gl.buffer_sub_data_with_i32_and_u8_array(target, offset, data);
+    }
+
+    fn get_buffer_sub_data(&self, target: u32, offset: i32, destination: &mut [u8]) {
+        self.gl.get_buffer_sub_data_with_i32_and_u8_array(target, offset, destination);
+    }
+
+    fn create_vertex_array(&self) -> Option<VertexArrayHandle> {
+        let vertex_array = self.gl.create_vertex_array()?;
+        Some(VertexArrayHandle(self.objects.borrow_mut().vertex_arrays.insert(vertex_array)))
+    }
+
+    fn delete_vertex_array(&self, vertex_array: Option<VertexArrayHandle>) {
+        let vertex_array = vertex_array.and_then(|vertex_array| self.objects.borrow_mut().vertex_arrays.remove(vertex_array.0));
+        self.gl.delete_vertex_array(vertex_array.as_ref());
+    }
+
+    fn bind_vertex_array(&self, vertex_array: Option<VertexArrayHandle>) {
+        let vertex_array = vertex_array.and_then(|vertex_array| self.objects.borrow().vertex_arrays.get(vertex_array.0));
+        self.gl.bind_vertex_array(vertex_array.as_ref());
+    }
+
+    fn enable_vertex_attrib_array(&self, index: u32) {
+        self.gl.enable_vertex_attrib_array(index);
+    }
+
+    fn vertex_attrib_pointer(&self, index: u32, size: i32, data_type: u32, normalized: bool, stride: i32, offset: i32) {
+        self.gl
+            .vertex_attrib_pointer_with_i32(index, size, data_type, normalized, stride, offset);
+    }
+
+    fn vertex_attrib_divisor(&self, index: u32, divisor: u32) {
+        self.gl.vertex_attrib_divisor(index, divisor);
+    }
+
+    fn draw_arrays(&self, mode: u32, first: i32, count: i32) {
+        self.gl.draw_arrays(mode, first, count);
+    }
+
+    fn draw_elements(&self, mode: u32, count: i32, index_type: u32, offset: i32) {
+        self.gl.draw_elements_with_i32(mode, count, index_type, offset);
+    }
+
+    fn draw_elements_instanced(&self, mode: u32, count: i32, index_type: u32, offset: i32, instance_count: i32) {
+        self.gl
+            .draw_elements_instanced_with_i32(mode, count, index_type, offset, instance_count);
+    }
+
+    fn create_shader(&self, shader_type: u32) -> Option<ShaderHandle> {
+        let shader = self.gl.create_shader(shader_type)?;
+        Some(ShaderHandle(self.objects.borrow_mut().shaders.insert(shader)))
+    }
+
+    fn delete_shader(&self, shader: Option<ShaderHandle>) {
+        let shader = shader.and_then(|shader| self.objects.borrow_mut().shaders.remove(shader.0));
+        self.gl.delete_shader(shader.as_ref());
+    }
+
+    fn shader_source(&self, shader: ShaderHandle, source: &str) {
+        if let Some(shader) = self.shader(shader) {
+            self.gl.shader_source(&shader, source);
+        }
+    }
+
+    fn compile_shader(&self, shader: ShaderHandle) {
+        if let Some(shader) = self.shader(shader) {
+            self.gl.compile_shader(&shader);
+        }
+    }
+
+    fn get_shader_compile_status(&self, shader: ShaderHandle) -> bool {
+        self.shader(shader).is_some_and(|shader| {
+            self.gl
+                .get_shader_parameter(&shader, WebGl2RenderingContext::COMPILE_STATUS)
+                .as_bool()
+                .unwrap_or(false)
+        })
+    }
+
+    fn get_shader_info_log(&self, shader: ShaderHandle) -> Option<String> {
+        self.gl.get_shader_info_log(&self.shader(shader)?)
+    }
+
+    fn create_program(&self) -> Option<ProgramHandle> {
+        let program = self.gl.create_program()?;
+        Some(ProgramHandle(self.objects.borrow_mut().programs.insert(program)))
+    }
+
+    fn delete_program(&self, program: Option<ProgramHandle>) {
+        let program = program.and_then(|program| {
+            let mut objects = self.objects.borrow_mut();
+
+            for location in objects.program_uniform_locations.remove(&program.0).unwrap_or_default() {
+                objects.uniform_locations.remove(location);
+            }
+
+            objects.programs.remove(program.0)
+        });
+
+        self.gl.delete_program(program.as_ref());
+    }
+
+    fn attach_shader(&self, program: ProgramHandle, shader: ShaderHandle) {
+        if let (Some(program), Some(shader)) = (self.program(program), self.shader(shader)) {
+            self.gl.attach_shader(&program, &shader);
+        }
+    }
+
+    fn bind_attrib_location(&self, program: ProgramHandle, index: u32, name: &str) {
+        if let Some(program) = self.program(program) {
+            self.gl.bind_attrib_location(&program, index, name);
+        }
+    }
+
+    fn link_program(&self, program: ProgramHandle) {
+        if let Some(program) = self.program(program) {
+            self.gl.link_program(&program);
+        }
+    }
+
+    fn get_program_link_status(&self, program: ProgramHandle) -> bool {
+        self.program(program).is_some_and(|program| {
+            self.gl
+                .get_program_parameter(&program, WebGl2RenderingContext::LINK_STATUS)
+                .as_bool()
+                .unwrap_or(false)
+        })
+    }
+
+    fn get_program_info_log(&self, program: ProgramHandle) -> Option<String> {
+        self.gl.get_program_info_log(&self.program(program)?)
+    }
+
+    fn get_program_parameter(&self, program: ProgramHandle, parameter: u32) -> i32 {
+        let Some(program) = self.program(program) else {
+            return 0;
+        };
+
+        let value = self.gl.get_program_parameter(&program, parameter);
+        value
+            .as_f64()
+            .map(|value| value as i32)
+            .or_else(|| value.as_bool().map(i32::from))
+            .unwrap_or(0)
+    }
+
+    fn use_program(&self, program: Option<ProgramHandle>) {
+        let program = program.and_then(|program| self.program(program));
+        self.gl.use_program(program.as_ref());
+    }
+
+    fn get_active_attrib(&self, program: ProgramHandle, index: u32) -> Option<ActiveInfo> {
+        let info = self.gl.get_active_attrib(&self.program(program)?, index)?;
+
+        Some(ActiveInfo {
+            name: info.name(),
+            size: info.size(),
+            kind: info.type_(),
+        })
+    }
+
+    fn get_active_uniform(&self, program: ProgramHandle, index: u32) -> Option<ActiveInfo> {
+        let info = self.gl.get_active_uniform(&self.program(program)?, index)?;
+
+        Some(ActiveInfo {
+            name: info.name(),
+            size: info.size(),
+            kind: info.type_(),
+        })
+    }
+
+    fn get_attrib_location(&self, program: ProgramHandle, name: &str) -> i32 {
+        self.program(program)
+            .map_or(-1, |program| self.gl.get_attrib_location(&program, name))
+    }
+
+    fn get_uniform_location(&self, program: ProgramHandle, name: &str) -> Option<UniformLocation> {
+        let location = self.gl.get_uniform_location(&self.program(program)?, name)?;
+
+        let mut objects = self.objects.borrow_mut();
+        let id = objects.uniform_locations.insert(location);
+        objects.program_uniform_locations.entry(program.0).or_default().push(id);
+
+        Some(UniformLocation(id))
+    }
+
+    fn get_active_uniforms(&self, program: ProgramHandle, indices: &[u32], parameter: u32) -> Vec<i32> {
+        let Some(program) = self.program(program) else {
+            return vec![0; indices.len()];
+        };
+
+        let values = self.gl.get_active_uniforms(&program, &Uint32Array::from(indices), parameter);
+        web_sys::js_sys::Array::from(&values)
+            .iter()
+            .map(|value| {
+                value
+                    .as_f64()
+                    .map(|value| value as i32)
+                    .or_else(|| value.as_bool().map(i32::from))
+                    .unwrap_or(0)
+            })
+            .collect()
+    }
+
+    fn get_active_uniform_block_name(&self, program: ProgramHandle, index: u32) -> Option<String> {
+        self.gl.get_active_uniform_block_name(&self.program(program)?, index)
+    }
+
+    fn get_active_uniform_block_parameter(&self, program: ProgramHandle, index: u32, parameter: u32) -> i32 {
+        self.program(program)
+            .and_then(|program| self.gl.get_active_uniform_block_parameter(&program, index, parameter).ok())
+            .and_then(|value| value.as_f64())
+            .map_or(0, |value| value as i32)
+    }
+
+    fn uniform_block_binding(&self, program: ProgramHandle, block_index: u32, binding: u32) {
+        if let Some(program) = self.program(program) {
+            self.gl.uniform_block_binding(&program, block_index, binding);
+        }
+    }
+
+    fn uniform1f(&self, location: Option<UniformLocation>, value: f32) {
+        self.gl.uniform1f(self.uniform_location(location).as_ref(), value);
+    }
+
+    fn uniform1fv(&self, location: Option<UniformLocation>, values: &[f32]) {
+        self.gl.uniform1fv_with_f32_array(self.uniform_location(location).as_ref(), values);
+    }
+
+    fn uniform2fv(&self, location: Option<UniformLocation>, values: &[f32]) {
+        self.gl.uniform2fv_with_f32_array(self.uniform_location(location).as_ref(), values);
+    }
+
+    fn uniform3fv(&self, location: Option<UniformLocation>, values: &[f32]) {
+        self.gl.uniform3fv_with_f32_array(self.uniform_location(location).as_ref(), values);
+    }
+
+    fn uniform4fv(&self, location: Option<UniformLocation>, values: &[f32]) {
+        self.gl.uniform4fv_with_f32_array(self.uniform_location(location).as_ref(), values);
+    }
+
+    fn uniform1i(&self, location: Option<UniformLocation>, value: i32) {
+        self.gl.uniform1i(self.uniform_location(location).as_ref(), value);
+    }
+
+    fn uniform1iv(&self, location: Option<UniformLocation>, values: &[i32]) {
+        self.gl.uniform1iv_with_i32_array(self.uniform_location(location).as_ref(), values);
+    }
+
+    fn uniform2iv(&self, location: Option<UniformLocation>, values: &[i32]) {
+        self.gl.uniform2iv_with_i32_array(self.uniform_location(location).as_ref(), values);
+    }
+
+    fn uniform3iv(&self, location: Option<UniformLocation>, values: &[i32]) {
+        self.gl.uniform3iv_with_i32_array(self.uniform_location(location).as_ref(), values);
+    }
+
+    fn uniform4iv(&self, location: Option<UniformLocation>, values: &[i32]) {
+        self.gl.uniform4iv_with_i32_array(self.uniform_location(location).as_ref(), values);
+    }
+
+    fn uniform1ui(&self, location: Option<UniformLocation>, value: u32) {
+        self.gl.uniform1ui(self.uniform_location(location).as_ref(), value);
+    }
+
+    fn uniform1uiv(&self, location: Option<UniformLocation>, values: &[u32]) {
+        self.gl.uniform1uiv_with_u32_array(self.uniform_location(location).as_ref(), values);
+    }
+
+    fn uniform2uiv(&self, location: Option<UniformLocation>, values: &[u32]) {
+        self.gl.uniform2uiv_with_u32_array(self.uniform_location(location).as_ref(), values);
+    }
+
+    fn uniform3uiv(&self, location: Option<UniformLocation>, values: &[u32]) {
+        self.gl.uniform3uiv_with_u32_array(self.uniform_location(location).as_ref(), values);
+    }
+
+    fn uniform4uiv(&self, location: Option<UniformLocation>, values: &[u32]) {
+        self.gl.uniform4uiv_with_u32_array(self.uniform_location(location).as_ref(), values);
+    }
+
+    fn uniform_matrix2fv(&self, location: Option<UniformLocation>, transpose: bool, values: &[f32]) {
+        self.gl
+            .uniform_matrix2fv_with_f32_array(self.uniform_location(location).as_ref(), transpose, values);
+    }
+
+    fn uniform_matrix3fv(&self, location: Option<UniformLocation>, transpose: bool, values: &[f32]) {
+        self.gl
+            .uniform_matrix3fv_with_f32_array(self.uniform_location(location).as_ref(), transpose, values);
+    }
+
+    fn uniform_matrix4fv(&self, location: Option<UniformLocation>, transpose: bool, values: &[f32]) {
+        self.gl
+            .uniform_matrix4fv_with_f32_array(self.uniform_location(location).as_ref(), transpose, values);
+    }
+
+    fn create_texture(&self) -> Option<TextureHandle> {
+        let texture = self.gl.create_texture()?;
+        Some(TextureHandle(self.objects.borrow_mut().textures.insert(texture)))
+    }
+
+    fn delete_texture(&self, texture: Option<TextureHandle>) {
+        let texture = texture.and_then(|texture| self.objects.borrow_mut().textures.remove(texture.0));
+        self.gl.delete_texture(texture.as_ref());
+    }
+
+    fn active_texture(&self, unit: u32) {
+        self.gl.active_texture(unit);
+    }
+
+    fn bind_texture(&self, target: u32, texture: Option<TextureHandle>) {
+        let texture = texture.and_then(|texture| self.objects.borrow().textures.get(texture.0));
+        self.gl.bind_texture(target, texture.as_ref());
+    }
+
+    fn tex_parameteri(&self, target: u32, parameter: u32, value: i32) {
+        self.gl.tex_parameteri(target, parameter, value);
+    }
+
+    fn tex_storage_2d(&self, target: u32, levels: i32, internal_format: u32, width: i32, height: i32) {
+        self.gl.tex_storage_2d(target, levels, internal_format, width, height);
+    }
+
+    fn tex_image_2d(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        data_type: u32,
+        pixels: Option<&[u8]>,
+    ) -> Result<(), BackendError> {
+        self.gl
+            .tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+                target,
+                level,
+                internal_format,
+                width,
+                height,
+                0,
+                format,
+                data_type,
+                pixels,
+            )
+            .map_err(backend_error)
+    }
+
+    fn tex_image_2d_with_html_image_element(
+        &self,
+        target: u32,
+        level: i32,
+        internal_format: i32,
+        format: u32,
+        data_type: u32,
+        image: &HtmlImageElement,
+    ) -> Result<(), BackendError> {
+        self.gl
+            .tex_image_2d_with_u32_and_u32_and_html_image_element(target, level, internal_format, format, data_type, image)
+            .map_err(backend_error)
+    }
+
+    fn create_sampler(&self) -> Option<SamplerHandle> {
+        let sampler = self.gl.create_sampler()?;
+        Some(SamplerHandle(self.objects.borrow_mut().samplers.insert(sampler)))
+    }
+
+    fn delete_sampler(&self, sampler: Option<SamplerHandle>) {
+        let sampler = sampler.and_then(|sampler| self.objects.borrow_mut().samplers.remove(sampler.0));
+        self.gl.delete_sampler(sampler.as_ref());
+    }
+
+    fn bind_sampler(&self, unit: u32, sampler: Option<SamplerHandle>) {
+        let sampler = sampler.and_then(|sampler| self.objects.borrow().samplers.get(sampler.0));
+        self.gl.bind_sampler(unit, sampler.as_ref());
+    }
+
+    fn sampler_parameteri(&self, sampler: SamplerHandle, parameter: u32, value: i32) {
+        if let Some(sampler) = self.objects.borrow().samplers.get(sampler.0) {
+            self.gl.sampler_parameteri(&sampler, parameter, value);
+        }
+    }
+
+    fn create_framebuffer(&self) -> Option<FramebufferHandle> {
+        let framebuffer = self.gl.create_framebuffer()?;
+        Some(FramebufferHandle(self.objects.borrow_mut().framebuffers.insert(framebuffer)))
+    }
+
+    fn delete_framebuffer(&self, framebuffer: Option<FramebufferHandle>) {
+        let framebuffer = framebuffer.and_then(|framebuffer| self.objects.borrow_mut().framebuffers.remove(framebuffer.0));
+        self.gl.delete_framebuffer(framebuffer.as_ref());
+    }
+
+    fn bind_framebuffer(&self, target: u32, framebuffer: Option<FramebufferHandle>) {
+        let framebuffer = framebuffer.and_then(|framebuffer| self.objects.borrow().framebuffers.get(framebuffer.0));
+        self.gl.bind_framebuffer(target, framebuffer.as_ref());
+    }
+
+    fn framebuffer_texture_2d(&self, target: u32, attachment: u32, texture_target: u32, texture: Option<TextureHandle>, level: i32) {
+        let texture = texture.and_then(|texture| self.objects.borrow().textures.get(texture.0));
+        self.gl
+            .framebuffer_texture_2d(target, attachment, texture_target, texture.as_ref(), level);
+    }
+
+    fn framebuffer_renderbuffer(&self, target: u32, attachment: u32, renderbuffer_target: u32, renderbuffer: Option<RenderbufferHandle>) {
+        let renderbuffer = renderbuffer.and_then(|renderbuffer| self.objects.borrow().renderbuffers.get(renderbuffer.0));
+        self.gl
+            .framebuffer_renderbuffer(target, attachment, renderbuffer_target, renderbuffer.as_ref());
+    }
+
+    fn check_framebuffer_status(&self, target: u32) -> u32 {
+        self.gl.check_framebuffer_status(target)
+    }
+
+    fn draw_buffers(&self, buffers: &[u32]) {
+        let buffers: web_sys::js_sys::Array = buffers.iter().map(|buffer| JsValue::from(*buffer)).collect();
+        self.gl.draw_buffers(&buffers);
+    }
+
+    fn read_buffer(&self, source: u32) {
+        self.gl.read_buffer(source);
+    }
+
+    fn create_renderbuffer(&self) -> Option<RenderbufferHandle> {
+        let renderbuffer = self.gl.create_renderbuffer()?;
+        Some(RenderbufferHandle(self.objects.borrow_mut().renderbuffers.insert(renderbuffer)))
+    }
+
+    fn delete_renderbuffer(&self, renderbuffer: Option<RenderbufferHandle>) {
+        let renderbuffer = renderbuffer.and_then(|renderbuffer| self.objects.borrow_mut().renderbuffers.remove(renderbuffer.0));
+        self.gl.delete_renderbuffer(renderbuffer.as_ref());
+    }
+
+    fn bind_renderbuffer(&self, target: u32, renderbuffer: Option<RenderbufferHandle>) {
+        let renderbuffer = renderbuffer.and_then(|renderbuffer| self.objects.borrow().renderbuffers.get(renderbuffer.0));
+        self.gl.bind_renderbuffer(target, renderbuffer.as_ref());
+    }
+
+    fn renderbuffer_storage(&self, target: u32, internal_format: u32, width: i32, height: i32) {
+        self.gl.renderbuffer_storage(target, internal_format, width, height);
+    }
+
+    fn read_pixels(
+        &self,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        data_type: u32,
+        destination: &mut [u8],
+    ) -> Result<(), BackendError> {
+        let buffer = ArrayBuffer::new(destination.len() as u32);
+
+        self.gl
+            .read_pixels_with_opt_array_buffer_view(x, y, width, height, format, data_type, Some(&pixel_array_view(&buffer, data_type)))
+            .map_err(backend_error)?;
+
+        Uint8Array::new(&buffer).copy_to(destination);
+        Ok(())
+    }
+
+    fn read_pixels_to_pixel_buffer(
+        &self,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+        format: u32,
+        data_type: u32,
+        offset: i32,
+    ) -> Result<(), BackendError> {
+        self.gl
+            .read_pixels_with_i32(x, y, width, height, format, data_type, offset)
+            .map_err(backend_error)
+    }
+
+    fn fence_sync(&self, condition: u32, flags: u32) -> Option<SyncHandle> {
+        let sync = self.gl.fence_sync(condition, flags)?;
+        Some(SyncHandle(self.objects.borrow_mut().syncs.insert(sync)))
+    }
+
+    fn client_wait_sync(&self, sync: SyncHandle, flags: u32, timeout: u32) -> u32 {
+        match self.objects.borrow().syncs.get(sync.0) {
+            Some(sync) => self.gl.client_wait_sync_with_u32(&sync, flags, timeout),
+            None => WebGl2RenderingContext::WAIT_FAILED,
+        }
+    }
+
+    fn delete_sync(&self, sync: Option<SyncHandle>) {
+        let sync = sync.and_then(|sync| self.objects.borrow_mut().syncs.remove(sync.0));
+        self.gl.delete_sync(sync.as_ref());
+    }
+}
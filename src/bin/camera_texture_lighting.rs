@@ -10,28 +10,22 @@ fn main() {
     spawn_local(main_async());
 }
 
-const VERTEX_SHADER_SOURCE: &'static str = r#"#version 300 es
+const VERTEX_SHADER_SOURCE: &str = r#"#include "camera"
 in vec3 position;
 in vec3 normal;
 
-uniform mat4 projection_matrix;
-uniform mat4 camera_inverse_matrix;
-uniform mat4 transform;
-
 in vec2 uv;
 out vec3 v_normal;
 out vec2 v_texture_coordinate;
 
 void main() {
     v_texture_coordinate = uv;
-    v_normal = mat3(camera_inverse_matrix * transform) * normal;
-    gl_Position = projection_matrix * camera_inverse_matrix * transform * vec4(position, 1.0);
+    v_normal = view_normal(normal);
+    gl_Position = project(position);
 }
 "#;
 
-const FRAGMENT_SHADER_SOURCE: &'static str = r#"#version 300 es
-precision mediump float;
-
+const FRAGMENT_SHADER_SOURCE: &str = r#"#include "lights"
 in vec3 v_normal;
 in vec2 v_texture_coordinate;
 
@@ -39,10 +33,8 @@ out vec4 fragment_color;
 uniform sampler2D texture_sampler;
 
 void main() {
-    vec3 normal = normalize(v_normal);
-    float light = dot(normal, normalize(vec3(0.25, 25.0, 25.0)));
     fragment_color = texture(texture_sampler, v_texture_coordinate);
-    fragment_color.rgb *= max(0.2, light);
+    fragment_color.rgb *= directional_light(v_normal, vec3(0.25, 25.0, 25.0));
 }
 "#;
 
@@ -23,16 +23,12 @@ fn main() {
     spawn_local(main_async());
 }
 
-const VERTEX_SHADER_SOURCE: &'static str = r#"#version 300 es
+const VERTEX_SHADER_SOURCE: &str = r#"#include "camera"
 in vec3 position;
 in vec3 normal;
 
-uniform mat4 projection_matrix;
-uniform mat4 camera_inverse_matrix;
-uniform mat4 transform;
-
 void main() {
-    gl_Position = projection_matrix * camera_inverse_matrix * transform * vec4(position, 1.0);
+    gl_Position = project(position);
     gl_PointSize = 2.0;
 }
 "#;
@@ -102,6 +98,8 @@ async fn main_async() {
         indices:                    Some(index_buffer),
         vertex_buffers:             vec![VertexBuffer::new("position", positions)],
         interleaved_vertex_buffers: vec![],
+        bounds:                     None,
+        bvh:                        None,
     };
     let mut mesh = Mesh::new(geometry, material);
     mesh.transform.scale *= 0.075;
@@ -78,6 +78,8 @@ async fn main_async() {
         indices:                    Some(index_buffer),
         vertex_buffers:             vec![VertexBuffer::new("position", positions), VertexBuffer::new("normal", normals)],
         interleaved_vertex_buffers: vec![],
+        bounds:                     None,
+        bvh:                        None,
     };
 
     let mut mesh = Mesh::new(geometry, material);
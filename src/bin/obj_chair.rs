@@ -27,9 +27,7 @@ void main() {
 }
 "#;
 
-const FRAGMENT_SHADER_SOURCE: &'static str = r#"#version 300 es
-precision mediump float;
-
+const FRAGMENT_SHADER_SOURCE: &str = r#"#include "lights"
 in vec3 v_normal;
 in vec2 v_texture_coordinate;
 
@@ -37,10 +35,8 @@ out vec4 fragment_color;
 uniform sampler2D chair_texture;
 
 void main() {
-    vec3 normal = normalize(v_normal);
-    float light = dot(normal, normalize(vec3(0.25, 25.0, -25.0)));
     fragment_color = texture(chair_texture, v_texture_coordinate);
-    fragment_color.rgb *= max(0.2, light);
+    fragment_color.rgb *= directional_light(v_normal, vec3(0.25, 25.0, -25.0));
 }
 "#;
 
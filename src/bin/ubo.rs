@@ -1,11 +1,7 @@
+use glam::Vec4;
 use suricato::{
-    geometry::Geometry,
-    material::Material,
-    mesh::Mesh,
-    renderer::Renderer,
-    ubo::UniformBufferObject,
-    uniforms::Uniform,
-    utils::{request_animation_frame, to_bytes},
+    geometry::Geometry, material::Material, mesh::Mesh, renderer::Renderer, std140::Std140, ubo::UniformBufferObject, uniforms::Uniform,
+    utils::request_animation_frame,
 };
 use wasm_bindgen_futures::spawn_local;
 
@@ -41,35 +37,39 @@ void main() {
 }
 "#;
 
+#[derive(Std140)]
+struct Colors {
+    colors: [Vec4; 3],
+}
+
 async fn main_async() {
     let mut renderer = Renderer::new();
     let material = Material::new(VERTEX_SHADER_SOURCE, FRAGMENT_SHADER_SOURCE);
     let geometry = Geometry::quad();
     let mut mesh = Mesh::new(geometry, material);
 
-    let ubo_binding_point = 1;
-
     // UBO #1
-    let colors: Vec<f32> = vec![
-        1.0, 0.0, 0.0, 1.0, // colors[0]
-        0.0, 1.0, 0.0, 1.0, // colors[1]
-        0.0, 0.0, 1.0, 1.0, // colors[2]
-    ];
-    let mut ubo = UniformBufferObject::new(&renderer, to_bytes(&colors));
+    let colors = Colors {
+        colors: [
+            Vec4::new(1.0, 0.0, 0.0, 1.0),
+            Vec4::new(0.0, 1.0, 0.0, 1.0),
+            Vec4::new(0.0, 0.0, 1.0, 1.0),
+        ],
+    };
+    let mut ubo = UniformBufferObject::from_std140(&renderer, &colors);
 
     // UBO #2
-    let colors2: Vec<f32> = vec![
-        1.0, 0.5, 0.5, 1.0, // colors[0]
-        0.5, 1.0, 0.5, 1.0, // colors[1]
-        0.5, 0.5, 1.0, 1.0, // colors[2]
-    ];
-    let mut ubo2 = UniformBufferObject::new(&renderer, to_bytes(&colors2));
-
-    mesh.material
-        .resources
-        .as_ref()
-        .unwrap()
-        .set_uniform_block("Colors", ubo_binding_point);
+    let colors2 = Colors {
+        colors: [
+            Vec4::new(1.0, 0.5, 0.5, 1.0),
+            Vec4::new(0.5, 1.0, 0.5, 1.0),
+            Vec4::new(0.5, 0.5, 1.0, 1.0),
+        ],
+    };
+    let mut ubo2 = UniformBufferObject::from_std140(&renderer, &colors2);
+
+    // Binds the `Colors` block of every program, the second buffer then takes its binding point in turn
+    let ubo_binding_point = renderer.register_uniform_buffer("Colors", &mut ubo).unwrap();
 
     request_animation_frame(Box::new(move || {
         renderer.clear();
@@ -0,0 +1,343 @@
+use glam::{Mat4, Vec3, Vec4};
+
+/// Axis aligned bounding box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl BoundingBox {
+    pub fn new(min: Vec3, max: Vec3) -> BoundingBox {
+        BoundingBox { min, max }
+    }
+
+    /// Returns `None` when there are no points.
+    pub fn from_points(points: &[Vec3]) -> Option<BoundingBox> {
+        let first = *points.first()?;
+        let mut bounding_box = BoundingBox::new(first, first);
+
+        for point in &points[1..] {
+            bounding_box.expand_to_include(*point);
+        }
+
+        Some(bounding_box)
+    }
+
+    pub fn expand_to_include(&mut self, point: Vec3) {
+        self.min = self.min.min(point);
+        self.max = self.max.max(point);
+    }
+
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        BoundingBox::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        let size = self.size();
+        2.0 * (size.x * size.y + size.y * size.z + size.z * size.x)
+    }
+
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        point.cmpge(self.min).all() && point.cmple(self.max).all()
+    }
+
+    /// Zero for points inside the box.
+    pub fn distance_squared_to_point(&self, point: Vec3) -> f32 {
+        point.clamp(self.min, self.max).distance_squared(point)
+    }
+
+    pub fn intersects_box(&self, other: &BoundingBox) -> bool {
+        self.min.cmple(other.max).all() && self.max.cmpge(other.min).all()
+    }
+
+    /// Bounding box of this box after being transformed by `matrix`.
+    ///
+    /// Uses the method from "Transforming Axis-Aligned Bounding Boxes" (Jim Arvo, Graphics Gems 1990)
+    /// which avoids transforming all eight corners.
+    pub fn transform(&self, matrix: &Mat4) -> BoundingBox {
+        let translation = matrix.w_axis.truncate();
+        let mut min = translation;
+        let mut max = translation;
+
+        for (column_index, column) in [matrix.x_axis, matrix.y_axis, matrix.z_axis].iter().enumerate() {
+            let a = column.truncate() * self.min[column_index];
+            let b = column.truncate() * self.max[column_index];
+
+            min += a.min(b);
+            max += a.max(b);
+        }
+
+        BoundingBox::new(min, max)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    pub fn new(center: Vec3, radius: f32) -> BoundingSphere {
+        BoundingSphere { center, radius }
+    }
+
+    /// Sphere centered on the bounding box of the points, which is not minimal
+    /// but never larger than the sphere enclosing the box.
+    pub fn from_points(points: &[Vec3]) -> Option<BoundingSphere> {
+        let center = BoundingBox::from_points(points)?.center();
+        let radius_squared = points.iter().map(|point| point.distance_squared(center)).fold(0.0, f32::max);
+
+        Some(BoundingSphere::new(center, radius_squared.sqrt()))
+    }
+
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        self.center.distance_squared(point) <= self.radius * self.radius
+    }
+
+    /// Non uniform scales grow the sphere by the largest axis scale.
+    pub fn transform(&self, matrix: &Mat4) -> BoundingSphere {
+        let max_scale = matrix
+            .x_axis
+            .truncate()
+            .length_squared()
+            .max(matrix.y_axis.truncate().length_squared())
+            .max(matrix.z_axis.truncate().length_squared())
+            .sqrt();
+
+        BoundingSphere::new(matrix.transform_point3(self.center), self.radius * max_scale)
+    }
+}
+
+/// Plane defined by `normal · point + distance = 0`, with the normal pointing to the positive half space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    pub normal:   Vec3,
+    pub distance: f32,
+}
+
+impl Plane {
+    /// Builds a plane from the `(a, b, c, d)` coefficients of `ax + by + cz + d = 0`, normalizing them.
+    pub fn from_coefficients(coefficients: Vec4) -> Plane {
+        let length = coefficients.truncate().length();
+
+        Plane {
+            normal:   coefficients.truncate() / length,
+            distance: coefficients.w / length,
+        }
+    }
+
+    pub fn signed_distance(&self, point: Vec3) -> f32 {
+        self.normal.dot(point) + self.distance
+    }
+}
+
+/// The six planes enclosing the volume visible by a camera, with normals pointing inwards.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Extracts the planes from a combined `projection * view` matrix.
+    ///
+    /// Based on "Fast Extraction of Viewing Frustum Planes from the World-View-Projection Matrix"
+    /// (Gribb & Hartmann), for OpenGL clip space where `-w <= z <= w`.
+    pub fn from_matrix(view_projection: &Mat4) -> Frustum {
+        let row_0 = view_projection.row(0);
+        let row_1 = view_projection.row(1);
+        let row_2 = view_projection.row(2);
+        let row_3 = view_projection.row(3);
+
+        Frustum {
+            planes: [
+                Plane::from_coefficients(row_3 + row_0), // Left
+                Plane::from_coefficients(row_3 - row_0), // Right
+                Plane::from_coefficients(row_3 + row_1), // Bottom
+                Plane::from_coefficients(row_3 - row_1), // Top
+                Plane::from_coefficients(row_3 + row_2), // Near
+                Plane::from_coefficients(row_3 - row_2), // Far
+            ],
+        }
+    }
+
+    pub fn contains_point(&self, point: Vec3) -> bool {
+        self.planes.iter().all(|plane| plane.signed_distance(point) >= 0.0)
+    }
+
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(sphere.center) >= -sphere.radius)
+    }
+
+    /// Conservative test: boxes near the frustum corners may be reported as visible.
+    pub fn intersects_box(&self, bounding_box: &BoundingBox) -> bool {
+        self.planes.iter().all(|plane| {
+            // Corner of the box farthest along the plane normal
+            let positive_vertex = Vec3::select(plane.normal.cmpge(Vec3::ZERO), bounding_box.max, bounding_box.min);
+            plane.signed_distance(positive_vertex) >= 0.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use glam::Quat;
+
+    use super::*;
+
+    /// 90° field of view looking down -Z from the origin, between 1 and 10 units away.
+    fn frustum() -> Frustum {
+        Frustum::from_matrix(&Mat4::perspective_rh_gl(FRAC_PI_2, 1.0, 1.0, 10.0))
+    }
+
+    fn assert_plane_eq(plane: &Plane, normal: Vec3, distance: f32) {
+        assert!(
+            plane.normal.abs_diff_eq(normal.normalize(), 1e-5),
+            "{} != {}",
+            plane.normal,
+            normal.normalize()
+        );
+        assert!((plane.distance - distance).abs() < 1e-5, "{} != {distance}", plane.distance);
+    }
+
+    #[test]
+    fn extracts_the_planes_of_a_perspective_matrix() {
+        let [left, right, bottom, top, near, far] = frustum().planes;
+
+        assert_plane_eq(&left, Vec3::new(1.0, 0.0, -1.0), 0.0);
+        assert_plane_eq(&right, Vec3::new(-1.0, 0.0, -1.0), 0.0);
+        assert_plane_eq(&bottom, Vec3::new(0.0, 1.0, -1.0), 0.0);
+        assert_plane_eq(&top, Vec3::new(0.0, -1.0, -1.0), 0.0);
+        assert_plane_eq(&near, Vec3::NEG_Z, -1.0);
+        assert_plane_eq(&far, Vec3::Z, 10.0);
+    }
+
+    #[test]
+    fn moves_the_planes_with_the_view() {
+        // Camera at z = 5, so the visible range is between z = 4 and z = -5
+        let view = Mat4::from_translation(Vec3::new(0.0, 0.0, -5.0));
+        let frustum = Frustum::from_matrix(&(Mat4::perspective_rh_gl(FRAC_PI_2, 1.0, 1.0, 10.0) * view));
+
+        assert!(frustum.contains_point(Vec3::new(0.0, 0.0, 3.0)));
+        assert!(frustum.contains_point(Vec3::new(0.0, 0.0, -4.0)));
+        assert!(!frustum.contains_point(Vec3::new(0.0, 0.0, 4.5)));
+        assert!(!frustum.contains_point(Vec3::new(0.0, 0.0, -6.0)));
+    }
+
+    #[test]
+    fn tests_points_against_the_frustum() {
+        let frustum = frustum();
+
+        assert!(frustum.contains_point(Vec3::new(0.0, 0.0, -5.0)));
+        assert!(frustum.contains_point(Vec3::new(4.9, -4.9, -5.0)));
+        assert!(!frustum.contains_point(Vec3::new(5.1, 0.0, -5.0)));
+        assert!(!frustum.contains_point(Vec3::new(0.0, 0.0, -0.5)));
+        assert!(!frustum.contains_point(Vec3::new(0.0, 0.0, -10.5)));
+        assert!(!frustum.contains_point(Vec3::new(0.0, 0.0, 5.0)));
+    }
+
+    #[test]
+    fn tests_boxes_inside_outside_and_straddling_the_frustum() {
+        let frustum = frustum();
+        let unit_box = |center: Vec3| BoundingBox::new(center - 0.5, center + 0.5);
+
+        assert!(frustum.intersects_box(&unit_box(Vec3::new(0.0, 0.0, -5.0))));
+        assert!(!frustum.intersects_box(&unit_box(Vec3::new(10.0, 0.0, -5.0))));
+        assert!(!frustum.intersects_box(&unit_box(Vec3::new(0.0, 0.0, 5.0))));
+        assert!(!frustum.intersects_box(&unit_box(Vec3::new(0.0, 0.0, -12.0))));
+
+        // Across the right plane, the near plane and the far plane
+        assert!(frustum.intersects_box(&unit_box(Vec3::new(5.0, 0.0, -5.0))));
+        assert!(frustum.intersects_box(&unit_box(Vec3::new(0.0, 0.0, -1.0))));
+        assert!(frustum.intersects_box(&unit_box(Vec3::new(0.0, 0.0, -10.0))));
+
+        // Larger than the frustum
+        assert!(frustum.intersects_box(&BoundingBox::new(Vec3::splat(-100.0), Vec3::splat(100.0))));
+    }
+
+    #[test]
+    fn tests_spheres_inside_outside_and_straddling_the_frustum() {
+        let frustum = frustum();
+
+        assert!(frustum.intersects_sphere(&BoundingSphere::new(Vec3::new(0.0, 0.0, -5.0), 1.0)));
+        assert!(frustum.intersects_sphere(&BoundingSphere::new(Vec3::new(5.5, 0.0, -5.0), 1.0)));
+        assert!(frustum.intersects_sphere(&BoundingSphere::new(Vec3::new(0.0, 0.0, -0.5), 1.0)));
+        assert!(!frustum.intersects_sphere(&BoundingSphere::new(Vec3::new(10.0, 0.0, -5.0), 1.0)));
+        assert!(!frustum.intersects_sphere(&BoundingSphere::new(Vec3::new(0.0, 0.0, -12.0), 1.0)));
+    }
+
+    #[test]
+    fn transforms_boxes_by_rotation_scale_and_translation() {
+        let bounding_box = BoundingBox::new(Vec3::new(-1.0, -2.0, -3.0), Vec3::new(1.0, 2.0, 3.0));
+
+        let scaled = bounding_box.transform(&Mat4::from_scale_rotation_translation(
+            Vec3::new(2.0, 1.0, 0.5),
+            Quat::IDENTITY,
+            Vec3::new(10.0, 0.0, 0.0),
+        ));
+        assert!(scaled.min.abs_diff_eq(Vec3::new(8.0, -2.0, -1.5), 1e-5));
+        assert!(scaled.max.abs_diff_eq(Vec3::new(12.0, 2.0, 1.5), 1e-5));
+
+        // A quarter turn around Z swaps the X and Y extents
+        let rotated = bounding_box.transform(&Mat4::from_rotation_z(FRAC_PI_2));
+        assert!(rotated.min.abs_diff_eq(Vec3::new(-2.0, -1.0, -3.0), 1e-5));
+        assert!(rotated.max.abs_diff_eq(Vec3::new(2.0, 1.0, 3.0), 1e-5));
+
+        // An eighth of a turn encloses the rotated corners, matching the transformed corners
+        let matrix = Mat4::from_rotation_y(FRAC_PI_2 / 2.0);
+        let corners: Vec<Vec3> = (0..8)
+            .map(|corner| {
+                let select = |bit: usize, min: f32, max: f32| if corner & bit == 0 { min } else { max };
+                let point = Vec3::new(
+                    select(1, bounding_box.min.x, bounding_box.max.x),
+                    select(2, bounding_box.min.y, bounding_box.max.y),
+                    select(4, bounding_box.min.z, bounding_box.max.z),
+                );
+                matrix.transform_point3(point)
+            })
+            .collect();
+        let expected = BoundingBox::from_points(&corners).unwrap();
+        let transformed = bounding_box.transform(&matrix);
+        assert!(transformed.min.abs_diff_eq(expected.min, 1e-5));
+        assert!(transformed.max.abs_diff_eq(expected.max, 1e-5));
+    }
+
+    #[test]
+    fn transforms_spheres_by_the_largest_scale() {
+        let sphere = BoundingSphere::new(Vec3::X, 1.0);
+        let matrix = Mat4::from_scale_rotation_translation(Vec3::new(1.0, 3.0, 2.0), Quat::from_rotation_z(FRAC_PI_2), Vec3::Z);
+
+        let transformed = sphere.transform(&matrix);
+
+        assert!(transformed.center.abs_diff_eq(Vec3::new(0.0, 1.0, 1.0), 1e-5));
+        assert!((transformed.radius - 3.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn builds_bounds_from_points() {
+        let points = [Vec3::new(-1.0, 0.0, 0.0), Vec3::new(1.0, 2.0, 0.0), Vec3::new(0.0, 0.0, 4.0)];
+
+        let bounding_box = BoundingBox::from_points(&points).unwrap();
+        assert_eq!(bounding_box, BoundingBox::new(Vec3::new(-1.0, 0.0, 0.0), Vec3::new(1.0, 2.0, 4.0)));
+
+        let sphere = BoundingSphere::from_points(&points).unwrap();
+        assert_eq!(sphere.center, Vec3::new(0.0, 1.0, 2.0));
+        assert!(points.iter().all(|point| sphere.contains_point(*point)));
+
+        assert_eq!(BoundingBox::from_points(&[]), None);
+        assert_eq!(BoundingSphere::from_points(&[]), None);
+    }
+}
@@ -1,6 +1,9 @@
-use web_sys::{WebGl2RenderingContext as GL, WebGlBuffer};
+use std::{error::Error, fmt, ops::Range};
 
-use crate::utils::to_bytes;
+use crate::{
+    backend::{BufferHandle, Context as GL},
+    utils::{generate_id, to_bytes},
+};
 
 #[repr(u32)]
 #[derive(Debug, Clone, Copy)]
@@ -23,13 +26,29 @@ pub enum BufferError {
     CreationFailed,
 }
 
+impl fmt::Display for BufferError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BufferError::UsageChangeAfterCreation => write!(f, "The buffer usage can not change once the buffer is created"),
+            BufferError::CreationFailed => write!(f, "Failed to create the buffer"),
+        }
+    }
+}
+
+impl Error for BufferError {}
+
 #[derive(Debug, Clone)]
 pub struct BufferGPU {
-    kind:         BufferKind,
-    usage:        BufferUsage,
-    buffer_cpu:   Vec<u8>,
-    buffer_gpu:   Option<WebGlBuffer>,
-    needs_update: bool,
+    kind:        BufferKind,
+    usage:       BufferUsage,
+    buffer_cpu:  Vec<u8>,
+    buffer_gpu:  Option<BufferHandle>,
+    /// Bytes written since the last upload, the only ones sent again.
+    dirty_range: Option<Range<usize>>,
+
+    /// Changes on every CPU side write, used to invalidate data derived from the buffer.
+    /// Unique across buffers, so replacing a buffer also invalidates that data.
+    version: u64,
 }
 
 impl BufferGPU {
@@ -39,56 +58,133 @@ impl BufferGPU {
             usage,
             buffer_cpu,
             buffer_gpu: None,
-            needs_update: false,
+            dirty_range: None,
+            version: generate_id(),
         }
     }
 
-    pub fn get_buffer_gpu(&mut self, gl: &GL) -> Option<&WebGlBuffer> {
+    /// `None` when the buffer could not be created.
+    pub fn get_buffer_gpu(&mut self, gl: &GL) -> Option<BufferHandle> {
         if self.buffer_gpu.is_none() {
-            self.create_buffer_gpu(gl);
+            self.create_buffer_gpu(gl).ok()?;
         }
 
-        self.buffer_gpu.as_ref()
+        self.buffer_gpu
     }
 
-    fn create_buffer_gpu(&mut self, gl: &GL) {
-        let webgl_buffer = gl.create_buffer().unwrap();
-        gl.bind_buffer(self.kind as u32, Some(&webgl_buffer));
-        gl.buffer_data_with_u8_array(self.kind as u32, &self.buffer_cpu, self.usage as u32);
-        self.buffer_gpu = Some(webgl_buffer);
+    fn create_buffer_gpu(&mut self, gl: &GL) -> Result<(), BufferError> {
+        let buffer_gpu = gl.create_buffer().ok_or(BufferError::CreationFailed)?;
+        gl.bind_buffer(self.kind as u32, Some(buffer_gpu));
+        gl.buffer_data(self.kind as u32, &self.buffer_cpu, self.usage as u32);
+        self.buffer_gpu = Some(buffer_gpu);
+        self.dirty_range = None;
+        Ok(())
     }
 
     #[inline]
     pub fn set_bytes<T>(&mut self, byte_offset: usize, value: &[T]) {
         let bytes = to_bytes(&value);
-        self.buffer_cpu[byte_offset..byte_offset + bytes.len()].copy_from_slice(bytes);
-        self.needs_update = true;
+        let range = byte_offset..byte_offset + bytes.len();
+        self.buffer_cpu[range.clone()].copy_from_slice(bytes);
+
+        self.dirty_range = Some(match self.dirty_range.take() {
+            Some(dirty_range) => dirty_range.start.min(range.start)..dirty_range.end.max(range.end),
+            None => range,
+        });
+        self.version = generate_id();
     }
 
     #[inline(always)]
     pub fn on_before_render(&mut self, gl: &GL) {
+        if let Err(error) = self.try_on_before_render(gl) {
+            panic!("{error}");
+        }
+    }
+
+    #[inline(always)]
+    pub fn try_on_before_render(&mut self, gl: &GL) -> Result<(), BufferError> {
         if self.buffer_gpu.is_none() {
-            self.create_buffer_gpu(gl);
+            self.create_buffer_gpu(gl)?;
         }
 
-        if !self.needs_update {
-            return;
+        if let Some(dirty_range) = self.dirty_range.take() {
+            self.update_buffer_gpu(gl, dirty_range);
         }
 
-        self.update_buffer_gpu(gl);
-        self.needs_update = false;
+        Ok(())
     }
 
-    fn update_buffer_gpu(&mut self, gl: &GL) {
-        gl.bind_buffer(self.kind as u32, self.buffer_gpu.as_ref());
-        gl.buffer_sub_data_with_i32_and_u8_array(self.kind as u32, 0, &self.buffer_cpu);
+    fn update_buffer_gpu(&mut self, gl: &GL, range: Range<usize>) {
+        gl.bind_buffer(self.kind as u32, self.buffer_gpu);
+        gl.buffer_sub_data(self.kind as u32, range.start as i32, &self.buffer_cpu[range]);
     }
 
     pub fn bind(&self, gl: &GL) {
-        gl.bind_buffer(self.kind as u32, self.buffer_gpu.as_ref());
+        gl.bind_buffer(self.kind as u32, self.buffer_gpu);
     }
 
     pub fn size(&self) -> usize {
         self.buffer_cpu.len()
     }
+
+    /// CPU copy of the buffer contents.
+    pub fn data(&self) -> &[u8] {
+        &self.buffer_cpu
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::backend::mock::MockContext;
+
+    #[test]
+    fn uploads_dirty_buffer_once() {
+        let mock = Rc::new(MockContext::new(1, 1));
+        let gl = mock.context();
+
+        let mut buffer = BufferGPU::new(BufferKind::ArrayBuffer, BufferUsage::DynamicDraw, vec![0; 8]);
+        buffer.on_before_render(&gl);
+        buffer.on_before_render(&gl);
+        assert_eq!(mock.state().call_count("buffer_data"), 1);
+        assert_eq!(mock.state().call_count("buffer_sub_data"), 0);
+
+        buffer.set_bytes(4, &[7u8, 8]);
+        buffer.set_bytes(0, &[1u8]);
+        buffer.on_before_render(&gl);
+        buffer.on_before_render(&gl);
+
+        let state = mock.state();
+        assert_eq!(state.call_count("buffer_data"), 1);
+        assert_eq!(state.call_count("buffer_sub_data"), 1);
+
+        let uploaded = &state.buffers[&buffer.buffer_gpu.unwrap().0];
+        assert_eq!(uploaded.data, vec![1, 0, 0, 0, 7, 8, 0, 0]);
+        assert_eq!(uploaded.upload_count, 2);
+    }
+
+    #[test]
+    fn uploads_only_the_written_range() {
+        let mock = Rc::new(MockContext::new(1, 1));
+        let gl = mock.context();
+
+        let mut buffer = BufferGPU::new(BufferKind::UniformBuffer, BufferUsage::DynamicDraw, vec![0; 8]);
+        buffer.on_before_render(&gl);
+
+        // Changed behind the back of the CPU copy, kept as long as it is not uploaded again
+        gl.buffer_sub_data(GL::UNIFORM_BUFFER, 0, &[9]);
+
+        buffer.set_bytes(6, &[1u8]);
+        buffer.set_bytes(3, &[2u8]);
+        buffer.on_before_render(&gl);
+
+        let state = mock.state();
+        assert_eq!(state.buffers[&buffer.buffer_gpu.unwrap().0].data, vec![9, 0, 0, 2, 0, 0, 1, 0]);
+    }
 }
@@ -0,0 +1,666 @@
+use glam::Vec3;
+
+use crate::{
+    bounds::{BoundingBox, BoundingSphere},
+    raycast::{Ray, TriangleIntersection},
+};
+
+/// Leaves are not split further once they hold this many triangles or fewer.
+const MAX_TRIANGLES_PER_LEAF: u32 = 4;
+
+/// Number of candidate split planes evaluated per axis when building.
+const SAH_BIN_COUNT: usize = 12;
+
+#[derive(Debug, Clone, Copy)]
+struct BvhNode {
+    bounds:        BoundingBox,
+    /// Index of the left child for interior nodes (the right child follows it),
+    /// or of the first entry in `triangle_order` for leaves.
+    left_or_first: u32,
+    /// Number of triangles, zero for interior nodes.
+    count:         u32,
+}
+
+impl BvhNode {
+    fn is_leaf(&self) -> bool {
+        self.count > 0
+    }
+}
+
+/// Closest point on the surface of a geometry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NearestPoint {
+    pub point:          Vec3,
+    pub distance:       f32,
+    pub triangle_index: usize,
+}
+
+/// Bounding volume hierarchy over the triangles of a geometry, in the geometry local space.
+///
+/// Built with the surface area heuristic. When only vertex positions change, [`Bvh::refit`]
+/// updates the bounds without rebuilding the tree.
+#[derive(Debug, Clone)]
+pub struct Bvh {
+    nodes:          Vec<BvhNode>,
+    triangle_order: Vec<u32>,
+    triangles:      Vec<[u32; 3]>,
+    positions:      Vec<Vec3>,
+}
+
+impl Bvh {
+    /// Triangles referencing vertices outside of `positions` are left out of the tree,
+    /// without renumbering the others, so hits keep the triangle index of the geometry.
+    pub fn new(positions: Vec<Vec3>, triangles: Vec<[u32; 3]>) -> Bvh {
+        let is_valid = |triangle: &[u32; 3]| triangle.iter().all(|index| (*index as usize) < positions.len());
+        let triangle_order: Vec<u32> = (0..triangles.len() as u32)
+            .filter(|triangle_index| is_valid(&triangles[*triangle_index as usize]))
+            .collect();
+
+        // Indexed like `triangles`, left out triangles are never read
+        let centroids: Vec<Vec3> = triangles
+            .iter()
+            .map(|triangle| {
+                if !is_valid(triangle) {
+                    return Vec3::ZERO;
+                }
+
+                let [a, b, c] = triangle.map(|index| positions[index as usize]);
+                (a + b + c) / 3.0
+            })
+            .collect();
+
+        let mut bvh = Bvh {
+            nodes: Vec::with_capacity(triangle_order.len() * 2),
+            triangle_order,
+            triangles,
+            positions,
+        };
+
+        if bvh.triangle_order.is_empty() {
+            return bvh;
+        }
+
+        bvh.nodes.push(BvhNode {
+            bounds:        BoundingBox::new(Vec3::ZERO, Vec3::ZERO),
+            left_or_first: 0,
+            count:         bvh.triangle_order.len() as u32,
+        });
+
+        let mut nodes_to_split = vec![0];
+
+        while let Some(node_index) = nodes_to_split.pop() {
+            bvh.update_node_bounds(node_index);
+
+            if let Some((left_index, right_index)) = bvh.split_node(node_index, &centroids) {
+                nodes_to_split.push(left_index);
+                nodes_to_split.push(right_index);
+            }
+        }
+
+        bvh
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    /// Vertex indices of a triangle.
+    pub fn triangle_indices(&self, triangle_index: usize) -> [u32; 3] {
+        self.triangles[triangle_index]
+    }
+
+    /// Vertex positions of a triangle, panics for triangles left out of the tree.
+    pub fn triangle(&self, triangle_index: usize) -> [Vec3; 3] {
+        self.triangles[triangle_index].map(|index| self.positions[index as usize])
+    }
+
+    pub fn bounds(&self) -> Option<BoundingBox> {
+        self.nodes.first().map(|root| root.bounds)
+    }
+
+    /// Updates the node bounds for new vertex positions, keeping the tree structure.
+    ///
+    /// Much cheaper than rebuilding, but the tree quality degrades if vertices move a lot.
+    pub fn refit(&mut self, positions: Vec<Vec3>) {
+        if positions.len() != self.positions.len() {
+            *self = Bvh::new(positions, std::mem::take(&mut self.triangles));
+            return;
+        }
+
+        self.positions = positions;
+
+        // Children are always stored after their parent
+        for node_index in (0..self.nodes.len()).rev() {
+            let node = self.nodes[node_index];
+
+            if node.is_leaf() {
+                self.update_node_bounds(node_index);
+            } else {
+                let left = self.nodes[node.left_or_first as usize].bounds;
+                let right = self.nodes[node.left_or_first as usize + 1].bounds;
+                self.nodes[node_index].bounds = left.union(&right);
+            }
+        }
+    }
+
+    /// Closest triangle hit by `ray`.
+    pub fn raycast(&self, ray: &Ray) -> Option<(usize, TriangleIntersection)> {
+        let mut closest: Option<(usize, TriangleIntersection)> = None;
+        let mut stack = Vec::with_capacity(64);
+
+        if let Some(root) = self.nodes.first()
+            && ray.intersect_box(&root.bounds).is_some()
+        {
+            stack.push(0);
+        }
+
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+
+            if node.is_leaf() {
+                for triangle_index in self.leaf_triangles(node) {
+                    let [a, b, c] = self.triangle(triangle_index);
+
+                    if let Some(intersection) = ray.intersect_triangle(a, b, c)
+                        && closest.is_none_or(|(_, closest)| intersection.distance < closest.distance)
+                    {
+                        closest = Some((triangle_index, intersection));
+                    }
+                }
+
+                continue;
+            }
+
+            let closest_distance = closest.map_or(f32::INFINITY, |(_, closest)| closest.distance);
+            let left_index = node.left_or_first as usize;
+            let right_index = left_index + 1;
+
+            let left = ray.intersect_box(&self.nodes[left_index].bounds).filter(|t| *t <= closest_distance);
+            let right = ray
+                .intersect_box(&self.nodes[right_index].bounds)
+                .filter(|t| *t <= closest_distance);
+
+            // The nearest child is pushed last so it is visited first
+            match (left, right) {
+                (Some(left), Some(right)) if left <= right => stack.extend([right_index, left_index]),
+                (Some(_), Some(_)) => stack.extend([left_index, right_index]),
+                (Some(_), None) => stack.push(left_index),
+                (None, Some(_)) => stack.push(right_index),
+                (None, None) => {}
+            }
+        }
+
+        closest
+    }
+
+    /// Indices of every triangle touching `sphere`.
+    pub fn intersect_sphere(&self, sphere: &BoundingSphere) -> Vec<usize> {
+        let radius_squared = sphere.radius * sphere.radius;
+
+        self.query(
+            |bounds| bounds.distance_squared_to_point(sphere.center) <= radius_squared,
+            |[a, b, c]| closest_point_on_triangle(sphere.center, a, b, c).distance_squared(sphere.center) <= radius_squared,
+        )
+    }
+
+    /// Indices of every triangle touching `bounding_box`.
+    pub fn intersect_box(&self, bounding_box: &BoundingBox) -> Vec<usize> {
+        self.query(
+            |bounds| bounds.intersects_box(bounding_box),
+            |[a, b, c]| triangle_intersects_box(a, b, c, bounding_box),
+        )
+    }
+
+    /// Closest point on any triangle to `point`, ignoring triangles farther than `max_distance`.
+    pub fn nearest_point(&self, point: Vec3, max_distance: f32) -> Option<NearestPoint> {
+        let mut nearest: Option<NearestPoint> = None;
+        let mut best_distance_squared = max_distance * max_distance;
+        let mut stack = Vec::with_capacity(64);
+
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+
+            if node.bounds.distance_squared_to_point(point) > best_distance_squared {
+                continue;
+            }
+
+            if node.is_leaf() {
+                for triangle_index in self.leaf_triangles(node) {
+                    let [a, b, c] = self.triangle(triangle_index);
+                    let closest_point = closest_point_on_triangle(point, a, b, c);
+                    let distance_squared = closest_point.distance_squared(point);
+
+                    if distance_squared <= best_distance_squared {
+                        best_distance_squared = distance_squared;
+                        nearest = Some(NearestPoint {
+                            point: closest_point,
+                            distance: distance_squared.sqrt(),
+                            triangle_index,
+                        });
+                    }
+                }
+
+                continue;
+            }
+
+            let left_index = node.left_or_first as usize;
+            let right_index = left_index + 1;
+
+            let left_distance = self.nodes[left_index].bounds.distance_squared_to_point(point);
+            let right_distance = self.nodes[right_index].bounds.distance_squared_to_point(point);
+
+            if left_distance <= right_distance {
+                stack.extend([right_index, left_index]);
+            } else {
+                stack.extend([left_index, right_index]);
+            }
+        }
+
+        nearest
+    }
+
+    fn query(&self, node_filter: impl Fn(&BoundingBox) -> bool, triangle_filter: impl Fn([Vec3; 3]) -> bool) -> Vec<usize> {
+        let mut result = Vec::new();
+        let mut stack = Vec::with_capacity(64);
+
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+
+            if !node_filter(&node.bounds) {
+                continue;
+            }
+
+            if node.is_leaf() {
+                result.extend(
+                    self.leaf_triangles(node)
+                        .filter(|triangle_index| triangle_filter(self.triangle(*triangle_index))),
+                );
+            } else {
+                stack.extend([node.left_or_first as usize, node.left_or_first as usize + 1]);
+            }
+        }
+
+        result
+    }
+
+    fn leaf_triangles(&self, node: &BvhNode) -> impl Iterator<Item = usize> + '_ {
+        let first = node.left_or_first as usize;
+        let last = first + node.count as usize;
+
+        self.triangle_order[first..last]
+            .iter()
+            .map(|triangle_index| *triangle_index as usize)
+    }
+
+    fn update_node_bounds(&mut self, node_index: usize) {
+        let node = self.nodes[node_index];
+        let first = node.left_or_first as usize;
+        let mut bounds: Option<BoundingBox> = None;
+
+        for order_index in first..first + node.count as usize {
+            for vertex in self.triangle(self.triangle_order[order_index] as usize) {
+                match &mut bounds {
+                    Some(bounds) => bounds.expand_to_include(vertex),
+                    None => bounds = Some(BoundingBox::new(vertex, vertex)),
+                }
+            }
+        }
+
+        if let Some(bounds) = bounds {
+            self.nodes[node_index].bounds = bounds;
+        }
+    }
+
+    /// Splits a leaf in two using binned SAH, returning the new children when the split is worth it.
+    fn split_node(&mut self, node_index: usize, centroids: &[Vec3]) -> Option<(usize, usize)> {
+        let node = self.nodes[node_index];
+
+        if node.count <= MAX_TRIANGLES_PER_LEAF {
+            return None;
+        }
+
+        let first = node.left_or_first as usize;
+        let last = first + node.count as usize;
+
+        let centroid_bounds = BoundingBox::from_points(
+            &self.triangle_order[first..last]
+                .iter()
+                .map(|triangle_index| centroids[*triangle_index as usize])
+                .collect::<Vec<Vec3>>(),
+        )?;
+
+        let (axis, split_position, split_cost) = self.find_best_split(first..last, &centroid_bounds, centroids)?;
+
+        let leaf_cost = node.count as f32 * node.bounds.surface_area();
+        if split_cost >= leaf_cost {
+            return None;
+        }
+
+        // Partition the triangles in place around the split plane
+        let mut left_end = first;
+        for order_index in first..last {
+            if centroids[self.triangle_order[order_index] as usize][axis] < split_position {
+                self.triangle_order.swap(order_index, left_end);
+                left_end += 1;
+            }
+        }
+
+        let left_count = (left_end - first) as u32;
+        if left_count == 0 || left_count == node.count {
+            return None;
+        }
+
+        let left_index = self.nodes.len();
+
+        self.nodes.push(BvhNode {
+            bounds:        node.bounds,
+            left_or_first: first as u32,
+            count:         left_count,
+        });
+
+        self.nodes.push(BvhNode {
+            bounds:        node.bounds,
+            left_or_first: left_end as u32,
+            count:         node.count - left_count,
+        });
+
+        self.nodes[node_index].left_or_first = left_index as u32;
+        self.nodes[node_index].count = 0;
+
+        Some((left_index, left_index + 1))
+    }
+
+    /// Returns the axis, position and SAH cost of the cheapest split plane.
+    fn find_best_split(
+        &self,
+        order_range: std::ops::Range<usize>,
+        centroid_bounds: &BoundingBox,
+        centroids: &[Vec3],
+    ) -> Option<(usize, f32, f32)> {
+        let mut best: Option<(usize, f32, f32)> = None;
+
+        for axis in [0, 1, 2] {
+            let axis_min = centroid_bounds.min[axis];
+            let extent = centroid_bounds.max[axis] - axis_min;
+
+            if extent <= f32::EPSILON {
+                continue;
+            }
+
+            let scale = SAH_BIN_COUNT as f32 / extent;
+            let mut bin_bounds: [Option<BoundingBox>; SAH_BIN_COUNT] = [None; SAH_BIN_COUNT];
+            let mut bin_counts = [0u32; SAH_BIN_COUNT];
+
+            for order_index in order_range.clone() {
+                let triangle_index = self.triangle_order[order_index] as usize;
+                let bin = (((centroids[triangle_index][axis] - axis_min) * scale) as usize).min(SAH_BIN_COUNT - 1);
+
+                bin_counts[bin] += 1;
+
+                for vertex in self.triangle(triangle_index) {
+                    match &mut bin_bounds[bin] {
+                        Some(bounds) => bounds.expand_to_include(vertex),
+                        None => bin_bounds[bin] = Some(BoundingBox::new(vertex, vertex)),
+                    }
+                }
+            }
+
+            // Sweep from both sides to get the area and count on each side of every plane
+            let mut left_areas = [0.0; SAH_BIN_COUNT - 1];
+            let mut left_counts = [0u32; SAH_BIN_COUNT - 1];
+            let mut accumulated_bounds: Option<BoundingBox> = None;
+            let mut accumulated_count = 0;
+
+            for plane in 0..SAH_BIN_COUNT - 1 {
+                accumulated_count += bin_counts[plane];
+                accumulated_bounds = union_optional(accumulated_bounds, bin_bounds[plane]);
+                left_counts[plane] = accumulated_count;
+                left_areas[plane] = accumulated_bounds.map_or(0.0, |bounds| bounds.surface_area());
+            }
+
+            accumulated_bounds = None;
+            accumulated_count = 0;
+
+            for plane in (0..SAH_BIN_COUNT - 1).rev() {
+                accumulated_count += bin_counts[plane + 1];
+                accumulated_bounds = union_optional(accumulated_bounds, bin_bounds[plane + 1]);
+
+                let right_area = accumulated_bounds.map_or(0.0, |bounds| bounds.surface_area());
+                let cost = left_counts[plane] as f32 * left_areas[plane] + accumulated_count as f32 * right_area;
+
+                if left_counts[plane] > 0 && accumulated_count > 0 && best.is_none_or(|(_, _, best_cost)| cost < best_cost) {
+                    let split_position = axis_min + (plane + 1) as f32 / scale;
+                    best = Some((axis, split_position, cost));
+                }
+            }
+        }
+
+        best
+    }
+}
+
+fn union_optional(a: Option<BoundingBox>, b: Option<BoundingBox>) -> Option<BoundingBox> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.union(&b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// From "Real-Time Collision Detection" (Christer Ericson), section 5.1.5.
+pub fn closest_point_on_triangle(point: Vec3, a: Vec3, b: Vec3, c: Vec3) -> Vec3 {
+    let ab = b - a;
+    let ac = c - a;
+    let ap = point - a;
+
+    let d1 = ab.dot(ap);
+    let d2 = ac.dot(ap);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return a;
+    }
+
+    let bp = point - b;
+    let d3 = ab.dot(bp);
+    let d4 = ac.dot(bp);
+    if d3 >= 0.0 && d4 <= d3 {
+        return b;
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return a + ab * (d1 / (d1 - d3));
+    }
+
+    let cp = point - c;
+    let d5 = ab.dot(cp);
+    let d6 = ac.dot(cp);
+    if d6 >= 0.0 && d5 <= d6 {
+        return c;
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return a + ac * (d2 / (d2 - d6));
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0 {
+        return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+    }
+
+    let denominator = 1.0 / (va + vb + vc);
+    a + ab * (vb * denominator) + ac * (vc * denominator)
+}
+
+/// Separating axis test between a triangle and a box.
+///
+/// From "Fast 3D Triangle-Box Overlap Testing" (Tomas Akenine-Möller).
+pub fn triangle_intersects_box(a: Vec3, b: Vec3, c: Vec3, bounding_box: &BoundingBox) -> bool {
+    let center = bounding_box.center();
+    let half_size = bounding_box.size() * 0.5;
+
+    // Move the box to the origin
+    let vertices = [a - center, b - center, c - center];
+    let edges = [vertices[1] - vertices[0], vertices[2] - vertices[1], vertices[0] - vertices[2]];
+
+    let is_separating_axis = |axis: Vec3| {
+        let projections = vertices.map(|vertex| vertex.dot(axis));
+        let min = projections[0].min(projections[1]).min(projections[2]);
+        let max = projections[0].max(projections[1]).max(projections[2]);
+        let radius = half_size.dot(axis.abs());
+
+        min > radius || max < -radius
+    };
+
+    // Cross products of the box axes and the triangle edges
+    for edge in edges {
+        for box_axis in [Vec3::X, Vec3::Y, Vec3::Z] {
+            let axis = box_axis.cross(edge);
+
+            if axis.length_squared() > f32::EPSILON && is_separating_axis(axis) {
+                return false;
+            }
+        }
+    }
+
+    // Box face normals
+    for box_axis in [Vec3::X, Vec3::Y, Vec3::Z] {
+        if is_separating_axis(box_axis) {
+            return false;
+        }
+    }
+
+    // Triangle normal
+    let normal = edges[0].cross(edges[1]);
+    normal.length_squared() <= f32::EPSILON || !is_separating_axis(normal)
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+
+    use super::*;
+
+    /// A grid of `size * size` quads on the XY plane, two triangles each.
+    fn grid(size: u32) -> (Vec<Vec3>, Vec<[u32; 3]>) {
+        let mut positions = Vec::new();
+        let mut triangles = Vec::new();
+
+        for y in 0..=size {
+            for x in 0..=size {
+                positions.push(Vec3::new(x as f32, y as f32, 0.0));
+            }
+        }
+
+        for y in 0..size {
+            for x in 0..size {
+                let i = y * (size + 1) + x;
+                triangles.push([i, i + 1, i + size + 1]);
+                triangles.push([i + 1, i + size + 2, i + size + 1]);
+            }
+        }
+
+        (positions, triangles)
+    }
+
+    fn brute_force_raycast(bvh: &Bvh, ray: &Ray) -> Option<f32> {
+        (0..bvh.triangle_count())
+            .filter_map(|triangle_index| {
+                let [a, b, c] = bvh.triangle(triangle_index);
+                ray.intersect_triangle(a, b, c).map(|hit| hit.distance)
+            })
+            .min_by(f32::total_cmp)
+    }
+
+    #[test]
+    fn raycast_matches_brute_force() {
+        let (positions, triangles) = grid(16);
+        let bvh = Bvh::new(positions, triangles);
+
+        for i in 0..50 {
+            let origin = Vec3::new((i % 10) as f32 * 1.7 - 0.5, (i / 10) as f32 * 3.3 - 0.5, 5.0);
+            let ray = Ray::new(origin, Vec3::new(0.1, 0.05, -1.0));
+
+            let expected = brute_force_raycast(&bvh, &ray);
+            let actual = bvh.raycast(&ray).map(|(_, hit)| hit.distance);
+
+            assert_eq!(expected, actual, "ray {i}");
+        }
+    }
+
+    #[test]
+    fn refit_follows_moved_vertices() {
+        let (mut positions, triangles) = grid(8);
+        let mut bvh = Bvh::new(positions.clone(), triangles);
+
+        let ray = Ray::new(Vec3::new(4.2, 4.3, 5.0), Vec3::NEG_Z);
+        assert!((bvh.raycast(&ray).unwrap().1.distance - 5.0).abs() < 1e-5);
+
+        for position in &mut positions {
+            position.z = 2.0;
+        }
+
+        bvh.refit(positions);
+
+        assert!((bvh.raycast(&ray).unwrap().1.distance - 3.0).abs() < 1e-5);
+        assert_eq!(bvh.bounds().unwrap().min.z, 2.0);
+    }
+
+    #[test]
+    fn sphere_and_box_queries() {
+        let (positions, triangles) = grid(8);
+        let bvh = Bvh::new(positions, triangles);
+
+        // Touches the two triangles of the quad under it
+        let sphere = BoundingSphere::new(Vec3::new(2.5, 2.5, 0.1), 0.2);
+        assert_eq!(bvh.intersect_sphere(&sphere).len(), 2);
+
+        let far_sphere = BoundingSphere::new(Vec3::new(2.5, 2.5, 1.0), 0.5);
+        assert!(bvh.intersect_sphere(&far_sphere).is_empty());
+
+        // Covers 2x2 quads fully and touches the neighbours on its edges
+        let bounding_box = BoundingBox::new(Vec3::new(1.1, 1.1, -1.0), Vec3::new(2.9, 2.9, 1.0));
+        assert_eq!(bvh.intersect_box(&bounding_box).len(), 8);
+    }
+
+    #[test]
+    fn nearest_point_on_surface() {
+        let (positions, triangles) = grid(8);
+        let bvh = Bvh::new(positions, triangles);
+
+        let nearest = bvh.nearest_point(Vec3::new(3.3, 4.6, 2.0), f32::INFINITY).unwrap();
+        assert!(nearest.point.abs_diff_eq(Vec3::new(3.3, 4.6, 0.0), 1e-5));
+        assert!((nearest.distance - 2.0).abs() < 1e-5);
+
+        let outside = bvh.nearest_point(Vec3::new(-1.0, -1.0, 0.0), f32::INFINITY).unwrap();
+        assert!(outside.point.abs_diff_eq(Vec3::ZERO, 1e-5));
+
+        assert!(bvh.nearest_point(Vec3::new(3.0, 3.0, 2.0), 1.0).is_none());
+    }
+
+    #[test]
+    fn keeps_triangle_indices_when_leaving_out_invalid_triangles() {
+        let (positions, triangles) = grid(4);
+        let reference = Bvh::new(positions.clone(), triangles.clone());
+
+        let mut with_invalid = vec![[0, 1, 1000]];
+        with_invalid.extend(triangles);
+        let bvh = Bvh::new(positions, with_invalid);
+
+        let ray = Ray::new(Vec3::new(2.3, 1.6, 5.0), Vec3::NEG_Z);
+        let (expected_index, _) = reference.raycast(&ray).unwrap();
+        let (triangle_index, _) = bvh.raycast(&ray).unwrap();
+        assert_eq!(triangle_index, expected_index + 1);
+        assert_eq!(bvh.triangle(triangle_index), reference.triangle(expected_index));
+
+        let nearest = bvh.nearest_point(Vec3::new(2.3, 1.6, 1.0), f32::INFINITY).unwrap();
+        assert_eq!(nearest.triangle_index, expected_index + 1);
+    }
+}
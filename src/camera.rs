@@ -1,6 +1,6 @@
-use glam::Mat4;
+use glam::{Mat4, Vec2, Vec3};
 
-use crate::transform::Transform3D;
+use crate::{bounds::Frustum, raycast::Ray, std140::Std140, transform::Transform3D};
 
 pub struct PerspectiveCamera {
     pub fov:    f32,
@@ -27,16 +27,79 @@ impl PerspectiveCamera {
     pub fn update_projection_matrix(&mut self) {
         self.projection_matrix = Mat4::perspective_rh_gl(self.fov, self.aspect, self.near, self.far);
     }
+
+    /// Transforms from world space to camera space.
+    pub fn view_matrix(&self) -> Mat4 {
+        self.transform.to_mat4().inverse()
+    }
+
+    /// World space frustum, requires an up to date projection matrix.
+    pub fn frustum(&self) -> Frustum {
+        Frustum::from_matrix(&(self.projection_matrix * self.view_matrix()))
+    }
+
+    /// World space ray starting on the near plane and going through `screen_point`.
+    ///
+    /// `screen_point` is in pixels with the origin at the top left corner of a viewport of size `screen_size`,
+    /// as in the `offsetX` and `offsetY` of a mouse event on the canvas.
+    pub fn screen_point_to_ray(&self, screen_point: Vec2, screen_size: Vec2) -> Ray {
+        let x = (screen_point.x / screen_size.x) * 2.0 - 1.0;
+        let y = 1.0 - (screen_point.y / screen_size.y) * 2.0;
+
+        let inverse_view_projection = (self.projection_matrix * self.view_matrix()).inverse();
+        let near_point = inverse_view_projection.project_point3(Vec3::new(x, y, -1.0));
+        let far_point = inverse_view_projection.project_point3(Vec3::new(x, y, 1.0));
+
+        Ray::new(near_point, far_point - near_point)
+    }
+}
+
+/// Values of the `Camera` uniform block declared by the `camera` chunk, uploaded by the renderer once per frame.
+#[derive(Debug, Clone, Copy, PartialEq, Std140)]
+pub struct CameraBlock {
+    pub view_matrix:            Mat4,
+    pub projection_matrix:      Mat4,
+    pub view_projection_matrix: Mat4,
+    /// World space position of the camera.
+    pub position:               Vec3,
+    /// Seconds, see [`Renderer::time`](crate::renderer::Renderer::time).
+    pub time:                   f32,
+    /// Size in pixels of the viewport being drawn.
+    pub viewport:               Vec2,
+}
+
+impl CameraBlock {
+    pub fn new(camera: &PerspectiveCamera, time: f32, viewport: Vec2) -> CameraBlock {
+        let view_matrix = camera.view_matrix();
+
+        CameraBlock {
+            view_matrix,
+            projection_matrix: camera.projection_matrix,
+            view_projection_matrix: camera.projection_matrix * view_matrix,
+            position: camera.transform.translation,
+            time,
+            viewport,
+        }
+    }
+}
+
+/// Browser window aspect ratio, square without the `web` feature.
+fn default_aspect() -> f32 {
+    #[cfg(feature = "web")]
+    if let Some(window) = web_sys::window() {
+        let width = window.inner_width().unwrap().as_f64().unwrap() as f32;
+        let height = window.inner_height().unwrap().as_f64().unwrap() as f32;
+        return width / height;
+    }
+
+    1.0
 }
 
 impl Default for PerspectiveCamera {
     fn default() -> Self {
-        let width = web_sys::window().unwrap().inner_width().unwrap().as_f64().unwrap() as f32;
-        let height = web_sys::window().unwrap().inner_height().unwrap().as_f64().unwrap() as f32;
-
         let mut camera = PerspectiveCamera {
             fov:               (45.0 * 3.14) / 180.0,
-            aspect:            width / height,
+            aspect:            default_aspect(),
             near:              0.1,
             far:               100.0,
             transform:         Transform3D::new(),
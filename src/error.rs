@@ -0,0 +1,279 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt,
+    mem::{self, Discriminant},
+};
+
+use crate::{
+    backend::BackendError, buffer_gpu::BufferError, material::MaterialError, mesh::MeshError, picking::PickingError,
+    readback::ReadbackError, render_target::RenderTargetError, shader::reflection::GlslType, texture::TextureError,
+    ubo::UniformBufferError,
+};
+
+/// Any error returned by the renderer, wrapping the error of the module it comes from.
+#[derive(Debug)]
+pub enum SuricatoError {
+    ContextCreationFailed(String),
+    MeshError(MeshError),
+    BufferError(BufferError),
+    MaterialError(MaterialError),
+    TextureError(TextureError),
+    PickingError(PickingError),
+    RenderTargetError(RenderTargetError),
+    ReadbackError(ReadbackError),
+    BackendError(BackendError),
+    UniformBufferError(UniformBufferError),
+
+    // Non-fatal, handled according to the `ErrorPolicy`
+    /// The material sets a uniform its program does not use, usually a typo.
+    UnknownUniform(String),
+    /// The program reads an attribute the geometry does not provide.
+    MissingAttribute(String),
+    /// The value of a uniform does not match its declaration and can not be converted without loss.
+    UniformTypeMismatch {
+        name:     String,
+        expected: GlslType,
+        found:    GlslType,
+    },
+    /// The array value has more elements than the uniform declares from where it is set.
+    UniformArrayTooLong {
+        name:     String,
+        capacity: usize,
+        length:   usize,
+    },
+}
+
+impl fmt::Display for SuricatoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SuricatoError::ContextCreationFailed(reason) => write!(f, "Failed to create the graphics context: {reason}"),
+            SuricatoError::MeshError(error) => write!(f, "{error}"),
+            SuricatoError::BufferError(error) => write!(f, "{error}"),
+            SuricatoError::MaterialError(error) => write!(f, "{error}"),
+            SuricatoError::TextureError(error) => write!(f, "{error}"),
+            SuricatoError::PickingError(error) => write!(f, "{error}"),
+            SuricatoError::RenderTargetError(error) => write!(f, "{error}"),
+            SuricatoError::ReadbackError(error) => write!(f, "{error}"),
+            SuricatoError::BackendError(error) => write!(f, "{error}"),
+            SuricatoError::UniformBufferError(error) => write!(f, "{error}"),
+            SuricatoError::UnknownUniform(name) => write!(f, "Uniform `{name}` is not used by the program"),
+            SuricatoError::MissingAttribute(name) => write!(f, "Attribute `{name}` is not provided by the geometry"),
+            SuricatoError::UniformTypeMismatch { name, expected, found } => {
+                write!(
+                    f,
+                    "Uniform `{name}` is declared as `{expected}` and can not be set from a `{found}` value"
+                )
+            }
+            SuricatoError::UniformArrayTooLong { name, capacity, length } => {
+                write!(
+                    f,
+                    "Uniform `{name}` can not be set from {length} elements, the array ends after {capacity}"
+                )
+            }
+        }
+    }
+}
+
+impl Error for SuricatoError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SuricatoError::MeshError(error) => Some(error),
+            SuricatoError::BufferError(error) => Some(error),
+            SuricatoError::MaterialError(error) => Some(error),
+            SuricatoError::TextureError(error) => Some(error),
+            SuricatoError::PickingError(error) => Some(error),
+            SuricatoError::RenderTargetError(error) => Some(error),
+            SuricatoError::ReadbackError(error) => Some(error),
+            SuricatoError::BackendError(error) => Some(error),
+            SuricatoError::UniformBufferError(error) => Some(error),
+            SuricatoError::ContextCreationFailed(_)
+            | SuricatoError::UnknownUniform(_)
+            | SuricatoError::MissingAttribute(_)
+            | SuricatoError::UniformTypeMismatch { .. }
+            | SuricatoError::UniformArrayTooLong { .. } => None,
+        }
+    }
+}
+
+macro_rules! from_module_error {
+    ($($error:ident),* $(,)?) => {
+        $(
+            impl From<$error> for SuricatoError {
+                fn from(value: $error) -> Self {
+                    SuricatoError::$error(value)
+                }
+            }
+        )*
+    };
+}
+
+from_module_error!(
+    MeshError,
+    BufferError,
+    MaterialError,
+    TextureError,
+    PickingError,
+    RenderTargetError,
+    ReadbackError,
+    BackendError,
+    UniformBufferError,
+);
+
+impl SuricatoError {
+    /// Uniform or attribute a non-fatal error is about, telling apart the errors of a variant.
+    fn subject(&self) -> Option<&str> {
+        match self {
+            SuricatoError::UnknownUniform(name)
+            | SuricatoError::MissingAttribute(name)
+            | SuricatoError::UniformTypeMismatch { name, .. }
+            | SuricatoError::UniformArrayTooLong { name, .. } => Some(name),
+            _ => None,
+        }
+    }
+}
+
+/// What to do with non-fatal errors, which are skipped so the rest of the frame still renders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorPolicy {
+    Panic,
+    /// Logs each distinct error the first time it happens.
+    #[default]
+    LogOnce,
+    Ignore,
+}
+
+/// Applies an [`ErrorPolicy`] to the non-fatal errors found while rendering.
+#[derive(Debug, Default)]
+pub struct ErrorReporter {
+    pub policy: ErrorPolicy,
+    /// Subjects already logged for each variant, checked without formatting the error.
+    subjects:   HashMap<Discriminant<SuricatoError>, HashSet<String>>,
+    reported:   Vec<String>,
+}
+
+impl ErrorReporter {
+    pub fn new(policy: ErrorPolicy) -> ErrorReporter {
+        ErrorReporter {
+            policy,
+            subjects: HashMap::new(),
+            reported: Vec::new(),
+        }
+    }
+
+    pub fn report(&mut self, error: SuricatoError) {
+        match self.policy {
+            ErrorPolicy::Panic => panic!("{error}"),
+            ErrorPolicy::LogOnce => {
+                let variant = mem::discriminant(&error);
+                // Errors without a subject are not found while rendering, their message is used instead
+                let subject = match error.subject() {
+                    Some(subject) => Cow::Borrowed(subject),
+                    None => Cow::Owned(error.to_string()),
+                };
+
+                if self
+                    .subjects
+                    .get(&variant)
+                    .is_some_and(|subjects| subjects.contains(subject.as_ref()))
+                {
+                    return;
+                }
+
+                let message = error.to_string();
+                crate::log!("{message}");
+                self.subjects.entry(variant).or_default().insert(subject.into_owned());
+                self.reported.push(message);
+            }
+            ErrorPolicy::Ignore => {}
+        }
+    }
+
+    /// Messages logged so far with [`ErrorPolicy::LogOnce`], in the order they were logged.
+    pub fn get_reported(&self) -> impl Iterator<Item = &str> {
+        self.reported.iter().map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{backend::mock::MockContext, geometry::Geometry, material::Material, mesh::Mesh, renderer::Renderer, uniforms::Uniform};
+
+    const VERTEX_SHADER_SOURCE: &str =
+        "#version 300 es\nin vec2 position;\nin vec3 normal;\nvoid main() {\n    gl_Position = vec4(position, 0.0, 1.0);\n}\n";
+    const FRAGMENT_SHADER_SOURCE: &str = "#version 300 es\nprecision mediump float;\nuniform float opacity;\nout vec4 color;\nvoid main() {\n    color = vec4(opacity);\n}\n";
+
+    fn render_with_policy(policy: ErrorPolicy) -> Renderer {
+        let mock = Rc::new(MockContext::new(8, 8));
+        let mut renderer = Renderer::with_context(mock.context());
+        renderer.error_reporter.policy = policy;
+
+        let mut material = Material::new(VERTEX_SHADER_SOURCE, FRAGMENT_SHADER_SOURCE);
+        material.set_uniform("opacity", Uniform::Float(1.0));
+        material.set_uniform("opactiy", Uniform::Float(1.0));
+        let mut mesh = Mesh::new(Geometry::quad(), material);
+
+        renderer.try_render(&mut mesh).unwrap();
+        renderer.try_render(&mut mesh).unwrap();
+        assert_eq!(mock.state().draws.len(), 2);
+
+        renderer
+    }
+
+    #[test]
+    fn logs_each_error_once() {
+        let renderer = render_with_policy(ErrorPolicy::LogOnce);
+
+        let mut reported: Vec<&str> = renderer.error_reporter.get_reported().collect();
+        reported.sort();
+
+        assert_eq!(
+            reported,
+            [
+                "Attribute `normal` is not provided by the geometry",
+                "Uniform `opactiy` is not used by the program"
+            ]
+        );
+    }
+
+    #[test]
+    fn logs_once_per_variant_and_subject() {
+        let mut error_reporter = ErrorReporter::new(ErrorPolicy::LogOnce);
+        let too_long = |length| {
+            SuricatoError::UniformArrayTooLong {
+                name: String::from("bones"),
+                capacity: 4,
+                length,
+            }
+        };
+
+        error_reporter.report(too_long(5));
+        error_reporter.report(too_long(6));
+        error_reporter.report(SuricatoError::UnknownUniform(String::from("bones")));
+        error_reporter.report(SuricatoError::UnknownUniform(String::from("bones")));
+
+        assert_eq!(
+            error_reporter.get_reported().collect::<Vec<_>>(),
+            [
+                too_long(5).to_string(),
+                SuricatoError::UnknownUniform(String::from("bones")).to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn ignores_errors() {
+        let renderer = render_with_policy(ErrorPolicy::Ignore);
+        assert_eq!(renderer.error_reporter.get_reported().count(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not")]
+    fn panics_on_errors() {
+        render_with_policy(ErrorPolicy::Panic);
+    }
+}
@@ -1,17 +1,53 @@
+use glam::Vec3;
+
 use crate::{
-    buffer_gpu::BufferUsage,
+    bounds::{BoundingBox, BoundingSphere},
+    buffer_gpu::{BufferGPU, BufferUsage},
+    bvh::Bvh,
     index_buffer::IndexBuffer,
-    obj_parser::OBJ,
+    mesh::RenderPrimitive,
     transform::Transform2D,
-    vertex_buffer::{Data, InterleavedVertexBuffer, VertexBuffer, VertexData},
+    vertex_buffer::{Data, InterleavedVertexBuffer, VertexBuffer, VertexData, VertexLayout},
 };
 
+#[cfg(feature = "obj")]
+use crate::obj_parser::OBJ;
+
+/// Name of the vertex attribute used for CPU side queries such as bounds.
+pub const POSITION_ATTRIBUTE: &str = "position";
+
 pub struct Geometry {
     pub instance_count:             Option<usize>,
     pub vertex_count:               usize,
     pub indices:                    Option<IndexBuffer>,
     pub vertex_buffers:             Vec<VertexBuffer>,
     pub interleaved_vertex_buffers: Vec<InterleavedVertexBuffer>,
+
+    /// Cached by [`Geometry::get_bounds`], leave as `None` when building a geometry.
+    pub bounds: Option<GeometryBounds>,
+
+    /// Cached by [`Geometry::get_bvh`], leave as `None` when building a geometry.
+    pub bvh: Option<GeometryBvh>,
+}
+
+/// Bounding volumes of a geometry in local space.
+#[derive(Debug, Clone, Copy)]
+pub struct GeometryBounds {
+    pub bounding_box:    BoundingBox,
+    pub bounding_sphere: BoundingSphere,
+
+    /// Version of the position buffer the bounds were computed from.
+    position_version: u64,
+}
+
+/// Triangle BVH of a geometry and the buffer versions it was built from.
+#[derive(Debug, Clone)]
+pub struct GeometryBvh {
+    pub bvh: Bvh,
+
+    render_primitive: RenderPrimitive,
+    position_version: u64,
+    index_version:    Option<u64>,
 }
 
 static QUAD_POSITIONS: [[f32; 2]; 4] = [
@@ -41,6 +77,157 @@ static QUAD_INDICES: [u8; 6] = [
 ];
 
 impl Geometry {
+    /// Layouts of every attribute, separate and interleaved.
+    pub fn get_vertex_layouts(&self) -> impl Iterator<Item = &VertexLayout> {
+        self.vertex_buffers.iter().map(|vertex_buffer| &vertex_buffer.layout).chain(
+            self.interleaved_vertex_buffers
+                .iter()
+                .flat_map(|vertex_buffer| &vertex_buffer.layouts),
+        )
+    }
+
+    /// Returns the layout and buffer holding the `position` attribute.
+    pub fn get_position_attribute(&self) -> Option<(&VertexLayout, &BufferGPU)> {
+        for vertex_buffer in &self.vertex_buffers {
+            if vertex_buffer.layout.name == POSITION_ATTRIBUTE {
+                return Some((&vertex_buffer.layout, &vertex_buffer.buffer));
+            }
+        }
+
+        for interleaved_vertex_buffer in &self.interleaved_vertex_buffers {
+            for layout in &interleaved_vertex_buffer.layouts {
+                if layout.name == POSITION_ATTRIBUTE {
+                    return Some((layout, &interleaved_vertex_buffer.buffer));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Reads the `position` attribute from the CPU copy of its buffer.
+    /// Two component positions are returned with `z = 0`.
+    pub fn get_positions(&self) -> Option<Vec<Vec3>> {
+        let (layout, buffer) = self.get_position_attribute()?;
+        let vertex_count = buffer.size() / layout.stride;
+
+        (0..vertex_count)
+            .map(|vertex_index| layout.read_vec3(buffer.data(), vertex_index))
+            .collect()
+    }
+
+    /// Vertex indices of every triangle drawn with `render_primitive`, in draw order.
+    ///
+    /// Strips and fans are expanded into independent triangles with consistent winding.
+    /// Points and lines produce no triangles.
+    pub fn get_triangle_indices(&self, render_primitive: RenderPrimitive) -> Vec<[u32; 3]> {
+        let indices = match &self.indices {
+            Some(indices) => indices.get_indices(),
+            None => (0..self.vertex_count as u32).collect(),
+        };
+
+        match render_primitive {
+            RenderPrimitive::Triangles => {
+                indices
+                    .chunks_exact(3)
+                    .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+                    .collect()
+            }
+            RenderPrimitive::TriangleStrip => {
+                indices
+                    .windows(3)
+                    .enumerate()
+                    .map(|(i, triangle)| {
+                        if i % 2 == 0 {
+                            [triangle[0], triangle[1], triangle[2]]
+                        } else {
+                            [triangle[1], triangle[0], triangle[2]]
+                        }
+                    })
+                    .collect()
+            }
+            RenderPrimitive::TriangleFan => {
+                let Some(&center) = indices.first() else {
+                    return Vec::new();
+                };
+
+                indices[1..].windows(2).map(|edge| [center, edge[0], edge[1]]).collect()
+            }
+            RenderPrimitive::Points | RenderPrimitive::Lines | RenderPrimitive::LineStrip | RenderPrimitive::LineLoop => Vec::new(),
+        }
+    }
+
+    /// Returns the local space bounds, recomputing them if the positions changed since the last call.
+    ///
+    /// `None` if the geometry has no float `position` attribute.
+    pub fn get_bounds(&mut self) -> Option<&GeometryBounds> {
+        let position_version = self.get_position_attribute()?.1.version();
+
+        let is_outdated = self.bounds.is_none_or(|bounds| bounds.position_version != position_version);
+
+        if is_outdated {
+            self.compute_bounds();
+        }
+
+        self.bounds.as_ref()
+    }
+
+    pub fn compute_bounds(&mut self) {
+        self.bounds = None;
+
+        let Some((_, buffer)) = self.get_position_attribute() else {
+            return;
+        };
+
+        let position_version = buffer.version();
+
+        let Some(positions) = self.get_positions() else {
+            return;
+        };
+
+        let (Some(bounding_box), Some(bounding_sphere)) = (BoundingBox::from_points(&positions), BoundingSphere::from_points(&positions))
+        else {
+            return;
+        };
+
+        self.bounds = Some(GeometryBounds {
+            bounding_box,
+            bounding_sphere,
+            position_version,
+        });
+    }
+
+    /// Returns a BVH over the triangles drawn with `render_primitive`.
+    ///
+    /// The BVH is built on the first call and cached. It is refitted when the positions were modified
+    /// (e.g. through [`VertexBuffer::set_vertex`] or [`InterleavedVertexBuffer::update_vertex`]) and
+    /// rebuilt when the indices or the primitive change.
+    pub fn get_bvh(&mut self, render_primitive: RenderPrimitive) -> Option<&Bvh> {
+        let position_version = self.get_position_attribute()?.1.version();
+        let index_version = self.indices.as_ref().map(|indices| indices.buffer.version());
+
+        match self.bvh.take() {
+            Some(mut cached) if cached.render_primitive == render_primitive && cached.index_version == index_version => {
+                if cached.position_version != position_version {
+                    cached.bvh.refit(self.get_positions()?);
+                    cached.position_version = position_version;
+                }
+
+                self.bvh = Some(cached);
+            }
+            _ => {
+                self.bvh = Some(GeometryBvh {
+                    bvh: Bvh::new(self.get_positions()?, self.get_triangle_indices(render_primitive)),
+                    render_primitive,
+                    position_version,
+                    index_version,
+                });
+            }
+        }
+
+        self.bvh.as_ref().map(|cached| &cached.bvh)
+    }
+
     pub fn get_vertex_buffer(&mut self, name: &str) -> Option<&mut VertexBuffer> {
         for vertex_buffer in &mut self.vertex_buffers {
             if vertex_buffer.layout.name == name {
@@ -212,6 +399,8 @@ impl Geometry {
                 VertexBuffer::with_config(BufferUsage::StaticDraw, uvs),
             ],
             interleaved_vertex_buffers: vec![],
+            bounds:                     None,
+            bvh:                        None,
         }
     }
 
@@ -254,6 +443,8 @@ impl Geometry {
                 VertexBuffer::with_config(BufferUsage::StaticDraw, uvs),
             ],
             interleaved_vertex_buffers: vec![],
+            bounds:                     None,
+            bvh:                        None,
         }
     }
 
@@ -274,6 +465,8 @@ impl Geometry {
             indices:                    Some(indices),
             vertex_buffers:             vec![],
             interleaved_vertex_buffers: vec![InterleavedVertexBuffer::new(BufferUsage::StaticDraw, vec![position, color, uvs])],
+            bounds:                     None,
+            bvh:                        None,
         }
     }
 
@@ -312,6 +505,8 @@ impl Geometry {
             instance_count:             Some(count),
             interleaved_vertex_buffers: vec![InterleavedVertexBuffer::new(BufferUsage::StaticDraw, vec![position, color, uvs])],
             vertex_buffers:             vec![transform_buffer],
+            bounds:                     None,
+            bvh:                        None,
         }
     }
 
@@ -352,6 +547,8 @@ impl Geometry {
             instance_count:             Some(count),
             interleaved_vertex_buffers: vec![],
             vertex_buffers:             vec![color, position, uvs, per_instance_transforms],
+            bounds:                     None,
+            bvh:                        None,
         }
     }
 }
@@ -364,6 +561,8 @@ impl From<VertexBuffer> for Geometry {
             indices:                    None,
             vertex_buffers:             vec![vertex_buffer],
             interleaved_vertex_buffers: vec![],
+            bounds:                     None,
+            bvh:                        None,
         }
     }
 }
@@ -376,10 +575,13 @@ impl From<InterleavedVertexBuffer> for Geometry {
             indices:                    None,
             vertex_buffers:             vec![],
             interleaved_vertex_buffers: vec![interleaved_vertex_buffer],
+            bounds:                     None,
+            bvh:                        None,
         }
     }
 }
 
+#[cfg(feature = "obj")]
 impl From<OBJ> for Geometry {
     fn from(obj: OBJ) -> Geometry {
         let mut positions: Vec<[f32; 3]> = Vec::new();
@@ -424,3 +626,38 @@ impl From<OBJ> for Geometry {
         Geometry::from(InterleavedVertexBuffer::new(BufferUsage::StaticDraw, vec![positions, normals, uvs]))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recomputes_bounds_and_bvh_when_the_position_buffer_is_replaced() {
+        let mut geometry = Geometry::quad();
+        let old_bounds = geometry.get_bounds().unwrap().bounding_box;
+        let old_bvh_bounds = geometry.get_bvh(RenderPrimitive::Triangles).unwrap().bounds().unwrap();
+
+        // A new buffer has never been written to, like the one it replaces
+        let moved = old_bounds.min.x + 10.0;
+        let positions = VertexData {
+            name:      String::from(POSITION_ATTRIBUTE),
+            data:      Data::Vec3(vec![
+                [moved, 0.0, 0.0],
+                [moved + 1.0, 0.0, 0.0],
+                [moved + 1.0, 1.0, 0.0],
+                [moved, 1.0, 0.0],
+            ]),
+            normalize: false,
+            divisor:   0,
+        };
+        geometry.vertex_buffers[0] = VertexBuffer::with_config(BufferUsage::StaticDraw, positions);
+
+        let bounds = geometry.get_bounds().unwrap().bounding_box;
+        assert_ne!(bounds, old_bounds);
+        assert_eq!(bounds.min, Vec3::new(moved, 0.0, 0.0));
+
+        let bvh_bounds = geometry.get_bvh(RenderPrimitive::Triangles).unwrap().bounds().unwrap();
+        assert_ne!(bvh_bounds, old_bvh_bounds);
+        assert_eq!(bvh_bounds.min, Vec3::new(moved, 0.0, 0.0));
+    }
+}
@@ -1,6 +1,4 @@
-use web_sys::WebGl2RenderingContext as GL;
-
-use crate::{buffer_gpu::*, utils::to_bytes};
+use crate::{backend::Context as GL, buffer_gpu::*, utils::to_bytes};
 
 pub struct IndexBuffer {
     pub kind:   u32,
@@ -37,3 +35,31 @@ impl IndexBuffer {
         }
     }
 }
+
+impl IndexBuffer {
+    /// Reads the `count` indices starting at `offset` from the CPU copy of the buffer.
+    pub fn get_indices(&self) -> Vec<u32> {
+        let bytes = self.buffer.data();
+
+        let index_size = match self.kind {
+            GL::UNSIGNED_BYTE => 1,
+            GL::UNSIGNED_SHORT => 2,
+            _ => 4,
+        };
+
+        (0..self.count)
+            .filter_map(|i| {
+                let start = self.offset + i * index_size;
+                let index_bytes = bytes.get(start..start + index_size)?;
+
+                let index = match index_size {
+                    1 => index_bytes[0] as u32,
+                    2 => u16::from_le_bytes([index_bytes[0], index_bytes[1]]) as u32,
+                    _ => u32::from_le_bytes([index_bytes[0], index_bytes[1], index_bytes[2], index_bytes[3]]),
+                };
+
+                Some(index)
+            })
+            .collect()
+    }
+}
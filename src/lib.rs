@@ -1,12 +1,30 @@
+// Lets the derive macros refer to `::suricato` from inside the crate
+extern crate self as suricato;
+
+#[cfg(feature = "animation")]
 pub mod animation;
+pub mod backend;
+pub mod bounds;
 pub mod buffer_gpu;
+pub mod bvh;
 pub mod camera;
+pub mod error;
 pub mod geometry;
 pub mod index_buffer;
 pub mod material;
 pub mod mesh;
+#[cfg(feature = "obj")]
 pub mod obj_parser;
+pub mod picking;
+pub mod program_cache;
+pub mod raycast;
+pub mod readback;
+pub mod render_queue;
+pub mod render_state;
+pub mod render_target;
 pub mod renderer;
+pub mod shader;
+pub mod std140;
 pub mod texture;
 pub mod transform;
 pub mod ubo;
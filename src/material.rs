@@ -1,166 +1,696 @@
-use std::collections::HashMap;
-use web_sys::{WebGl2RenderingContext as GL, WebGlProgram, WebGlShader, WebGlUniformLocation};
+use std::{
+    cell::Cell,
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt,
+    rc::Rc,
+};
 
-use crate::{uniforms::Uniform, vertex_buffer::VertexLayout};
+use crate::{
+    backend::{Context as GL, ProgramHandle, ShaderHandle, UniformLocation},
+    error::{ErrorPolicy, ErrorReporter, SuricatoError},
+    program_cache::ProgramCache,
+    render_queue::RenderQueue,
+    render_state::RenderState,
+    shader::{
+        ShaderStage, SourceMap,
+        chunks::with_shader_chunks,
+        compile_error::ShaderCompileError,
+        preprocessor::{PreprocessError, PreprocessedSource, preprocess},
+        reflection::{GlslType, ProgramReflection},
+    },
+    texture::{TextureBindingCache, TextureError},
+    uniforms::{Uniform, UniformHandle},
+    utils::generate_id,
+    vertex_buffer::VertexLayout,
+};
 
 #[derive(Debug)]
 pub enum MaterialError {
     // Program
     ProgramCreationFailed,
     ProgramLinkingFailed(Option<String>),
+    TooManyTextureUnits { required: u32, available: u32 },
 
     // Shader
     ShaderCreationFailed,
-    ShaderCompilationFailed(Option<String>),
+    ShaderCompilationFailed(ShaderCompileError),
+    PreprocessError(PreprocessError),
+
+    TextureError(TextureError),
+}
+
+impl fmt::Display for MaterialError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MaterialError::ProgramCreationFailed => write!(f, "Failed to create the program"),
+            MaterialError::ProgramLinkingFailed(log) => write!(f, "Failed to link the program: {}", log.as_deref().unwrap_or("no log")),
+            MaterialError::TooManyTextureUnits { required, available } => {
+                write!(
+                    f,
+                    "The program samples {required} textures but only {available} texture units are available"
+                )
+            }
+            MaterialError::ShaderCreationFailed => write!(f, "Failed to create the shader"),
+            MaterialError::ShaderCompilationFailed(error) => write!(f, "{error}"),
+            MaterialError::PreprocessError(error) => write!(f, "{error}"),
+            MaterialError::TextureError(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl Error for MaterialError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MaterialError::ShaderCompilationFailed(error) => Some(error),
+            MaterialError::PreprocessError(error) => Some(error),
+            MaterialError::TextureError(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<PreprocessError> for MaterialError {
+    fn from(value: PreprocessError) -> Self {
+        MaterialError::PreprocessError(value)
+    }
+}
+
+impl From<TextureError> for MaterialError {
+    fn from(value: TextureError) -> Self {
+        MaterialError::TextureError(value)
+    }
 }
 
 pub struct Material {
-    pub uniforms:               HashMap<String, Uniform>,
+    pub id:                     u64,
+    /// In the order they were first set.
+    uniforms:                   Vec<MaterialUniform>,
     pub vertex_shader_source:   String,
     pub fragment_shader_source: String,
+    pub render_state:           RenderState,
+
+    /// Added to both shaders as `#define NAME VALUE`, see [`preprocess`].
+    /// Each combination is a variant with its own program, chosen when drawing.
+    pub defines: BTreeMap<String, String>,
+
+    /// When `None` the queue is derived from `render_state`: blended
+    /// materials are transparent and everything else is opaque.
+    /// Materials that `discard` fragments should use [`RenderQueue::AlphaTested`].
+    pub render_queue: Option<RenderQueue>,
 
     // WebGL resouces
-    pub resources: Option<MaterialResources>,
+    /// Resources of the current variant, shared with the materials using the same program.
+    pub resources:    Option<Rc<MaterialResources>>,
+    /// Every variant used so far, kept alive so switching back does not compile again.
+    variants:         Vec<Rc<MaterialResources>>,
+    /// Used when rendering without a renderer, keeps the sampler objects from being created again every frame.
+    texture_bindings: TextureBindingCache,
 }
 
 impl Material {
     pub fn new(vertex_shader_source: &str, fragment_shader_source: &str) -> Material {
         Material {
-            uniforms:               HashMap::new(),
+            id:                     generate_id(),
+            uniforms:               Vec::new(),
             vertex_shader_source:   String::from(vertex_shader_source),
             fragment_shader_source: String::from(fragment_shader_source),
+            render_state:           RenderState::default(),
+            defines:                BTreeMap::new(),
+            render_queue:           None,
             resources:              None,
+            variants:               Vec::new(),
+            texture_bindings:       TextureBindingCache::default(),
+        }
+    }
+
+    /// `uniform_name` is a path as written in GLSL: struct members like `light.color` or `lights[1].color`,
+    /// whole arrays like `bones` and single elements like `bones[3]`, array values being set from there on.
+    ///
+    /// Uniforms set every frame should be set through a [`UniformHandle`], see [`Material::get_uniform_handle`].
+    /// Values equal to the current one are not uploaded again.
+    pub fn set_uniform(&mut self, uniform_name: impl Into<UniformHandle>, uniform: Uniform) {
+        let handle = uniform_name.into();
+
+        match self.uniforms.iter_mut().find(|current| current.handle == handle) {
+            Some(current) => {
+                if !current.value.is_same_value(&uniform) {
+                    current.version = generate_id();
+                }
+
+                current.value = uniform;
+            }
+            None => {
+                self.uniforms.push(MaterialUniform {
+                    handle,
+                    value: uniform,
+                    version: generate_id(),
+                });
+            }
+        }
+    }
+
+    /// Resolves `uniform_name` once, the handle is valid for every material.
+    pub fn get_uniform_handle(&self, uniform_name: &str) -> UniformHandle {
+        UniformHandle::new(uniform_name)
+    }
+
+    pub fn get_uniform(&self, uniform_name: impl Into<UniformHandle>) -> Option<&Uniform> {
+        let handle = uniform_name.into();
+        self.uniforms
+            .iter()
+            .find(|uniform| uniform.handle == handle)
+            .map(|uniform| &uniform.value)
+    }
+
+    /// Uniforms in the order they were first set.
+    pub fn get_uniforms(&self) -> impl Iterator<Item = (UniformHandle, &Uniform)> {
+        self.uniforms.iter().map(|uniform| (uniform.handle, &uniform.value))
+    }
+
+    pub fn remove_uniform(&mut self, uniform_name: impl Into<UniformHandle>) -> Option<Uniform> {
+        let handle = uniform_name.into();
+        let index = self.uniforms.iter().position(|uniform| uniform.handle == handle)?;
+
+        Some(self.uniforms.remove(index).value)
+    }
+
+    /// Keeps the uniforms for which `keep` returns `true`.
+    pub fn retain_uniforms(&mut self, mut keep: impl FnMut(UniformHandle, &Uniform) -> bool) {
+        self.uniforms.retain(|uniform| keep(uniform.handle, &uniform.value));
+    }
+
+    /// Switches to the variant with this define on the next draw.
+    ///
+    /// Uniforms only used by other variants are reported as unknown, see [`ErrorPolicy`].
+    pub fn set_define(&mut self, name: &str, value: &str) {
+        self.defines.insert(String::from(name), String::from(value));
+    }
+
+    pub fn remove_define(&mut self, name: &str) {
+        self.defines.remove(name);
+    }
+
+    /// Sources of the current variant as given to the compiler.
+    pub fn preprocess(&self) -> Result<(PreprocessedSource, PreprocessedSource), PreprocessError> {
+        with_shader_chunks(|chunks| {
+            Ok((
+                preprocess(&self.vertex_shader_source, ShaderStage::Vertex, &self.defines, chunks)?,
+                preprocess(&self.fragment_shader_source, ShaderStage::Fragment, &self.defines, chunks)?,
+            ))
+        })
+    }
+
+    pub fn get_render_queue(&self) -> RenderQueue {
+        if let Some(render_queue) = self.render_queue {
+            return render_queue;
+        }
+
+        if self.render_state.blend.is_some() {
+            RenderQueue::Transparent
+        } else {
+            RenderQueue::Opaque
         }
     }
 
-    pub fn set_uniform(&mut self, uniform_name: &str, uniform: Uniform) {
-        self.uniforms.insert(String::from(uniform_name), uniform);
+    pub fn get_or_create_resources(&mut self, gl: &GL) -> &MaterialResources {
+        if let Err(error) = self.try_get_or_create_resources(gl) {
+            panic!("{error}");
+        }
+
+        self.resources.as_ref().unwrap()
+    }
+
+    /// Compiles a program of its own, see [`Material::try_get_or_create_cached_resources`] to share it.
+    pub fn try_get_or_create_resources(&mut self, gl: &GL) -> Result<&MaterialResources, MaterialError> {
+        self.try_get_or_create_cached_resources(gl, &mut ProgramCache::new())
+    }
+
+    /// Resources of the current variant, reusing the program of any material with the same shaders and defines.
+    pub fn try_get_or_create_cached_resources(
+        &mut self,
+        gl: &GL,
+        program_cache: &mut ProgramCache,
+    ) -> Result<&MaterialResources, MaterialError> {
+        if self.resources.as_ref().is_none_or(|resources| resources.defines != self.defines) {
+            let variant = self.variants.iter().find(|variant| variant.defines == self.defines).cloned();
+
+            let resources = match variant {
+                Some(resources) => resources,
+                None => {
+                    let resources = program_cache.get_or_create(gl, self, &HashMap::new())?;
+                    self.variants.push(resources.clone());
+                    resources
+                }
+            };
+
+            self.resources = Some(resources);
+        }
+
+        Ok(self.resources.as_ref().unwrap())
     }
 
+    /// Panics on any error, including uniforms the program does not use.
     pub fn on_before_render(&mut self, gl: &GL) {
-        if self.resources.is_none() {
-            self.resources = Some(MaterialResources::new(gl, &self).unwrap());
+        if let Err(error) = self.try_on_before_render(gl, &mut ErrorReporter::new(ErrorPolicy::Panic)) {
+            panic!("{error}");
         }
+    }
+
+    /// Uniforms the program does not use, or set with a value their type can not hold,
+    /// are skipped and reported to `error_reporter`.
+    ///
+    /// Binds every texture, see [`Material::try_on_before_render_cached`] to skip the ones already bound.
+    pub fn try_on_before_render(&mut self, gl: &GL, error_reporter: &mut ErrorReporter) -> Result<(), MaterialError> {
+        let mut texture_bindings = std::mem::take(&mut self.texture_bindings);
+        texture_bindings.invalidate();
+
+        let result = self.try_on_before_render_cached(gl, error_reporter, &mut texture_bindings);
+        self.texture_bindings = texture_bindings;
+        result
+    }
+
+    pub fn try_on_before_render_cached(
+        &mut self,
+        gl: &GL,
+        error_reporter: &mut ErrorReporter,
+        texture_binding_cache: &mut TextureBindingCache,
+    ) -> Result<(), MaterialError> {
+        gl.use_program(Some(self.try_get_or_create_resources(gl)?.program));
+        let resources = self.resources.as_ref().unwrap();
 
-        gl.use_program(Some(&self.resources.as_ref().unwrap().program));
+        // Upload textures before binding any, an upload would replace the texture bound to the active unit
+        for uniform in &mut self.uniforms {
+            if resources.get_texture_unit(uniform.handle).is_some() {
+                for texture in uniform.value.textures_mut() {
+                    texture_binding_cache.upload(gl, texture)?;
+                }
+            }
+        }
 
         // Set uniforms
-        let mut current_texture_unit = 0;
-        for (name, uniform) in &mut self.uniforms {
-            self.resources.as_ref().unwrap().set_uniform(&name, &uniform, current_texture_unit);
+        for uniform in &mut self.uniforms {
+            if let Err(error) = resources.set_uniform(uniform) {
+                error_reporter.report(error);
+                continue;
+            }
+
+            // Samplers read from the units assigned when linking, texture arrays from consecutive ones
+            let Some((texture_unit, target)) = resources.get_texture_unit(uniform.handle) else {
+                continue;
+            };
 
-            if let Uniform::Texture(texture) = uniform {
-                gl.bind_texture(GL::TEXTURE_2D, Some(texture.get_webgl_texture(gl).unwrap()));
-                current_texture_unit = current_texture_unit + 1;
+            let sampler = uniform.value.get_sampler();
+            for (texture, texture_unit) in uniform.value.textures_mut().iter_mut().zip(texture_unit..) {
+                texture_binding_cache.bind(gl, texture_unit, target, texture, sampler)?;
             }
         }
+
+        Ok(())
     }
+
+    /// Sets a uniform provided by the renderer, like the camera matrices,
+    /// only when the program of the current resources uses it.
+    pub(crate) fn set_renderer_uniform(&mut self, handle: UniformHandle, uniform: Uniform) {
+        if self.resources.as_ref().is_some_and(|resources| resources.has_uniform(handle)) {
+            self.set_uniform(handle, uniform);
+        }
+    }
+}
+
+/// Value set on a material, `version` changes with the value so programs know when to upload it.
+#[derive(Debug, Clone)]
+struct MaterialUniform {
+    handle:  UniformHandle,
+    value:   Uniform,
+    /// Unique across materials, as materials with the same shaders share their program.
+    version: u64,
 }
 
 pub struct MaterialResources {
     gl:                      GL,
-    program:                 WebGlProgram,
-    uniform_locations:       HashMap<String, WebGlUniformLocation>,
+    program:                 ProgramHandle,
+    defines:                 BTreeMap<String, String>,
+    /// Indexed by [`UniformHandle`].
+    uniform_locations:       Vec<Option<ActiveUniform>>,
     attribute_locations:     HashMap<String, u32>,
     uniform_block_locations: HashMap<String, u32>,
+    reflection:              ProgramReflection,
 }
 
 impl MaterialResources {
     pub fn new(gl: &GL, material: &Material) -> Result<MaterialResources, MaterialError> {
-        let program = gl.create_program().ok_or_else(|| MaterialError::ProgramCreationFailed)?;
+        MaterialResources::with_attribute_locations(gl, material, &HashMap::new())
+    }
 
-        let vertex_shader = MaterialResources::compile_shader(gl, &material.vertex_shader_source, GL::VERTEX_SHADER)?;
-        let fragment_shader = MaterialResources::compile_shader(gl, &material.fragment_shader_source, GL::FRAGMENT_SHADER)?;
+    /// Binds the given attributes to fixed locations before linking, so a
+    /// vertex array object created for another program can be reused.
+    pub fn with_attribute_locations(
+        gl: &GL,
+        material: &Material,
+        attribute_locations: &HashMap<String, u32>,
+    ) -> Result<MaterialResources, MaterialError> {
+        let (vertex_shader_source, fragment_shader_source) = material.preprocess()?;
+
+        MaterialResources::from_sources(
+            gl,
+            &vertex_shader_source,
+            &fragment_shader_source,
+            &material.defines,
+            attribute_locations,
+        )
+    }
+
+    pub(crate) fn from_sources(
+        gl: &GL,
+        vertex_shader_source: &PreprocessedSource,
+        fragment_shader_source: &PreprocessedSource,
+        defines: &BTreeMap<String, String>,
+        attribute_locations: &HashMap<String, u32>,
+    ) -> Result<MaterialResources, MaterialError> {
+        let program = gl.create_program().ok_or(MaterialError::ProgramCreationFailed)?;
+
+        let vertex_shader = MaterialResources::compile_shader(
+            gl,
+            &vertex_shader_source.source,
+            &vertex_shader_source.source_map,
+            ShaderStage::Vertex,
+        )?;
+        let fragment_shader = MaterialResources::compile_shader(
+            gl,
+            &fragment_shader_source.source,
+            &fragment_shader_source.source_map,
+            ShaderStage::Fragment,
+        )?;
+
+        gl.attach_shader(program, vertex_shader);
+        gl.attach_shader(program, fragment_shader);
+
+        for (name, location) in attribute_locations {
+            gl.bind_attrib_location(program, *location, name);
+        }
 
-        gl.attach_shader(&program, &vertex_shader);
-        gl.attach_shader(&program, &fragment_shader);
-        gl.link_program(&program);
+        gl.link_program(program);
 
-        let program_link_status_is_ok = gl.get_program_parameter(&program, GL::LINK_STATUS).as_bool().unwrap_or(false);
+        let program_link_status_is_ok = gl.get_program_link_status(program);
 
         if !program_link_status_is_ok {
-            return Err(MaterialError::ProgramLinkingFailed(gl.get_program_info_log(&program)));
+            return Err(MaterialError::ProgramLinkingFailed(gl.get_program_info_log(program)));
         }
 
-        let uniform_locations = MaterialResources::get_uniform_locations(gl, &program);
-        let attribute_locations = MaterialResources::get_attribute_locations(gl, &program);
-        let uniform_block_locations = MaterialResources::get_uniform_block_locations(gl, &program);
+        // Sampler units are set while reading the uniforms
+        gl.use_program(Some(program));
+        let uniform_locations = MaterialResources::get_uniform_locations(gl, program)?;
+        let attribute_locations = MaterialResources::get_attribute_locations(gl, program);
+        let uniform_block_locations = MaterialResources::get_uniform_block_locations(gl, program);
+        let reflection = ProgramReflection::from_program(gl, program);
 
         Ok(MaterialResources {
             gl: gl.clone(),
             program,
+            defines: defines.clone(),
             uniform_locations,
             attribute_locations,
             uniform_block_locations,
+            reflection,
         })
     }
 
-    fn compile_shader(gl: &GL, shader_source: &str, shader_type: u32) -> Result<WebGlShader, MaterialError> {
-        let shader = gl.create_shader(shader_type).ok_or_else(|| MaterialError::ShaderCreationFailed)?;
-        gl.shader_source(&shader, &shader_source);
-        gl.compile_shader(&shader);
-        let shader_status_is_ok = gl.get_shader_parameter(&shader, GL::COMPILE_STATUS).as_bool().unwrap_or(false);
+    fn compile_shader(gl: &GL, shader_source: &str, source_map: &SourceMap, stage: ShaderStage) -> Result<ShaderHandle, MaterialError> {
+        let shader = gl.create_shader(stage as u32).ok_or(MaterialError::ShaderCreationFailed)?;
+        gl.shader_source(shader, shader_source);
+        gl.compile_shader(shader);
+        let shader_status_is_ok = gl.get_shader_compile_status(shader);
 
         if shader_status_is_ok {
             Ok(shader)
         } else {
-            Err(MaterialError::ShaderCompilationFailed(gl.get_shader_info_log(&shader)))
+            let log = gl.get_shader_info_log(shader);
+            Err(MaterialError::ShaderCompilationFailed(ShaderCompileError::new(
+                stage,
+                log,
+                shader_source,
+                source_map,
+            )))
         }
     }
 
     /// UNIFORMS
-    fn set_uniform(&self, uniform_name: &str, uniform: &Uniform, current_texture_unit: u32) {
-        let location = self.uniform_locations.get(uniform_name).unwrap();
-
-        match uniform {
-            Uniform::Float(v) => self.gl.uniform1f(Some(location), *v),
-            Uniform::Vec2(v) => self.gl.uniform2fv_with_f32_array(Some(location), v),
-            Uniform::Vec3(v) => self.gl.uniform3fv_with_f32_array(Some(location), v),
-            Uniform::Vec4(v) => self.gl.uniform4fv_with_f32_array(Some(location), v),
-
-            Uniform::Int(v) => self.gl.uniform1i(Some(location), *v),
-            Uniform::IntVec2(v) => self.gl.uniform2iv_with_i32_array(Some(location), v),
-            Uniform::IntVec3(v) => self.gl.uniform3iv_with_i32_array(Some(location), v),
-            Uniform::IntVec4(v) => self.gl.uniform4iv_with_i32_array(Some(location), v),
-
-            Uniform::UnsignedInt(v) => self.gl.uniform1ui(Some(location), *v),
-            Uniform::UnsignedIntVec2(v) => self.gl.uniform2uiv_with_u32_array(Some(location), v),
-            Uniform::UnsignedIntVec3(v) => self.gl.uniform2uiv_with_u32_array(Some(location), v),
-            Uniform::UnsignedIntVec4(v) => self.gl.uniform2uiv_with_u32_array(Some(location), v),
-
-            Uniform::Mat2(v) => self.gl.uniform_matrix2fv_with_f32_array(Some(location), false, v),
-            Uniform::Mat3(v) => self.gl.uniform_matrix3fv_with_f32_array(Some(location), false, v),
-            Uniform::Mat4(v) => self.gl.uniform_matrix4fv_with_f32_array(Some(location), false, v),
-
-            Uniform::Texture(_) => {
-                self.gl.uniform1i(Some(location), current_texture_unit as i32);
-                self.gl.active_texture(GL::TEXTURE0 + current_texture_unit);
+    /// Sends `uniform` with the call matching the declared type of the uniform.
+    ///
+    /// Booleans accept any value with the same number of components, as in GL. Integers are converted
+    /// between signed and unsigned, and to floats, when every component is represented exactly.
+    /// Arrays are set from the element `uniform_name` refers to and may be shorter than the declaration.
+    ///
+    /// Skipped when the program already holds this version of the value. Textures are only checked
+    /// against the sampler type, their units are set when linking.
+    fn set_uniform(&self, material_uniform: &MaterialUniform) -> Result<(), SuricatoError> {
+        let uniform_name = || String::from(&*material_uniform.handle.get_name());
+        let active_uniform = self
+            .get_active_uniform(material_uniform.handle)
+            .ok_or_else(|| SuricatoError::UnknownUniform(uniform_name()))?;
+
+        if active_uniform.uploaded.get() == Some(material_uniform.version) {
+            return Ok(());
+        }
+
+        let uniform = &material_uniform.value;
+
+        let location = Some(active_uniform.location);
+        // Types the reflection does not know are trusted to match the value
+        let kind = active_uniform.kind.unwrap_or_else(|| uniform.glsl_type());
+
+        let mismatch = || {
+            SuricatoError::UniformTypeMismatch {
+                name:     uniform_name(),
+                expected: kind,
+                found:    uniform.glsl_type(),
             }
+        };
+
+        let length = uniform.element_count();
+        if length > active_uniform.size as usize {
+            return Err(SuricatoError::UniformArrayTooLong {
+                name: uniform_name(),
+                capacity: active_uniform.size as usize,
+                length,
+            });
+        }
+
+        // GL rejects empty arrays
+        if length == 0 {
+            return Ok(());
+        }
+
+        match (uniform, kind) {
+            (Uniform::Mat2(v), GlslType::Mat2) => self.gl.uniform_matrix2fv(location, false, v),
+            (Uniform::Mat3(v), GlslType::Mat3) => self.gl.uniform_matrix3fv(location, false, v),
+            (Uniform::Mat4(v), GlslType::Mat4) => self.gl.uniform_matrix4fv(location, false, v),
+            (Uniform::Mat2Array(v), GlslType::Mat2) => self.gl.uniform_matrix2fv(location, false, v.as_flattened()),
+            (Uniform::Mat3Array(v), GlslType::Mat3) => self.gl.uniform_matrix3fv(location, false, v.as_flattened()),
+            (Uniform::Mat4Array(v), GlslType::Mat4) => self.gl.uniform_matrix4fv(location, false, v.as_flattened()),
+
+            // Textures are always two-dimensional
+            (Uniform::Texture(_) | Uniform::SampledTexture(..) | Uniform::TextureArray(_), kind)
+                if kind.texture_target() == Some(GL::TEXTURE_2D) => {}
+
+            _ => {
+                let (scalar, count) = vector_type(kind).ok_or_else(mismatch)?;
+                let components = Components::of(uniform)
+                    .filter(|components| components.len() == count * length)
+                    .ok_or_else(mismatch)?;
+
+                match (components, scalar) {
+                    (Components::Float(v), ScalarType::Float | ScalarType::Bool) => self.uniform_f32(location, count, v),
+                    (Components::Int(v), ScalarType::Int | ScalarType::Bool) => self.uniform_i32(location, count, v),
+                    (Components::UnsignedInt(v), ScalarType::UnsignedInt | ScalarType::Bool) => self.uniform_u32(location, count, v),
+
+                    (Components::Int(v), ScalarType::UnsignedInt) => {
+                        let v: Vec<u32> = v
+                            .iter()
+                            .map(|&v| u32::try_from(v).ok())
+                            .collect::<Option<_>>()
+                            .ok_or_else(mismatch)?;
+                        self.uniform_u32(location, count, &v);
+                    }
+                    (Components::UnsignedInt(v), ScalarType::Int) => {
+                        let v: Vec<i32> = v
+                            .iter()
+                            .map(|&v| i32::try_from(v).ok())
+                            .collect::<Option<_>>()
+                            .ok_or_else(mismatch)?;
+                        self.uniform_i32(location, count, &v);
+                    }
+                    (Components::Int(v), ScalarType::Float) => {
+                        let v: Vec<f32> = v.iter().map(|&v| exact_f32(v as i64)).collect::<Option<_>>().ok_or_else(mismatch)?;
+                        self.uniform_f32(location, count, &v);
+                    }
+                    (Components::UnsignedInt(v), ScalarType::Float) => {
+                        let v: Vec<f32> = v.iter().map(|&v| exact_f32(v as i64)).collect::<Option<_>>().ok_or_else(mismatch)?;
+                        self.uniform_f32(location, count, &v);
+                    }
+
+                    _ => return Err(mismatch()),
+                }
+            }
+        }
+
+        active_uniform.uploaded.set(Some(material_uniform.version));
+
+        Ok(())
+    }
+
+    /// `values` holds one or more elements of `count` components each.
+    fn uniform_f32(&self, location: Option<UniformLocation>, count: usize, values: &[f32]) {
+        match (count, values) {
+            (1, [value]) => self.gl.uniform1f(location, *value),
+            (1, _) => self.gl.uniform1fv(location, values),
+            (2, _) => self.gl.uniform2fv(location, values),
+            (3, _) => self.gl.uniform3fv(location, values),
+            _ => self.gl.uniform4fv(location, values),
+        }
+    }
+
+    fn uniform_i32(&self, location: Option<UniformLocation>, count: usize, values: &[i32]) {
+        match (count, values) {
+            (1, [value]) => self.gl.uniform1i(location, *value),
+            (1, _) => self.gl.uniform1iv(location, values),
+            (2, _) => self.gl.uniform2iv(location, values),
+            (3, _) => self.gl.uniform3iv(location, values),
+            _ => self.gl.uniform4iv(location, values),
+        }
+    }
+
+    fn uniform_u32(&self, location: Option<UniformLocation>, count: usize, values: &[u32]) {
+        match (count, values) {
+            (1, [value]) => self.gl.uniform1ui(location, *value),
+            (1, _) => self.gl.uniform1uiv(location, values),
+            (2, _) => self.gl.uniform2uiv(location, values),
+            (3, _) => self.gl.uniform3uiv(location, values),
+            _ => self.gl.uniform4uiv(location, values),
         }
     }
 
-    fn get_uniform_locations(gl: &GL, program: &WebGlProgram) -> HashMap<String, WebGlUniformLocation> {
-        let mut uniform_locations = HashMap::new();
+    /// Samplers get consecutive texture units in the order the program reports them, so the units of
+    /// a program never change. They are set here and materials only bind their textures to them.
+    fn get_uniform_locations(gl: &GL, program: ProgramHandle) -> Result<Vec<Option<ActiveUniform>>, MaterialError> {
+        let mut uniform_locations = Vec::new();
+        let mut insert = |name: &str, active_uniform: ActiveUniform| {
+            let index = UniformHandle::new(name).index();
+
+            if uniform_locations.len() <= index {
+                uniform_locations.resize_with(index + 1, || None);
+            }
+
+            uniform_locations[index] = Some(active_uniform);
+        };
+
+        let mut samplers = Vec::new();
+        let mut texture_unit_count = 0;
 
-        let number_of_uniforms = gl
-            .get_program_parameter(&program, GL::ACTIVE_UNIFORMS)
-            .as_f64()
-            .expect("Unable to get the number of uniforms");
+        let number_of_uniforms = gl.get_program_parameter(program, GL::ACTIVE_UNIFORMS);
 
         for i in 0..number_of_uniforms as u32 {
-            let uniform = gl.get_active_uniform(&program, i).unwrap();
-            let uniform_name = uniform.name();
+            let uniform = gl.get_active_uniform(program, i).unwrap();
+            let kind = GlslType::from_gl(uniform.kind);
+            let size = uniform.size.max(1) as u32;
 
             // Uniforms inside uniform blocks do not have locations
-            if let Some(location) = gl.get_uniform_location(&program, &uniform_name) {
-                uniform_locations.insert(uniform_name, location);
+            let Some(location) = gl.get_uniform_location(program, &uniform.name) else {
+                continue;
+            };
+
+            let mut texture_unit = None;
+            if kind.is_some_and(|kind| kind.is_sampler()) {
+                texture_unit = Some(texture_unit_count);
+                samplers.push((location, texture_unit_count, size));
+                texture_unit_count += size;
+            }
+
+            // Every name of an array writes the same storage, so they share the version uploaded last
+            let uploaded = Rc::new(Cell::new(None));
+            let active_uniform = |location, size, texture_unit| {
+                ActiveUniform {
+                    location,
+                    kind,
+                    size,
+                    texture_unit,
+                    uploaded: uploaded.clone(),
+                }
+            };
+
+            // Arrays are reported as `name[0]`, they are set by name or from any element like `bones[3]`
+            if let Some(base_name) = uniform.name.strip_suffix("[0]") {
+                for element in 1..size {
+                    let element_name = format!("{base_name}[{element}]");
+
+                    if let Some(location) = gl.get_uniform_location(program, &element_name) {
+                        let texture_unit = texture_unit.map(|texture_unit| texture_unit + element);
+                        insert(&element_name, active_uniform(location, size - element, texture_unit));
+                    }
+                }
+
+                insert(base_name, active_uniform(location, size, texture_unit));
+            }
+
+            insert(&uniform.name, active_uniform(location, size, texture_unit));
+        }
+
+        let available = gl.get_parameter_i32(GL::MAX_COMBINED_TEXTURE_IMAGE_UNITS).max(0) as u32;
+        if texture_unit_count > available {
+            return Err(MaterialError::TooManyTextureUnits {
+                required: texture_unit_count,
+                available,
+            });
+        }
+
+        for (location, texture_unit, size) in samplers {
+            let texture_units: Vec<i32> = (texture_unit..texture_unit + size)
+                .map(|texture_unit| texture_unit as i32)
+                .collect();
+
+            match texture_units.as_slice() {
+                [texture_unit] => gl.uniform1i(Some(location), *texture_unit),
+                texture_units => gl.uniform1iv(Some(location), texture_units),
             }
         }
 
-        uniform_locations
+        Ok(uniform_locations)
+    }
+
+    fn get_active_uniform(&self, handle: UniformHandle) -> Option<&ActiveUniform> {
+        self.uniform_locations.get(handle.index())?.as_ref()
+    }
+
+    /// First texture unit and target of a sampler uniform.
+    pub fn get_texture_unit(&self, uniform_name: impl Into<UniformHandle>) -> Option<(u32, u32)> {
+        let active_uniform = self.get_active_uniform(uniform_name.into())?;
+        active_uniform.texture_unit.zip(active_uniform.kind?.texture_target())
+    }
+
+    pub fn get_program(&self) -> ProgramHandle {
+        self.program
+    }
+
+    /// Defines of the variant this program was compiled for.
+    pub fn get_defines(&self) -> &BTreeMap<String, String> {
+        &self.defines
+    }
+
+    /// Active uniforms, attributes and uniform blocks with their types, as reported when linking.
+    pub fn get_reflection(&self) -> &ProgramReflection {
+        &self.reflection
+    }
+
+    pub fn has_uniform(&self, uniform_name: impl Into<UniformHandle>) -> bool {
+        self.get_active_uniform(uniform_name.into()).is_some()
     }
 
     /// ATTRIBUTES
+    pub fn attribute_locations(&self) -> &HashMap<String, u32> {
+        &self.attribute_locations
+    }
+
     pub fn set_attribute_buffer(&self, vertex_layout: &VertexLayout) {
         if self.attribute_locations.get(&vertex_layout.name).is_none() {
             return;
@@ -170,7 +700,7 @@ impl MaterialResources {
 
         if vertex_layout.number_of_columns == 1 {
             self.gl.enable_vertex_attrib_array(*location);
-            self.gl.vertex_attrib_pointer_with_i32(
+            self.gl.vertex_attrib_pointer(
                 *location,
                 vertex_layout.component_count as i32,
                 vertex_layout.component_type as u32,
@@ -194,7 +724,7 @@ impl MaterialResources {
             let offset = vertex_layout.offset + (i * components_per_column * vertex_layout.component_type.size_in_bytes()) as usize;
 
             self.gl.enable_vertex_attrib_array(column_location);
-            self.gl.vertex_attrib_pointer_with_i32(
+            self.gl.vertex_attrib_pointer(
                 column_location,
                 components_per_column as i32,
                 vertex_layout.component_type as u32,
@@ -209,17 +739,14 @@ impl MaterialResources {
         }
     }
 
-    fn get_attribute_locations(gl: &GL, program: &WebGlProgram) -> HashMap<String, u32> {
+    fn get_attribute_locations(gl: &GL, program: ProgramHandle) -> HashMap<String, u32> {
         let mut attribute_locations = HashMap::new();
 
-        let number_of_attributes = gl
-            .get_program_parameter(&program, GL::ACTIVE_ATTRIBUTES)
-            .as_f64()
-            .expect("Unable to get the number of attributes");
+        let number_of_attributes = gl.get_program_parameter(program, GL::ACTIVE_ATTRIBUTES);
 
         for i in 0..number_of_attributes as u32 {
             let attribute = gl.get_active_attrib(program, i).unwrap();
-            let attribute_name = attribute.name();
+            let attribute_name = attribute.name;
 
             let location = gl.get_attrib_location(program, &attribute_name) as u32;
             attribute_locations.insert(attribute_name, location);
@@ -229,9 +756,9 @@ impl MaterialResources {
     }
 
     /// UNIFORM BLOCKS
-    pub fn get_uniform_block_locations(gl: &GL, program: &WebGlProgram) -> HashMap<String, u32> {
+    pub fn get_uniform_block_locations(gl: &GL, program: ProgramHandle) -> HashMap<String, u32> {
         let mut uniform_block_locations = HashMap::new();
-        let number_of_uniform_blocks = gl.get_program_parameter(program, GL::ACTIVE_UNIFORM_BLOCKS).as_f64().unwrap() as u32;
+        let number_of_uniform_blocks = gl.get_program_parameter(program, GL::ACTIVE_UNIFORM_BLOCKS) as u32;
 
         for uniform_block_location in 0..number_of_uniform_blocks {
             let name = gl.get_active_uniform_block_name(program, uniform_block_location).unwrap();
@@ -246,12 +773,666 @@ impl MaterialResources {
             return;
         };
 
-        self.gl.uniform_block_binding(&self.program, *block_location, ubo_binding_point);
+        self.gl.uniform_block_binding(self.program, *block_location, ubo_binding_point);
     }
 }
 
+/// Location and declared type of a uniform set one by one.
+#[derive(Debug)]
+struct ActiveUniform {
+    location:     UniformLocation,
+    kind:         Option<GlslType>,
+    /// Elements from this one to the end of the array, `1` for single values.
+    size:         u32,
+    /// Unit of the first element for samplers.
+    texture_unit: Option<u32>,
+    /// Version of the material uniform sent last through any name of the same array.
+    /// Versions are unique across uniforms, so a write through another name invalidates it.
+    uploaded:     Rc<Cell<Option<u64>>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScalarType {
+    Float,
+    Int,
+    UnsignedInt,
+    Bool,
+}
+
+/// Scalar type and number of components of scalar and vector types.
+fn vector_type(kind: GlslType) -> Option<(ScalarType, usize)> {
+    let vector_type = match kind {
+        GlslType::Float => (ScalarType::Float, 1),
+        GlslType::Vec2 => (ScalarType::Float, 2),
+        GlslType::Vec3 => (ScalarType::Float, 3),
+        GlslType::Vec4 => (ScalarType::Float, 4),
+        GlslType::Int => (ScalarType::Int, 1),
+        GlslType::IntVec2 => (ScalarType::Int, 2),
+        GlslType::IntVec3 => (ScalarType::Int, 3),
+        GlslType::IntVec4 => (ScalarType::Int, 4),
+        GlslType::UnsignedInt => (ScalarType::UnsignedInt, 1),
+        GlslType::UnsignedIntVec2 => (ScalarType::UnsignedInt, 2),
+        GlslType::UnsignedIntVec3 => (ScalarType::UnsignedInt, 3),
+        GlslType::UnsignedIntVec4 => (ScalarType::UnsignedInt, 4),
+        GlslType::Bool => (ScalarType::Bool, 1),
+        GlslType::BoolVec2 => (ScalarType::Bool, 2),
+        GlslType::BoolVec3 => (ScalarType::Bool, 3),
+        GlslType::BoolVec4 => (ScalarType::Bool, 4),
+        _ => return None,
+    };
+
+    Some(vector_type)
+}
+
+/// Components of scalar and vector uniforms, of every element for arrays.
+enum Components<'a> {
+    Float(&'a [f32]),
+    Int(&'a [i32]),
+    UnsignedInt(&'a [u32]),
+}
+
+impl Components<'_> {
+    fn of(uniform: &Uniform) -> Option<Components<'_>> {
+        let components = match uniform {
+            Uniform::Float(v) => Components::Float(std::slice::from_ref(v)),
+            Uniform::Vec2(v) => Components::Float(v),
+            Uniform::Vec3(v) => Components::Float(v),
+            Uniform::Vec4(v) => Components::Float(v),
+            Uniform::Int(v) => Components::Int(std::slice::from_ref(v)),
+            Uniform::IntVec2(v) => Components::Int(v),
+            Uniform::IntVec3(v) => Components::Int(v),
+            Uniform::IntVec4(v) => Components::Int(v),
+            Uniform::UnsignedInt(v) => Components::UnsignedInt(std::slice::from_ref(v)),
+            Uniform::UnsignedIntVec2(v) => Components::UnsignedInt(v),
+            Uniform::UnsignedIntVec3(v) => Components::UnsignedInt(v),
+            Uniform::UnsignedIntVec4(v) => Components::UnsignedInt(v),
+            Uniform::FloatArray(v) => Components::Float(v),
+            Uniform::Vec2Array(v) => Components::Float(v.as_flattened()),
+            Uniform::Vec3Array(v) => Components::Float(v.as_flattened()),
+            Uniform::Vec4Array(v) => Components::Float(v.as_flattened()),
+            Uniform::IntArray(v) => Components::Int(v),
+            Uniform::IntVec2Array(v) => Components::Int(v.as_flattened()),
+            Uniform::IntVec3Array(v) => Components::Int(v.as_flattened()),
+            Uniform::IntVec4Array(v) => Components::Int(v.as_flattened()),
+            Uniform::UnsignedIntArray(v) => Components::UnsignedInt(v),
+            Uniform::UnsignedIntVec2Array(v) => Components::UnsignedInt(v.as_flattened()),
+            Uniform::UnsignedIntVec3Array(v) => Components::UnsignedInt(v.as_flattened()),
+            Uniform::UnsignedIntVec4Array(v) => Components::UnsignedInt(v.as_flattened()),
+            Uniform::Mat2(_)
+            | Uniform::Mat3(_)
+            | Uniform::Mat4(_)
+            | Uniform::Mat2Array(_)
+            | Uniform::Mat3Array(_)
+            | Uniform::Mat4Array(_)
+            | Uniform::Texture(_)
+            | Uniform::SampledTexture(..)
+            | Uniform::TextureArray(_) => return None,
+        };
+
+        Some(components)
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Components::Float(v) => v.len(),
+            Components::Int(v) => v.len(),
+            Components::UnsignedInt(v) => v.len(),
+        }
+    }
+}
+
+/// `value` as a float when it is represented exactly, integers up to 2^24 always are.
+fn exact_f32(value: i64) -> Option<f32> {
+    let float = value as f32;
+    (float as i64 == value).then_some(float)
+}
+
 impl Drop for MaterialResources {
     fn drop(&mut self) {
-        self.gl.delete_program(Some(&self.program));
+        self.gl.delete_program(Some(self.program));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{
+        backend::mock::{MockContext, MockUniformValue},
+        texture::{ImagePixelData, MagnificationFilter, MinificationFilter, Sampler, Texture, TextureData},
+    };
+
+    const VERTEX_SHADER: &str = "in vec3 position;\nvoid main() {}\n";
+    const FRAGMENT_SHADER: &str = "
+uniform float float_value;
+uniform vec2 vec2_value;
+uniform vec3 vec3_value;
+uniform vec4 vec4_value;
+uniform int int_value;
+uniform ivec2 ivec2_value;
+uniform ivec3 ivec3_value;
+uniform ivec4 ivec4_value;
+uniform uint uint_value;
+uniform uvec2 uvec2_value;
+uniform uvec3 uvec3_value;
+uniform uvec4 uvec4_value;
+uniform bool bool_value;
+uniform bvec3 bvec3_value;
+uniform mat2 mat2_value;
+uniform mat3 mat3_value;
+uniform mat4 mat4_value;
+uniform sampler2D texture_value;
+uniform float float_array[4];
+uniform vec3 lights[8];
+uniform ivec2 ivec2_array[2];
+uniform uint uint_array[3];
+uniform mat4 mat4_array[2];
+uniform sampler2D texture_array[3];
+uniform samplerCube cube_value;
+out vec4 color;
+void main() {}
+";
+
+    fn texture() -> Texture {
+        Texture::new(TextureData::ImagePixelData(ImagePixelData {
+            width:  1,
+            height: 1,
+            bytes:  vec![255; 4],
+        }))
+    }
+
+    /// Renders a material with the uniform `name` set to `uniform` and returns the calls made to set it.
+    fn set_uniform(name: &str, uniform: Uniform) -> (Vec<&'static str>, Option<MockUniformValue>, Vec<String>) {
+        let mock = Rc::new(MockContext::new(8, 8));
+        let gl = mock.context();
+
+        let mut material = Material::new(VERTEX_SHADER, FRAGMENT_SHADER);
+        let program = material.get_or_create_resources(&gl).get_program();
+        let calls_before = mock.state().calls.len();
+
+        material.set_uniform(name, uniform);
+        let mut error_reporter = ErrorReporter::new(ErrorPolicy::LogOnce);
+        material.try_on_before_render(&gl, &mut error_reporter).unwrap();
+
+        let state = mock.state();
+        let uniform_calls = state.calls[calls_before..]
+            .iter()
+            .filter(|call| call.starts_with("uniform"))
+            .copied()
+            .collect();
+        let errors = error_reporter.get_reported().map(String::from).collect();
+
+        (uniform_calls, state.uniform_value(program.0, name).cloned(), errors)
+    }
+
+    #[test]
+    fn sends_every_variant_with_the_matching_call() {
+        use MockUniformValue::{Float, Int, Matrix, UnsignedInt};
+
+        let cases = [
+            ("float_value", Uniform::Float(1.0), "uniform1f", Float(vec![1.0])),
+            ("vec2_value", Uniform::Vec2([1.0, 2.0]), "uniform2fv", Float(vec![1.0, 2.0])),
+            (
+                "vec3_value",
+                Uniform::Vec3([1.0, 2.0, 3.0]),
+                "uniform3fv",
+                Float(vec![1.0, 2.0, 3.0]),
+            ),
+            (
+                "vec4_value",
+                Uniform::Vec4([1.0, 2.0, 3.0, 4.0]),
+                "uniform4fv",
+                Float(vec![1.0, 2.0, 3.0, 4.0]),
+            ),
+            ("int_value", Uniform::Int(-1), "uniform1i", Int(vec![-1])),
+            ("ivec2_value", Uniform::IntVec2([-1, 2]), "uniform2iv", Int(vec![-1, 2])),
+            ("ivec3_value", Uniform::IntVec3([-1, 2, 3]), "uniform3iv", Int(vec![-1, 2, 3])),
+            ("ivec4_value", Uniform::IntVec4([-1, 2, 3, 4]), "uniform4iv", Int(vec![-1, 2, 3, 4])),
+            ("uint_value", Uniform::UnsignedInt(1), "uniform1ui", UnsignedInt(vec![1])),
+            (
+                "uvec2_value",
+                Uniform::UnsignedIntVec2([1, 2]),
+                "uniform2uiv",
+                UnsignedInt(vec![1, 2]),
+            ),
+            (
+                "uvec3_value",
+                Uniform::UnsignedIntVec3([1, 2, 3]),
+                "uniform3uiv",
+                UnsignedInt(vec![1, 2, 3]),
+            ),
+            (
+                "uvec4_value",
+                Uniform::UnsignedIntVec4([1, 2, 3, 4]),
+                "uniform4uiv",
+                UnsignedInt(vec![1, 2, 3, 4]),
+            ),
+            ("mat2_value", Uniform::Mat2([1.0; 4]), "uniform_matrix2fv", Matrix(vec![1.0; 4])),
+            ("mat3_value", Uniform::Mat3([1.0; 9]), "uniform_matrix3fv", Matrix(vec![1.0; 9])),
+            ("mat4_value", Uniform::Mat4([1.0; 16]), "uniform_matrix4fv", Matrix(vec![1.0; 16])),
+        ];
+
+        for (name, uniform, expected_call, expected_value) in cases {
+            let (calls, value, errors) = set_uniform(name, uniform);
+
+            assert_eq!(calls, [expected_call], "{name}");
+            assert_eq!(value, Some(expected_value), "{name}");
+            assert!(errors.is_empty(), "{name}: {errors:?}");
+        }
+    }
+
+    #[test]
+    fn converts_values_without_loss() {
+        use MockUniformValue::{Float, Int, UnsignedInt};
+
+        let cases = [
+            // Booleans accept any scalar type, as in GL
+            ("bool_value", Uniform::Float(1.0), "uniform1f", Float(vec![1.0])),
+            ("bool_value", Uniform::Int(1), "uniform1i", Int(vec![1])),
+            ("bool_value", Uniform::UnsignedInt(1), "uniform1ui", UnsignedInt(vec![1])),
+            ("bvec3_value", Uniform::IntVec3([1, 0, 1]), "uniform3iv", Int(vec![1, 0, 1])),
+            // Integers fitting the declared type
+            ("uint_value", Uniform::Int(7), "uniform1ui", UnsignedInt(vec![7])),
+            (
+                "uvec4_value",
+                Uniform::IntVec4([1, 2, 3, 4]),
+                "uniform4uiv",
+                UnsignedInt(vec![1, 2, 3, 4]),
+            ),
+            ("ivec2_value", Uniform::UnsignedIntVec2([1, 2]), "uniform2iv", Int(vec![1, 2])),
+            ("float_value", Uniform::Int(-16_777_216), "uniform1f", Float(vec![-16_777_216.0])),
+            (
+                "vec3_value",
+                Uniform::UnsignedIntVec3([1, 2, 3]),
+                "uniform3fv",
+                Float(vec![1.0, 2.0, 3.0]),
+            ),
+        ];
+
+        for (name, uniform, expected_call, expected_value) in cases {
+            let (calls, value, errors) = set_uniform(name, uniform);
+
+            assert_eq!(calls, [expected_call], "{name}");
+            assert_eq!(value, Some(expected_value), "{name}");
+            assert!(errors.is_empty(), "{name}: {errors:?}");
+        }
+    }
+
+    #[test]
+    fn reports_mismatched_values() {
+        let cases = [
+            ("vec4_value", Uniform::Float(1.0), "float"),
+            ("vec4_value", Uniform::Vec2([1.0; 2]), "vec2"),
+            ("vec4_value", Uniform::Vec3([1.0; 3]), "vec3"),
+            ("float_value", Uniform::Vec4([1.0; 4]), "vec4"),
+            ("float_value", Uniform::IntVec2([1; 2]), "ivec2"),
+            ("int_value", Uniform::Float(1.0), "float"),
+            ("int_value", Uniform::IntVec3([1; 3]), "ivec3"),
+            ("ivec3_value", Uniform::IntVec4([1; 4]), "ivec4"),
+            ("uint_value", Uniform::Int(-1), "int"),
+            ("uvec2_value", Uniform::UnsignedInt(1), "uint"),
+            ("int_value", Uniform::UnsignedInt(u32::MAX), "uint"),
+            ("float_value", Uniform::Int(16_777_217), "int"),
+            ("uvec2_value", Uniform::UnsignedIntVec3([1; 3]), "uvec3"),
+            ("uvec3_value", Uniform::UnsignedIntVec4([1; 4]), "uvec4"),
+            ("bvec3_value", Uniform::UnsignedIntVec2([1; 2]), "uvec2"),
+            ("mat3_value", Uniform::Mat2([1.0; 4]), "mat2"),
+            ("mat4_value", Uniform::Mat3([1.0; 9]), "mat3"),
+            ("texture_value", Uniform::Mat4([1.0; 16]), "mat4"),
+            ("vec4_value", Uniform::Texture(texture()), "sampler2D"),
+            ("cube_value", Uniform::Texture(texture()), "sampler2D"),
+        ];
+
+        for (name, uniform, found) in cases {
+            let (calls, value, errors) = set_uniform(name, uniform);
+            let expected = match name {
+                "texture_value" => GlslType::Sampler2D,
+                "cube_value" => GlslType::SamplerCube,
+                name => GlslType::from_glsl_name(name.trim_end_matches("_value")).unwrap(),
+            };
+
+            assert!(calls.is_empty(), "{name}: {calls:?}");
+            // Samplers keep the unit they got when the program was linked
+            if !expected.is_sampler() {
+                assert_eq!(value, None, "{name}");
+            }
+            assert_eq!(
+                errors,
+                [format!(
+                    "Uniform `{name}` is declared as `{expected}` and can not be set from a `{found}` value"
+                )]
+            );
+        }
+    }
+
+    #[test]
+    fn sends_arrays_and_their_elements() {
+        use MockUniformValue::{Float, Int, Matrix, UnsignedInt};
+
+        let cases = [
+            (
+                "float_array",
+                Uniform::FloatArray(vec![1.0, 2.0]),
+                "uniform1fv",
+                Float(vec![1.0, 2.0]),
+            ),
+            ("float_array[3]", Uniform::Float(4.0), "uniform1f", Float(vec![4.0])),
+            (
+                "lights",
+                Uniform::Vec3Array(vec![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]),
+                "uniform3fv",
+                Float(vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]),
+            ),
+            (
+                "lights[6]",
+                Uniform::Vec3Array(vec![[1.0; 3], [2.0; 3]]),
+                "uniform3fv",
+                Float(vec![1.0, 1.0, 1.0, 2.0, 2.0, 2.0]),
+            ),
+            (
+                "lights[7]",
+                Uniform::Vec3([1.0, 2.0, 3.0]),
+                "uniform3fv",
+                Float(vec![1.0, 2.0, 3.0]),
+            ),
+            (
+                "ivec2_array",
+                Uniform::IntVec2Array(vec![[1, 2], [3, 4]]),
+                "uniform2iv",
+                Int(vec![1, 2, 3, 4]),
+            ),
+            (
+                "uint_array",
+                Uniform::from(vec![1_u32, 2, 3]),
+                "uniform1uiv",
+                UnsignedInt(vec![1, 2, 3]),
+            ),
+            ("uint_array", Uniform::IntArray(vec![1, 2]), "uniform1uiv", UnsignedInt(vec![1, 2])),
+            (
+                "mat4_array",
+                Uniform::Mat4Array(vec![[1.0; 16]; 2]),
+                "uniform_matrix4fv",
+                Matrix(vec![1.0; 32]),
+            ),
+            (
+                "mat4_array[1]",
+                Uniform::Mat4([2.0; 16]),
+                "uniform_matrix4fv",
+                Matrix(vec![2.0; 16]),
+            ),
+        ];
+
+        for (name, uniform, expected_call, expected_value) in cases {
+            let (calls, value, errors) = set_uniform(name, uniform);
+
+            assert_eq!(calls, [expected_call], "{name}");
+            assert_eq!(value, Some(expected_value), "{name}");
+            assert!(errors.is_empty(), "{name}: {errors:?}");
+        }
+    }
+
+    #[test]
+    fn reports_mismatched_arrays() {
+        let cases = [
+            (
+                "lights[7]",
+                Uniform::Vec3Array(vec![[1.0; 3]; 2]),
+                "Uniform `lights[7]` can not be set from 2 elements, the array ends after 1",
+            ),
+            (
+                "float_array",
+                Uniform::FloatArray(vec![1.0; 5]),
+                "Uniform `float_array` can not be set from 5 elements, the array ends after 4",
+            ),
+            (
+                "float_value",
+                Uniform::FloatArray(vec![1.0; 2]),
+                "Uniform `float_value` can not be set from 2 elements, the array ends after 1",
+            ),
+            (
+                "lights",
+                Uniform::FloatArray(vec![1.0; 3]),
+                "Uniform `lights` is declared as `vec3` and can not be set from a `float` value",
+            ),
+            (
+                "mat4_array",
+                Uniform::Mat3Array(vec![[1.0; 9]]),
+                "Uniform `mat4_array` is declared as `mat4` and can not be set from a `mat3` value",
+            ),
+            (
+                "lights[8]",
+                Uniform::Vec3([1.0; 3]),
+                "Uniform `lights[8]` is not used by the program",
+            ),
+        ];
+
+        for (name, uniform, expected) in cases {
+            let (calls, value, errors) = set_uniform(name, uniform);
+
+            assert!(calls.is_empty(), "{name}: {calls:?}");
+            assert_eq!(value, None, "{name}");
+            assert_eq!(errors, [expected]);
+        }
+    }
+
+    #[test]
+    fn binds_texture_arrays_to_consecutive_units() {
+        let mock = Rc::new(MockContext::new(8, 8));
+        let gl = mock.context();
+
+        let mut material = Material::new(VERTEX_SHADER, FRAGMENT_SHADER);
+        material.set_uniform("texture_array", Uniform::TextureArray(vec![texture(), texture()]));
+        material.on_before_render(&gl);
+
+        let state = mock.state();
+        let Some(Uniform::TextureArray(textures)) = material.get_uniform("texture_array") else {
+            panic!("texture_array is not set");
+        };
+        let bound: Vec<Option<u32>> = (1..4)
+            .map(|unit| state.bound_textures.get(&(unit, GL::TEXTURE_2D)).copied())
+            .collect();
+
+        assert_eq!(
+            bound,
+            [
+                textures[0].webgl_texture.map(|texture| texture.0),
+                textures[1].webgl_texture.map(|texture| texture.0),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn assigns_texture_units_when_linking() {
+        use MockUniformValue::Int;
+
+        for _ in 0..2 {
+            let mock = Rc::new(MockContext::new(8, 8));
+            let gl = mock.context();
+
+            let mut material = Material::new(VERTEX_SHADER, FRAGMENT_SHADER);
+            let resources = material.get_or_create_resources(&gl);
+            let program = resources.get_program();
+
+            assert_eq!(resources.get_texture_unit("texture_value"), Some((0, GL::TEXTURE_2D)));
+            assert_eq!(resources.get_texture_unit("texture_array"), Some((1, GL::TEXTURE_2D)));
+            assert_eq!(resources.get_texture_unit("texture_array[2]"), Some((3, GL::TEXTURE_2D)));
+            assert_eq!(resources.get_texture_unit("cube_value"), Some((4, GL::TEXTURE_CUBE_MAP)));
+            assert_eq!(resources.get_texture_unit("float_value"), None);
+
+            let state = mock.state();
+            assert_eq!(state.uniform_value(program.0, "texture_value"), Some(&Int(vec![0])));
+            assert_eq!(state.uniform_value(program.0, "texture_array"), Some(&Int(vec![1, 2, 3])));
+            assert_eq!(state.uniform_value(program.0, "cube_value"), Some(&Int(vec![4])));
+        }
+
+        let (calls, _, errors) = set_uniform("texture_array", Uniform::TextureArray(vec![texture(); 3]));
+        assert!(calls.is_empty(), "{calls:?}");
+        assert!(errors.is_empty(), "{errors:?}");
+    }
+
+    #[test]
+    fn reports_too_many_samplers() {
+        let mock = Rc::new(MockContext::new(8, 8));
+        mock.set_parameter(GL::MAX_COMBINED_TEXTURE_IMAGE_UNITS, 4);
+
+        let mut material = Material::new(VERTEX_SHADER, FRAGMENT_SHADER);
+        let error = material.try_get_or_create_resources(&mock.context()).err().unwrap();
+
+        assert_eq!(
+            error.to_string(),
+            "The program samples 5 textures but only 4 texture units are available"
+        );
+    }
+
+    #[test]
+    fn skips_redundant_texture_binds() {
+        let mock = Rc::new(MockContext::new(8, 8));
+        let gl = mock.context();
+        let mut cache = TextureBindingCache::default();
+        let mut error_reporter = ErrorReporter::new(ErrorPolicy::Panic);
+
+        let mut material = Material::new(VERTEX_SHADER, FRAGMENT_SHADER);
+        material.set_uniform("texture_value", Uniform::Texture(texture()));
+        material.set_uniform("texture_array", Uniform::TextureArray(vec![texture(); 3]));
+
+        let binds = |from: usize| mock.state().calls[from..].iter().filter(|call| **call == "bind_texture").count();
+
+        material.try_on_before_render_cached(&gl, &mut error_reporter, &mut cache).unwrap();
+        let calls = mock.state().calls.len();
+        material.try_on_before_render_cached(&gl, &mut error_reporter, &mut cache).unwrap();
+        assert_eq!(binds(calls), 0);
+
+        cache.invalidate();
+        material.try_on_before_render_cached(&gl, &mut error_reporter, &mut cache).unwrap();
+        assert_eq!(binds(calls), 4);
+    }
+
+    #[test]
+    fn shares_sampler_objects_with_the_same_parameters() {
+        let mock = Rc::new(MockContext::new(8, 8));
+        let gl = mock.context();
+        let mut cache = TextureBindingCache::default();
+        let mut error_reporter = ErrorReporter::new(ErrorPolicy::Panic);
+        let sampler = Sampler::new(MinificationFilter::Linear, MagnificationFilter::Linear);
+
+        let mut materials = [0, 1].map(|_| {
+            let mut material = Material::new(VERTEX_SHADER, FRAGMENT_SHADER);
+            material.set_uniform("texture_value", Uniform::SampledTexture(texture(), sampler));
+            material.set_uniform("texture_array", Uniform::TextureArray(vec![texture()]));
+            material
+        });
+
+        for material in &mut materials {
+            material.try_on_before_render_cached(&gl, &mut error_reporter, &mut cache).unwrap();
+        }
+
+        {
+            let state = mock.state();
+            let webgl_sampler = *state.bound_samplers.get(&0).unwrap();
+
+            assert_eq!(state.call_count("create_sampler"), 1);
+            assert_eq!(state.samplers[&webgl_sampler][&GL::TEXTURE_MAG_FILTER], GL::LINEAR as i32);
+            assert_eq!(state.bound_samplers.get(&1), None);
+        }
+
+        materials[0].set_uniform("texture_value", Uniform::Texture(texture()));
+        materials[0]
+            .try_on_before_render_cached(&gl, &mut error_reporter, &mut cache)
+            .unwrap();
+        assert_eq!(mock.state().bound_samplers.get(&0), None);
+    }
+
+    fn uniform_calls(mock: &MockContext, from: usize) -> usize {
+        mock.state().calls[from..].iter().filter(|call| call.starts_with("uniform")).count()
+    }
+
+    #[test]
+    fn uploads_values_only_when_they_change() {
+        let mock = Rc::new(MockContext::new(8, 8));
+        let gl = mock.context();
+
+        let mut material = Material::new(VERTEX_SHADER, FRAGMENT_SHADER);
+        let handle = material.get_uniform_handle("vec3_value");
+        material.set_uniform(handle, Uniform::Vec3([1.0, 2.0, 3.0]));
+        material.on_before_render(&gl);
+
+        let calls = mock.state().calls.len();
+        material.on_before_render(&gl);
+        material.set_uniform(handle, Uniform::Vec3([1.0, 2.0, 3.0]));
+        material.on_before_render(&gl);
+        assert_eq!(uniform_calls(&mock, calls), 0);
+
+        material.set_uniform("vec3_value", Uniform::Vec3([4.0, 5.0, 6.0]));
+        material.on_before_render(&gl);
+        assert_eq!(uniform_calls(&mock, calls), 1);
+        assert_eq!(material.get_uniform(handle).map(Uniform::glsl_type), Some(GlslType::Vec3));
+    }
+
+    #[test]
+    fn uploads_again_after_a_material_sharing_the_program() {
+        let mock = Rc::new(MockContext::new(8, 8));
+        let gl = mock.context();
+        let mut program_cache = ProgramCache::new();
+
+        let mut materials = [1.0, 2.0].map(|value| {
+            let mut material = Material::new(VERTEX_SHADER, FRAGMENT_SHADER);
+            material.set_uniform("float_value", Uniform::Float(value));
+            material.try_get_or_create_cached_resources(&gl, &mut program_cache).unwrap();
+            material
+        });
+
+        let calls = mock.state().calls.len();
+        for index in [0, 1, 0, 1] {
+            materials[index].on_before_render(&gl);
+        }
+
+        assert_eq!(uniform_calls(&mock, calls), 4);
+    }
+
+    #[test]
+    fn uploads_again_after_a_material_sets_another_name_of_the_array() {
+        let mock = Rc::new(MockContext::new(8, 8));
+        let gl = mock.context();
+        let mut program_cache = ProgramCache::new();
+
+        let uniforms = [
+            ("float_array", Uniform::FloatArray(vec![1.0, 2.0, 3.0, 4.0])),
+            ("float_array[1]", Uniform::Float(9.0)),
+        ];
+        let mut materials = uniforms.map(|(name, uniform)| {
+            let mut material = Material::new(VERTEX_SHADER, FRAGMENT_SHADER);
+            material.set_uniform(name, uniform);
+            material.try_get_or_create_cached_resources(&gl, &mut program_cache).unwrap();
+            material
+        });
+
+        materials[0].on_before_render(&gl);
+        materials[1].on_before_render(&gl);
+
+        let calls = mock.state().calls.len();
+        materials[0].on_before_render(&gl);
+        assert_eq!(uniform_calls(&mock, calls), 1);
+
+        // Unchanged since, skipped again
+        let calls = mock.state().calls.len();
+        materials[0].on_before_render(&gl);
+        assert_eq!(uniform_calls(&mock, calls), 0);
+    }
+
+    #[test]
+    fn uploads_every_name_of_an_array_set_by_one_material() {
+        let mock = Rc::new(MockContext::new(8, 8));
+        let gl = mock.context();
+
+        let mut material = Material::new(VERTEX_SHADER, FRAGMENT_SHADER);
+        material.set_uniform("float_array", Uniform::FloatArray(vec![1.0, 2.0, 3.0, 4.0]));
+        material.set_uniform("float_array[3]", Uniform::Float(9.0));
+        material.on_before_render(&gl);
+
+        let calls = mock.state().calls.len();
+        material.on_before_render(&gl);
+        assert_eq!(uniform_calls(&mock, calls), 2);
     }
 }
@@ -1,10 +1,17 @@
-use web_sys::{WebGl2RenderingContext as GL, WebGlVertexArrayObject};
+use std::{error::Error, fmt};
 
-use crate::{geometry::Geometry, material::Material, transform::Transform3D};
+use crate::{
+    backend::{Context as GL, ProgramHandle, VertexArrayHandle},
+    bounds::Frustum,
+    error::{ErrorPolicy, ErrorReporter, SuricatoError},
+    geometry::Geometry,
+    material::Material,
+    transform::Transform3D,
+};
 
 /// https://developer.mozilla.org/en-US/docs/Web/API/WebGL2RenderingContext/drawArraysInstanced#mode
 #[repr(u32)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RenderPrimitive {
     Points        = GL::POINTS,
     LineStrip     = GL::LINE_STRIP,
@@ -21,12 +28,34 @@ pub enum MeshError {
     UninitializedMaterial,
 }
 
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MeshError::VAOCreationFailed => write!(f, "Failed to create the vertex array object"),
+            MeshError::UninitializedMaterial => write!(f, "The material resources were not created before the vertex array object"),
+        }
+    }
+}
+
+impl Error for MeshError {}
+
 pub struct Mesh {
     pub transform:        Transform3D,
     pub geometry:         Geometry,
     pub material:         Material,
     pub render_primitive: RenderPrimitive,
-    pub vao:              Option<WebGlVertexArrayObject>,
+
+    /// Meshes with a lower value are drawn first within their render queue,
+    /// overriding the depth based sorting.
+    pub render_order: i32,
+
+    /// Skip drawing the mesh when its bounds are outside the camera frustum.
+    /// Disable for meshes whose vertex shader moves vertices outside of the geometry bounds.
+    pub frustum_culled: bool,
+
+    pub vao:     Option<VertexArrayHandle>,
+    /// Program whose attribute locations the VAO was created with.
+    vao_program: Option<ProgramHandle>,
 }
 
 impl Mesh {
@@ -34,45 +63,105 @@ impl Mesh {
         Mesh {
             transform: Transform3D::new(),
             vao: None,
+            vao_program: None,
             geometry,
             material,
             render_primitive: RenderPrimitive::Triangles,
+            render_order: 0,
+            frustum_culled: true,
+        }
+    }
+
+    /// Tests the geometry bounds, transformed to world space, against `frustum`.
+    ///
+    /// Meshes without bounds and instanced meshes, whose instances can be anywhere,
+    /// are always considered visible.
+    pub fn is_in_frustum(&mut self, frustum: &Frustum) -> bool {
+        if self.geometry.instance_count.is_some() {
+            return true;
+        }
+
+        let Some(bounds) = self.geometry.get_bounds() else {
+            return true;
+        };
+
+        let world_matrix = self.transform.to_mat4();
+
+        // The sphere test is cheaper and rejects most meshes, the box test is tighter
+        frustum.intersects_sphere(&bounds.bounding_sphere.transform(&world_matrix))
+            && frustum.intersects_box(&bounds.bounding_box.transform(&world_matrix))
+    }
+
+    /// Logs missing attributes and panics on any other error.
+    pub fn get_or_create_vao(&mut self, gl: &GL) -> Option<VertexArrayHandle> {
+        match self.try_get_or_create_vao(gl, &mut ErrorReporter::new(ErrorPolicy::LogOnce)) {
+            Ok(vao) => Some(vao),
+            Err(error) => panic!("{error}"),
         }
     }
 
-    pub fn get_or_create_vao(&mut self, gl: &GL) -> Option<&WebGlVertexArrayObject> {
-        if self.vao.is_none() {
-            let vao = self.create_vao(gl).unwrap();
-            self.vao = Some(vao);
+    /// Attributes the program reads but the geometry does not provide are reported to `error_reporter`.
+    ///
+    /// The VAO is created again when the material switches to a variant with another program.
+    pub fn try_get_or_create_vao(&mut self, gl: &GL, error_reporter: &mut ErrorReporter) -> Result<VertexArrayHandle, MeshError> {
+        let program = self
+            .material
+            .resources
+            .as_ref()
+            .ok_or(MeshError::UninitializedMaterial)?
+            .get_program();
+
+        if let Some(vao) = self.vao {
+            if self.vao_program == Some(program) {
+                return Ok(vao);
+            }
+
+            gl.delete_vertex_array(Some(vao));
+            self.vao = None;
         }
 
-        self.vao.as_ref()
+        let vao = self.create_vao(gl, error_reporter)?;
+        self.vao = Some(vao);
+        self.vao_program = Some(program);
+        Ok(vao)
     }
 
-    fn create_vao(&mut self, gl: &GL) -> Result<WebGlVertexArrayObject, MeshError> {
+    fn create_vao(&mut self, gl: &GL, error_reporter: &mut ErrorReporter) -> Result<VertexArrayHandle, MeshError> {
+        let resources = self.material.resources.as_ref().ok_or(MeshError::UninitializedMaterial)?;
+
         let Some(vao) = gl.create_vertex_array() else {
             return Err(MeshError::VAOCreationFailed);
         };
 
-        gl.bind_vertex_array(Some(&vao));
+        gl.bind_vertex_array(Some(vao));
 
         for vertex_buffer in &self.geometry.vertex_buffers {
             vertex_buffer.buffer.bind(gl);
-            self.material
-                .resources
-                .as_ref()
-                .unwrap()
-                .set_attribute_buffer(&vertex_buffer.layout);
+            resources.set_attribute_buffer(&vertex_buffer.layout);
         }
 
         for vertex_buffer in &self.geometry.interleaved_vertex_buffers {
             vertex_buffer.buffer.bind(gl);
             for vertex_layout in &vertex_buffer.layouts {
-                self.material.resources.as_ref().unwrap().set_attribute_buffer(vertex_layout);
+                resources.set_attribute_buffer(vertex_layout);
             }
         }
 
         gl.bind_vertex_array(None);
+
+        let provided_attributes: Vec<&str> = self
+            .geometry
+            .get_vertex_layouts()
+            .map(|vertex_layout| vertex_layout.name.as_str())
+            .collect();
+
+        for name in resources.attribute_locations().keys() {
+            // Built-in inputs like `gl_VertexID` are listed as active attributes by some drivers
+            if !name.starts_with("gl_") && !provided_attributes.contains(&name.as_str()) {
+                error_reporter.report(SuricatoError::MissingAttribute(name.clone()));
+            }
+        }
+
         Ok(vao)
     }
 }
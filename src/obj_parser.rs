@@ -150,6 +150,8 @@ impl fmt::Display for OBJParseError {
     }
 }
 
+impl std::error::Error for OBJParseError {}
+
 impl From<std::num::ParseFloatError> for OBJParseError {
     fn from(error: std::num::ParseFloatError) -> OBJParseError {
         OBJParseError::InvalidFloat(error)
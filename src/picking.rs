@@ -0,0 +1,620 @@
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    error::Error,
+    fmt,
+};
+
+use glam::Vec2;
+
+use crate::{
+    backend::{BufferHandle, Context as GL, FramebufferHandle, ProgramHandle, RenderbufferHandle, SyncHandle, TextureHandle},
+    buffer_gpu::BufferError,
+    camera::PerspectiveCamera,
+    error::ErrorReporter,
+    material::{Material, MaterialError},
+    mesh::{Mesh, MeshError},
+    program_cache::ProgramCache,
+    render_state::{CompareFunction, RenderState, RenderStateCache},
+    renderer::Renderer,
+    shader::preprocessor::header_end,
+    ubo::UniformBufferError,
+    uniforms::{Uniform, UniformHandle},
+};
+
+const MESH_ID_UNIFORM: &str = "suricato_picking_mesh_id";
+const INSTANCE_ID_VARYING: &str = "suricato_picking_instance_id";
+
+/// Replaces the fragment shader of every material while picking.
+const PICKING_FRAGMENT_SHADER_SOURCE: &str = r#"#version 300 es
+precision highp float;
+precision highp int;
+
+uniform uint suricato_picking_mesh_id;
+flat in uint suricato_picking_instance_id;
+
+layout(location = 0) out uint mesh_id;
+layout(location = 1) out uint instance_id;
+
+void main() {
+    mesh_id = suricato_picking_mesh_id;
+    instance_id = suricato_picking_instance_id;
+}
+"#;
+
+/// One `RGBA_INTEGER` / `UNSIGNED_INT` pixel per attachment, the only integer
+/// read format every WebGL 2 implementation must support.
+const PIXEL_SIZE: i32 = 16;
+
+#[derive(Debug)]
+pub enum PickingError {
+    FramebufferCreationFailed,
+    FramebufferIncomplete(u32),
+    TextureCreationFailed,
+    RenderbufferCreationFailed,
+    BufferCreationFailed,
+    ReadPixelsFailed,
+
+    MaterialError(MaterialError),
+    MeshError(MeshError),
+    BufferError(BufferError),
+    UniformBufferError(UniformBufferError),
+}
+
+impl fmt::Display for PickingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PickingError::FramebufferCreationFailed => write!(f, "Failed to create the picking framebuffer"),
+            PickingError::FramebufferIncomplete(status) => write!(f, "The picking framebuffer is incomplete, status {status:#x}"),
+            PickingError::TextureCreationFailed => write!(f, "Failed to create the picking texture"),
+            PickingError::RenderbufferCreationFailed => write!(f, "Failed to create the picking depth buffer"),
+            PickingError::BufferCreationFailed => write!(f, "Failed to create the picking pixel buffer"),
+            PickingError::ReadPixelsFailed => write!(f, "Failed to read the picked pixel"),
+            PickingError::MaterialError(error) => write!(f, "{error}"),
+            PickingError::MeshError(error) => write!(f, "{error}"),
+            PickingError::BufferError(error) => write!(f, "{error}"),
+            PickingError::UniformBufferError(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl Error for PickingError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PickingError::MaterialError(error) => Some(error),
+            PickingError::MeshError(error) => Some(error),
+            PickingError::BufferError(error) => Some(error),
+            PickingError::UniformBufferError(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<MaterialError> for PickingError {
+    fn from(value: MaterialError) -> Self {
+        PickingError::MaterialError(value)
+    }
+}
+
+impl From<MeshError> for PickingError {
+    fn from(value: MeshError) -> Self {
+        PickingError::MeshError(value)
+    }
+}
+
+impl From<BufferError> for PickingError {
+    fn from(value: BufferError) -> Self {
+        PickingError::BufferError(value)
+    }
+}
+
+impl From<UniformBufferError> for PickingError {
+    fn from(value: UniformBufferError) -> Self {
+        PickingError::UniformBufferError(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PickResult {
+    /// Index of the mesh in the scene passed when the pick was requested.
+    pub mesh_index:  usize,
+    /// `gl_InstanceID` of the instance under the cursor, `0` for meshes that are not instanced.
+    pub instance_id: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickStatus {
+    /// No pick was requested.
+    Idle,
+    /// The GPU has not finished rendering the ID buffer yet.
+    Pending,
+    /// `None` when there is nothing under the cursor.
+    Ready(Option<PickResult>),
+}
+
+enum PickRequest {
+    /// Waiting for the fence inserted after `readPixels`.
+    Fenced(SyncHandle),
+    /// Fences are unavailable, the result is read on the next poll even if that stalls.
+    Unfenced,
+    /// The point was outside of the canvas.
+    Missed,
+}
+
+/// Framebuffer with two `R32UI` color attachments, one for the mesh and one for the instance ID,
+/// plus a depth attachment so only the closest surface is kept.
+struct PickingTarget {
+    framebuffer:         FramebufferHandle,
+    mesh_id_texture:     TextureHandle,
+    instance_id_texture: TextureHandle,
+    depth_renderbuffer:  RenderbufferHandle,
+    width:               u32,
+    height:              u32,
+}
+
+impl PickingTarget {
+    fn new(gl: &GL, width: u32, height: u32) -> Result<PickingTarget, PickingError> {
+        let framebuffer = gl.create_framebuffer().ok_or(PickingError::FramebufferCreationFailed)?;
+        gl.bind_framebuffer(GL::FRAMEBUFFER, Some(framebuffer));
+
+        let mesh_id_texture = PickingTarget::create_id_texture(gl, width, height)?;
+        gl.framebuffer_texture_2d(GL::FRAMEBUFFER, GL::COLOR_ATTACHMENT0, GL::TEXTURE_2D, Some(mesh_id_texture), 0);
+
+        let instance_id_texture = PickingTarget::create_id_texture(gl, width, height)?;
+        gl.framebuffer_texture_2d(GL::FRAMEBUFFER, GL::COLOR_ATTACHMENT1, GL::TEXTURE_2D, Some(instance_id_texture), 0);
+
+        let depth_renderbuffer = gl.create_renderbuffer().ok_or(PickingError::RenderbufferCreationFailed)?;
+        gl.bind_renderbuffer(GL::RENDERBUFFER, Some(depth_renderbuffer));
+        gl.renderbuffer_storage(GL::RENDERBUFFER, GL::DEPTH_COMPONENT24, width as i32, height as i32);
+        gl.framebuffer_renderbuffer(GL::FRAMEBUFFER, GL::DEPTH_ATTACHMENT, GL::RENDERBUFFER, Some(depth_renderbuffer));
+        gl.bind_renderbuffer(GL::RENDERBUFFER, None);
+
+        gl.draw_buffers(&[GL::COLOR_ATTACHMENT0, GL::COLOR_ATTACHMENT1]);
+
+        let status = gl.check_framebuffer_status(GL::FRAMEBUFFER);
+        gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+
+        let target = PickingTarget {
+            framebuffer,
+            mesh_id_texture,
+            instance_id_texture,
+            depth_renderbuffer,
+            width,
+            height,
+        };
+
+        if status != GL::FRAMEBUFFER_COMPLETE {
+            target.delete(gl);
+            return Err(PickingError::FramebufferIncomplete(status));
+        }
+
+        Ok(target)
+    }
+
+    fn create_id_texture(gl: &GL, width: u32, height: u32) -> Result<TextureHandle, PickingError> {
+        let texture = gl.create_texture().ok_or(PickingError::TextureCreationFailed)?;
+        gl.bind_texture(GL::TEXTURE_2D, Some(texture));
+        gl.tex_storage_2d(GL::TEXTURE_2D, 1, GL::R32UI, width as i32, height as i32);
+
+        // Integer textures can not be filtered
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::NEAREST as i32);
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, GL::NEAREST as i32);
+        gl.bind_texture(GL::TEXTURE_2D, None);
+
+        Ok(texture)
+    }
+
+    fn delete(&self, gl: &GL) {
+        gl.delete_framebuffer(Some(self.framebuffer));
+        gl.delete_texture(Some(self.mesh_id_texture));
+        gl.delete_texture(Some(self.instance_id_texture));
+        gl.delete_renderbuffer(Some(self.depth_renderbuffer));
+    }
+}
+
+/// Renders mesh and instance IDs into an offscreen target and reads back the pixel under the cursor.
+///
+/// Unlike [`raycast`](crate::raycast::raycast) this is exact for anything the vertex shader does,
+/// including instancing and skinning, at the cost of an extra draw per mesh.
+/// Every material is drawn with its own vertex shader and a fragment shader that outputs the IDs.
+#[derive(Default)]
+pub struct PickingPass {
+    target:       Option<PickingTarget>,
+    pixel_buffer: Option<BufferHandle>,
+    request:      Option<PickRequest>,
+
+    /// Picking variants of every material drawn so far, by material ID and program of the material variant.
+    materials: HashMap<(u64, ProgramHandle), Material>,
+}
+
+impl PickingPass {
+    pub fn new() -> PickingPass {
+        PickingPass::default()
+    }
+
+    /// Picks the mesh under `screen_point` waiting for the GPU to finish, which stalls the pipeline.
+    ///
+    /// `screen_point` is in pixels from the top left corner of the canvas.
+    #[allow(clippy::too_many_arguments)]
+    pub fn pick(
+        &mut self,
+        gl: &GL,
+        render_state_cache: &mut RenderStateCache,
+        program_cache: &mut ProgramCache,
+        error_reporter: &mut ErrorReporter,
+        scene: &mut [Mesh],
+        camera: &PerspectiveCamera,
+        screen_point: Vec2,
+    ) -> Result<Option<PickResult>, PickingError> {
+        self.request_pick(gl, render_state_cache, program_cache, error_reporter, scene, camera, screen_point)?;
+
+        match self.request.take() {
+            Some(PickRequest::Fenced(sync)) => {
+                gl.delete_sync(Some(sync));
+                Ok(self.read_result(gl))
+            }
+            Some(PickRequest::Unfenced) => Ok(self.read_result(gl)),
+            Some(PickRequest::Missed) | None => Ok(None),
+        }
+    }
+
+    /// Starts a pick whose result is retrieved with [`PickingPass::poll`] on a later frame,
+    /// replacing any pick still pending.
+    #[allow(clippy::too_many_arguments)]
+    pub fn request_pick(
+        &mut self,
+        gl: &GL,
+        render_state_cache: &mut RenderStateCache,
+        program_cache: &mut ProgramCache,
+        error_reporter: &mut ErrorReporter,
+        scene: &mut [Mesh],
+        camera: &PerspectiveCamera,
+        screen_point: Vec2,
+    ) -> Result<(), PickingError> {
+        self.cancel(gl);
+
+        let width = gl.drawing_buffer_width() as u32;
+        let height = gl.drawing_buffer_height() as u32;
+
+        if screen_point.x < 0.0 || screen_point.y < 0.0 || screen_point.x >= width as f32 || screen_point.y >= height as f32 {
+            self.request = Some(PickRequest::Missed);
+            return Ok(());
+        }
+
+        // Framebuffer coordinates start at the bottom left corner
+        let x = screen_point.x as i32;
+        let y = height as i32 - 1 - screen_point.y as i32;
+
+        self.prepare_target(gl, width, height)?;
+        let target = self.target.as_ref().unwrap();
+        gl.bind_framebuffer(GL::FRAMEBUFFER, Some(target.framebuffer));
+
+        // Only the pixel under the cursor is needed
+        gl.enable(GL::SCISSOR_TEST);
+        gl.scissor(x, y, 1, 1);
+
+        render_state_cache.prepare_clear(gl);
+        gl.clear_buffer_u32(GL::COLOR, 0, &[0; 4]);
+        gl.clear_buffer_u32(GL::COLOR, 1, &[0; 4]);
+        gl.clear_buffer_f32(GL::DEPTH, 0, &[1.0]);
+
+        let draw_result = self.draw_scene(gl, render_state_cache, program_cache, error_reporter, scene, camera);
+        let read_result = draw_result.and_then(|_| self.read_pixel(gl, x, y));
+
+        gl.disable(GL::SCISSOR_TEST);
+        gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+        read_result?;
+
+        self.request = match gl.fence_sync(GL::SYNC_GPU_COMMANDS_COMPLETE, 0) {
+            Some(sync) => {
+                // Makes sure the fence is eventually signaled without waiting for the next frame
+                gl.flush();
+                Some(PickRequest::Fenced(sync))
+            }
+            None => Some(PickRequest::Unfenced),
+        };
+
+        Ok(())
+    }
+
+    /// Checks whether the last requested pick is ready, without blocking when fences are available.
+    pub fn poll(&mut self, gl: &GL) -> PickStatus {
+        match self.request.take() {
+            None => PickStatus::Idle,
+            Some(PickRequest::Missed) => PickStatus::Ready(None),
+            Some(PickRequest::Unfenced) => PickStatus::Ready(self.read_result(gl)),
+            Some(PickRequest::Fenced(sync)) => {
+                if gl.client_wait_sync(sync, 0, 0) == GL::TIMEOUT_EXPIRED {
+                    self.request = Some(PickRequest::Fenced(sync));
+                    return PickStatus::Pending;
+                }
+
+                gl.delete_sync(Some(sync));
+                PickStatus::Ready(self.read_result(gl))
+            }
+        }
+    }
+
+    /// Drops the pending pick, if any.
+    pub fn cancel(&mut self, gl: &GL) {
+        if let Some(PickRequest::Fenced(sync)) = self.request.take() {
+            gl.delete_sync(Some(sync));
+        }
+    }
+
+    /// Releases the GPU resources, they are created again on the next pick.
+    pub fn dispose(&mut self, gl: &GL) {
+        self.cancel(gl);
+
+        if let Some(target) = self.target.take() {
+            target.delete(gl);
+        }
+
+        gl.delete_buffer(self.pixel_buffer.take());
+        self.materials.clear();
+    }
+
+    fn prepare_target(&mut self, gl: &GL, width: u32, height: u32) -> Result<(), PickingError> {
+        if let Some(target) = &self.target
+            && (target.width != width || target.height != height)
+        {
+            target.delete(gl);
+            self.target = None;
+        }
+
+        if self.target.is_none() {
+            self.target = Some(PickingTarget::new(gl, width, height)?);
+        }
+
+        if self.pixel_buffer.is_none() {
+            let pixel_buffer = gl.create_buffer().ok_or(PickingError::BufferCreationFailed)?;
+            gl.bind_buffer(GL::PIXEL_PACK_BUFFER, Some(pixel_buffer));
+            gl.buffer_data_size(GL::PIXEL_PACK_BUFFER, 2 * PIXEL_SIZE, GL::STREAM_READ);
+            gl.bind_buffer(GL::PIXEL_PACK_BUFFER, None);
+            self.pixel_buffer = Some(pixel_buffer);
+        }
+
+        Ok(())
+    }
+
+    fn draw_scene(
+        &mut self,
+        gl: &GL,
+        render_state_cache: &mut RenderStateCache,
+        program_cache: &mut ProgramCache,
+        error_reporter: &mut ErrorReporter,
+        scene: &mut [Mesh],
+        camera: &PerspectiveCamera,
+    ) -> Result<(), PickingError> {
+        let frustum = camera.frustum();
+        let projection_matrix = Uniform::from(&camera.projection_matrix);
+        let camera_inverse_matrix = Uniform::from(&camera.view_matrix());
+
+        for (mesh_index, mesh) in scene.iter_mut().enumerate() {
+            if mesh.frustum_culled && !mesh.is_in_frustum(&frustum) {
+                continue;
+            }
+
+            let material = &mut mesh.material;
+            material.try_get_or_create_cached_resources(gl, program_cache)?;
+            material.set_renderer_uniform(UniformHandle::TRANSFORM, Uniform::Mat4(mesh.transform.to_array()));
+            material.set_renderer_uniform(UniformHandle::PROJECTION_MATRIX, projection_matrix.clone());
+            material.set_renderer_uniform(UniformHandle::CAMERA_INVERSE_MATRIX, camera_inverse_matrix.clone());
+
+            Renderer::upload_vertex_buffers(gl, mesh)?;
+
+            let Some(picking_material) = self.get_picking_material(gl, program_cache, &mesh.material)? else {
+                continue;
+            };
+
+            // Uniforms only used by the original fragment shader are not active in the picking program
+            let resources = picking_material.resources.clone().unwrap();
+            let mesh_id = UniformHandle::new(MESH_ID_UNIFORM);
+            picking_material.retain_uniforms(|handle, _| handle == mesh_id || mesh.material.get_uniform(handle).is_some());
+
+            for (handle, uniform) in mesh.material.get_uniforms() {
+                if resources.has_uniform(handle) {
+                    picking_material.set_uniform(handle, uniform.clone());
+                }
+            }
+
+            picking_material.set_uniform(mesh_id, Uniform::UnsignedInt(mesh_index as u32 + 1));
+            picking_material.try_on_before_render(gl, error_reporter)?;
+
+            // Blending would mix IDs and the closest surface must always win
+            render_state_cache.apply(
+                gl,
+                &RenderState {
+                    cull_face: mesh.material.render_state.cull_face,
+                    polygon_offset: mesh.material.render_state.polygon_offset,
+                    depth_test: Some(CompareFunction::Less),
+                    ..RenderState::default()
+                },
+            );
+
+            Renderer::draw(gl, mesh, error_reporter)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns `None` when the vertex shader has no `main` function to wrap.
+    fn get_picking_material(
+        &mut self,
+        gl: &GL,
+        program_cache: &mut ProgramCache,
+        material: &Material,
+    ) -> Result<Option<&mut Material>, MaterialError> {
+        // The resources of the current variant were created before
+        let resources = material.resources.as_ref().unwrap();
+
+        let picking_material = match self.materials.entry((material.id, resources.get_program())) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let Some(vertex_shader_source) = picking_vertex_shader_source(&material.vertex_shader_source) else {
+                    return Ok(None);
+                };
+                let mut picking_material = Material::new(&vertex_shader_source, PICKING_FRAGMENT_SHADER_SOURCE);
+                picking_material.defines = material.defines.clone();
+
+                // Same attribute locations as the original program so the mesh VAO can be reused
+                picking_material.resources = Some(program_cache.get_or_create(gl, &picking_material, resources.attribute_locations())?);
+
+                entry.insert(picking_material)
+            }
+        };
+
+        Ok(Some(picking_material))
+    }
+
+    fn read_pixel(&self, gl: &GL, x: i32, y: i32) -> Result<(), PickingError> {
+        gl.bind_buffer(GL::PIXEL_PACK_BUFFER, self.pixel_buffer);
+
+        let mut result = Ok(());
+
+        for (attachment_index, attachment) in [GL::COLOR_ATTACHMENT0, GL::COLOR_ATTACHMENT1].into_iter().enumerate() {
+            gl.read_buffer(attachment);
+
+            if gl
+                .read_pixels_to_pixel_buffer(x, y, 1, 1, GL::RGBA_INTEGER, GL::UNSIGNED_INT, attachment_index as i32 * PIXEL_SIZE)
+                .is_err()
+            {
+                result = Err(PickingError::ReadPixelsFailed);
+            }
+        }
+
+        gl.read_buffer(GL::COLOR_ATTACHMENT0);
+        gl.bind_buffer(GL::PIXEL_PACK_BUFFER, None);
+        result
+    }
+
+    fn read_result(&self, gl: &GL) -> Option<PickResult> {
+        let mut bytes = [0; 2 * PIXEL_SIZE as usize];
+
+        gl.bind_buffer(GL::PIXEL_PACK_BUFFER, self.pixel_buffer);
+        gl.get_buffer_sub_data(GL::PIXEL_PACK_BUFFER, 0, &mut bytes);
+        gl.bind_buffer(GL::PIXEL_PACK_BUFFER, None);
+
+        decode_pick(&bytes)
+    }
+}
+
+/// The mesh ID is offset by one so that `0`, the clear value, means nothing was hit.
+fn decode_pick(bytes: &[u8]) -> Option<PickResult> {
+    let mesh_id = u32::from_ne_bytes(bytes[0..4].try_into().unwrap());
+    let instance_id = u32::from_ne_bytes(bytes[PIXEL_SIZE as usize..PIXEL_SIZE as usize + 4].try_into().unwrap());
+
+    if mesh_id == 0 {
+        return None;
+    }
+
+    Some(PickResult {
+        mesh_index: mesh_id as usize - 1,
+        instance_id,
+    })
+}
+
+/// Renames the `main` function of a vertex shader and wraps it with one that also
+/// forwards `gl_InstanceID` to the picking fragment shader.
+pub fn picking_vertex_shader_source(source: &str) -> Option<String> {
+    let main_start = find_main_function(source)?;
+
+    let mut output = String::with_capacity(source.len() + 256);
+
+    // Declarations must come after the version and extension directives
+    let header_end = header_end(source);
+
+    output.push_str(&source[..header_end]);
+    if !output.is_empty() && !output.ends_with('\n') {
+        output.push('\n');
+    }
+    output.push_str(&format!("flat out highp uint {INSTANCE_ID_VARYING};\n"));
+    output.push_str(&source[header_end..main_start]);
+    output.push_str("suricato_original_main");
+    output.push_str(&source[main_start + "main".len()..]);
+    output.push_str(&format!(
+        "\nvoid main() {{\n    suricato_original_main();\n    {INSTANCE_ID_VARYING} = uint(gl_InstanceID);\n}}\n"
+    ));
+
+    Some(output)
+}
+
+/// Byte offset of the `main` identifier in the `void main(` definition.
+fn find_main_function(source: &str) -> Option<usize> {
+    let is_identifier = |character: char| character.is_ascii_alphanumeric() || character == '_';
+
+    source.match_indices("main").map(|(index, _)| index).find(|&index| {
+        let before = &source[..index];
+        let after = &source[index + "main".len()..];
+
+        before.trim_end().ends_with("void")
+            && before.ends_with(char::is_whitespace)
+            && !before.trim_end().trim_end_matches("void").ends_with(is_identifier)
+            && after.trim_start().starts_with('(')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::shader::{ShaderStage, chunks::ShaderChunks, preprocessor::preprocess};
+
+    #[test]
+    fn wraps_vertex_shader_main() {
+        let source = "#version 300 es\nin vec3 position;\nvoid main() {\n    gl_Position = vec4(position, 1.0);\n}\n";
+        let output = picking_vertex_shader_source(source).unwrap();
+
+        assert!(output.starts_with("#version 300 es\nflat out highp uint suricato_picking_instance_id;\nin vec3 position;\n"));
+        assert!(output.contains("void suricato_original_main() {\n    gl_Position"));
+        assert!(output.contains("suricato_picking_instance_id = uint(gl_InstanceID);"));
+        assert_eq!(output.matches("void main()").count(), 1);
+    }
+
+    #[test]
+    fn declares_the_varying_after_leading_comments_and_extensions() {
+        let source =
+            "// Vertex shader of the demo\n\n#version 300 es\n#extension GL_OVR_multiview2 : require\nin vec3 position;\nvoid main() {}\n";
+        let output = picking_vertex_shader_source(source).unwrap();
+
+        assert!(output.starts_with(
+            "// Vertex shader of the demo\n\n#version 300 es\n#extension GL_OVR_multiview2 : require\nflat out highp uint suricato_picking_instance_id;\nin vec3 position;\n"
+        ));
+
+        let preprocessed = preprocess(&output, ShaderStage::Vertex, &BTreeMap::new(), &ShaderChunks::default()).unwrap();
+        assert!(preprocessed.source.starts_with("#version 300 es\n"));
+        assert_eq!(preprocessed.source.matches("#version").count(), 1);
+
+        // Without a version directive, the one added by the preprocessor stays first
+        let output = picking_vertex_shader_source("// No version\nvoid main() {}").unwrap();
+        assert!(output.starts_with("// No version\nflat out highp uint suricato_picking_instance_id;\n"));
+    }
+
+    #[test]
+    fn ignores_identifiers_containing_main() {
+        let source = "#version 300 es\nvoid domain (float remaining) {}\nvoid   main (void) {}\n";
+        let output = picking_vertex_shader_source(source).unwrap();
+
+        assert!(output.contains("void domain (float remaining) {}"));
+        assert!(output.contains("void   suricato_original_main (void) {}"));
+
+        assert!(picking_vertex_shader_source("void helper() {}").is_none());
+    }
+
+    #[test]
+    fn decodes_ids() {
+        let mut bytes = [0; 2 * PIXEL_SIZE as usize];
+        assert_eq!(decode_pick(&bytes), None);
+
+        bytes[0..4].copy_from_slice(&3u32.to_ne_bytes());
+        bytes[16..20].copy_from_slice(&42u32.to_ne_bytes());
+
+        assert_eq!(
+            decode_pick(&bytes),
+            Some(PickResult {
+                mesh_index:  2,
+                instance_id: 42,
+            })
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    rc::{Rc, Weak},
+};
+
+use crate::{
+    backend::Context as GL,
+    material::{Material, MaterialError, MaterialResources},
+    ubo::UniformBufferError,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ProgramKey {
+    vertex_source:       String,
+    fragment_source:     String,
+    defines:             BTreeMap<String, String>,
+    attribute_locations: BTreeMap<String, u32>,
+}
+
+/// Programs shared by every material with the same shaders, owned by the [`Renderer`](crate::renderer::Renderer).
+///
+/// Programs are keyed by their preprocessed sources and defines, so materials only differing by their
+/// uniforms share a program. A program is deleted once the last material using it is dropped.
+#[derive(Default)]
+pub struct ProgramCache {
+    programs:               HashMap<ProgramKey, Weak<MaterialResources>>,
+    /// Binding point of the blocks bound by name, applied to every program when it is created.
+    uniform_block_bindings: HashMap<String, u32>,
+}
+
+impl ProgramCache {
+    pub fn new() -> ProgramCache {
+        ProgramCache::default()
+    }
+
+    /// Program for the current sources and defines of `material`, compiled only if no other material uses it.
+    ///
+    /// `attribute_locations` are bound before linking, see [`MaterialResources::with_attribute_locations`].
+    pub fn get_or_create(
+        &mut self,
+        gl: &GL,
+        material: &Material,
+        attribute_locations: &HashMap<String, u32>,
+    ) -> Result<Rc<MaterialResources>, MaterialError> {
+        let (vertex_source, fragment_source) = material.preprocess()?;
+
+        let key = ProgramKey {
+            vertex_source:       vertex_source.source.clone(),
+            fragment_source:     fragment_source.source.clone(),
+            defines:             material.defines.clone(),
+            attribute_locations: attribute_locations
+                .iter()
+                .map(|(name, location)| (name.clone(), *location))
+                .collect(),
+        };
+
+        if let Some(resources) = self.programs.get(&key).and_then(Weak::upgrade) {
+            return Ok(resources);
+        }
+
+        let resources = Rc::new(MaterialResources::from_sources(
+            gl,
+            &vertex_source,
+            &fragment_source,
+            &material.defines,
+            attribute_locations,
+        )?);
+
+        for (name, binding_point) in &self.uniform_block_bindings {
+            resources.set_uniform_block(name, *binding_point);
+        }
+
+        self.programs.retain(|_, resources| resources.strong_count() > 0);
+        self.programs.insert(key, Rc::downgrade(&resources));
+
+        Ok(resources)
+    }
+
+    /// Binds the block `name` of every program, including the ones created later, to a binding point of its own.
+    ///
+    /// A block already bound keeps its binding point, the lowest free one is taken otherwise.
+    pub fn bind_uniform_block(&mut self, gl: &GL, name: &str) -> Result<u32, UniformBufferError> {
+        let binding_point = match self.uniform_block_bindings.get(name) {
+            Some(binding_point) => *binding_point,
+            None => {
+                let available = gl.get_parameter_i32(GL::MAX_UNIFORM_BUFFER_BINDINGS).max(0) as u32;
+                let used: HashSet<u32> = self.uniform_block_bindings.values().copied().collect();
+
+                (0..available)
+                    .find(|binding_point| !used.contains(binding_point))
+                    .ok_or(UniformBufferError::NoBindingPointLeft { available })?
+            }
+        };
+
+        for resources in self.programs.values().filter_map(Weak::upgrade) {
+            resources.set_uniform_block(name, binding_point);
+        }
+
+        self.uniform_block_bindings.insert(String::from(name), binding_point);
+        Ok(binding_point)
+    }
+
+    /// Frees the binding point of the block `name`, programs already bound to it are left as they are.
+    pub fn unbind_uniform_block(&mut self, name: &str) -> Option<u32> {
+        self.uniform_block_bindings.remove(name)
+    }
+
+    pub fn get_uniform_block_binding(&self, name: &str) -> Option<u32> {
+        self.uniform_block_bindings.get(name).copied()
+    }
+
+    /// Number of programs still used by a material.
+    pub fn len(&self) -> usize {
+        self.programs.values().filter(|resources| resources.strong_count() > 0).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{backend::mock::MockContext, geometry::Geometry, mesh::Mesh, renderer::Renderer, ubo::UniformBufferObject};
+
+    const VERTEX_SHADER_SOURCE: &str = "#version 300 es\nin vec3 position;\nvoid main() {\n    gl_Position = vec4(position, 1.0);\n}\n";
+    const FRAGMENT_SHADER_SOURCE: &str =
+        "out vec4 color;\nvoid main() {\n#ifdef USE_RED\n    color = vec4(1.0, 0.0, 0.0, 1.0);\n#else\n    color = vec4(1.0);\n#endif\n}\n";
+
+    fn mesh() -> Mesh {
+        Mesh::new(Geometry::quad(), Material::new(VERTEX_SHADER_SOURCE, FRAGMENT_SHADER_SOURCE))
+    }
+
+    #[test]
+    fn shares_programs_between_materials() {
+        let mock = Rc::new(MockContext::new(8, 8));
+        let mut renderer = Renderer::with_context(mock.context());
+
+        let mut meshes: Vec<Mesh> = (0..3).map(|_| mesh()).collect();
+        for mesh in &mut meshes {
+            renderer.render(mesh);
+        }
+
+        assert_eq!(mock.state().call_count("link_program"), 1);
+        assert_eq!(renderer.get_program_cache().len(), 1);
+
+        drop(meshes);
+        assert_eq!(mock.state().call_count("delete_program"), 1);
+        assert!(renderer.get_program_cache().is_empty());
+    }
+
+    #[test]
+    fn switches_between_define_variants() {
+        let mock = Rc::new(MockContext::new(8, 8));
+        let mut renderer = Renderer::with_context(mock.context());
+        let mut mesh = mesh();
+
+        renderer.render(&mut mesh);
+        let default_program = mesh.material.resources.as_ref().unwrap().get_program();
+
+        mesh.material.set_define("USE_RED", "1");
+        renderer.render(&mut mesh);
+        let red_program = mesh.material.resources.as_ref().unwrap().get_program();
+
+        mesh.material.remove_define("USE_RED");
+        renderer.render(&mut mesh);
+
+        let state = mock.state();
+        assert_ne!(default_program, red_program);
+        assert_eq!(
+            state.draws.iter().map(|draw| draw.program).collect::<Vec<_>>(),
+            [Some(default_program.0), Some(red_program.0), Some(default_program.0)]
+        );
+        assert_eq!(state.call_count("link_program"), 2);
+
+        // The vertex array follows the attribute locations of the program
+        assert_eq!(state.call_count("create_vertex_array"), 3);
+    }
+
+    #[test]
+    fn binds_registered_blocks_of_every_program() {
+        let mock = Rc::new(MockContext::new(8, 8));
+        let mut renderer = Renderer::with_context(mock.context());
+
+        let block_shader = |block: &str| {
+            format!(
+                "layout(std140) uniform {block} {{\n    vec4 color;\n}};\nout vec4 color;\nvoid main() {{\n    color = {block}_color;\n}}\n"
+            )
+        };
+        let mut lights = Mesh::new(Geometry::quad(), Material::new(VERTEX_SHADER_SOURCE, &block_shader("Lights")));
+        let mut fog = Mesh::new(Geometry::quad(), Material::new(VERTEX_SHADER_SOURCE, &block_shader("Fog")));
+        renderer.render(&mut lights);
+
+        let mut lights_ubo = UniformBufferObject::new(&renderer, &[0; 16]);
+        let mut fog_ubo = UniformBufferObject::new(&renderer, &[0; 16]);
+        assert_eq!(renderer.register_uniform_buffer("Fog", &mut fog_ubo).unwrap(), 0);
+        assert_eq!(renderer.register_uniform_buffer("Lights", &mut lights_ubo).unwrap(), 1);
+        assert_eq!(renderer.register_uniform_buffer("Lights", &mut lights_ubo).unwrap(), 1);
+
+        // Programs created after the buffer was registered are bound when linked
+        renderer.render(&mut fog);
+
+        let program = |mesh: &Mesh| mesh.material.resources.as_ref().unwrap().get_program().0;
+        let state = mock.state();
+        assert_eq!(state.programs[&program(&lights)].uniform_block_bindings[&0], 1);
+        assert_eq!(state.programs[&program(&fog)].uniform_block_bindings[&0], 0);
+        assert_eq!(
+            state.uniform_buffer_bindings[&1],
+            lights_ubo.buffer.get_buffer_gpu(&renderer.gl).unwrap().0
+        );
+        drop(state);
+
+        // Freed binding points are taken again, until there is none left
+        mock.set_parameter(GL::MAX_UNIFORM_BUFFER_BINDINGS, 2);
+        assert_eq!(renderer.unregister_uniform_buffer("Fog"), Some(0));
+        assert_eq!(renderer.register_uniform_buffer("Sky", &mut fog_ubo).unwrap(), 0);
+        assert!(matches!(
+            renderer.register_uniform_buffer("Fog", &mut fog_ubo),
+            Err(UniformBufferError::NoBindingPointLeft { available: 2 })
+        ));
+    }
+}
@@ -0,0 +1,367 @@
+use glam::{Mat4, Vec3};
+
+use crate::{
+    bounds::{BoundingBox, BoundingSphere},
+    mesh::Mesh,
+};
+
+/// Triangles closer to parallel than this to a ray are not intersected.
+const EPSILON: f32 = 1e-7;
+
+/// Bound on the relative rounding error of the slab distances, `3u / (1 - 3u)` for single precision.
+const BOX_INTERSECTION_ERROR: f32 = 1.8e-7;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin:    Vec3,
+    pub direction: Vec3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TriangleIntersection {
+    pub distance: f32,
+
+    /// Weights of the second and third vertices, the first one is `1 - u - v`.
+    pub u: f32,
+    pub v: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RaycastHit {
+    /// Distance along the ray in world units.
+    pub distance:       f32,
+    pub point:          Vec3,
+    /// World space geometric normal of the triangle, facing the ray origin.
+    pub normal:         Vec3,
+    /// Weights of the three triangle vertices at the hit point.
+    pub barycentric:    Vec3,
+    /// Index of the triangle in draw order, see [`Geometry::get_triangle_indices`](crate::geometry::Geometry::get_triangle_indices).
+    pub triangle_index: usize,
+    /// Index of the mesh in the scene passed to [`raycast`].
+    pub mesh_index:     usize,
+}
+
+impl Ray {
+    /// The direction is normalized so distances are measured in world units.
+    pub fn new(origin: Vec3, direction: Vec3) -> Ray {
+        Ray {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    pub fn at(&self, distance: f32) -> Vec3 {
+        self.origin + self.direction * distance
+    }
+
+    /// Transforms the ray without normalizing the direction, so a distance
+    /// along the transformed ray is also a distance along the original one.
+    pub fn transform(&self, matrix: &Mat4) -> Ray {
+        Ray {
+            origin:    matrix.transform_point3(self.origin),
+            direction: matrix.transform_vector3(self.direction),
+        }
+    }
+
+    /// Möller–Trumbore ray-triangle intersection, hitting both front and back faces.
+    pub fn intersect_triangle(&self, a: Vec3, b: Vec3, c: Vec3) -> Option<TriangleIntersection> {
+        let edge_1 = b - a;
+        let edge_2 = c - a;
+
+        let p = self.direction.cross(edge_2);
+        let determinant = edge_1.dot(p);
+
+        if determinant.abs() < EPSILON {
+            return None;
+        }
+
+        let inverse_determinant = 1.0 / determinant;
+        let t = self.origin - a;
+
+        let u = t.dot(p) * inverse_determinant;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let q = t.cross(edge_1);
+
+        let v = self.direction.dot(q) * inverse_determinant;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let distance = edge_2.dot(q) * inverse_determinant;
+        if distance < 0.0 {
+            return None;
+        }
+
+        Some(TriangleIntersection { distance, u, v })
+    }
+
+    /// Slab test. Returns the distance at which the ray enters the box, or `0.0` if it starts inside.
+    pub fn intersect_box(&self, bounding_box: &BoundingBox) -> Option<f32> {
+        let inverse_direction = self.direction.recip();
+
+        let t_1 = (bounding_box.min - self.origin) * inverse_direction;
+        let t_2 = (bounding_box.max - self.origin) * inverse_direction;
+
+        let t_near = t_1.min(t_2).max_element();
+        let t_far = t_1.max(t_2).min_element();
+
+        // Widen the far distance slightly so rays grazing an edge or hitting a flat box are not lost to rounding,
+        // see "Robust BVH Ray Traversal" (Thiago Ize, 2013)
+        let t_far = t_far * (1.0 + 2.0 * BOX_INTERSECTION_ERROR);
+
+        if t_near > t_far || t_far < 0.0 {
+            return None;
+        }
+
+        Some(t_near.max(0.0))
+    }
+
+    /// Returns the distance at which the ray enters the sphere, or `0.0` if it starts inside.
+    pub fn intersect_sphere(&self, sphere: &BoundingSphere) -> Option<f32> {
+        let to_center = sphere.center - self.origin;
+        let direction_length_squared = self.direction.length_squared();
+
+        // Solve |origin + t * direction - center|² = radius²
+        let b = to_center.dot(self.direction);
+        let c = to_center.length_squared() - sphere.radius * sphere.radius;
+        let discriminant = b * b - direction_length_squared * c;
+
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        let square_root = discriminant.sqrt();
+        let t_near = (b - square_root) / direction_length_squared;
+        let t_far = (b + square_root) / direction_length_squared;
+
+        if t_far < 0.0 {
+            return None;
+        }
+
+        Some(t_near.max(0.0))
+    }
+}
+
+/// Closest intersection between `ray` and the triangles of the meshes in `scene`.
+pub fn raycast(scene: &mut [Mesh], ray: &Ray) -> Option<RaycastHit> {
+    let mut closest_hit: Option<RaycastHit> = None;
+
+    for (mesh_index, mesh) in scene.iter_mut().enumerate() {
+        let Some(mut hit) = raycast_mesh(mesh, ray) else {
+            continue;
+        };
+
+        if closest_hit.is_none_or(|closest_hit| hit.distance < closest_hit.distance) {
+            hit.mesh_index = mesh_index;
+            closest_hit = Some(hit);
+        }
+    }
+
+    closest_hit
+}
+
+/// Closest intersection between `ray` and the triangles of `mesh`.
+///
+/// Triangles are read from the CPU copy of the geometry buffers according to the
+/// mesh `render_primitive` and accelerated with the geometry BVH. Instanced meshes are tested without their per instance data.
+/// The returned `mesh_index` is always `0`.
+pub fn raycast_mesh(mesh: &mut Mesh, ray: &Ray) -> Option<RaycastHit> {
+    let world_matrix = mesh.transform.to_mat4();
+    let inverse_world_matrix = world_matrix.inverse();
+    let local_ray = ray.transform(&inverse_world_matrix);
+
+    let bvh = mesh.geometry.get_bvh(mesh.render_primitive)?;
+    let (triangle_index, intersection) = bvh.raycast(&local_ray)?;
+    let [a, b, c] = bvh.triangle(triangle_index);
+
+    let local_normal = (b - a).cross(c - a);
+    let mut normal = inverse_world_matrix.transpose().transform_vector3(local_normal).normalize();
+
+    if normal.dot(ray.direction) > 0.0 {
+        normal = -normal;
+    }
+
+    Some(RaycastHit {
+        distance: intersection.distance,
+        point: ray.at(intersection.distance),
+        normal,
+        barycentric: Vec3::new(1.0 - intersection.u - intersection.v, intersection.u, intersection.v),
+        triangle_index,
+        mesh_index: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Quat, Vec2, Vec3};
+
+    use super::*;
+    use crate::{camera::PerspectiveCamera, geometry::Geometry, material::Material, mesh::RenderPrimitive};
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        assert!(a.abs_diff_eq(b, 1e-5), "{a} != {b}");
+    }
+
+    fn box_mesh() -> Mesh {
+        Mesh::new(Geometry::box_geometry(), Material::new("", ""))
+    }
+
+    #[test]
+    fn triangle_hit_returns_distance_and_barycentrics() {
+        let ray = Ray::new(Vec3::new(0.25, 0.25, 5.0), Vec3::NEG_Z);
+        let hit = ray.intersect_triangle(Vec3::ZERO, Vec3::X, Vec3::Y).unwrap();
+
+        assert!((hit.distance - 5.0).abs() < 1e-6);
+        assert!((hit.u - 0.25).abs() < 1e-6);
+        assert!((hit.v - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn triangle_back_face_is_hit() {
+        let ray = Ray::new(Vec3::new(0.25, 0.25, -5.0), Vec3::Z);
+        assert!(ray.intersect_triangle(Vec3::ZERO, Vec3::X, Vec3::Y).is_some());
+    }
+
+    #[test]
+    fn triangle_miss_outside_edges() {
+        let ray = Ray::new(Vec3::new(0.75, 0.75, 5.0), Vec3::NEG_Z);
+        assert!(ray.intersect_triangle(Vec3::ZERO, Vec3::X, Vec3::Y).is_none());
+    }
+
+    #[test]
+    fn triangle_behind_ray_is_not_hit() {
+        let ray = Ray::new(Vec3::new(0.25, 0.25, 5.0), Vec3::Z);
+        assert!(ray.intersect_triangle(Vec3::ZERO, Vec3::X, Vec3::Y).is_none());
+    }
+
+    #[test]
+    fn parallel_ray_does_not_hit_triangle() {
+        let ray = Ray::new(Vec3::new(-1.0, 0.25, 0.0), Vec3::X);
+        assert!(ray.intersect_triangle(Vec3::ZERO, Vec3::X, Vec3::Y).is_none());
+    }
+
+    #[test]
+    fn box_intersection() {
+        let bounding_box = BoundingBox::new(Vec3::splat(-1.0), Vec3::splat(1.0));
+
+        let outside = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::NEG_Z);
+        assert_eq!(outside.intersect_box(&bounding_box), Some(4.0));
+
+        let inside = Ray::new(Vec3::ZERO, Vec3::X);
+        assert_eq!(inside.intersect_box(&bounding_box), Some(0.0));
+
+        let miss = Ray::new(Vec3::new(3.0, 0.0, 5.0), Vec3::NEG_Z);
+        assert_eq!(miss.intersect_box(&bounding_box), None);
+
+        let behind = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::Z);
+        assert_eq!(behind.intersect_box(&bounding_box), None);
+    }
+
+    #[test]
+    fn sphere_intersection() {
+        let sphere = BoundingSphere::new(Vec3::ZERO, 1.0);
+
+        let outside = Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::NEG_Z);
+        assert!((outside.intersect_sphere(&sphere).unwrap() - 4.0).abs() < 1e-6);
+
+        let inside = Ray::new(Vec3::ZERO, Vec3::Y);
+        assert_eq!(inside.intersect_sphere(&sphere), Some(0.0));
+
+        let miss = Ray::new(Vec3::new(0.0, 2.0, 5.0), Vec3::NEG_Z);
+        assert_eq!(miss.intersect_sphere(&sphere), None);
+    }
+
+    #[test]
+    fn raycast_hits_front_face_of_transformed_box() {
+        let mut mesh = box_mesh();
+        mesh.transform.translation = Vec3::new(0.0, 0.0, -10.0);
+        mesh.transform.scale = Vec3::splat(2.0);
+
+        let ray = Ray::new(Vec3::ZERO, Vec3::NEG_Z);
+        let hit = raycast_mesh(&mut mesh, &ray).unwrap();
+
+        assert!((hit.distance - 9.0).abs() < 1e-5);
+        assert_close(hit.point, Vec3::new(0.0, 0.0, -9.0));
+        assert_close(hit.normal, Vec3::Z);
+        assert!((hit.barycentric.element_sum() - 1.0).abs() < 1e-5);
+
+        // Front face triangles are the first two in the box geometry
+        assert!(hit.triangle_index < 2);
+    }
+
+    #[test]
+    fn raycast_normal_follows_rotation() {
+        let mut mesh = box_mesh();
+        mesh.transform.rotation = Quat::from_rotation_y(std::f32::consts::FRAC_PI_2);
+
+        let ray = Ray::new(Vec3::new(5.0, 0.1, 0.1), Vec3::NEG_X);
+        let hit = raycast_mesh(&mut mesh, &ray).unwrap();
+
+        assert!((hit.distance - 4.5).abs() < 1e-5);
+        assert_close(hit.normal, Vec3::X);
+    }
+
+    #[test]
+    fn raycast_returns_closest_mesh() {
+        let mut near = box_mesh();
+        near.transform.translation.z = -5.0;
+
+        let mut far = box_mesh();
+        far.transform.translation.z = -10.0;
+
+        let mut scene = vec![far, near];
+        let hit = raycast(&mut scene, &Ray::new(Vec3::ZERO, Vec3::NEG_Z)).unwrap();
+
+        assert_eq!(hit.mesh_index, 1);
+        assert!((hit.distance - 4.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn raycast_ignores_line_primitives() {
+        let mut mesh = box_mesh();
+        mesh.render_primitive = RenderPrimitive::Lines;
+
+        assert!(raycast_mesh(&mut mesh, &Ray::new(Vec3::new(0.0, 0.0, 5.0), Vec3::NEG_Z)).is_none());
+    }
+
+    #[test]
+    fn triangle_strip_and_fan_expansion() {
+        let mut geometry = Geometry::quad();
+        geometry.indices = None;
+
+        assert_eq!(
+            geometry.get_triangle_indices(RenderPrimitive::TriangleStrip),
+            vec![[0, 1, 2], [2, 1, 3]]
+        );
+        assert_eq!(
+            geometry.get_triangle_indices(RenderPrimitive::TriangleFan),
+            vec![[0, 1, 2], [0, 2, 3]]
+        );
+        assert_eq!(geometry.get_triangle_indices(RenderPrimitive::Triangles), vec![[0, 1, 2]]);
+    }
+
+    #[test]
+    fn screen_center_ray_points_forward() {
+        let mut camera = PerspectiveCamera::new(1.0, 2.0, 0.1, 100.0);
+        camera.update_projection_matrix();
+        camera.transform.translation = Vec3::new(1.0, 2.0, 3.0);
+
+        let ray = camera.screen_point_to_ray(Vec2::new(400.0, 200.0), Vec2::new(800.0, 400.0));
+
+        assert_close(ray.direction, Vec3::NEG_Z);
+        assert!((ray.origin.x - 1.0).abs() < 1e-5 && (ray.origin.y - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn screen_corner_ray_points_up_left() {
+        let mut camera = PerspectiveCamera::new(1.0, 1.0, 0.1, 100.0);
+        camera.update_projection_matrix();
+
+        let ray = camera.screen_point_to_ray(Vec2::ZERO, Vec2::new(100.0, 100.0));
+
+        assert!(ray.direction.x < 0.0 && ray.direction.y > 0.0 && ray.direction.z < 0.0);
+    }
+}
@@ -0,0 +1,352 @@
+use std::{error::Error, fmt};
+
+#[cfg(feature = "web")]
+use wasm_bindgen::{JsCast, JsValue};
+#[cfg(feature = "web")]
+use wasm_bindgen_futures::JsFuture;
+#[cfg(feature = "web")]
+use web_sys::js_sys::{Array, Promise, Uint8Array};
+#[cfg(feature = "web")]
+use web_sys::{Blob, BlobPropertyBag, HtmlAnchorElement, Url};
+
+use crate::{
+    backend::{Context as GL, FramebufferHandle},
+    render_target::RenderTargetError,
+    texture::ImagePixelData,
+};
+
+#[derive(Debug)]
+pub enum ReadbackError {
+    RenderTargetError(RenderTargetError),
+    /// The requested rectangle is not inside the framebuffer.
+    OutOfBounds,
+    ReadPixelsFailed,
+    BufferCreationFailed,
+    EncodingFailed,
+}
+
+impl fmt::Display for ReadbackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadbackError::RenderTargetError(error) => write!(f, "{error}"),
+            ReadbackError::OutOfBounds => write!(f, "The requested pixels are outside of the framebuffer"),
+            ReadbackError::ReadPixelsFailed => write!(f, "Failed to read the pixels"),
+            ReadbackError::BufferCreationFailed => write!(f, "Failed to create the pixel buffer"),
+            ReadbackError::EncodingFailed => write!(f, "Failed to encode the image"),
+        }
+    }
+}
+
+impl Error for ReadbackError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ReadbackError::RenderTargetError(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<RenderTargetError> for ReadbackError {
+    fn from(value: RenderTargetError) -> Self {
+        ReadbackError::RenderTargetError(value)
+    }
+}
+
+/// RGBA components, four per pixel.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PixelData {
+    UnsignedByte(Vec<u8>),
+    Float(Vec<f32>),
+}
+
+/// https://developer.mozilla.org/en-US/docs/Web/API/WebGL2RenderingContext/readPixels#type
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelType {
+    UnsignedByte = GL::UNSIGNED_BYTE,
+    Float        = GL::FLOAT,
+}
+
+impl PixelType {
+    pub fn size_in_bytes(&self) -> usize {
+        match self {
+            PixelType::UnsignedByte => 1,
+            PixelType::Float => 4,
+        }
+    }
+}
+
+/// RGBA pixels with the rows ordered from top to bottom, as in image files.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pixels {
+    pub width:  u32,
+    pub height: u32,
+    pub data:   PixelData,
+}
+
+impl Pixels {
+    /// Float components are clamped to `[0, 1]`, no tone mapping or color space conversion is applied.
+    pub fn to_rgba8(&self) -> ImagePixelData {
+        let bytes = match &self.data {
+            PixelData::UnsignedByte(bytes) => bytes.clone(),
+            PixelData::Float(values) => values.iter().map(|value| (value.clamp(0.0, 1.0) * 255.0).round() as u8).collect(),
+        };
+
+        ImagePixelData {
+            width: self.width,
+            height: self.height,
+            bytes,
+        }
+    }
+
+    pub fn to_png(&self) -> Result<Vec<u8>, png::EncodingError> {
+        encode_png(&self.to_rgba8())
+    }
+
+    /// Builds the pixels from bytes read from a framebuffer, whose rows go from bottom to top.
+    fn from_framebuffer_bytes(width: u32, height: u32, pixel_type: PixelType, bytes: &[u8]) -> Pixels {
+        let row_size = width as usize * 4 * pixel_type.size_in_bytes();
+        let flipped: Vec<u8> = bytes.chunks_exact(row_size).rev().flatten().copied().collect();
+
+        let data = match pixel_type {
+            PixelType::UnsignedByte => PixelData::UnsignedByte(flipped),
+            PixelType::Float => {
+                PixelData::Float(
+                    flipped
+                        .chunks_exact(4)
+                        .map(|chunk| f32::from_ne_bytes(chunk.try_into().unwrap()))
+                        .collect(),
+                )
+            }
+        };
+
+        Pixels { width, height, data }
+    }
+}
+
+/// Encodes 8 bit RGBA pixels, rows from top to bottom, as a PNG file.
+pub fn encode_png(image: &ImagePixelData) -> Result<Vec<u8>, png::EncodingError> {
+    let mut bytes = Vec::new();
+
+    let mut encoder = png::Encoder::new(&mut bytes, image.width, image.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&image.bytes)?;
+    writer.finish()?;
+
+    Ok(bytes)
+}
+
+/// Object URL for the bytes that stays valid until passed to `URL.revokeObjectURL`.
+#[cfg(feature = "web")]
+pub fn create_blob_url(bytes: &[u8], mime_type: &str) -> Result<String, JsValue> {
+    let options = BlobPropertyBag::new();
+    options.set_type(mime_type);
+
+    let parts = Array::of1(&Uint8Array::from(bytes));
+    let blob = Blob::new_with_u8_array_sequence_and_options(&parts, &options)?;
+
+    Url::create_object_url_with_blob(&blob)
+}
+
+/// Makes the browser save the bytes as a file named `file_name`.
+#[cfg(feature = "web")]
+pub fn download(bytes: &[u8], file_name: &str, mime_type: &str) -> Result<(), JsValue> {
+    let url = create_blob_url(bytes, mime_type)?;
+
+    let document = web_sys::window().unwrap().document().unwrap();
+    let anchor = document.create_element("a")?.dyn_into::<HtmlAnchorElement>()?;
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+
+    Url::revoke_object_url(&url)
+}
+
+/// Rectangle of a framebuffer to read, with `y` measured from the top like the other screen coordinates.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ReadRegion {
+    pub x:                  u32,
+    pub y:                  u32,
+    pub width:              u32,
+    pub height:             u32,
+    pub framebuffer_width:  u32,
+    pub framebuffer_height: u32,
+    pub pixel_type:         PixelType,
+}
+
+impl ReadRegion {
+    fn size_in_bytes(&self) -> usize {
+        self.width as usize * self.height as usize * 4 * self.pixel_type.size_in_bytes()
+    }
+
+    fn check_bounds(&self) -> Result<(), ReadbackError> {
+        if self.x.saturating_add(self.width) > self.framebuffer_width || self.y.saturating_add(self.height) > self.framebuffer_height {
+            return Err(ReadbackError::OutOfBounds);
+        }
+
+        Ok(())
+    }
+
+    /// Reads into `destination` or, when `None`, into the bound `PIXEL_PACK_BUFFER`.
+    fn read(&self, gl: &GL, destination: Option<&mut [u8]>) -> Result<(), ReadbackError> {
+        let x = self.x as i32;
+        let y = (self.framebuffer_height - self.y - self.height) as i32;
+        let (width, height) = (self.width as i32, self.height as i32);
+
+        let result = match destination {
+            Some(destination) => gl.read_pixels(x, y, width, height, GL::RGBA, self.pixel_type as u32, destination),
+            None => gl.read_pixels_to_pixel_buffer(x, y, width, height, GL::RGBA, self.pixel_type as u32, 0),
+        };
+
+        result.map_err(|_| ReadbackError::ReadPixelsFailed)
+    }
+}
+
+/// Reads the region of `framebuffer`, the canvas when `None`, blocking until the GPU is done.
+///
+/// `bound_framebuffer` is the framebuffer bound for drawing, bound again for reading once done.
+pub(crate) fn read_pixels(
+    gl: &GL,
+    framebuffer: Option<FramebufferHandle>,
+    region: ReadRegion,
+    bound_framebuffer: Option<FramebufferHandle>,
+) -> Result<Pixels, ReadbackError> {
+    region.check_bounds()?;
+    gl.bind_framebuffer(GL::READ_FRAMEBUFFER, framebuffer);
+
+    let mut bytes = vec![0; region.size_in_bytes()];
+    let result = region.read(gl, Some(&mut bytes));
+
+    gl.bind_framebuffer(GL::READ_FRAMEBUFFER, bound_framebuffer);
+    result?;
+
+    Ok(Pixels::from_framebuffer_bytes(
+        region.width,
+        region.height,
+        region.pixel_type,
+        &bytes,
+    ))
+}
+
+/// Copies the region into a pixel buffer and waits for a fence before mapping it,
+/// so the CPU does not stall while the GPU finishes the frame.
+///
+/// Falls back to a blocking read when fences are not available.
+pub(crate) async fn read_pixels_async(
+    gl: GL,
+    framebuffer: Option<FramebufferHandle>,
+    region: ReadRegion,
+    bound_framebuffer: Option<FramebufferHandle>,
+) -> Result<Pixels, ReadbackError> {
+    region.check_bounds()?;
+
+    let pixel_buffer = gl.create_buffer().ok_or(ReadbackError::BufferCreationFailed)?;
+    gl.bind_buffer(GL::PIXEL_PACK_BUFFER, Some(pixel_buffer));
+    gl.buffer_data_size(GL::PIXEL_PACK_BUFFER, region.size_in_bytes() as i32, GL::STREAM_READ);
+
+    gl.bind_framebuffer(GL::READ_FRAMEBUFFER, framebuffer);
+    let read_result = region.read(&gl, None);
+    gl.bind_framebuffer(GL::READ_FRAMEBUFFER, bound_framebuffer);
+    gl.bind_buffer(GL::PIXEL_PACK_BUFFER, None);
+
+    if let Err(error) = read_result {
+        gl.delete_buffer(Some(pixel_buffer));
+        return Err(error);
+    }
+
+    if let Some(sync) = gl.fence_sync(GL::SYNC_GPU_COMMANDS_COMPLETE, 0) {
+        gl.flush();
+
+        while gl.client_wait_sync(sync, 0, 0) == GL::TIMEOUT_EXPIRED {
+            next_task().await;
+        }
+
+        gl.delete_sync(Some(sync));
+    }
+
+    let mut bytes = vec![0; region.size_in_bytes()];
+    gl.bind_buffer(GL::PIXEL_PACK_BUFFER, Some(pixel_buffer));
+    gl.get_buffer_sub_data(GL::PIXEL_PACK_BUFFER, 0, &mut bytes);
+    gl.bind_buffer(GL::PIXEL_PACK_BUFFER, None);
+    gl.delete_buffer(Some(pixel_buffer));
+
+    Ok(Pixels::from_framebuffer_bytes(
+        region.width,
+        region.height,
+        region.pixel_type,
+        &bytes,
+    ))
+}
+
+/// Yields to the browser so it can process the GPU work the fence is waiting on.
+#[cfg(feature = "web")]
+async fn next_task() {
+    let promise = Promise::new(&mut |resolve, _| {
+        web_sys::window().unwrap().set_timeout_with_callback(&resolve).unwrap();
+    });
+
+    JsFuture::from(promise).await.ok();
+}
+
+/// Yields to the executor once, the driver keeps processing GPU work on its own.
+#[cfg(not(feature = "web"))]
+async fn next_task() {
+    let mut yielded = false;
+
+    std::future::poll_fn(|context| {
+        if yielded {
+            return std::task::Poll::Ready(());
+        }
+
+        yielded = true;
+        context.waker().wake_by_ref();
+        std::task::Poll::Pending
+    })
+    .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flips_rows_to_top_down_order() {
+        // Two rows of one pixel, bottom row first as returned by readPixels
+        let bytes = [1, 2, 3, 4, 5, 6, 7, 8];
+        let pixels = Pixels::from_framebuffer_bytes(1, 2, PixelType::UnsignedByte, &bytes);
+
+        assert_eq!(pixels.data, PixelData::UnsignedByte(vec![5, 6, 7, 8, 1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn converts_float_pixels() {
+        let values = [0.5_f32, -1.0, 2.0, 1.0];
+        let bytes: Vec<u8> = values.iter().flat_map(|value| value.to_ne_bytes()).collect();
+        let pixels = Pixels::from_framebuffer_bytes(1, 1, PixelType::Float, &bytes);
+
+        assert_eq!(pixels.data, PixelData::Float(values.to_vec()));
+        assert_eq!(pixels.to_rgba8().bytes, vec![128, 0, 255, 255]);
+    }
+
+    #[test]
+    fn encodes_png() {
+        let image = ImagePixelData {
+            width:  2,
+            height: 1,
+            bytes:  vec![255, 0, 0, 255, 0, 0, 255, 128],
+        };
+
+        let png = encode_png(&image).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+
+        let decoder = png::Decoder::new(std::io::Cursor::new(png));
+        let mut reader = decoder.read_info().unwrap();
+        let mut decoded = vec![0; reader.output_buffer_size().unwrap()];
+        reader.next_frame(&mut decoded).unwrap();
+
+        assert_eq!(decoded, image.bytes);
+    }
+}
@@ -0,0 +1,183 @@
+use std::cmp::Ordering;
+
+use glam::{Mat4, Vec3};
+
+use crate::{
+    backend::{Context as GL, ProgramHandle},
+    bounds::Frustum,
+    material::MaterialError,
+    mesh::Mesh,
+    program_cache::ProgramCache,
+};
+
+/// Determines when a mesh is drawn relative to the others in a scene.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RenderQueue {
+    Opaque,
+    AlphaTested,
+    Transparent,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RenderItem {
+    pub mesh_index:   usize,
+    pub render_order: i32,
+    pub program:      ProgramHandle,
+    pub material_id:  u64,
+    pub view_depth:   f32,
+}
+
+/// Draw lists rebuilt every frame by [`Renderer::render_scene`](crate::renderer::Renderer::render_scene).
+///
+/// Meshes are referenced by their index in the scene so the scene itself is never reordered.
+#[derive(Debug, Default)]
+pub struct RenderQueues {
+    pub opaque:       Vec<RenderItem>,
+    pub alpha_tested: Vec<RenderItem>,
+    pub transparent:  Vec<RenderItem>,
+}
+
+impl RenderQueues {
+    /// Returns the number of meshes left out because they are outside `frustum`.
+    ///
+    /// The programs of the visible meshes are created here, as the opaque queues are sorted by program.
+    pub fn build(
+        &mut self,
+        gl: &GL,
+        program_cache: &mut ProgramCache,
+        scene: &mut [Mesh],
+        view_matrix: &Mat4,
+        frustum: &Frustum,
+    ) -> Result<usize, MaterialError> {
+        self.opaque.clear();
+        self.alpha_tested.clear();
+        self.transparent.clear();
+
+        let mut culled_count = 0;
+
+        for (mesh_index, mesh) in scene.iter_mut().enumerate() {
+            if mesh.frustum_culled && !mesh.is_in_frustum(frustum) {
+                culled_count += 1;
+                continue;
+            }
+
+            // Sort by the center of the bounds when available, as the origin of a mesh may be far from its vertices
+            let world_matrix = mesh.transform.to_mat4();
+            let local_center = mesh.geometry.bounds.map_or(Vec3::ZERO, |bounds| bounds.bounding_sphere.center);
+            let view_position = view_matrix.transform_point3(world_matrix.transform_point3(local_center));
+
+            let program = mesh.material.try_get_or_create_cached_resources(gl, program_cache)?.get_program();
+
+            let item = RenderItem {
+                mesh_index,
+                render_order: mesh.render_order,
+                program,
+                material_id: mesh.material.id,
+                // The camera looks down -Z, so distances in front of it are negative
+                view_depth: -view_position.z,
+            };
+
+            match mesh.material.get_render_queue() {
+                RenderQueue::Opaque => self.opaque.push(item),
+                RenderQueue::AlphaTested => self.alpha_tested.push(item),
+                RenderQueue::Transparent => self.transparent.push(item),
+            }
+        }
+
+        self.opaque.sort_unstable_by(RenderQueues::front_to_back);
+        self.alpha_tested.sort_unstable_by(RenderQueues::front_to_back);
+        self.transparent.sort_unstable_by(RenderQueues::back_to_front);
+
+        Ok(culled_count)
+    }
+
+    /// Every item in draw order: opaque, then alpha tested, then transparent.
+    pub fn iter(&self) -> impl Iterator<Item = &RenderItem> {
+        self.opaque.iter().chain(self.alpha_tested.iter()).chain(self.transparent.iter())
+    }
+
+    /// Groups by program to reduce program switches, then draws the closest meshes first
+    /// so the depth test rejects hidden fragments early. Materials only break depth ties.
+    fn front_to_back(a: &RenderItem, b: &RenderItem) -> Ordering {
+        a.render_order
+            .cmp(&b.render_order)
+            .then(a.program.0.cmp(&b.program.0))
+            .then(a.view_depth.total_cmp(&b.view_depth))
+            .then(a.material_id.cmp(&b.material_id))
+    }
+
+    /// Blending requires drawing the farthest meshes first.
+    fn back_to_front(a: &RenderItem, b: &RenderItem) -> Ordering {
+        a.render_order.cmp(&b.render_order).then(b.view_depth.total_cmp(&a.view_depth))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{backend::mock::MockContext, camera::PerspectiveCamera, geometry::Geometry, material::Material, renderer::Renderer};
+
+    const VERTEX_SHADER_SOURCE: &str = "#version 300 es\nin vec3 position;\nvoid main() {\n    gl_Position = vec4(position, 1.0);\n}\n";
+    const FRAGMENT_SHADER_SOURCE: &str = "#version 300 es\nout vec4 color;\nvoid main() {\n#ifdef USE_RED\n    color = vec4(1.0, 0.0, 0.0, 1.0);\n#else\n    color = vec4(1.0);\n#endif\n}\n";
+
+    #[test]
+    fn draws_each_program_of_a_queue_once() {
+        let mock = Rc::new(MockContext::new(8, 8));
+        let mut renderer = Renderer::with_context(mock.context());
+        let mut camera = PerspectiveCamera::new(1.0, 1.0, 0.1, 100.0);
+        camera.update_projection_matrix();
+        camera.transform.translation.z = 10.0;
+
+        // Every material is different, the depths alternate between the two programs
+        let mut scene: Vec<Mesh> = (0..6)
+            .map(|index| {
+                let mut material = Material::new(VERTEX_SHADER_SOURCE, FRAGMENT_SHADER_SOURCE);
+                if index % 2 == 0 {
+                    material.set_define("USE_RED", "");
+                }
+
+                let mut mesh = Mesh::new(Geometry::quad(), material);
+                mesh.transform.translation.z = -(index as f32);
+                mesh
+            })
+            .collect();
+
+        renderer.render_scene(&mut scene, &mut camera);
+
+        let state = mock.state();
+        let programs: Vec<_> = state.draws.iter().map(|draw| draw.program).collect();
+        let program_switches = programs.windows(2).filter(|pair| pair[0] != pair[1]).count();
+        assert_eq!(programs.len(), 6);
+        assert_eq!(program_switches, 1);
+        assert_eq!(renderer.get_program_cache().len(), 2);
+    }
+
+    #[test]
+    fn sorts_by_depth_across_materials_of_a_program() {
+        let mock = Rc::new(MockContext::new(8, 8));
+        let gl = mock.context();
+        let mut program_cache = ProgramCache::new();
+        let mut camera = PerspectiveCamera::new(1.0, 1.0, 0.1, 100.0);
+        camera.update_projection_matrix();
+        camera.transform.translation.z = 10.0;
+
+        // Created from the farthest to the closest, so material ids go against depth
+        let mut scene: Vec<Mesh> = (0..4)
+            .map(|index| {
+                let mut mesh = Mesh::new(Geometry::quad(), Material::new(VERTEX_SHADER_SOURCE, FRAGMENT_SHADER_SOURCE));
+                mesh.transform.translation.z = index as f32;
+                mesh
+            })
+            .collect();
+
+        let mut render_queues = RenderQueues::default();
+        render_queues
+            .build(&gl, &mut program_cache, &mut scene, &camera.view_matrix(), &camera.frustum())
+            .unwrap();
+
+        let order: Vec<usize> = render_queues.opaque.iter().map(|item| item.mesh_index).collect();
+        assert_eq!(order, [3, 2, 1, 0]);
+    }
+}
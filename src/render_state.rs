@@ -0,0 +1,530 @@
+use crate::backend::Context as GL;
+
+/// https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/blendEquation#mode
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendEquation {
+    Add             = GL::FUNC_ADD,
+    Subtract        = GL::FUNC_SUBTRACT,
+    ReverseSubtract = GL::FUNC_REVERSE_SUBTRACT,
+    Min             = GL::MIN,
+    Max             = GL::MAX,
+}
+
+/// https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/blendFunc#constants
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendFactor {
+    Zero                     = GL::ZERO,
+    One                      = GL::ONE,
+    SourceColor              = GL::SRC_COLOR,
+    OneMinusSourceColor      = GL::ONE_MINUS_SRC_COLOR,
+    DestinationColor         = GL::DST_COLOR,
+    OneMinusDestinationColor = GL::ONE_MINUS_DST_COLOR,
+    SourceAlpha              = GL::SRC_ALPHA,
+    OneMinusSourceAlpha      = GL::ONE_MINUS_SRC_ALPHA,
+    DestinationAlpha         = GL::DST_ALPHA,
+    OneMinusDestinationAlpha = GL::ONE_MINUS_DST_ALPHA,
+    ConstantColor            = GL::CONSTANT_COLOR,
+    OneMinusConstantColor    = GL::ONE_MINUS_CONSTANT_COLOR,
+    ConstantAlpha            = GL::CONSTANT_ALPHA,
+    OneMinusConstantAlpha    = GL::ONE_MINUS_CONSTANT_ALPHA,
+    SourceAlphaSaturate      = GL::SRC_ALPHA_SATURATE,
+}
+
+/// Used by both the depth test and the stencil test.
+/// https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/depthFunc#func
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareFunction {
+    Never          = GL::NEVER,
+    Less           = GL::LESS,
+    Equal          = GL::EQUAL,
+    LessOrEqual    = GL::LEQUAL,
+    Greater        = GL::GREATER,
+    NotEqual       = GL::NOTEQUAL,
+    GreaterOrEqual = GL::GEQUAL,
+    Always         = GL::ALWAYS,
+}
+
+/// https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/cullFace#mode
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CullFace {
+    Front        = GL::FRONT,
+    Back         = GL::BACK,
+    FrontAndBack = GL::FRONT_AND_BACK,
+}
+
+/// https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/stencilOp#fail
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StencilOperation {
+    Keep          = GL::KEEP,
+    Zero          = GL::ZERO,
+    Replace       = GL::REPLACE,
+    Increment     = GL::INCR,
+    IncrementWrap = GL::INCR_WRAP,
+    Decrement     = GL::DECR,
+    DecrementWrap = GL::DECR_WRAP,
+    Invert        = GL::INVERT,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Blend {
+    pub color_equation:           BlendEquation,
+    pub alpha_equation:           BlendEquation,
+    pub source_color_factor:      BlendFactor,
+    pub destination_color_factor: BlendFactor,
+    pub source_alpha_factor:      BlendFactor,
+    pub destination_alpha_factor: BlendFactor,
+    pub constant_color:           [f32; 4],
+}
+
+impl Blend {
+    pub fn new(source_factor: BlendFactor, destination_factor: BlendFactor) -> Blend {
+        Blend {
+            color_equation:           BlendEquation::Add,
+            alpha_equation:           BlendEquation::Add,
+            source_color_factor:      source_factor,
+            destination_color_factor: destination_factor,
+            source_alpha_factor:      source_factor,
+            destination_alpha_factor: destination_factor,
+            constant_color:           [0.0; 4],
+        }
+    }
+
+    /// Straight (non premultiplied) alpha blending.
+    pub fn alpha() -> Blend {
+        Blend {
+            source_alpha_factor: BlendFactor::One,
+            ..Blend::new(BlendFactor::SourceAlpha, BlendFactor::OneMinusSourceAlpha)
+        }
+    }
+
+    pub fn premultiplied_alpha() -> Blend {
+        Blend::new(BlendFactor::One, BlendFactor::OneMinusSourceAlpha)
+    }
+
+    pub fn additive() -> Blend {
+        Blend {
+            source_alpha_factor: BlendFactor::One,
+            ..Blend::new(BlendFactor::SourceAlpha, BlendFactor::One)
+        }
+    }
+
+    pub fn multiply() -> Blend {
+        Blend::new(BlendFactor::DestinationColor, BlendFactor::OneMinusSourceAlpha)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PolygonOffset {
+    pub factor: f32,
+    pub units:  f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StencilFace {
+    pub function:   CompareFunction,
+    pub reference:  i32,
+    pub read_mask:  u32,
+    pub write_mask: u32,
+    pub fail:       StencilOperation,
+    pub depth_fail: StencilOperation,
+    pub pass:       StencilOperation,
+}
+
+impl Default for StencilFace {
+    fn default() -> StencilFace {
+        StencilFace {
+            function:   CompareFunction::Always,
+            reference:  0,
+            read_mask:  0xFF,
+            write_mask: 0xFF,
+            fail:       StencilOperation::Keep,
+            depth_fail: StencilOperation::Keep,
+            pass:       StencilOperation::Keep,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Stencil {
+    pub front: StencilFace,
+    pub back:  StencilFace,
+}
+
+impl Stencil {
+    /// Uses the same configuration for front and back faces.
+    pub fn new(face: StencilFace) -> Stencil {
+        Stencil { front: face, back: face }
+    }
+
+    fn has_custom_write_mask(&self) -> bool {
+        self.front.write_mask != 0xFF || self.back.write_mask != 0xFF
+    }
+}
+
+/// Fixed function state used when drawing a [`Material`](crate::material::Material).
+///
+/// `None` disables the corresponding GL capability.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderState {
+    pub blend:            Option<Blend>,
+    pub depth_test:       Option<CompareFunction>,
+    pub depth_write:      bool,
+    pub cull_face:        Option<CullFace>,
+    pub color_write_mask: [bool; 4],
+    pub polygon_offset:   Option<PolygonOffset>,
+    pub stencil:          Option<Stencil>,
+}
+
+impl Default for RenderState {
+    fn default() -> RenderState {
+        RenderState {
+            blend:            None,
+            depth_test:       Some(CompareFunction::Less),
+            depth_write:      true,
+            cull_face:        None,
+            color_write_mask: [true; 4],
+            polygon_offset:   None,
+            stencil:          None,
+        }
+    }
+}
+
+impl RenderState {
+    /// Blended and depth tested, without writing depth.
+    pub fn transparent() -> RenderState {
+        RenderState {
+            blend: Some(Blend::alpha()),
+            depth_write: false,
+            ..RenderState::default()
+        }
+    }
+}
+
+/// Mirrors the state currently set on the GL context so that only the
+/// parts of a [`RenderState`] that changed are issued.
+///
+/// Every field starts as `None` (unknown) so the first `apply` sets everything.
+#[derive(Debug, Default)]
+pub struct RenderStateCache {
+    blend:            Option<Option<Blend>>,
+    depth_test:       Option<Option<CompareFunction>>,
+    depth_write:      Option<bool>,
+    cull_face:        Option<Option<CullFace>>,
+    color_write_mask: Option<[bool; 4]>,
+    polygon_offset:   Option<Option<PolygonOffset>>,
+    stencil:          Option<Option<Stencil>>,
+}
+
+impl RenderStateCache {
+    pub fn apply(&mut self, gl: &GL, state: &RenderState) {
+        self.set_blend(gl, state.blend);
+        self.set_depth_test(gl, state.depth_test);
+        self.set_depth_write(gl, state.depth_write);
+        self.set_cull_face(gl, state.cull_face);
+        self.set_color_write_mask(gl, state.color_write_mask);
+        self.set_polygon_offset(gl, state.polygon_offset);
+        self.set_stencil(gl, state.stencil);
+    }
+
+    /// Forgets the cached state, forcing the next `apply` to set everything.
+    /// Needed after GL state is changed outside of the cache.
+    pub fn invalidate(&mut self) {
+        *self = RenderStateCache::default();
+    }
+
+    pub fn set_blend(&mut self, gl: &GL, blend: Option<Blend>) {
+        if self.blend == Some(blend) {
+            return;
+        }
+
+        let previous = self.blend.flatten();
+        self.blend = Some(blend);
+
+        let Some(blend) = blend else {
+            gl.disable(GL::BLEND);
+            return;
+        };
+
+        if previous.is_none() {
+            gl.enable(GL::BLEND);
+        }
+
+        gl.blend_equation_separate(blend.color_equation as u32, blend.alpha_equation as u32);
+        gl.blend_func_separate(
+            blend.source_color_factor as u32,
+            blend.destination_color_factor as u32,
+            blend.source_alpha_factor as u32,
+            blend.destination_alpha_factor as u32,
+        );
+
+        let [r, g, b, a] = blend.constant_color;
+        gl.blend_color(r, g, b, a);
+    }
+
+    pub fn set_depth_test(&mut self, gl: &GL, depth_test: Option<CompareFunction>) {
+        if self.depth_test == Some(depth_test) {
+            return;
+        }
+
+        self.depth_test = Some(depth_test);
+
+        match depth_test {
+            Some(function) => {
+                gl.enable(GL::DEPTH_TEST);
+                gl.depth_func(function as u32);
+            }
+            None => gl.disable(GL::DEPTH_TEST),
+        }
+    }
+
+    pub fn set_depth_write(&mut self, gl: &GL, depth_write: bool) {
+        if self.depth_write == Some(depth_write) {
+            return;
+        }
+
+        self.depth_write = Some(depth_write);
+        gl.depth_mask(depth_write);
+    }
+
+    pub fn set_cull_face(&mut self, gl: &GL, cull_face: Option<CullFace>) {
+        if self.cull_face == Some(cull_face) {
+            return;
+        }
+
+        self.cull_face = Some(cull_face);
+
+        match cull_face {
+            Some(face) => {
+                gl.enable(GL::CULL_FACE);
+                gl.cull_face(face as u32);
+            }
+            None => gl.disable(GL::CULL_FACE),
+        }
+    }
+
+    pub fn set_color_write_mask(&mut self, gl: &GL, mask: [bool; 4]) {
+        if self.color_write_mask == Some(mask) {
+            return;
+        }
+
+        self.color_write_mask = Some(mask);
+        gl.color_mask(mask[0], mask[1], mask[2], mask[3]);
+    }
+
+    pub fn set_polygon_offset(&mut self, gl: &GL, polygon_offset: Option<PolygonOffset>) {
+        if self.polygon_offset == Some(polygon_offset) {
+            return;
+        }
+
+        self.polygon_offset = Some(polygon_offset);
+
+        match polygon_offset {
+            Some(offset) => {
+                gl.enable(GL::POLYGON_OFFSET_FILL);
+                gl.polygon_offset(offset.factor, offset.units);
+            }
+            None => gl.disable(GL::POLYGON_OFFSET_FILL),
+        }
+    }
+
+    pub fn set_stencil(&mut self, gl: &GL, stencil: Option<Stencil>) {
+        if self.stencil == Some(stencil) {
+            return;
+        }
+
+        let previous = self.stencil.flatten();
+        self.stencil = Some(stencil);
+
+        let Some(stencil) = stencil else {
+            gl.disable(GL::STENCIL_TEST);
+
+            // The write mask also applies to `clear`, so it is restored when stenciling stops
+            if previous.is_some_and(|previous| previous.has_custom_write_mask()) {
+                gl.stencil_mask(0xFF);
+            }
+
+            return;
+        };
+
+        gl.enable(GL::STENCIL_TEST);
+
+        for (face, state) in [(GL::FRONT, stencil.front), (GL::BACK, stencil.back)] {
+            gl.stencil_func_separate(face, state.function as u32, state.reference, state.read_mask);
+            gl.stencil_op_separate(face, state.fail as u32, state.depth_fail as u32, state.pass as u32);
+            gl.stencil_mask_separate(face, state.write_mask);
+        }
+    }
+
+    /// `clear` is affected by the depth, color and stencil write masks, so they
+    /// must allow writing everything before clearing the framebuffer.
+    pub fn prepare_clear(&mut self, gl: &GL) {
+        self.set_depth_write(gl, true);
+        self.set_color_write_mask(gl, [true; 4]);
+
+        if let Some(Some(stencil)) = self.stencil
+            && stencil.has_custom_write_mask()
+        {
+            gl.stencil_mask(0xFF);
+            self.stencil = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{backend::mock::MockContext, geometry::Geometry, material::Material, mesh::Mesh, renderer::Renderer};
+
+    const VERTEX_SHADER_SOURCE: &str = "#version 300 es\nin vec3 position;\nvoid main() {\n    gl_Position = vec4(position, 1.0);\n}\n";
+    const FRAGMENT_SHADER_SOURCE: &str = "#version 300 es\nout vec4 color;\nvoid main() {\n    color = vec4(1.0);\n}\n";
+
+    const STATE_CALLS: [&str; 10] = [
+        "enable",
+        "disable",
+        "blend_func_separate",
+        "depth_func",
+        "depth_mask",
+        "color_mask",
+        "stencil_func_separate",
+        "stencil_op_separate",
+        "stencil_mask_separate",
+        "stencil_mask",
+    ];
+
+    fn mesh(render_state: RenderState) -> Mesh {
+        let mut material = Material::new(VERTEX_SHADER_SOURCE, FRAGMENT_SHADER_SOURCE);
+        material.render_state = render_state;
+        Mesh::new(Geometry::quad(), material)
+    }
+
+    fn state_call_counts(mock: &MockContext) -> Vec<(&'static str, usize)> {
+        STATE_CALLS.iter().map(|name| (*name, mock.state().call_count(name))).collect()
+    }
+
+    fn stencil() -> Stencil {
+        Stencil::new(StencilFace {
+            function: CompareFunction::Equal,
+            reference: 1,
+            write_mask: 0,
+            ..StencilFace::default()
+        })
+    }
+
+    #[test]
+    fn skips_the_state_shared_by_two_materials() {
+        let mock = Rc::new(MockContext::new(8, 8));
+        let mut renderer = Renderer::with_context(mock.context());
+        let mut first = mesh(RenderState::default());
+        let mut second = mesh(RenderState::default());
+
+        renderer.render(&mut first);
+        assert_eq!(mock.state().call_count("depth_mask"), 1);
+        assert_eq!(mock.state().call_count("color_mask"), 1);
+
+        mock.clear_calls();
+        renderer.render(&mut second);
+
+        assert!(state_call_counts(&mock).iter().all(|(_, count)| *count == 0));
+    }
+
+    #[test]
+    fn sets_only_the_state_that_differs_between_two_materials() {
+        let mock = Rc::new(MockContext::new(8, 8));
+        let mut renderer = Renderer::with_context(mock.context());
+        let mut opaque = mesh(RenderState::default());
+        let mut transparent = mesh(RenderState {
+            stencil: Some(stencil()),
+            ..RenderState::transparent()
+        });
+
+        renderer.render(&mut opaque);
+        mock.clear_calls();
+        renderer.render(&mut transparent);
+
+        // Blending and stenciling are enabled, the depth test is unchanged
+        assert_eq!(mock.state().call_count("enable"), 2);
+        assert_eq!(mock.state().call_count("disable"), 0);
+        assert_eq!(mock.state().call_count("blend_func_separate"), 1);
+        assert_eq!(mock.state().call_count("depth_func"), 0);
+        assert_eq!(mock.state().call_count("depth_mask"), 1);
+        assert_eq!(mock.state().call_count("color_mask"), 0);
+        assert_eq!(mock.state().call_count("stencil_func_separate"), 2);
+        assert_eq!(mock.state().call_count("stencil_op_separate"), 2);
+        assert_eq!(mock.state().call_count("stencil_mask_separate"), 2);
+        assert!(!mock.state().pipeline.depth_mask);
+
+        mock.clear_calls();
+        renderer.render(&mut opaque);
+
+        // The custom stencil write mask is restored along with the disabled capabilities
+        assert_eq!(mock.state().call_count("enable"), 0);
+        assert_eq!(mock.state().call_count("disable"), 2);
+        assert_eq!(mock.state().call_count("blend_func_separate"), 0);
+        assert_eq!(mock.state().call_count("depth_mask"), 1);
+        assert_eq!(mock.state().call_count("stencil_mask"), 1);
+        assert_eq!(mock.state().call_count("stencil_func_separate"), 0);
+        assert!(mock.state().pipeline.depth_mask);
+    }
+
+    #[test]
+    fn invalidate_sets_everything_again() {
+        let mock = Rc::new(MockContext::new(8, 8));
+        let gl = mock.context();
+        let mut cache = RenderStateCache::default();
+        let state = RenderState::transparent();
+
+        cache.apply(&gl, &state);
+        let first_counts = state_call_counts(&mock);
+
+        mock.clear_calls();
+        cache.apply(&gl, &state);
+        assert!(state_call_counts(&mock).iter().all(|(_, count)| *count == 0));
+
+        mock.clear_calls();
+        cache.invalidate();
+        cache.apply(&gl, &state);
+        assert_eq!(state_call_counts(&mock), first_counts);
+    }
+
+    #[test]
+    fn prepare_clear_forces_the_write_masks_on() {
+        let mock = Rc::new(MockContext::new(8, 8));
+        let mut renderer = Renderer::with_context(mock.context());
+        let mut masked = mesh(RenderState {
+            depth_write: false,
+            color_write_mask: [false; 4],
+            stencil: Some(stencil()),
+            ..RenderState::default()
+        });
+
+        renderer.render(&mut masked);
+        mock.clear_calls();
+        renderer.clear();
+
+        assert_eq!(mock.state().call_count("depth_mask"), 1);
+        assert_eq!(mock.state().call_count("color_mask"), 1);
+        assert_eq!(mock.state().call_count("stencil_mask"), 1);
+        assert!(mock.state().pipeline.depth_mask);
+        assert_eq!(mock.state().pipeline.color_mask, [true; 4]);
+
+        // Already writable, nothing left to restore
+        mock.clear_calls();
+        renderer.clear();
+        assert!(state_call_counts(&mock).iter().all(|(_, count)| *count == 0));
+
+        // The material masks are set again on the next draw
+        mock.clear_calls();
+        renderer.render(&mut masked);
+        assert_eq!(mock.state().call_count("depth_mask"), 1);
+        assert_eq!(mock.state().call_count("color_mask"), 1);
+        assert_eq!(mock.state().call_count("stencil_mask_separate"), 2);
+        assert!(!mock.state().pipeline.depth_mask);
+    }
+}
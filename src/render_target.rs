@@ -0,0 +1,204 @@
+use std::{error::Error, fmt};
+
+use crate::backend::{Context as GL, FramebufferHandle, RenderbufferHandle, TextureHandle};
+
+/// https://developer.mozilla.org/en-US/docs/Web/API/WebGL2RenderingContext/texStorage2D#internalformat
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderTargetFormat {
+    RGBA8   = GL::RGBA8,
+    RGBA16F = GL::RGBA16F,
+    RGBA32F = GL::RGBA32F,
+}
+
+impl RenderTargetFormat {
+    pub fn is_float(&self) -> bool {
+        matches!(self, RenderTargetFormat::RGBA16F | RenderTargetFormat::RGBA32F)
+    }
+}
+
+#[derive(Debug)]
+pub enum RenderTargetError {
+    FramebufferCreationFailed,
+    FramebufferIncomplete(u32),
+    TextureCreationFailed,
+    RenderbufferCreationFailed,
+    /// Rendering to float textures requires `EXT_color_buffer_float`.
+    FloatExtensionUnavailable,
+}
+
+impl fmt::Display for RenderTargetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RenderTargetError::FramebufferCreationFailed => write!(f, "Failed to create the render target framebuffer"),
+            RenderTargetError::FramebufferIncomplete(status) => {
+                write!(f, "The render target framebuffer is incomplete, status {status:#x}")
+            }
+            RenderTargetError::TextureCreationFailed => write!(f, "Failed to create the render target texture"),
+            RenderTargetError::RenderbufferCreationFailed => write!(f, "Failed to create the render target depth buffer"),
+            RenderTargetError::FloatExtensionUnavailable => write!(f, "Float render targets require EXT_color_buffer_float"),
+        }
+    }
+}
+
+impl Error for RenderTargetError {}
+
+/// Offscreen color texture with an optional depth buffer that can be rendered to instead of the canvas.
+#[derive(Debug)]
+pub struct RenderTarget {
+    pub width:  u32,
+    pub height: u32,
+    pub format: RenderTargetFormat,
+    pub depth:  bool,
+
+    // WebGL resources
+    resources: Option<RenderTargetResources>,
+}
+
+#[derive(Debug)]
+struct RenderTargetResources {
+    gl:                 GL,
+    framebuffer:        FramebufferHandle,
+    color_texture:      TextureHandle,
+    depth_renderbuffer: Option<RenderbufferHandle>,
+}
+
+impl RenderTarget {
+    pub fn new(width: u32, height: u32, format: RenderTargetFormat) -> RenderTarget {
+        RenderTarget {
+            width,
+            height,
+            format,
+            depth: true,
+            resources: None,
+        }
+    }
+
+    /// The GPU resources are recreated on the next use.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if width != self.width || height != self.height {
+            self.width = width;
+            self.height = height;
+            self.resources = None;
+        }
+    }
+
+    pub fn get_framebuffer(&mut self, gl: &GL) -> Result<FramebufferHandle, RenderTargetError> {
+        Ok(self.get_or_create_resources(gl)?.framebuffer)
+    }
+
+    /// Texture holding the rendered colors, to be sampled by other materials.
+    pub fn get_color_texture(&mut self, gl: &GL) -> Result<TextureHandle, RenderTargetError> {
+        Ok(self.get_or_create_resources(gl)?.color_texture)
+    }
+
+    fn get_or_create_resources(&mut self, gl: &GL) -> Result<&RenderTargetResources, RenderTargetError> {
+        if self.resources.is_none() {
+            self.resources = Some(self.create_resources(gl)?);
+        }
+
+        Ok(self.resources.as_ref().unwrap())
+    }
+
+    fn create_resources(&self, gl: &GL) -> Result<RenderTargetResources, RenderTargetError> {
+        if self.format.is_float() && !gl.enable_extension("EXT_color_buffer_float") {
+            return Err(RenderTargetError::FloatExtensionUnavailable);
+        }
+
+        let framebuffer = gl.create_framebuffer().ok_or(RenderTargetError::FramebufferCreationFailed)?;
+        let Some(color_texture) = gl.create_texture() else {
+            gl.delete_framebuffer(Some(framebuffer));
+            return Err(RenderTargetError::TextureCreationFailed);
+        };
+
+        // Deletes everything created so far when returning early
+        let mut resources = RenderTargetResources {
+            gl: gl.clone(),
+            framebuffer,
+            color_texture,
+            depth_renderbuffer: None,
+        };
+
+        gl.bind_texture(GL::TEXTURE_2D, Some(color_texture));
+        gl.tex_storage_2d(GL::TEXTURE_2D, 1, self.format as u32, self.width as i32, self.height as i32);
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, GL::LINEAR as i32);
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, GL::CLAMP_TO_EDGE as i32);
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, GL::CLAMP_TO_EDGE as i32);
+        gl.bind_texture(GL::TEXTURE_2D, None);
+
+        if self.depth {
+            let renderbuffer = gl.create_renderbuffer().ok_or(RenderTargetError::RenderbufferCreationFailed)?;
+            resources.depth_renderbuffer = Some(renderbuffer);
+
+            gl.bind_renderbuffer(GL::RENDERBUFFER, Some(renderbuffer));
+            gl.renderbuffer_storage(GL::RENDERBUFFER, GL::DEPTH24_STENCIL8, self.width as i32, self.height as i32);
+            gl.bind_renderbuffer(GL::RENDERBUFFER, None);
+        }
+
+        gl.bind_framebuffer(GL::FRAMEBUFFER, Some(framebuffer));
+        gl.framebuffer_texture_2d(GL::FRAMEBUFFER, GL::COLOR_ATTACHMENT0, GL::TEXTURE_2D, Some(color_texture), 0);
+        if let Some(renderbuffer) = resources.depth_renderbuffer {
+            gl.framebuffer_renderbuffer(GL::FRAMEBUFFER, GL::DEPTH_STENCIL_ATTACHMENT, GL::RENDERBUFFER, Some(renderbuffer));
+        }
+
+        let status = gl.check_framebuffer_status(GL::FRAMEBUFFER);
+        gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+
+        if status != GL::FRAMEBUFFER_COMPLETE {
+            return Err(RenderTargetError::FramebufferIncomplete(status));
+        }
+
+        Ok(resources)
+    }
+}
+
+impl Drop for RenderTargetResources {
+    fn drop(&mut self) {
+        self.gl.delete_framebuffer(Some(self.framebuffer));
+        self.gl.delete_texture(Some(self.color_texture));
+        self.gl.delete_renderbuffer(self.depth_renderbuffer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::backend::mock::MockContext;
+
+    #[test]
+    fn deletes_the_attachments_of_an_incomplete_framebuffer() {
+        let mock = Rc::new(MockContext::new(8, 8));
+        let gl = mock.context();
+        let mut render_target = RenderTarget::new(4, 4, RenderTargetFormat::RGBA8);
+
+        mock.set_framebuffer_status(GL::FRAMEBUFFER_UNSUPPORTED);
+        let result = render_target.get_framebuffer(&gl);
+
+        assert!(matches!(
+            result,
+            Err(RenderTargetError::FramebufferIncomplete(GL::FRAMEBUFFER_UNSUPPORTED))
+        ));
+        let state = mock.state();
+        assert_eq!(state.call_count("create_framebuffer"), 1);
+        assert_eq!(state.call_count("create_texture"), 1);
+        assert_eq!(state.call_count("create_renderbuffer"), 1);
+        assert!(state.framebuffers.is_empty());
+        assert!(state.textures.is_empty());
+        assert!(state.renderbuffers.is_empty());
+        assert_eq!(state.bound_framebuffer, None);
+        drop(state);
+
+        // Created again on the next use
+        mock.set_framebuffer_status(GL::FRAMEBUFFER_COMPLETE);
+        let framebuffer = render_target.get_framebuffer(&gl).unwrap();
+        assert!(mock.state().framebuffers.contains(&framebuffer.0));
+        assert_eq!(mock.state().textures.len(), 1);
+
+        drop(render_target);
+        assert!(mock.state().framebuffers.is_empty());
+        assert!(mock.state().textures.is_empty());
+        assert!(mock.state().renderbuffers.is_empty());
+    }
+}
@@ -1,117 +1,488 @@
+#[cfg(feature = "web")]
+use std::rc::Rc;
+
+use glam::Vec2;
+#[cfg(feature = "web")]
 use web_sys::wasm_bindgen::JsCast;
-use web_sys::{HtmlCanvasElement, WebGl2RenderingContext as GL};
+#[cfg(feature = "web")]
+use web_sys::{HtmlCanvasElement, WebGl2RenderingContext, WebGlContextAttributes};
 
+#[cfg(feature = "web")]
+use crate::backend::webgl::WebGlContext;
 use crate::{
+    backend::{Context as GL, FramebufferHandle},
     buffer_gpu::BufferError,
-    camera::PerspectiveCamera,
-    material::MaterialError,
+    camera::{CameraBlock, PerspectiveCamera},
+    error::{ErrorReporter, SuricatoError},
     mesh::{Mesh, MeshError},
-    uniforms::Uniform,
+    picking::{PickResult, PickStatus, PickingError, PickingPass},
+    program_cache::ProgramCache,
+    readback::{self, PixelType, Pixels, ReadRegion, ReadbackError},
+    render_queue::RenderQueues,
+    render_state::RenderStateCache,
+    render_target::{RenderTarget, RenderTargetError},
+    std140::Std140,
+    texture::TextureBindingCache,
+    ubo::{UniformBufferError, UniformBufferObject},
+    uniforms::{Uniform, UniformHandle},
 };
 
-#[derive(Debug)]
-pub enum RenderError {
-    MeshError(MeshError),
-    BufferError(BufferError),
-    MaterialError(MaterialError),
-}
+/// Block of the `camera` chunk, see [`CameraBlock`].
+pub const CAMERA_BLOCK: &str = "Camera";
 
-impl From<BufferError> for RenderError {
-    fn from(value: BufferError) -> Self {
-        RenderError::BufferError(value)
-    }
+/// Counters for the last call to [`Renderer::render_scene`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RenderStatistics {
+    pub meshes_drawn:  usize,
+    pub meshes_culled: usize,
 }
 
-impl From<MaterialError> for RenderError {
-    fn from(value: MaterialError) -> Self {
-        RenderError::MaterialError(value)
-    }
-}
+pub struct Renderer {
+    pub gl:             GL,
+    /// `None` when rendering without a browser, see [`Renderer::with_context`].
+    #[cfg(feature = "web")]
+    pub canvas:         Option<HtmlCanvasElement>,
+    pub statistics:     RenderStatistics,
+    /// Handles the non-fatal errors found while rendering, see [`ErrorPolicy`](crate::error::ErrorPolicy).
+    pub error_reporter: ErrorReporter,
+    /// Seconds given to shaders as `camera.time`, advanced by the application.
+    pub time:           f32,
 
-impl From<MeshError> for RenderError {
-    fn from(value: MeshError) -> Self {
-        RenderError::MeshError(value)
-    }
+    render_state_cache:    RenderStateCache,
+    /// Invalidated whenever a texture may have been bound outside of it, like when creating render targets.
+    texture_binding_cache: TextureBindingCache,
+    program_cache:         ProgramCache,
+    render_queues:         RenderQueues,
+    picking_pass:          PickingPass,
+    /// Size handled by the last resize when drawing without a canvas.
+    drawing_buffer_size:   (i32, i32),
+    /// Size of the canvas or render target being drawn, in pixels.
+    viewport_size:         Vec2,
+    /// Bound by [`Renderer::set_render_target`], `None` for the canvas. Bound again after picking and readbacks.
+    framebuffer:           Option<FramebufferHandle>,
+    /// Created with the first frame, bound to the `Camera` block of every program.
+    camera_block:          Option<UniformBufferObject>,
 }
 
-pub struct Renderer {
-    pub gl:     GL,
-    pub canvas: HtmlCanvasElement,
+#[cfg(feature = "web")]
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Renderer {
+    /// Renderer drawing into a new canvas filling the browser window.
+    #[cfg(feature = "web")]
     pub fn new() -> Renderer {
-        let document = web_sys::window().unwrap().document().unwrap();
-        let canvas = document.create_element("canvas").unwrap().dyn_into::<HtmlCanvasElement>().unwrap();
-        document.body().unwrap().append_child(&canvas).unwrap();
-        let width = web_sys::window().unwrap().inner_width().unwrap().as_f64().unwrap() as u32;
-        let height = web_sys::window().unwrap().inner_height().unwrap().as_f64().unwrap() as u32;
+        match Renderer::try_new() {
+            Ok(renderer) => renderer,
+            Err(error) => panic!("{error}"),
+        }
+    }
+
+    /// Same as [`Renderer::new`] but fails instead of panicking, for example when WebGL 2 is unavailable.
+    #[cfg(feature = "web")]
+    pub fn try_new() -> Result<Renderer, SuricatoError> {
+        let creation_failed = |reason: &str| SuricatoError::ContextCreationFailed(String::from(reason));
+
+        let window = web_sys::window().ok_or_else(|| creation_failed("No window"))?;
+        let document = window.document().ok_or_else(|| creation_failed("No document"))?;
+        let canvas = document
+            .create_element("canvas")
+            .ok()
+            .and_then(|element| element.dyn_into::<HtmlCanvasElement>().ok())
+            .ok_or_else(|| creation_failed("Failed to create the canvas"))?;
+        let body = document.body().ok_or_else(|| creation_failed("No body"))?;
+        body.append_child(&canvas)
+            .map_err(|_| creation_failed("Failed to append the canvas"))?;
+
+        let width = window.inner_width().ok().and_then(|width| width.as_f64()).unwrap_or(0.0) as u32;
+        let height = window.inner_height().ok().and_then(|height| height.as_f64()).unwrap_or(0.0) as u32;
         canvas.set_width(width);
         canvas.set_height(height);
 
-        let gl = canvas.get_context("webgl2").unwrap().unwrap().dyn_into::<GL>().unwrap();
+        // The stencil buffer is not allocated unless explicitly requested
+        let context_attributes = WebGlContextAttributes::new();
+        context_attributes.set_stencil(true);
 
-        gl.enable(GL::DEPTH_TEST);
+        let gl = canvas
+            .get_context_with_context_options("webgl2", &context_attributes)
+            .ok()
+            .flatten()
+            .and_then(|context| context.dyn_into::<WebGl2RenderingContext>().ok())
+            .ok_or_else(|| creation_failed("WebGL 2 is not supported"))?;
+
+        let mut renderer = Renderer::with_context(GL::new(Rc::new(WebGlContext::new(gl))));
+        renderer.canvas = Some(canvas);
+        Ok(renderer)
+    }
 
-        Renderer { gl, canvas }
+    /// Renderer drawing through `gl` instead of a new canvas, for example a mock context in tests.
+    pub fn with_context(gl: GL) -> Renderer {
+        Renderer {
+            #[cfg(feature = "web")]
+            canvas: None,
+            statistics: RenderStatistics::default(),
+            error_reporter: ErrorReporter::default(),
+            time: 0.0,
+            render_state_cache: RenderStateCache::default(),
+            texture_binding_cache: TextureBindingCache::default(),
+            program_cache: ProgramCache::new(),
+            render_queues: RenderQueues::default(),
+            picking_pass: PickingPass::new(),
+            drawing_buffer_size: (gl.drawing_buffer_width(), gl.drawing_buffer_height()),
+            viewport_size: Vec2::new(gl.drawing_buffer_width() as f32, gl.drawing_buffer_height() as f32),
+            framebuffer: None,
+            camera_block: None,
+            gl,
+        }
     }
 
-    pub fn clear(&self) {
+    pub fn clear(&mut self) {
+        self.render_state_cache.prepare_clear(&self.gl);
         self.gl.clear_color(0.0, 0.0, 0.0, 1.0);
-        self.gl.clear(GL::COLOR_BUFFER_BIT | GL::DEPTH_BUFFER_BIT);
+        self.gl.clear(GL::COLOR_BUFFER_BIT | GL::DEPTH_BUFFER_BIT | GL::STENCIL_BUFFER_BIT);
+    }
+
+    /// Must be called after changing GL state directly through `gl`,
+    /// otherwise the renderer may skip state it believes is already set.
+    pub fn invalidate_render_state(&mut self) {
+        self.render_state_cache.invalidate();
+        self.texture_binding_cache.invalidate();
     }
 
+    /// Matches the canvas to the window size.
+    ///
+    /// Without a canvas the drawing buffer is resized by its owner, for example `NativeWindow::resize`,
+    /// and only the viewport and camera follow.
     pub fn handle_window_resize(&mut self, camera: &mut PerspectiveCamera) {
-        let width = web_sys::window().unwrap().inner_width().unwrap().as_f64().unwrap();
-        let height = web_sys::window().unwrap().inner_height().unwrap().as_f64().unwrap();
+        #[cfg(feature = "web")]
+        if let Some(canvas) = &self.canvas {
+            let width = web_sys::window().unwrap().inner_width().unwrap().as_f64().unwrap();
+            let height = web_sys::window().unwrap().inner_height().unwrap().as_f64().unwrap();
+
+            if width as u32 != canvas.width() || height as u32 != canvas.height() {
+                camera.aspect = width as f32 / height as f32;
+                camera.update_projection_matrix();
+
+                canvas.set_width(width as u32);
+                canvas.set_height(height as u32);
+
+                self.gl.viewport(0, 0, width as i32, height as i32);
+                self.viewport_size = Vec2::new(width as f32, height as f32);
+            }
+
+            return;
+        }
+
+        let size = (self.gl.drawing_buffer_width(), self.gl.drawing_buffer_height());
 
-        if width as u32 != self.canvas.width() || height as u32 != self.canvas.height() {
-            camera.aspect = width as f32 / height as f32;
+        if size != self.drawing_buffer_size && size.0 > 0 && size.1 > 0 {
+            self.drawing_buffer_size = size;
+
+            camera.aspect = size.0 as f32 / size.1 as f32;
             camera.update_projection_matrix();
 
-            self.canvas.set_width(width as u32);
-            self.canvas.set_height(height as u32);
+            self.gl.viewport(0, 0, size.0, size.1);
+            self.viewport_size = Vec2::new(size.0 as f32, size.1 as f32);
+        }
+    }
 
-            self.gl.viewport(0, 0, width as i32, height as i32);
+    pub fn render_scene(&mut self, scene: &mut [Mesh], camera: &mut PerspectiveCamera) {
+        if let Err(error) = self.try_render_scene(scene, camera) {
+            panic!("{error}");
         }
     }
 
-    pub fn render_scene(&mut self, scene: &mut Vec<Mesh>, camera: &mut PerspectiveCamera) {
+    pub fn try_render_scene(&mut self, scene: &mut [Mesh], camera: &mut PerspectiveCamera) -> Result<(), SuricatoError> {
         self.clear();
         self.handle_window_resize(camera);
+        self.update_camera_block(camera)?;
+
+        let view_matrix = camera.view_matrix();
+        let projection_matrix = Uniform::from(&camera.projection_matrix);
+        let camera_inverse_matrix = Uniform::from(&view_matrix);
+
+        // Taken out of `self` so the queues can be iterated while rendering
+        let mut render_queues = std::mem::take(&mut self.render_queues);
+        let meshes_culled = render_queues.build(&self.gl, &mut self.program_cache, scene, &view_matrix, &camera.frustum())?;
+
+        self.statistics = RenderStatistics {
+            meshes_drawn: scene.len() - meshes_culled,
+            meshes_culled,
+        };
 
-        for mesh in scene.iter_mut() {
-            mesh.material.set_uniform("transform", Uniform::Mat4(mesh.transform.to_array()));
+        let mut result = Ok(());
 
-            // Camera
-            let projection_matrix = Uniform::from(&camera.projection_matrix);
-            let camera_inverse_matrix = Uniform::Mat4(camera.transform.to_mat4().inverse().to_cols_array());
-            mesh.material.set_uniform("projection_matrix", projection_matrix);
-            mesh.material.set_uniform("camera_inverse_matrix", camera_inverse_matrix);
+        for item in render_queues.iter() {
+            let mesh = &mut scene[item.mesh_index];
 
-            self.render(mesh);
+            result = self.render_with_camera(mesh, &projection_matrix, &camera_inverse_matrix);
+            if result.is_err() {
+                break;
+            }
+        }
+
+        self.render_queues = render_queues;
+        result
+    }
+
+    fn render_with_camera(
+        &mut self,
+        mesh: &mut Mesh,
+        projection_matrix: &Uniform,
+        camera_inverse_matrix: &Uniform,
+    ) -> Result<(), SuricatoError> {
+        let material = &mut mesh.material;
+        material.try_get_or_create_cached_resources(&self.gl, &mut self.program_cache)?;
+        material.set_renderer_uniform(UniformHandle::TRANSFORM, Uniform::Mat4(mesh.transform.to_array()));
+
+        // Only for shaders declaring the camera uniforms themselves instead of using the `Camera` block
+        material.set_renderer_uniform(UniformHandle::PROJECTION_MATRIX, projection_matrix.clone());
+        material.set_renderer_uniform(UniformHandle::CAMERA_INVERSE_MATRIX, camera_inverse_matrix.clone());
+
+        self.try_render(mesh)
+    }
+
+    /// Draws into `render_target` until called again, or into the canvas when `None`.
+    pub fn set_render_target(&mut self, render_target: Option<&mut RenderTarget>) -> Result<(), RenderTargetError> {
+        // Creating the color texture unbinds the texture of the active unit
+        self.texture_binding_cache.invalidate();
+
+        match render_target {
+            Some(render_target) => {
+                let (width, height) = (render_target.width, render_target.height);
+                let framebuffer = render_target.get_framebuffer(&self.gl)?;
+                self.gl.bind_framebuffer(GL::FRAMEBUFFER, Some(framebuffer));
+                self.gl.viewport(0, 0, width as i32, height as i32);
+                self.viewport_size = Vec2::new(width as f32, height as f32);
+                self.framebuffer = Some(framebuffer);
+            }
+            None => {
+                let (width, height) = (self.gl.drawing_buffer_width(), self.gl.drawing_buffer_height());
+                self.gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+                self.gl.viewport(0, 0, width, height);
+                self.viewport_size = Vec2::new(width as f32, height as f32);
+                self.framebuffer = None;
+            }
         }
+
+        Ok(())
+    }
+
+    /// Binds `ubo` to the block `block_name` of every program drawn by this renderer, including the ones created later.
+    ///
+    /// The binding point is picked by the renderer and returned, a block registered again keeps its binding point.
+    pub fn register_uniform_buffer(&mut self, block_name: &str, ubo: &mut UniformBufferObject) -> Result<u32, UniformBufferError> {
+        let binding_point = self.program_cache.bind_uniform_block(&self.gl, block_name)?;
+        ubo.set_binding_point(binding_point);
+        Ok(binding_point)
+    }
+
+    /// Frees the binding point of `block_name` for other blocks.
+    pub fn unregister_uniform_buffer(&mut self, block_name: &str) -> Option<u32> {
+        self.program_cache.unbind_uniform_block(block_name)
+    }
+
+    /// Uploads what changed in the `Camera` block, once for all the meshes drawn from `camera`.
+    fn update_camera_block(&mut self, camera: &PerspectiveCamera) -> Result<(), UniformBufferError> {
+        let block = CameraBlock::new(camera, self.time, self.viewport_size);
+
+        if let Some(ubo) = &mut self.camera_block {
+            ubo.set_std140(&block);
+            return Ok(());
+        }
+
+        let mut ubo = UniformBufferObject::with_context(&self.gl, &block.to_std140_bytes());
+        self.register_uniform_buffer(CAMERA_BLOCK, &mut ubo)?;
+        self.camera_block = Some(ubo);
+        Ok(())
+    }
+
+    /// Reads a rectangle of `render_target`, or of the canvas when `None`, with `(x, y)` being its top left corner.
+    ///
+    /// Float render targets are read as floats and everything else as 8 bit RGBA.
+    /// The canvas is cleared once presented, so it must be read in the same frame it was drawn.
+    pub fn read_pixels(
+        &self,
+        render_target: Option<&mut RenderTarget>,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<Pixels, ReadbackError> {
+        let (framebuffer, region) = self.get_read_region(render_target, x, y, width, height)?;
+        readback::read_pixels(&self.gl, framebuffer, region, self.framebuffer)
+    }
+
+    /// Same as [`Renderer::read_pixels`] but resolves once the GPU is done, instead of stalling until then.
+    pub fn read_pixels_async(
+        &self,
+        render_target: Option<&mut RenderTarget>,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> impl Future<Output = Result<Pixels, ReadbackError>> + 'static {
+        let read_region = self.get_read_region(render_target, x, y, width, height);
+        let gl = self.gl.clone();
+        let bound_framebuffer = self.framebuffer;
+
+        async move {
+            let (framebuffer, region) = read_region?;
+            readback::read_pixels_async(gl, framebuffer, region, bound_framebuffer).await
+        }
+    }
+
+    /// Whole canvas as a PNG file.
+    pub fn screenshot_png(&self) -> Result<Vec<u8>, ReadbackError> {
+        let width = self.gl.drawing_buffer_width() as u32;
+        let height = self.gl.drawing_buffer_height() as u32;
+        let pixels = self.read_pixels(None, 0, 0, width, height)?;
+        pixels.to_png().map_err(|_| ReadbackError::EncodingFailed)
+    }
+
+    fn get_read_region(
+        &self,
+        render_target: Option<&mut RenderTarget>,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<(Option<FramebufferHandle>, ReadRegion), ReadbackError> {
+        let (framebuffer, framebuffer_width, framebuffer_height, pixel_type) = match render_target {
+            Some(render_target) => {
+                (
+                    Some(render_target.get_framebuffer(&self.gl)?),
+                    render_target.width,
+                    render_target.height,
+                    if render_target.format.is_float() {
+                        PixelType::Float
+                    } else {
+                        PixelType::UnsignedByte
+                    },
+                )
+            }
+            None => {
+                (
+                    None,
+                    self.gl.drawing_buffer_width() as u32,
+                    self.gl.drawing_buffer_height() as u32,
+                    PixelType::UnsignedByte,
+                )
+            }
+        };
+
+        let region = ReadRegion {
+            x,
+            y,
+            width,
+            height,
+            framebuffer_width,
+            framebuffer_height,
+            pixel_type,
+        };
+
+        Ok((framebuffer, region))
+    }
+
+    /// Returns the mesh and instance under `screen_point`, in pixels from the top left corner of the canvas.
+    ///
+    /// Blocks until the GPU has rendered the ID buffer, prefer [`Renderer::request_pick`] every frame.
+    pub fn pick(&mut self, scene: &mut [Mesh], camera: &PerspectiveCamera, screen_point: Vec2) -> Result<Option<PickResult>, PickingError> {
+        self.texture_binding_cache.invalidate();
+        self.update_camera_block(camera)?;
+        let result = self.picking_pass.pick(
+            &self.gl,
+            &mut self.render_state_cache,
+            &mut self.program_cache,
+            &mut self.error_reporter,
+            scene,
+            camera,
+            screen_point,
+        );
+
+        // The picking pass leaves the canvas bound
+        self.gl.bind_framebuffer(GL::FRAMEBUFFER, self.framebuffer);
+        result
+    }
+
+    /// Starts an asynchronous pick, see [`Renderer::poll_pick`].
+    pub fn request_pick(&mut self, scene: &mut [Mesh], camera: &PerspectiveCamera, screen_point: Vec2) -> Result<(), PickingError> {
+        self.texture_binding_cache.invalidate();
+        self.update_camera_block(camera)?;
+        let result = self.picking_pass.request_pick(
+            &self.gl,
+            &mut self.render_state_cache,
+            &mut self.program_cache,
+            &mut self.error_reporter,
+            scene,
+            camera,
+            screen_point,
+        );
+
+        // The picking pass leaves the canvas bound
+        self.gl.bind_framebuffer(GL::FRAMEBUFFER, self.framebuffer);
+        result
+    }
+
+    /// Programs shared by the materials drawn with this renderer.
+    pub fn get_program_cache(&self) -> &ProgramCache {
+        &self.program_cache
+    }
+
+    /// Result of the last [`Renderer::request_pick`], usually ready one or two frames later.
+    pub fn poll_pick(&mut self) -> PickStatus {
+        self.picking_pass.poll(&self.gl)
     }
 
     pub fn render(&mut self, mesh: &mut Mesh) {
+        if let Err(error) = self.try_render(mesh) {
+            panic!("{error}");
+        }
+    }
+
+    /// Fatal errors are returned, the others are handled by [`Renderer::error_reporter`].
+    pub fn try_render(&mut self, mesh: &mut Mesh) -> Result<(), SuricatoError> {
+        Renderer::upload_vertex_buffers(&self.gl, mesh)?;
+
+        mesh.material
+            .try_get_or_create_cached_resources(&self.gl, &mut self.program_cache)?;
+        mesh.material
+            .try_on_before_render_cached(&self.gl, &mut self.error_reporter, &mut self.texture_binding_cache)?;
+        self.render_state_cache.apply(&self.gl, &mesh.material.render_state);
+
+        Renderer::draw(&self.gl, mesh, &mut self.error_reporter)?;
+        Ok(())
+    }
+
+    /// Also uploads the index buffer, which is bound again after the vertex array in [`Renderer::draw`].
+    pub(crate) fn upload_vertex_buffers(gl: &GL, mesh: &mut Mesh) -> Result<(), BufferError> {
         for vertex_buffer in &mut mesh.geometry.vertex_buffers {
-            vertex_buffer.buffer.on_before_render(&self.gl);
+            vertex_buffer.buffer.try_on_before_render(gl)?;
         }
 
         for interleaved_vertex_buffer in &mut mesh.geometry.interleaved_vertex_buffers {
-            interleaved_vertex_buffer.buffer.on_before_render(&self.gl);
+            interleaved_vertex_buffer.buffer.try_on_before_render(gl)?;
         }
 
-        mesh.material.on_before_render(&self.gl);
+        if let Some(indices) = &mut mesh.geometry.indices {
+            indices.buffer.try_on_before_render(gl)?;
+        }
 
-        self.gl.bind_vertex_array(mesh.get_or_create_vao(&self.gl));
+        Ok(())
+    }
 
-        if let Some(indices) = &mut mesh.geometry.indices {
-            indices.buffer.on_before_render(&self.gl);
-            indices.buffer.bind(&self.gl);
+    /// Issues the draw call with whatever program and state are currently bound.
+    pub(crate) fn draw(gl: &GL, mesh: &mut Mesh, error_reporter: &mut ErrorReporter) -> Result<(), MeshError> {
+        gl.bind_vertex_array(Some(mesh.try_get_or_create_vao(gl, error_reporter)?));
+
+        if let Some(indices) = &mesh.geometry.indices {
+            indices.buffer.bind(gl);
 
             if let Some(instance_count) = mesh.geometry.instance_count {
-                self.gl.draw_elements_instanced_with_i32(
+                gl.draw_elements_instanced(
                     mesh.render_primitive as u32,
                     indices.count as i32,
                     indices.kind,
@@ -119,7 +490,7 @@ impl Renderer {
                     instance_count as i32,
                 );
             } else {
-                self.gl.draw_elements_with_i32(
+                gl.draw_elements(
                     mesh.render_primitive as u32,
                     indices.count as i32,
                     indices.kind,
@@ -127,8 +498,157 @@ impl Renderer {
                 );
             }
         } else {
-            self.gl
-                .draw_arrays(mesh.render_primitive as u32, 0, mesh.geometry.vertex_count as i32);
+            gl.draw_arrays(mesh.render_primitive as u32, 0, mesh.geometry.vertex_count as i32);
         }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use glam::{Quat, Vec3};
+
+    use super::*;
+    use crate::{backend::mock::MockContext, geometry::Geometry, material::Material, render_target::RenderTargetFormat};
+
+    const VERTEX_SHADER_SOURCE: &str = "#version 300 es\nin vec3 position;\nvoid main() {\n    gl_Position = vec4(position, 1.0);\n}\n";
+    const FRAGMENT_SHADER_SOURCE: &str =
+        "#version 300 es\nprecision mediump float;\nout vec4 color;\nvoid main() {\n    color = vec4(1.0);\n}\n";
+
+    #[test]
+    fn renders_mesh_with_one_draw_call() {
+        let mock = Rc::new(MockContext::new(64, 64));
+        let mut renderer = Renderer::with_context(mock.context());
+        let mut mesh = Mesh::new(Geometry::quad(), Material::new(VERTEX_SHADER_SOURCE, FRAGMENT_SHADER_SOURCE));
+
+        renderer.render(&mut mesh);
+        renderer.render(&mut mesh);
+
+        let state = mock.state();
+        assert_eq!(state.draws.len(), 2);
+        assert_eq!(state.draws[0], state.draws[1]);
+        assert!(state.draws[0].program.is_some());
+        assert!(state.draws[0].vertex_array.is_some());
+        assert_eq!(state.call_count("link_program"), 1);
+        assert_eq!(state.call_count("create_vertex_array"), 1);
+        assert_eq!(state.call_count("buffer_data"), mesh.geometry.vertex_buffers.len() + 1);
+    }
+
+    #[test]
+    fn uploads_camera_block_once_per_frame() {
+        let mock = Rc::new(MockContext::new(64, 32));
+        let mut renderer = Renderer::with_context(mock.context());
+        let mut camera = PerspectiveCamera::new(1.0, 2.0, 0.1, 100.0);
+        camera.update_projection_matrix();
+        camera.transform.translation.z = 3.0;
+
+        let vertex_shader = "#include \"camera\"\nin vec3 position;\nvoid main() {\n    gl_Position = project(position);\n}\n";
+        let mut scene: Vec<Mesh> = (0..3)
+            .map(|_| Mesh::new(Geometry::quad(), Material::new(vertex_shader, FRAGMENT_SHADER_SOURCE)))
+            .collect();
+
+        renderer.render_scene(&mut scene, &mut camera);
+
+        let camera_block = renderer.camera_block.as_mut().unwrap();
+        let camera_buffer = camera_block.buffer.get_buffer_gpu(&camera_block.gl).unwrap().0;
+        let expected = |camera: &PerspectiveCamera, time: f32| CameraBlock::new(camera, time, Vec2::new(64.0, 32.0)).to_std140_bytes();
+        let state = mock.state();
+        let program = scene[0].material.resources.as_ref().unwrap().get_program().0;
+        assert_eq!(state.programs[&program].uniform_block_bindings[&0], 0);
+        assert_eq!(state.uniform_buffer_bindings[&0], camera_buffer);
+        assert_eq!(state.buffers[&camera_buffer].data, expected(&camera, 0.0));
+        assert_eq!(state.uniform_value(program, "projection_matrix"), None);
+        drop(state);
+
+        mock.clear_calls();
+        renderer.render_scene(&mut scene, &mut camera);
+        assert_eq!(mock.state().call_count("buffer_sub_data"), 0);
+
+        camera.transform.translation.z = 5.0;
+        renderer.time = 1.5;
+        renderer.render_scene(&mut scene, &mut camera);
+
+        let state = mock.state();
+        assert_eq!(state.call_count("buffer_sub_data"), 1);
+        assert_eq!(state.buffers[&camera_buffer].data, expected(&camera, 1.5));
+    }
+
+    #[test]
+    fn culls_meshes_outside_the_camera_frustum() {
+        let mock = Rc::new(MockContext::new(64, 64));
+        let mut renderer = Renderer::with_context(mock.context());
+        let mut camera = PerspectiveCamera::new(1.0, 1.0, 0.1, 100.0);
+        camera.update_projection_matrix();
+        camera.transform.translation.z = 3.0;
+
+        let mesh_at = |translation: Vec3| {
+            let mut mesh = Mesh::new(Geometry::quad(), Material::new(VERTEX_SHADER_SOURCE, FRAGMENT_SHADER_SOURCE));
+            mesh.transform.translation = translation;
+            mesh
+        };
+
+        let visible = mesh_at(Vec3::ZERO);
+        let to_the_side = mesh_at(Vec3::new(5.0, 0.0, 0.0));
+        let behind = mesh_at(Vec3::new(0.0, 0.0, 5.0));
+
+        // Its bounds reach the view once scaled
+        let mut scaled = mesh_at(Vec3::new(5.0, 0.0, 0.0));
+        scaled.transform.scale = Vec3::splat(20.0);
+
+        // Stretched along X, then turned a quarter around Z to reach down into the view
+        let mut rotated = mesh_at(Vec3::new(0.0, 5.0, 0.0));
+        rotated.transform.scale = Vec3::new(20.0, 1.0, 1.0);
+        rotated.transform.rotation = Quat::from_rotation_z(std::f32::consts::FRAC_PI_2);
+
+        let mut not_culled = mesh_at(Vec3::new(5.0, 0.0, 0.0));
+        not_culled.frustum_culled = false;
+
+        let mut instanced = mesh_at(Vec3::new(5.0, 0.0, 0.0));
+        instanced.geometry.instance_count = Some(1);
+
+        let mut scene = vec![visible, to_the_side, behind, scaled, rotated, not_culled, instanced];
+        let frustum = camera.frustum();
+        let in_frustum: Vec<bool> = scene.iter_mut().map(|mesh| mesh.is_in_frustum(&frustum)).collect();
+        assert_eq!(in_frustum, [true, false, false, true, true, false, true]);
+
+        renderer.render_scene(&mut scene, &mut camera);
+
+        assert_eq!(renderer.statistics.meshes_culled, 2);
+        assert_eq!(renderer.statistics.meshes_drawn, 5);
+        assert_eq!(mock.state().draws.len(), 5);
+    }
+    #[test]
+    fn keeps_the_render_target_bound_after_picking_and_readback() {
+        let mock = Rc::new(MockContext::new(8, 8));
+        let mut renderer = Renderer::with_context(mock.context());
+        let mut camera = PerspectiveCamera::new(1.0, 1.0, 0.1, 100.0);
+        camera.update_projection_matrix();
+        let mut scene = vec![Mesh::new(
+            Geometry::quad(),
+            Material::new(VERTEX_SHADER_SOURCE, FRAGMENT_SHADER_SOURCE),
+        )];
+
+        let mut render_target = RenderTarget::new(4, 4, RenderTargetFormat::RGBA8);
+        renderer.set_render_target(Some(&mut render_target)).unwrap();
+        let framebuffer = Some(render_target.get_framebuffer(&renderer.gl).unwrap().0);
+
+        renderer.pick(&mut scene, &camera, Vec2::new(1.0, 1.0)).unwrap();
+        assert_eq!(mock.state().bound_framebuffer, framebuffer);
+        assert_eq!(mock.state().bound_read_framebuffer, framebuffer);
+
+        renderer.request_pick(&mut scene, &camera, Vec2::new(2.0, 2.0)).unwrap();
+        assert_eq!(mock.state().bound_framebuffer, framebuffer);
+
+        renderer.read_pixels(None, 0, 0, 2, 2).unwrap();
+        assert_eq!(mock.state().bound_framebuffer, framebuffer);
+        assert_eq!(mock.state().bound_read_framebuffer, framebuffer);
+
+        renderer.set_render_target(None).unwrap();
+        renderer.read_pixels(Some(&mut render_target), 0, 0, 2, 2).unwrap();
+        assert_eq!(mock.state().bound_framebuffer, None);
+        assert_eq!(mock.state().bound_read_framebuffer, None);
     }
 }
@@ -0,0 +1,59 @@
+use std::{borrow::Cow, cell::RefCell, collections::HashMap};
+
+/// Named pieces of GLSL that sources pull in with `#include "name"`.
+///
+/// Built in chunks:
+/// - `camera`: the `Camera` block as `camera`, see [`CameraBlock`](crate::camera::CameraBlock), and `transform`
+///   with `project` and `view_normal`.
+/// - `lights`: `directional_light`, with the `AMBIENT_LIGHT` define.
+/// - `skinning`: `joints` and `weights` attributes, `joint_matrices` and `skinning_matrix`, with the `MAX_JOINTS` define.
+/// - `fog`: `apply_fog` with `fog_color`, `fog_near` and `fog_far`.
+/// - `tone_mapping`: `aces_tone_mapping` and `linear_to_srgb`.
+#[derive(Debug, Clone)]
+pub struct ShaderChunks {
+    chunks: HashMap<String, Cow<'static, str>>,
+}
+
+impl Default for ShaderChunks {
+    fn default() -> Self {
+        let built_in_chunks = [
+            ("camera", include_str!("glsl/camera.glsl")),
+            ("lights", include_str!("glsl/lights.glsl")),
+            ("skinning", include_str!("glsl/skinning.glsl")),
+            ("fog", include_str!("glsl/fog.glsl")),
+            ("tone_mapping", include_str!("glsl/tone_mapping.glsl")),
+        ];
+
+        ShaderChunks {
+            chunks: built_in_chunks
+                .into_iter()
+                .map(|(name, source)| (String::from(name), Cow::Borrowed(source)))
+                .collect(),
+        }
+    }
+}
+
+impl ShaderChunks {
+    /// Adds a chunk, replacing any chunk with the same name including the built in ones.
+    pub fn register(&mut self, name: &str, source: &str) {
+        self.chunks.insert(String::from(name), Cow::Owned(String::from(source)));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.chunks.get(name).map(|source| source.as_ref())
+    }
+}
+
+thread_local! {
+    static SHADER_CHUNKS: RefCell<ShaderChunks> = RefCell::new(ShaderChunks::default());
+}
+
+/// Makes `source` available to every material compiled afterwards as `#include "name"`.
+pub fn register_shader_chunk(name: &str, source: &str) {
+    SHADER_CHUNKS.with_borrow_mut(|chunks| chunks.register(name, source));
+}
+
+/// Runs `f` with the chunks registered on this thread.
+pub fn with_shader_chunks<T>(f: impl FnOnce(&ShaderChunks) -> T) -> T {
+    SHADER_CHUNKS.with_borrow(f)
+}
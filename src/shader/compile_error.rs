@@ -0,0 +1,245 @@
+use std::{error::Error, fmt};
+
+use super::{ShaderStage, SourceLocation, SourceMap};
+
+/// Lines shown before and after the offending line.
+const SNIPPET_CONTEXT_LINES: u32 = 2;
+
+/// One error reported by the compiler.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShaderDiagnostic {
+    /// Line of the compiled source, starting at 1.
+    pub line:     u32,
+    /// Where the line was written, `None` when the compiler reports a line past the end of the source.
+    pub location: Option<SourceLocation>,
+    pub message:  String,
+    /// Offending line with its neighbours and a caret under the error, ready to print.
+    pub snippet:  String,
+}
+
+/// Compiler log parsed into diagnostics pointing at the source as written.
+///
+/// Understands the `ERROR: 0:LINE: message` format of browsers and ANGLE
+/// and the `0:LINE(COLUMN): error: message` format of Mesa.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShaderCompileError {
+    pub stage:       ShaderStage,
+    /// Empty when the log could not be parsed, see `log`.
+    pub diagnostics: Vec<ShaderDiagnostic>,
+    /// Log as returned by the driver.
+    pub log:         Option<String>,
+}
+
+impl ShaderCompileError {
+    /// `source` is the source given to the compiler and `source_map` maps its lines back to where they were written.
+    pub fn new(stage: ShaderStage, log: Option<String>, source: &str, source_map: &SourceMap) -> ShaderCompileError {
+        let source_lines: Vec<&str> = source.lines().collect();
+
+        let diagnostics = log
+            .as_deref()
+            .unwrap_or_default()
+            .lines()
+            .filter_map(parse_log_line)
+            .map(|(line, column, message)| {
+                ShaderDiagnostic {
+                    line,
+                    location: source_map.get(line).cloned(),
+                    snippet: snippet(&source_lines, source_map, line, column, &message),
+                    message,
+                }
+            })
+            .collect();
+
+        ShaderCompileError { stage, diagnostics, log }
+    }
+}
+
+impl fmt::Display for ShaderCompileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.diagnostics.is_empty() {
+            return write!(
+                f,
+                "Failed to compile the {} shader: {}",
+                self.stage,
+                self.log.as_deref().unwrap_or("no log")
+            );
+        }
+
+        write!(f, "Failed to compile the {} shader", self.stage)?;
+
+        for diagnostic in &self.diagnostics {
+            match &diagnostic.location {
+                Some(location) => write!(f, "\nerror at {}: {}", location, diagnostic.message)?,
+                None => write!(f, "\nerror at line {}: {}", diagnostic.line, diagnostic.message)?,
+            }
+
+            if !diagnostic.snippet.is_empty() {
+                write!(f, "\n{}", diagnostic.snippet)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for ShaderCompileError {}
+
+/// Returns the line, the column when reported and the message of an error, `None` for anything else.
+fn parse_log_line(log_line: &str) -> Option<(u32, Option<u32>, String)> {
+    let log_line = log_line.trim();
+
+    if log_line.starts_with("WARNING:") {
+        return None;
+    }
+
+    let rest = log_line.strip_prefix("ERROR:").unwrap_or(log_line).trim_start();
+
+    // Index of the source string, always 0 since a single string is given to `shaderSource`
+    let (source_index, rest) = rest.split_once(':')?;
+    source_index.parse::<u32>().ok()?;
+
+    let line_end = rest.find(|character: char| !character.is_ascii_digit())?;
+    let line = rest[..line_end].parse().ok()?;
+    let mut rest = &rest[line_end..];
+
+    let mut column = None;
+    if let Some(after_parenthesis) = rest.strip_prefix('(') {
+        let (column_digits, after_column) = after_parenthesis.split_once(')')?;
+        column = column_digits.parse().ok();
+        rest = after_column;
+    }
+
+    let message = rest.strip_prefix(':')?.trim();
+
+    if message.starts_with("warning:") {
+        return None;
+    }
+
+    let message = message.strip_prefix("error:").unwrap_or(message).trim();
+    Some((line, column, String::from(message)))
+}
+
+fn snippet(source_lines: &[&str], source_map: &SourceMap, line: u32, column: Option<u32>, message: &str) -> String {
+    let Some(error_line) = source_lines.get(line.wrapping_sub(1) as usize) else {
+        return String::new();
+    };
+
+    let first_line = line.saturating_sub(SNIPPET_CONTEXT_LINES).max(1);
+    let last_line = (line + SNIPPET_CONTEXT_LINES).min(source_lines.len() as u32);
+
+    let label = |line: u32| source_map.get(line).map_or(line, |location| location.line).to_string();
+    let gutter_width = (first_line..=last_line).map(|line| label(line).len()).max().unwrap_or(0);
+
+    let mut snippet = Vec::new();
+
+    for snippet_line in first_line..=last_line {
+        let text = source_lines[snippet_line as usize - 1];
+        snippet.push(format!("{:>gutter_width$} | {}", label(snippet_line), text).trim_end().to_string());
+
+        if snippet_line == line {
+            let caret_column = caret_column(error_line, column, message);
+            let indent: String = error_line
+                .chars()
+                .take(caret_column)
+                .map(|character| if character == '\t' { '\t' } else { ' ' })
+                .collect();
+
+            snippet.push(format!("{:>gutter_width$} | {}^", "", indent));
+        }
+    }
+
+    snippet.join("\n")
+}
+
+/// Column reported by the compiler, else the token quoted in the message, else the first non blank character.
+fn caret_column(source_line: &str, column: Option<u32>, message: &str) -> usize {
+    let line_length = source_line.chars().count();
+
+    if let Some(column) = column.filter(|&column| column > 0) {
+        return (column as usize - 1).min(line_length.saturating_sub(1));
+    }
+
+    // Browsers quote tokens as 'token' and Mesa as `token'
+    let token = message
+        .find(['\'', '`'])
+        .and_then(|start| message[start + 1..].split_once('\'').map(|(token, _)| token))
+        .filter(|token| !token.is_empty());
+
+    if let Some(byte_index) = token.and_then(|token| source_line.find(token)) {
+        return source_line[..byte_index].chars().count();
+    }
+
+    source_line.chars().take_while(|character| character.is_whitespace()).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "#version 300 es\nin vec3 position;\n\nvoid main() {\n    gl_Position = vec4(positon, 1.0);\n}\n";
+
+    #[test]
+    fn parses_browser_log() {
+        let log = "ERROR: 0:5: 'positon' : undeclared identifier\nERROR: 0:5: 'constructor' : not enough data provided for construction\nERROR: 2 compilation errors.  No code generated.\n";
+        let error = ShaderCompileError::new(ShaderStage::Vertex, Some(String::from(log)), SOURCE, &SourceMap::identity(SOURCE));
+
+        assert_eq!(error.diagnostics.len(), 2);
+        assert_eq!(error.diagnostics[0].line, 5);
+        assert_eq!(error.diagnostics[0].message, "'positon' : undeclared identifier");
+        assert_eq!(
+            error.to_string().lines().take(7).collect::<Vec<_>>(),
+            [
+                "Failed to compile the vertex shader",
+                "error at line 5: 'positon' : undeclared identifier",
+                "3 |",
+                "4 | void main() {",
+                "5 |     gl_Position = vec4(positon, 1.0);",
+                "  |                        ^",
+                "6 | }",
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_mesa_log_with_columns() {
+        let log = "0:5(24): error: `positon' undeclared\n0:5(19): warning: something harmless\n";
+        let error = ShaderCompileError::new(ShaderStage::Vertex, Some(String::from(log)), SOURCE, &SourceMap::identity(SOURCE));
+
+        assert_eq!(error.diagnostics.len(), 1);
+        assert_eq!(error.diagnostics[0].message, "`positon' undeclared");
+        assert!(error.diagnostics[0].snippet.contains("\n  |                        ^\n"));
+    }
+
+    #[test]
+    fn maps_lines_through_chunks() {
+        let source = "#version 300 es\nuniform mat4 projection_matrix;\nuniform mat4 camera_inverse_matrix\nvoid main() {}\n";
+
+        let mut source_map = SourceMap::default();
+        source_map.push(SourceLocation { chunk: None, line: 1 });
+        source_map.push(SourceLocation {
+            chunk: Some(String::from("camera")),
+            line:  1,
+        });
+        source_map.push(SourceLocation {
+            chunk: Some(String::from("camera")),
+            line:  2,
+        });
+        source_map.push(SourceLocation { chunk: None, line: 3 });
+
+        let log = "ERROR: 0:4: 'void' : syntax error";
+        let error = ShaderCompileError::new(ShaderStage::Fragment, Some(String::from(log)), source, &source_map);
+
+        assert_eq!(error.diagnostics[0].location, source_map.get(4).cloned());
+        assert!(error.to_string().contains("error at line 3: 'void' : syntax error"));
+        assert!(error.diagnostics[0].snippet.starts_with("1 | uniform mat4 projection_matrix;"));
+    }
+
+    #[test]
+    fn falls_back_to_the_raw_log() {
+        let log = "Shader compilation failed for an unknown reason";
+        let error = ShaderCompileError::new(ShaderStage::Fragment, Some(String::from(log)), SOURCE, &SourceMap::identity(SOURCE));
+
+        assert!(error.diagnostics.is_empty());
+        assert_eq!(error.to_string(), format!("Failed to compile the fragment shader: {log}"));
+    }
+}
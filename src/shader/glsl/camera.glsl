@@ -0,0 +1,19 @@
+layout(std140) uniform Camera {
+    mat4 view_matrix;
+    mat4 projection_matrix;
+    mat4 view_projection_matrix;
+    vec3 position;
+    float time;
+    vec2 viewport;
+} camera;
+
+uniform mat4 transform;
+
+vec4 project(vec3 position) {
+    return camera.view_projection_matrix * transform * vec4(position, 1.0);
+}
+
+// Normal in view space, assumes `transform` has a uniform scale
+vec3 view_normal(vec3 normal) {
+    return mat3(camera.view_matrix * transform) * normal;
+}
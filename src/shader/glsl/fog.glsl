@@ -0,0 +1,8 @@
+uniform vec3 fog_color;
+uniform float fog_near;
+uniform float fog_far;
+
+// `depth` is the distance to the camera in view space
+vec3 apply_fog(vec3 color, float depth) {
+    return mix(color, fog_color, smoothstep(fog_near, fog_far, depth));
+}
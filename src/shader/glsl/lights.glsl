@@ -0,0 +1,8 @@
+#ifndef AMBIENT_LIGHT
+#define AMBIENT_LIGHT 0.2
+#endif
+
+// Lambertian term of a directional light, never darker than the ambient light
+float directional_light(vec3 normal, vec3 light_direction) {
+    return max(AMBIENT_LIGHT, dot(normalize(normal), normalize(light_direction)));
+}
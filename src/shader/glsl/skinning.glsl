@@ -0,0 +1,15 @@
+#ifndef MAX_JOINTS
+#define MAX_JOINTS 64
+#endif
+
+in vec4 joints;
+in vec4 weights;
+
+uniform mat4 joint_matrices[MAX_JOINTS];
+
+mat4 skinning_matrix() {
+    return weights.x * joint_matrices[int(joints.x)]
+         + weights.y * joint_matrices[int(joints.y)]
+         + weights.z * joint_matrices[int(joints.z)]
+         + weights.w * joint_matrices[int(joints.w)];
+}
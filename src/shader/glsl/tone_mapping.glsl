@@ -0,0 +1,8 @@
+// Krzysztof Narkowicz's fit of the ACES filmic curve
+vec3 aces_tone_mapping(vec3 color) {
+    return clamp((color * (2.51 * color + 0.03)) / (color * (2.43 * color + 0.59) + 0.14), 0.0, 1.0);
+}
+
+vec3 linear_to_srgb(vec3 color) {
+    return pow(color, vec3(1.0 / 2.2));
+}
@@ -0,0 +1,78 @@
+pub mod chunks;
+pub mod compile_error;
+pub mod preprocessor;
+pub mod reflection;
+#[cfg(feature = "validation")]
+pub mod validation;
+
+use std::fmt;
+
+use crate::backend::Context as GL;
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShaderStage {
+    Vertex   = GL::VERTEX_SHADER,
+    Fragment = GL::FRAGMENT_SHADER,
+}
+
+impl fmt::Display for ShaderStage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShaderStage::Vertex => write!(f, "vertex"),
+            ShaderStage::Fragment => write!(f, "fragment"),
+        }
+    }
+}
+
+/// Line of the source as written, before any processing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    /// Name of the included chunk, `None` for the material source itself.
+    pub chunk: Option<String>,
+    /// Starts at 1, like the lines reported by the compiler.
+    pub line:  u32,
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.chunk {
+            Some(chunk) => write!(f, "`{}` line {}", chunk, self.line),
+            None => write!(f, "line {}", self.line),
+        }
+    }
+}
+
+/// Maps every line of the compiled source back to where it was written.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    locations: Vec<SourceLocation>,
+}
+
+impl SourceMap {
+    /// Every line maps to itself, for sources compiled as written.
+    pub fn identity(source: &str) -> SourceMap {
+        let locations = (1..=source.lines().count() as u32)
+            .map(|line| SourceLocation { chunk: None, line })
+            .collect();
+
+        SourceMap { locations }
+    }
+
+    pub fn push(&mut self, location: SourceLocation) {
+        self.locations.push(location);
+    }
+
+    /// Location of `line` of the compiled source, starting at 1.
+    pub fn get(&self, line: u32) -> Option<&SourceLocation> {
+        self.locations.get(line.checked_sub(1)? as usize)
+    }
+
+    pub fn len(&self) -> usize {
+        self.locations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.locations.is_empty()
+    }
+}
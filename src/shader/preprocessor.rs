@@ -0,0 +1,305 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    error::Error,
+    fmt,
+};
+
+use super::{ShaderStage, SourceLocation, SourceMap, chunks::ShaderChunks};
+
+/// Chunk name of the lines added by the preprocessor, like the version and the defines.
+pub const GENERATED_CHUNK: &str = "<generated>";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PreprocessError {
+    UnknownChunk { name: String, location: SourceLocation },
+    MalformedInclude(SourceLocation),
+}
+
+impl fmt::Display for PreprocessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PreprocessError::UnknownChunk { name, location } => write!(f, "Unknown shader chunk `{name}` included at {location}"),
+            PreprocessError::MalformedInclude(location) => write!(f, "Malformed include at {location}, expected `#include \"name\"`"),
+        }
+    }
+}
+
+impl Error for PreprocessError {}
+
+/// Source ready to compile, with the origin of each of its lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreprocessedSource {
+    pub source:     String,
+    pub source_map: SourceMap,
+}
+
+/// Produces the source given to the compiler:
+/// - `#version 300 es` is added when the source does not start with a version directive.
+///   Leading blank and comment lines are moved after it, as GLSL ES requires it on the first line.
+/// - `defines` are added as `#define NAME VALUE`, after the version and extension directives.
+/// - Fragment shaders without a float precision get `highp` float and int precisions,
+///   matching the vertex shader defaults so uniforms shared by both stages agree.
+/// - `#include "name"` lines are replaced by the chunk, each chunk being included at most once.
+///   Includes are expanded before the GLSL preprocessor runs, even inside `#ifdef` blocks.
+pub fn preprocess(
+    source: &str,
+    stage: ShaderStage,
+    defines: &BTreeMap<String, String>,
+    chunks: &ShaderChunks,
+) -> Result<PreprocessedSource, PreprocessError> {
+    let mut preprocessor = Preprocessor {
+        chunks,
+        output: PreprocessedSource {
+            source:     String::with_capacity(source.len()),
+            source_map: SourceMap::default(),
+        },
+        included: HashSet::new(),
+        generated_lines: 0,
+    };
+
+    let mut lines = source.lines().zip(1..).peekable();
+
+    let mut leading_lines = Vec::new();
+    let mut in_block_comment = false;
+    while let Some(line) = lines.next_if(|(line, _)| is_blank_or_comment(line, &mut in_block_comment)) {
+        leading_lines.push(line);
+    }
+
+    match lines.next_if(|(line, _)| line.trim_start().starts_with("#version")) {
+        Some((line, line_number)) => preprocessor.push(line, material_line(line_number)),
+        None => preprocessor.push_generated("#version 300 es"),
+    }
+
+    for (line, line_number) in leading_lines {
+        preprocessor.push(line, material_line(line_number));
+    }
+
+    // Extensions must come before anything but the version
+    while let Some((line, line_number)) = lines.next_if(|(line, _)| line.trim_start().starts_with("#extension")) {
+        preprocessor.push(line, material_line(line_number));
+    }
+
+    for (name, value) in defines {
+        preprocessor.push_generated(&format!("#define {name} {value}"));
+    }
+
+    if stage == ShaderStage::Fragment && !declares_float_precision(source) {
+        preprocessor.push_generated("precision highp float;");
+        preprocessor.push_generated("precision highp int;");
+    }
+
+    preprocessor.expand(lines, None)?;
+
+    Ok(preprocessor.output)
+}
+
+struct Preprocessor<'a> {
+    chunks:          &'a ShaderChunks,
+    output:          PreprocessedSource,
+    included:        HashSet<String>,
+    generated_lines: u32,
+}
+
+impl Preprocessor<'_> {
+    fn expand<'a>(&mut self, lines: impl Iterator<Item = (&'a str, u32)>, chunk: Option<&str>) -> Result<(), PreprocessError> {
+        for (line, line_number) in lines {
+            let location = SourceLocation {
+                chunk: chunk.map(String::from),
+                line:  line_number,
+            };
+
+            let Some(argument) = line.trim_start().strip_prefix("#include") else {
+                self.push(line, location);
+                continue;
+            };
+
+            let Some(name) = argument
+                .trim()
+                .strip_prefix('"')
+                .and_then(|name| name.strip_suffix('"'))
+                .filter(|name| !name.is_empty())
+            else {
+                return Err(PreprocessError::MalformedInclude(location));
+            };
+
+            let Some(chunk_source) = self.chunks.get(name) else {
+                return Err(PreprocessError::UnknownChunk {
+                    name: String::from(name),
+                    location,
+                });
+            };
+
+            if self.included.insert(String::from(name)) {
+                self.expand(chunk_source.lines().zip(1..), Some(name))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn push(&mut self, line: &str, location: SourceLocation) {
+        self.output.source.push_str(line);
+        self.output.source.push('\n');
+        self.output.source_map.push(location);
+    }
+
+    fn push_generated(&mut self, line: &str) {
+        self.generated_lines += 1;
+
+        let location = SourceLocation {
+            chunk: Some(String::from(GENERATED_CHUNK)),
+            line:  self.generated_lines,
+        };
+        self.push(line, location);
+    }
+}
+
+fn material_line(line: u32) -> SourceLocation {
+    SourceLocation { chunk: None, line }
+}
+
+/// Byte offset after the lines that must stay first in `source`: leading blank and comment lines,
+/// the version directive and the extension directives. Declarations can be inserted there.
+pub(crate) fn header_end(source: &str) -> usize {
+    let mut lines = source.split_inclusive('\n').peekable();
+    let mut end = 0;
+
+    let mut in_block_comment = false;
+    while let Some(line) = lines.next_if(|line| is_blank_or_comment(line, &mut in_block_comment)) {
+        end += line.len();
+    }
+
+    if let Some(line) = lines.next_if(|line| line.trim_start().starts_with("#version")) {
+        end += line.len();
+    }
+
+    while let Some(line) = lines.next_if(|line| line.trim_start().starts_with("#extension")) {
+        end += line.len();
+    }
+
+    end
+}
+
+/// Whether `line` holds nothing but whitespace and comments, tracking block comments spanning several lines.
+fn is_blank_or_comment(line: &str, in_block_comment: &mut bool) -> bool {
+    let mut rest = line.trim();
+
+    loop {
+        if *in_block_comment {
+            let Some(end) = rest.find("*/") else {
+                return true;
+            };
+
+            *in_block_comment = false;
+            rest = rest[end + 2..].trim_start();
+        } else if rest.is_empty() || rest.starts_with("//") {
+            return true;
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            *in_block_comment = true;
+            rest = comment;
+        } else {
+            return false;
+        }
+    }
+}
+
+fn declares_float_precision(source: &str) -> bool {
+    source.lines().any(|line| {
+        let mut tokens = line.split_whitespace();
+        tokens.next() == Some("precision") && tokens.any(|token| token.trim_end_matches(';') == "float")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunks() -> ShaderChunks {
+        let mut chunks = ShaderChunks::default();
+        chunks.register("common", "#include \"constants\"\nfloat twice(float x) { return TWO * x; }");
+        chunks.register("constants", "#define TWO 2.0");
+        chunks
+    }
+
+    #[test]
+    fn adds_headers_and_defines() {
+        let defines = BTreeMap::from([(String::from("USE_FOG"), String::from("1"))]);
+        let output = preprocess("out vec4 color;\nvoid main() {}", ShaderStage::Fragment, &defines, &chunks()).unwrap();
+
+        assert_eq!(
+            output.source,
+            "#version 300 es\n#define USE_FOG 1\nprecision highp float;\nprecision highp int;\nout vec4 color;\nvoid main() {}\n"
+        );
+        assert_eq!(output.source_map.get(5), Some(&SourceLocation { chunk: None, line: 1 }));
+    }
+
+    #[test]
+    fn keeps_version_extensions_and_precision() {
+        let source = "#version 300 es\n#extension GL_OVR_multiview2 : require\nprecision mediump float;\nvoid main() {}";
+        let defines = BTreeMap::from([(String::from("VIEWS"), String::from("2"))]);
+        let output = preprocess(source, ShaderStage::Fragment, &defines, &chunks()).unwrap();
+
+        assert_eq!(
+            output.source,
+            "#version 300 es\n#extension GL_OVR_multiview2 : require\n#define VIEWS 2\nprecision mediump float;\nvoid main() {}\n"
+        );
+    }
+
+    #[test]
+    fn includes_chunks_once_and_maps_their_lines() {
+        let source = "#version 300 es\n#include \"common\"\n#include \"constants\"\nvoid main() {}";
+        let output = preprocess(source, ShaderStage::Vertex, &BTreeMap::new(), &chunks()).unwrap();
+
+        assert_eq!(
+            output.source,
+            "#version 300 es\n#define TWO 2.0\nfloat twice(float x) { return TWO * x; }\nvoid main() {}\n"
+        );
+
+        let chunk_location = |chunk: &str, line| {
+            Some(SourceLocation {
+                chunk: Some(String::from(chunk)),
+                line,
+            })
+        };
+        assert_eq!(output.source_map.get(2).cloned(), chunk_location("constants", 1));
+        assert_eq!(output.source_map.get(3).cloned(), chunk_location("common", 2));
+        assert_eq!(output.source_map.get(4), Some(&SourceLocation { chunk: None, line: 4 }));
+    }
+
+    #[test]
+    fn rejects_unknown_and_malformed_includes() {
+        let unknown = preprocess("#include \"lighting\"", ShaderStage::Vertex, &BTreeMap::new(), &chunks());
+        assert_eq!(
+            unknown.unwrap_err().to_string(),
+            "Unknown shader chunk `lighting` included at line 1"
+        );
+
+        let malformed = preprocess(
+            "void main() {}\n#include <camera>",
+            ShaderStage::Vertex,
+            &BTreeMap::new(),
+            &chunks(),
+        );
+        assert_eq!(
+            malformed,
+            Err(PreprocessError::MalformedInclude(SourceLocation { chunk: None, line: 2 }))
+        );
+    }
+
+    #[test]
+    fn finds_the_version_after_leading_comments() {
+        let source = "\n// SPDX-License-Identifier: MIT\n/*\n * Copyright\n */\n#version 300 es\nvoid main() {}";
+        let output = preprocess(source, ShaderStage::Vertex, &BTreeMap::new(), &chunks()).unwrap();
+
+        assert_eq!(
+            output.source,
+            "#version 300 es\n\n// SPDX-License-Identifier: MIT\n/*\n * Copyright\n */\nvoid main() {}\n"
+        );
+        assert_eq!(output.source_map.get(1), Some(&SourceLocation { chunk: None, line: 6 }));
+        assert_eq!(output.source_map.get(2), Some(&SourceLocation { chunk: None, line: 1 }));
+
+        // Comments followed by code are not skipped
+        let output = preprocess("/* a */ void main() {}", ShaderStage::Vertex, &BTreeMap::new(), &chunks()).unwrap();
+        assert_eq!(output.source, "#version 300 es\n/* a */ void main() {}\n");
+    }
+}
@@ -0,0 +1,365 @@
+use std::fmt;
+
+use crate::{
+    backend::{Context as GL, ProgramHandle},
+    error::SuricatoError,
+    geometry::Geometry,
+    material::Material,
+};
+
+macro_rules! glsl_types {
+    ($($variant:ident = $constant:ident => $name:literal),* $(,)?) => {
+        /// Type of a uniform or attribute as declared in GLSL.
+        #[repr(u32)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum GlslType {
+            $($variant = GL::$constant,)*
+        }
+
+        impl GlslType {
+            const ALL: &[GlslType] = &[$(GlslType::$variant,)*];
+
+            /// Type from the GL enum returned by `getActiveUniform` and `getActiveAttrib`.
+            pub fn from_gl(kind: u32) -> Option<GlslType> {
+                match kind {
+                    $(GL::$constant => Some(GlslType::$variant),)*
+                    _ => None,
+                }
+            }
+
+            /// Name of the type in GLSL, like `vec3` or `sampler2D`.
+            pub fn glsl_name(&self) -> &'static str {
+                match self {
+                    $(GlslType::$variant => $name,)*
+                }
+            }
+        }
+    };
+}
+
+glsl_types! {
+    Float = FLOAT => "float",
+    Vec2 = FLOAT_VEC2 => "vec2",
+    Vec3 = FLOAT_VEC3 => "vec3",
+    Vec4 = FLOAT_VEC4 => "vec4",
+
+    Int = INT => "int",
+    IntVec2 = INT_VEC2 => "ivec2",
+    IntVec3 = INT_VEC3 => "ivec3",
+    IntVec4 = INT_VEC4 => "ivec4",
+
+    UnsignedInt = UNSIGNED_INT => "uint",
+    UnsignedIntVec2 = UNSIGNED_INT_VEC2 => "uvec2",
+    UnsignedIntVec3 = UNSIGNED_INT_VEC3 => "uvec3",
+    UnsignedIntVec4 = UNSIGNED_INT_VEC4 => "uvec4",
+
+    Bool = BOOL => "bool",
+    BoolVec2 = BOOL_VEC2 => "bvec2",
+    BoolVec3 = BOOL_VEC3 => "bvec3",
+    BoolVec4 = BOOL_VEC4 => "bvec4",
+
+    Mat2 = FLOAT_MAT2 => "mat2",
+    Mat3 = FLOAT_MAT3 => "mat3",
+    Mat4 = FLOAT_MAT4 => "mat4",
+    Mat2x3 = FLOAT_MAT2X3 => "mat2x3",
+    Mat2x4 = FLOAT_MAT2X4 => "mat2x4",
+    Mat3x2 = FLOAT_MAT3X2 => "mat3x2",
+    Mat3x4 = FLOAT_MAT3X4 => "mat3x4",
+    Mat4x2 = FLOAT_MAT4X2 => "mat4x2",
+    Mat4x3 = FLOAT_MAT4X3 => "mat4x3",
+
+    Sampler2D = SAMPLER_2D => "sampler2D",
+    Sampler3D = SAMPLER_3D => "sampler3D",
+    SamplerCube = SAMPLER_CUBE => "samplerCube",
+    Sampler2DArray = SAMPLER_2D_ARRAY => "sampler2DArray",
+    Sampler2DShadow = SAMPLER_2D_SHADOW => "sampler2DShadow",
+    SamplerCubeShadow = SAMPLER_CUBE_SHADOW => "samplerCubeShadow",
+    Sampler2DArrayShadow = SAMPLER_2D_ARRAY_SHADOW => "sampler2DArrayShadow",
+    IntSampler2D = INT_SAMPLER_2D => "isampler2D",
+    IntSampler3D = INT_SAMPLER_3D => "isampler3D",
+    IntSamplerCube = INT_SAMPLER_CUBE => "isamplerCube",
+    IntSampler2DArray = INT_SAMPLER_2D_ARRAY => "isampler2DArray",
+    UnsignedIntSampler2D = UNSIGNED_INT_SAMPLER_2D => "usampler2D",
+    UnsignedIntSampler3D = UNSIGNED_INT_SAMPLER_3D => "usampler3D",
+    UnsignedIntSamplerCube = UNSIGNED_INT_SAMPLER_CUBE => "usamplerCube",
+    UnsignedIntSampler2DArray = UNSIGNED_INT_SAMPLER_2D_ARRAY => "usampler2DArray",
+}
+
+impl GlslType {
+    pub fn from_glsl_name(name: &str) -> Option<GlslType> {
+        GlslType::ALL.iter().copied().find(|kind| kind.glsl_name() == name)
+    }
+
+    pub fn is_sampler(&self) -> bool {
+        self.texture_target().is_some()
+    }
+
+    /// Target the textures read by a sampler of this type are bound to.
+    pub fn texture_target(&self) -> Option<u32> {
+        let target = match self {
+            GlslType::Sampler2D | GlslType::Sampler2DShadow | GlslType::IntSampler2D | GlslType::UnsignedIntSampler2D => GL::TEXTURE_2D,
+            GlslType::Sampler3D | GlslType::IntSampler3D | GlslType::UnsignedIntSampler3D => GL::TEXTURE_3D,
+            GlslType::SamplerCube | GlslType::SamplerCubeShadow | GlslType::IntSamplerCube | GlslType::UnsignedIntSamplerCube => {
+                GL::TEXTURE_CUBE_MAP
+            }
+            GlslType::Sampler2DArray
+            | GlslType::Sampler2DArrayShadow
+            | GlslType::IntSampler2DArray
+            | GlslType::UnsignedIntSampler2DArray => GL::TEXTURE_2D_ARRAY,
+            _ => return None,
+        };
+
+        Some(target)
+    }
+}
+
+impl fmt::Display for GlslType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.glsl_name())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UniformInfo {
+    /// Name given to [`Material::set_uniform`], arrays are named without the `[0]` suffix
+    /// and struct members with their full path like `lights[1].color`.
+    pub name:       String,
+    pub kind:       GlslType,
+    /// Number of elements for arrays, `1` otherwise.
+    pub array_size: u32,
+    /// Name of the uniform block declaring the uniform, `None` for uniforms set one by one.
+    pub block:      Option<String>,
+    /// Offset in bytes from the start of the block.
+    pub offset:     Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributeInfo {
+    pub name:     String,
+    pub kind:     GlslType,
+    /// `None` for shaders reflected without being linked, see [`validation`](super::validation).
+    pub location: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UniformBlockInfo {
+    pub name:    String,
+    /// Size in bytes of the std140 layout, the minimum size of the buffer bound to the block.
+    pub size:    u32,
+    /// Binding point when the program was reflected, `None` for shaders reflected without being linked.
+    pub binding: Option<u32>,
+}
+
+/// Uniforms, attributes and uniform blocks of a program.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProgramReflection {
+    pub uniforms:       Vec<UniformInfo>,
+    pub attributes:     Vec<AttributeInfo>,
+    pub uniform_blocks: Vec<UniformBlockInfo>,
+}
+
+impl ProgramReflection {
+    /// Active uniforms, attributes and uniform blocks of a linked program, as reported by the driver.
+    ///
+    /// Unused declarations are optimized out by the driver and are not listed.
+    pub fn from_program(gl: &GL, program: ProgramHandle) -> ProgramReflection {
+        let block_count = gl.get_program_parameter(program, GL::ACTIVE_UNIFORM_BLOCKS).max(0) as u32;
+        let uniform_blocks: Vec<UniformBlockInfo> = (0..block_count)
+            .map(|index| {
+                UniformBlockInfo {
+                    name:    gl.get_active_uniform_block_name(program, index).unwrap_or_default(),
+                    size:    gl.get_active_uniform_block_parameter(program, index, GL::UNIFORM_BLOCK_DATA_SIZE) as u32,
+                    binding: Some(gl.get_active_uniform_block_parameter(program, index, GL::UNIFORM_BLOCK_BINDING) as u32),
+                }
+            })
+            .collect();
+
+        let uniform_count = gl.get_program_parameter(program, GL::ACTIVE_UNIFORMS).max(0) as u32;
+        let indices: Vec<u32> = (0..uniform_count).collect();
+        let block_indices = gl.get_active_uniforms(program, &indices, GL::UNIFORM_BLOCK_INDEX);
+        let offsets = gl.get_active_uniforms(program, &indices, GL::UNIFORM_OFFSET);
+
+        let uniforms = indices
+            .iter()
+            .filter_map(|&index| {
+                let info = gl.get_active_uniform(program, index)?;
+                let block = block_indices
+                    .get(index as usize)
+                    .and_then(|&block_index| uniform_blocks.get(usize::try_from(block_index).ok()?));
+
+                Some(UniformInfo {
+                    name:       String::from(info.name.strip_suffix("[0]").unwrap_or(&info.name)),
+                    kind:       GlslType::from_gl(info.kind)?,
+                    array_size: info.size.max(1) as u32,
+                    block:      block.map(|block| block.name.clone()),
+                    offset:     block.and(offsets.get(index as usize)).map(|&offset| offset as u32),
+                })
+            })
+            .collect();
+
+        let attribute_count = gl.get_program_parameter(program, GL::ACTIVE_ATTRIBUTES).max(0) as u32;
+        let attributes = (0..attribute_count)
+            .filter_map(|index| {
+                let info = gl.get_active_attrib(program, index)?;
+                let location = gl.get_attrib_location(program, &info.name);
+
+                Some(AttributeInfo {
+                    kind:     GlslType::from_gl(info.kind)?,
+                    location: u32::try_from(location).ok(),
+                    name:     info.name,
+                })
+            })
+            .collect();
+
+        ProgramReflection {
+            uniforms,
+            attributes,
+            uniform_blocks,
+        }
+    }
+
+    pub fn get_uniform(&self, name: &str) -> Option<&UniformInfo> {
+        self.uniforms.iter().find(|uniform| uniform.name == name)
+    }
+
+    /// Uniform outside of blocks set by `path`, either its name or one of its elements like `bones[3]`.
+    pub fn resolve_uniform(&self, path: &str) -> Option<&UniformInfo> {
+        let find = |name: &str| self.uniforms.iter().find(|uniform| uniform.block.is_none() && uniform.name == name);
+
+        find(path).or_else(|| {
+            let (name, index) = path.strip_suffix(']')?.rsplit_once('[')?;
+            let index: u32 = index.parse().ok()?;
+
+            find(name).filter(|uniform| index < uniform.array_size)
+        })
+    }
+
+    pub fn get_attribute(&self, name: &str) -> Option<&AttributeInfo> {
+        self.attributes.iter().find(|attribute| attribute.name == name)
+    }
+
+    pub fn get_uniform_block(&self, name: &str) -> Option<&UniformBlockInfo> {
+        self.uniform_blocks.iter().find(|block| block.name == name)
+    }
+
+    /// Uniforms of block `name`, ordered by offset.
+    pub fn get_uniform_block_members(&self, name: &str) -> Vec<&UniformInfo> {
+        let mut members: Vec<&UniformInfo> = self
+            .uniforms
+            .iter()
+            .filter(|uniform| uniform.block.as_deref() == Some(name))
+            .collect();
+
+        members.sort_by_key(|uniform| uniform.offset);
+        members
+    }
+
+    pub fn samplers(&self) -> impl Iterator<Item = &UniformInfo> {
+        self.uniforms.iter().filter(|uniform| uniform.kind.is_sampler())
+    }
+
+    /// Attributes read by the program that `geometry` does not provide.
+    pub fn check_geometry(&self, geometry: &Geometry) -> Vec<SuricatoError> {
+        self.attributes
+            .iter()
+            .filter(|attribute| !attribute.name.starts_with("gl_"))
+            .filter(|attribute| !geometry.get_vertex_layouts().any(|layout| layout.name == attribute.name))
+            .map(|attribute| SuricatoError::MissingAttribute(attribute.name.clone()))
+            .collect()
+    }
+
+    /// Uniforms set on `material` that the program does not declare, sorted by name.
+    pub fn check_uniforms(&self, material: &Material) -> Vec<SuricatoError> {
+        let mut unknown_uniforms: Vec<String> = material
+            .get_uniforms()
+            .map(|(handle, _)| handle.get_name())
+            .filter(|name| self.resolve_uniform(name).is_none())
+            .map(|name| String::from(&*name))
+            .collect();
+
+        unknown_uniforms.sort();
+        unknown_uniforms.into_iter().map(SuricatoError::UnknownUniform).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::{backend::mock::MockContext, uniforms::Uniform};
+
+    const VERTEX_SHADER: &str = "in vec3 position;\nin mat4 instance_matrix;\nuniform mat4 joint_matrices[4];\nvoid main() {}\n";
+    const FRAGMENT_SHADER: &str = "uniform sampler2D diffuse;\nuniform highp uvec3 ids;\nlayout(std140) uniform Camera {\n    mat4 view;\n};\nout vec4 color;\nvoid main() {}\n";
+
+    #[test]
+    fn reflects_linked_programs() {
+        let mock = Rc::new(MockContext::new(8, 8));
+        let mut material = Material::new(VERTEX_SHADER, FRAGMENT_SHADER);
+        let reflection = material.get_or_create_resources(&mock.context()).get_reflection().clone();
+
+        assert_eq!(
+            reflection.get_uniform("joint_matrices"),
+            Some(&UniformInfo {
+                name:       String::from("joint_matrices"),
+                kind:       GlslType::Mat4,
+                array_size: 4,
+                block:      None,
+                offset:     None,
+            })
+        );
+        assert_eq!(
+            reflection.get_uniform("ids").map(|uniform| uniform.kind),
+            Some(GlslType::UnsignedIntVec3)
+        );
+        assert_eq!(
+            reflection.samplers().map(|uniform| uniform.name.as_str()).collect::<Vec<_>>(),
+            ["diffuse"]
+        );
+
+        let attributes: Vec<(&str, GlslType, Option<u32>)> = reflection
+            .attributes
+            .iter()
+            .map(|attribute| (attribute.name.as_str(), attribute.kind, attribute.location))
+            .collect();
+        assert_eq!(
+            attributes,
+            [("position", GlslType::Vec3, Some(0)), ("instance_matrix", GlslType::Mat4, Some(1))]
+        );
+
+        assert_eq!(reflection.get_uniform_block("Camera").map(|block| block.binding), Some(Some(0)));
+    }
+
+    #[test]
+    fn converts_gl_types() {
+        assert_eq!(GlslType::from_gl(GL::FLOAT_MAT3X4), Some(GlslType::Mat3x4));
+        assert_eq!(GlslType::from_gl(GL::TEXTURE_2D), None);
+        assert_eq!(GlslType::from_glsl_name("usamplerCube"), Some(GlslType::UnsignedIntSamplerCube));
+        assert_eq!(GlslType::IntSampler2DArray.to_string(), "isampler2DArray");
+        assert!(GlslType::Sampler2DShadow.is_sampler());
+        assert!(!GlslType::Mat4.is_sampler());
+        assert_eq!(GlslType::UnsignedIntSamplerCube.texture_target(), Some(GL::TEXTURE_CUBE_MAP));
+        assert_eq!(GlslType::Sampler2DArrayShadow.texture_target(), Some(GL::TEXTURE_2D_ARRAY));
+    }
+
+    #[test]
+    fn checks_uniforms_against_the_program() {
+        let mock = Rc::new(MockContext::new(8, 8));
+        let mut material = Material::new(VERTEX_SHADER, FRAGMENT_SHADER);
+        material.set_uniform("ids", Uniform::UnsignedIntVec3([1, 2, 3]));
+        material.set_uniform("view", Uniform::Mat4([0.0; 16]));
+        material.set_uniform("joint_matrices[3]", Uniform::Mat4([0.0; 16]));
+        material.set_uniform("joint_matrices[4]", Uniform::Mat4([0.0; 16]));
+
+        let reflection = material.get_or_create_resources(&mock.context()).get_reflection().clone();
+        let errors: Vec<String> = reflection.check_uniforms(&material).iter().map(ToString::to_string).collect();
+
+        assert_eq!(
+            errors,
+            [
+                "Uniform `joint_matrices[4]` is not used by the program",
+                "Uniform `view` is not used by the program"
+            ]
+        );
+    }
+}
@@ -0,0 +1,740 @@
+//! Offline validation of material shaders with naga, for tests and build scripts.
+//!
+//! naga only reads Vulkan flavoured GLSL 450, so the preprocessed sources are translated first:
+//! uniforms get a binding, inputs and outputs a location, samplers are split into a texture and
+//! a sampler and `gl_VertexID` and `gl_InstanceID` become their Vulkan names.
+//! Errors are reported on the source as written, like the errors of the driver.
+//!
+//! Arrays of samplers are not supported.
+
+use std::collections::HashMap;
+
+use naga::{
+    AddressSpace, ArraySize, Binding, Module, ScalarKind, Span, Type, TypeInner, VectorSize,
+    front::glsl::{Frontend, Options},
+    valid::{Capabilities, EntryPointError, ValidationError, ValidationFlags, Validator, VaryingError},
+};
+
+use super::{
+    ShaderStage,
+    compile_error::ShaderCompileError,
+    preprocessor::PreprocessedSource,
+    reflection::{AttributeInfo, GlslType, ProgramReflection, UniformBlockInfo, UniformInfo},
+};
+use crate::{geometry::Geometry, material::Material, material::MaterialError};
+
+const TEXTURE_PREFIX: &str = "suricato_texture_";
+const SAMPLER_PREFIX: &str = "suricato_sampler_";
+
+/// Checks both shaders of the current variant of `material` and returns what they declare.
+///
+/// Attributes are the inputs of the vertex shader, uniforms and blocks are merged from both stages.
+pub fn validate_material(material: &Material) -> Result<ProgramReflection, MaterialError> {
+    let (vertex_source, fragment_source) = material.preprocess()?;
+
+    let vertex = validate_shader(&vertex_source, ShaderStage::Vertex).map_err(MaterialError::ShaderCompilationFailed)?;
+    let fragment = validate_shader(&fragment_source, ShaderStage::Fragment).map_err(MaterialError::ShaderCompilationFailed)?;
+
+    let mut reflection = vertex;
+
+    for uniform in fragment.uniforms {
+        if reflection.get_uniform(&uniform.name).is_none() {
+            reflection.uniforms.push(uniform);
+        }
+    }
+
+    for block in fragment.uniform_blocks {
+        if reflection.get_uniform_block(&block.name).is_none() {
+            reflection.uniform_blocks.push(block);
+        }
+    }
+
+    Ok(reflection)
+}
+
+/// Checks a preprocessed shader, see [`Material::preprocess`].
+///
+/// Attributes are only listed for vertex shaders.
+pub fn validate_shader(source: &PreprocessedSource, stage: ShaderStage) -> Result<ProgramReflection, ShaderCompileError> {
+    let translation = translate(&source.source);
+
+    let naga_stage = match stage {
+        ShaderStage::Vertex => naga::ShaderStage::Vertex,
+        ShaderStage::Fragment => naga::ShaderStage::Fragment,
+    };
+
+    let module = Frontend::default()
+        .parse(&Options::from(naga_stage), &translation.source)
+        .map_err(|error| {
+            let errors = error.errors.iter().map(|error| (error.meta, error.kind.to_string())).collect();
+            translation.compile_error(errors, source, stage)
+        })?;
+
+    // Loose uniforms have no layout in WebGL, so only blocks follow the std140 rules
+    let mut validator = Validator::new(ValidationFlags::all() - ValidationFlags::STRUCT_LAYOUTS, Capabilities::all());
+
+    if let Err(error) = validator.validate(&module) {
+        // WebGL accepts matrix attributes, naga only vectors
+        let is_matrix_attribute = matches!(
+            error.as_inner(),
+            ValidationError::EntryPoint {
+                source: EntryPointError::Argument(_, VaryingError::NotIOShareableType(_)),
+                ..
+            }
+        );
+
+        if !is_matrix_attribute {
+            let span = error.spans().map(|(span, _)| *span).last().unwrap_or_default();
+            return Err(translation.compile_error(vec![(span, error_chain(error.as_inner()))], source, stage));
+        }
+    }
+
+    Ok(translation.reflect(&module, stage))
+}
+
+/// Panics with every problem found, for tests making sure a material can be drawn with `geometry`.
+pub fn assert_valid_material(material: &Material, geometry: &Geometry) -> ProgramReflection {
+    let reflection = match validate_material(material) {
+        Ok(reflection) => reflection,
+        Err(error) => panic!("{error}"),
+    };
+
+    let problems: Vec<String> = reflection
+        .check_geometry(geometry)
+        .into_iter()
+        .chain(reflection.check_uniforms(material))
+        .map(|error| error.to_string())
+        .collect();
+
+    assert!(problems.is_empty(), "Invalid material:\n{}", problems.join("\n"));
+
+    reflection
+}
+
+fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+
+    while let Some(error) = source {
+        message.push_str(": ");
+        message.push_str(&error.to_string());
+        source = error.source();
+    }
+
+    message
+}
+
+/// What a binding given by the translation was declared as.
+enum Declaration {
+    Uniform,
+    UniformBlock(String),
+    Sampler { name: String, kind: GlslType },
+}
+
+struct Translation {
+    source:       String,
+    /// Line of the preprocessed source each line comes from, and whether it was changed.
+    lines:        Vec<(u32, bool)>,
+    declarations: HashMap<u32, Declaration>,
+}
+
+fn translate(source: &str) -> Translation {
+    let mut translation = Translation {
+        source:       String::with_capacity(source.len()),
+        lines:        Vec::new(),
+        declarations: HashMap::new(),
+    };
+
+    let mut depth = 0;
+    let mut next_binding = 0;
+    let mut next_location = [0, 0];
+
+    for (original_line, line_number) in source.lines().zip(1..) {
+        let trimmed = original_line.trim_start();
+
+        if trimmed.starts_with("#version") {
+            translation.push("#version 450", line_number, true);
+            continue;
+        }
+
+        if trimmed.starts_with('#') {
+            translation.push(original_line, line_number, false);
+            continue;
+        }
+
+        let code = original_line.split("//").next().unwrap_or_default();
+        let line_depth = depth;
+        depth += code.matches('{').count() as i32 - code.matches('}').count() as i32;
+
+        let line = replace_identifier(original_line, "gl_VertexID", "int(gl_VertexIndex)");
+        let line = replace_identifier(&line, "gl_InstanceID", "int(gl_InstanceIndex)");
+
+        let declaration = match line_depth {
+            0 => parse_declaration(&line),
+            _ => None,
+        };
+
+        let Some(declaration) = declaration else {
+            translation.push(&line, line_number, line != original_line);
+            continue;
+        };
+
+        let indent = &original_line[..original_line.len() - trimmed.len()];
+
+        match declaration.storage {
+            "uniform" => {
+                match GlslType::from_glsl_name(declaration.kind).filter(GlslType::is_sampler) {
+                    Some(kind) if !declaration.name.contains('[') => {
+                        let name = declaration.name.trim_end_matches(';');
+                        let texture_type = declaration.kind.trim_end_matches("Shadow").replace("sampler", "texture");
+                        let sampler_type = if declaration.kind.ends_with("Shadow") {
+                            "samplerShadow"
+                        } else {
+                            "sampler"
+                        };
+
+                        translation.push(
+                        &format!(
+                            "{indent}layout(binding={}) uniform {texture_type} {TEXTURE_PREFIX}{name}; layout(binding={}) uniform {sampler_type} {SAMPLER_PREFIX}{name};",
+                            next_binding,
+                            next_binding + 1
+                        ),
+                        line_number,
+                        true,
+                    );
+                        translation.push(
+                            &format!(
+                                "#define {name} {}({TEXTURE_PREFIX}{name}, {SAMPLER_PREFIX}{name})",
+                                declaration.kind
+                            ),
+                            line_number,
+                            true,
+                        );
+
+                        translation.declarations.insert(
+                            next_binding,
+                            Declaration::Sampler {
+                                name: String::from(name),
+                                kind,
+                            },
+                        );
+                        next_binding += 2;
+                    }
+                    _ => {
+                        let is_block = declaration.name.is_empty() || declaration.name.starts_with('{');
+                        let (layout, kind) = match is_block {
+                            true => {
+                                (
+                                    format!("std140, binding={next_binding}"),
+                                    Declaration::UniformBlock(String::from(declaration.kind)),
+                                )
+                            }
+                            false => (format!("binding={next_binding}"), Declaration::Uniform),
+                        };
+
+                        translation.push(&format!("{indent}layout({layout}) {}", line.trim_start()), line_number, true);
+                        translation.declarations.insert(next_binding, kind);
+                        next_binding += 1;
+                    }
+                }
+            }
+            storage if !declaration.layout.contains("location") => {
+                let next_location = &mut next_location[(storage == "out") as usize];
+                translation.push(
+                    &format!("{indent}layout(location={next_location}) {}", line.trim_start()),
+                    line_number,
+                    true,
+                );
+                // Matrices take a location per column
+                *next_location += 4;
+            }
+            _ => translation.push(&line, line_number, false),
+        }
+    }
+
+    translation
+}
+
+struct ParsedDeclaration<'a> {
+    layout:  &'a str,
+    storage: &'a str,
+    kind:    &'a str,
+    /// Everything after the type, the name for variables and `{` or nothing for blocks.
+    name:    &'a str,
+}
+
+/// Splits a global `uniform`, `in` or `out` declaration.
+fn parse_declaration(line: &str) -> Option<ParsedDeclaration<'_>> {
+    let mut rest = line.trim();
+    let mut layout = "";
+
+    if rest.starts_with("layout") {
+        let end = rest.find(')')? + 1;
+        layout = &rest[..end];
+        rest = rest[end..].trim_start();
+    }
+
+    let mut tokens = rest
+        .split_whitespace()
+        .filter(|token| !matches!(*token, "flat" | "smooth" | "centroid" | "invariant" | "highp" | "mediump" | "lowp"));
+
+    let storage = tokens.next().filter(|token| matches!(*token, "uniform" | "in" | "out"))?;
+    let kind = tokens.next()?.trim_end_matches('{');
+    let name = tokens.next().unwrap_or_default();
+
+    Some(ParsedDeclaration {
+        layout,
+        storage,
+        kind,
+        name,
+    })
+}
+
+fn replace_identifier(line: &str, identifier: &str, replacement: &str) -> String {
+    let is_identifier = |character: char| character.is_ascii_alphanumeric() || character == '_';
+    let mut output = String::with_capacity(line.len());
+    let mut rest = line;
+
+    while let Some(index) = rest.find(identifier) {
+        let end = index + identifier.len();
+        let before = rest[..index].chars().last().or_else(|| output.chars().last());
+        let after = rest[end..].chars().next();
+
+        output.push_str(&rest[..index]);
+
+        if before.is_some_and(is_identifier) || after.is_some_and(is_identifier) {
+            output.push_str(identifier);
+        } else {
+            output.push_str(replacement);
+        }
+
+        rest = &rest[end..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+impl Translation {
+    fn push(&mut self, line: &str, line_number: u32, changed: bool) {
+        self.source.push_str(line);
+        self.source.push('\n');
+        self.lines.push((line_number, changed));
+    }
+
+    /// Reports `errors` as a driver would, on the lines of the preprocessed source.
+    fn compile_error(&self, errors: Vec<(Span, String)>, source: &PreprocessedSource, stage: ShaderStage) -> ShaderCompileError {
+        let log = errors
+            .into_iter()
+            .map(|(span, message)| {
+                let location = span.location(&self.source);
+
+                match self.lines.get(location.line_number.saturating_sub(1) as usize) {
+                    Some((line, false)) => format!("0:{}({}): error: {}", line, location.line_position, message),
+                    Some((line, true)) => format!("0:{line}: error: {message}"),
+                    None => format!("0:{}: error: {}", location.line_number, message),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        ShaderCompileError::new(stage, Some(log), &source.source, &source.source_map)
+    }
+
+    fn reflect(&self, module: &Module, stage: ShaderStage) -> ProgramReflection {
+        let mut reflection = ProgramReflection::default();
+
+        for (_, global) in module.global_variables.iter() {
+            let Some(binding) = &global.binding else {
+                continue;
+            };
+
+            match (global.space, self.declarations.get(&binding.binding)) {
+                (AddressSpace::Uniform, Some(Declaration::Uniform)) => {
+                    let name = global.name.clone().unwrap_or_default();
+                    flatten_uniform(module, &module.types[global.ty], name, None, 0, &mut reflection.uniforms);
+                }
+                (AddressSpace::Uniform, Some(Declaration::UniformBlock(block_name))) => {
+                    let block = &module.types[global.ty];
+
+                    // Members of named blocks are prefixed by the block name, not the instance name
+                    let prefix = match global.name {
+                        Some(_) => block_name.clone(),
+                        None => String::new(),
+                    };
+                    flatten_uniform(module, block, prefix, Some(block_name), 0, &mut reflection.uniforms);
+
+                    let size = match block.inner {
+                        TypeInner::Struct { span, .. } => span,
+                        _ => 0,
+                    };
+                    reflection.uniform_blocks.push(UniformBlockInfo {
+                        name: block_name.clone(),
+                        size,
+                        binding: None,
+                    });
+                }
+                (AddressSpace::Handle, Some(Declaration::Sampler { name, kind })) => {
+                    reflection.uniforms.push(UniformInfo {
+                        name:       name.clone(),
+                        kind:       *kind,
+                        array_size: 1,
+                        block:      None,
+                        offset:     None,
+                    })
+                }
+                _ => {}
+            }
+        }
+
+        if stage == ShaderStage::Vertex {
+            for entry_point in &module.entry_points {
+                for argument in &entry_point.function.arguments {
+                    let (Some(name), Some(Binding::Location { .. })) = (&argument.name, &argument.binding) else {
+                        continue;
+                    };
+
+                    if let Some(kind) = glsl_type(&module.types[argument.ty].inner) {
+                        reflection.attributes.push(AttributeInfo {
+                            name: name.clone(),
+                            kind,
+                            location: None,
+                        });
+                    }
+                }
+            }
+        }
+
+        reflection
+    }
+}
+
+/// Lists `ty` as the uniforms WebGL reports: one per member of structs and one per element of arrays of structs.
+fn flatten_uniform(module: &Module, ty: &Type, name: String, block: Option<&String>, offset: u32, uniforms: &mut Vec<UniformInfo>) {
+    let join = |member: &str| {
+        match name.is_empty() {
+            true => String::from(member),
+            false => format!("{name}.{member}"),
+        }
+    };
+
+    match &ty.inner {
+        TypeInner::Struct { members, .. } => {
+            for member in members {
+                let member_name = join(member.name.as_deref().unwrap_or_default());
+                flatten_uniform(
+                    module,
+                    &module.types[member.ty],
+                    member_name,
+                    block,
+                    offset + member.offset,
+                    uniforms,
+                );
+            }
+        }
+        TypeInner::Array { base, size, stride } => {
+            let array_size = match size {
+                ArraySize::Constant(size) => size.get(),
+                _ => 0,
+            };
+            let base_type = &module.types[*base];
+
+            if let TypeInner::Struct { .. } = base_type.inner {
+                for index in 0..array_size {
+                    flatten_uniform(
+                        module,
+                        base_type,
+                        format!("{name}[{index}]"),
+                        block,
+                        offset + index * stride,
+                        uniforms,
+                    );
+                }
+            } else if let Some(kind) = glsl_type(&base_type.inner) {
+                uniforms.push(UniformInfo {
+                    name,
+                    kind,
+                    array_size,
+                    block: block.cloned(),
+                    offset: block.map(|_| offset),
+                });
+            }
+        }
+        inner => {
+            if let Some(kind) = glsl_type(inner) {
+                uniforms.push(UniformInfo {
+                    name,
+                    kind,
+                    array_size: 1,
+                    block: block.cloned(),
+                    offset: block.map(|_| offset),
+                });
+            }
+        }
+    }
+}
+
+fn glsl_type(inner: &TypeInner) -> Option<GlslType> {
+    let size = |size: VectorSize| size as u8;
+
+    let kind = match *inner {
+        TypeInner::Scalar(scalar) => {
+            match scalar.kind {
+                ScalarKind::Float => GlslType::Float,
+                ScalarKind::Sint => GlslType::Int,
+                ScalarKind::Uint => GlslType::UnsignedInt,
+                ScalarKind::Bool => GlslType::Bool,
+                _ => return None,
+            }
+        }
+        TypeInner::Vector { size: vector_size, scalar } => {
+            match (scalar.kind, size(vector_size)) {
+                (ScalarKind::Float, 2) => GlslType::Vec2,
+                (ScalarKind::Float, 3) => GlslType::Vec3,
+                (ScalarKind::Float, 4) => GlslType::Vec4,
+                (ScalarKind::Sint, 2) => GlslType::IntVec2,
+                (ScalarKind::Sint, 3) => GlslType::IntVec3,
+                (ScalarKind::Sint, 4) => GlslType::IntVec4,
+                (ScalarKind::Uint, 2) => GlslType::UnsignedIntVec2,
+                (ScalarKind::Uint, 3) => GlslType::UnsignedIntVec3,
+                (ScalarKind::Uint, 4) => GlslType::UnsignedIntVec4,
+                (ScalarKind::Bool, 2) => GlslType::BoolVec2,
+                (ScalarKind::Bool, 3) => GlslType::BoolVec3,
+                (ScalarKind::Bool, 4) => GlslType::BoolVec4,
+                _ => return None,
+            }
+        }
+        TypeInner::Matrix { columns, rows, .. } => {
+            match (size(columns), size(rows)) {
+                (2, 2) => GlslType::Mat2,
+                (3, 3) => GlslType::Mat3,
+                (4, 4) => GlslType::Mat4,
+                (2, 3) => GlslType::Mat2x3,
+                (2, 4) => GlslType::Mat2x4,
+                (3, 2) => GlslType::Mat3x2,
+                (3, 4) => GlslType::Mat3x4,
+                (4, 2) => GlslType::Mat4x2,
+                (4, 3) => GlslType::Mat4x3,
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+
+    Some(kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{camera::CameraBlock, shader::SourceLocation, std140::Std140, uniforms::Uniform};
+
+    const VERTEX_SHADER: &str = r#"#version 300 es
+#include "camera"
+#include "skinning"
+
+in vec3 position;
+in vec2 uv;
+in mat4 instance_matrix;
+
+out vec2 vertex_uv;
+flat out highp uint instance;
+
+void main() {
+    vertex_uv = uv;
+    instance = uint(gl_InstanceID);
+    gl_Position = project((instance_matrix * skinning_matrix() * vec4(position, 1.0)).xyz);
+}
+"#;
+
+    const FRAGMENT_SHADER: &str = r#"#version 300 es
+precision highp float;
+
+struct Light {
+    vec3 color;
+    float intensity;
+};
+
+uniform Light lights[2];
+uniform sampler2D diffuse;
+uniform highp sampler2DShadow shadow_map;
+
+layout(std140) uniform Palette {
+    vec4 colors[3];
+    float roughness;
+};
+
+in vec2 vertex_uv;
+flat in highp uint instance;
+out vec4 fragment_color;
+
+void main() {
+    vec3 light = lights[0].color * lights[0].intensity + lights[1].color * lights[1].intensity;
+    float shadow = texture(shadow_map, vec3(vertex_uv, 0.5));
+    fragment_color = texture(diffuse, vertex_uv) * colors[instance % 3u] * vec4(light * shadow, roughness);
+}
+"#;
+
+    fn uniform(name: &str, kind: GlslType, array_size: u32) -> UniformInfo {
+        UniformInfo {
+            name: String::from(name),
+            kind,
+            array_size,
+            block: None,
+            offset: None,
+        }
+    }
+
+    #[test]
+    fn reflects_uniforms_blocks_samplers_and_attributes() {
+        let reflection = validate_material(&Material::new(VERTEX_SHADER, FRAGMENT_SHADER)).unwrap();
+
+        let attributes: Vec<(&str, GlslType)> = reflection
+            .attributes
+            .iter()
+            .map(|attribute| (attribute.name.as_str(), attribute.kind))
+            .collect();
+        assert_eq!(
+            attributes,
+            [
+                ("joints", GlslType::Vec4),
+                ("weights", GlslType::Vec4),
+                ("position", GlslType::Vec3),
+                ("uv", GlslType::Vec2),
+                ("instance_matrix", GlslType::Mat4),
+            ]
+        );
+
+        assert_eq!(reflection.get_uniform("transform"), Some(&uniform("transform", GlslType::Mat4, 1)));
+        assert_eq!(
+            reflection.get_uniform("joint_matrices"),
+            Some(&uniform("joint_matrices", GlslType::Mat4, 64))
+        );
+        assert_eq!(
+            reflection.get_uniform("lights[1].intensity"),
+            Some(&uniform("lights[1].intensity", GlslType::Float, 1))
+        );
+        assert_eq!(
+            reflection.samplers().collect::<Vec<_>>(),
+            [
+                &uniform("diffuse", GlslType::Sampler2D, 1),
+                &uniform("shadow_map", GlslType::Sampler2DShadow, 1)
+            ]
+        );
+
+        assert_eq!(
+            reflection.uniform_blocks,
+            [
+                UniformBlockInfo {
+                    name:    String::from("Camera"),
+                    size:    CameraBlock::SIZE as u32,
+                    binding: None,
+                },
+                UniformBlockInfo {
+                    name:    String::from("Palette"),
+                    size:    64,
+                    binding: None,
+                }
+            ]
+        );
+        let members: Vec<(&str, u32, Option<u32>)> = reflection
+            .get_uniform_block_members("Palette")
+            .into_iter()
+            .map(|member| (member.name.as_str(), member.array_size, member.offset))
+            .collect();
+        assert_eq!(members, [("colors", 3, Some(0)), ("roughness", 1, Some(48))]);
+
+        // The block of the `camera` chunk matches the layout the renderer uploads
+        let fields = CameraBlock::STD140_FIELDS;
+        let offsets: Vec<Option<u32>> = reflection
+            .get_uniform_block_members("Camera")
+            .into_iter()
+            .map(|member| member.offset)
+            .collect();
+        assert_eq!(
+            offsets,
+            [
+                fields.view_matrix.offset(),
+                fields.projection_matrix.offset(),
+                fields.view_projection_matrix.offset(),
+                fields.position.offset(),
+                fields.time.offset(),
+                fields.viewport.offset(),
+            ]
+            .map(|offset| Some(offset as u32))
+        );
+    }
+
+    #[test]
+    fn reports_errors_on_the_source_as_written() {
+        let fragment_shader = "out vec4 color;\nvoid main() {\n    color = vec4(positon, 1.0);\n}\n";
+        let error = validate_material(&Material::new(VERTEX_SHADER, fragment_shader)).unwrap_err();
+
+        let MaterialError::ShaderCompilationFailed(error) = error else {
+            panic!("Expected a compilation error, got {error}");
+        };
+        assert_eq!(error.stage, ShaderStage::Fragment);
+        assert_eq!(error.diagnostics[0].location, Some(SourceLocation { chunk: None, line: 3 }));
+        assert_eq!(error.diagnostics[0].message, "Unknown variable: positon");
+        assert!(error.diagnostics[0].snippet.contains("\n  |                  ^\n"));
+
+        // Errors found by the validator after parsing
+        let fragment_shader =
+            "out vec4 color;\nuniform sampler2D diffuse;\nvoid main() {\n    float x = vec3(1.0);\n    color = vec4(x);\n}\n";
+        let error = validate_material(&Material::new(VERTEX_SHADER, fragment_shader)).unwrap_err();
+        assert!(error.to_string().contains("error at line 4: "), "{error}");
+
+        // Errors in chunks point at the chunk
+        let vertex_shader = "#include \"broken\"\nvoid main() {}\n";
+        crate::shader::chunks::register_shader_chunk("broken", "vec4 broken() {\n    return vec3(1.0);\n}\n");
+        let error = validate_material(&Material::new(vertex_shader, fragment_shader)).unwrap_err();
+        assert!(error.to_string().contains("error at `broken` line 2: "), "{error}");
+    }
+
+    #[test]
+    fn checks_geometry_and_uniforms() {
+        let mut material = Material::new(VERTEX_SHADER, FRAGMENT_SHADER);
+        material.set_uniform("transform", Uniform::Mat4([0.0; 16]));
+        material.set_uniform("roughness", Uniform::Float(0.5));
+        material.set_uniform("tranform", Uniform::Mat4([0.0; 16]));
+
+        let reflection = validate_material(&material).unwrap();
+
+        let errors: Vec<String> = reflection.check_uniforms(&material).iter().map(ToString::to_string).collect();
+        assert_eq!(
+            errors,
+            [
+                "Uniform `roughness` is not used by the program",
+                "Uniform `tranform` is not used by the program"
+            ]
+        );
+
+        let errors: Vec<String> = reflection
+            .check_geometry(&Geometry::quad())
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                "Attribute `joints` is not provided by the geometry",
+                "Attribute `weights` is not provided by the geometry",
+                "Attribute `instance_matrix` is not provided by the geometry",
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Attribute `uv` is not provided by the geometry")]
+    fn asserts_valid_materials() {
+        let vertex_shader = "in vec3 position;\nin vec2 uv;\nout vec2 vertex_uv;\nvoid main() {\n    vertex_uv = uv;\n    gl_Position = vec4(position, 1.0);\n}\n";
+        let fragment_shader = "in vec2 vertex_uv;\nout vec4 color;\nvoid main() {\n    color = vec4(vertex_uv, 0.0, 1.0);\n}\n";
+        let material = Material::new(vertex_shader, fragment_shader);
+
+        let mut geometry = Geometry::quad();
+        assert_valid_material(&material, &geometry);
+
+        geometry.vertex_buffers.retain(|vertex_buffer| vertex_buffer.layout.name != "uv");
+        assert_valid_material(&material, &geometry);
+    }
+}
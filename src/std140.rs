@@ -0,0 +1,346 @@
+use std::{fmt, marker::PhantomData};
+
+use glam::{IVec2, IVec3, IVec4, Mat2, Mat3, Mat4, UVec2, UVec3, UVec4, Vec2, Vec3, Vec4};
+pub use suricato_derive::Std140;
+
+use crate::{shader::reflection::GlslType, utils::to_bytes};
+
+/// Value laid out with the std140 rules of `layout(std140)` uniform blocks.
+///
+/// Implemented for scalars, glam vectors and matrices, arrays of those and structs deriving it.
+/// Rust arrays are GLSL arrays, with every element starting on 16 bytes: use glam types for vectors.
+pub trait Std140 {
+    /// Offsets of the value are a multiple of it.
+    const ALIGNMENT: usize;
+    /// Size in bytes, for structs padded to their alignment.
+    const SIZE: usize;
+
+    /// Writes the value at the start of `bytes`, leaving the padding as it is.
+    fn write_std140(&self, bytes: &mut [u8]);
+
+    fn to_std140_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0; Self::SIZE];
+        self.write_std140(&mut bytes);
+        bytes
+    }
+}
+
+pub const fn align_to(offset: usize, alignment: usize) -> usize {
+    offset.div_ceil(alignment) * alignment
+}
+
+/// Distance between the elements of an array of values of `size` bytes.
+pub const fn array_stride(size: usize) -> usize {
+    align_to(size, 16)
+}
+
+/// Alignment of a struct with members of `alignments`, at least the one of a `vec4`.
+pub const fn struct_alignment(alignments: &[usize]) -> usize {
+    let mut alignment = 16;
+    let mut index = 0;
+
+    while index < alignments.len() {
+        if alignments[index] > alignment {
+            alignment = alignments[index];
+        }
+        index += 1;
+    }
+
+    align_to(alignment, 16)
+}
+
+macro_rules! std140_values {
+    ($($type:ty => $size:literal, $alignment:literal),* $(,)?) => {
+        $(
+            impl Std140 for $type {
+                const ALIGNMENT: usize = $alignment;
+                const SIZE: usize = $size;
+
+                fn write_std140(&self, bytes: &mut [u8]) {
+                    bytes[..$size].copy_from_slice(&to_bytes(std::slice::from_ref(self))[..$size]);
+                }
+            }
+        )*
+    };
+}
+
+std140_values! {
+    f32 => 4, 4,
+    i32 => 4, 4,
+    u32 => 4, 4,
+    Vec2 => 8, 8,
+    Vec3 => 12, 16,
+    Vec4 => 16, 16,
+    IVec2 => 8, 8,
+    IVec3 => 12, 16,
+    IVec4 => 16, 16,
+    UVec2 => 8, 8,
+    UVec3 => 12, 16,
+    UVec4 => 16, 16,
+}
+
+/// Booleans take 4 bytes in blocks, like `uint`.
+impl Std140 for bool {
+    const ALIGNMENT: usize = 4;
+    const SIZE: usize = 4;
+
+    fn write_std140(&self, bytes: &mut [u8]) {
+        u32::from(*self).write_std140(bytes);
+    }
+}
+
+// Matrices are arrays of their columns
+macro_rules! std140_matrices {
+    ($($type:ty => $columns:literal),* $(,)?) => {
+        $(
+            impl Std140 for $type {
+                const ALIGNMENT: usize = 16;
+                const SIZE: usize = $columns * 16;
+
+                fn write_std140(&self, bytes: &mut [u8]) {
+                    for column in 0..$columns {
+                        self.col(column).write_std140(&mut bytes[column * 16..]);
+                    }
+                }
+            }
+        )*
+    };
+}
+
+std140_matrices! {
+    Mat2 => 2,
+    Mat3 => 3,
+    Mat4 => 4,
+}
+
+impl<T: Std140, const N: usize> Std140 for [T; N] {
+    const ALIGNMENT: usize = align_to(T::ALIGNMENT, 16);
+    const SIZE: usize = array_stride(T::SIZE) * N;
+
+    fn write_std140(&self, bytes: &mut [u8]) {
+        for (index, element) in self.iter().enumerate() {
+            element.write_std140(&mut bytes[index * array_stride(T::SIZE)..]);
+        }
+    }
+}
+
+/// Offset of a field of type `T` in a std140 layout, found in the `STD140_FIELDS` of structs deriving [`Std140`].
+pub struct Std140Field<T> {
+    offset: usize,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Std140> Std140Field<T> {
+    pub const fn at(offset: usize) -> Std140Field<T> {
+        Std140Field {
+            offset,
+            marker: PhantomData,
+        }
+    }
+
+    /// Field following one ending at `end`.
+    pub const fn after(end: usize) -> Std140Field<T> {
+        Std140Field::at(align_to(end, T::ALIGNMENT))
+    }
+
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub const fn end(&self) -> usize {
+        self.offset + T::SIZE
+    }
+
+    /// Field of the struct stored in this field, like `Scene::STD140_FIELDS.light.member(Light::STD140_FIELDS.color)`.
+    pub const fn member<U: Std140>(self, member: Std140Field<U>) -> Std140Field<U> {
+        Std140Field::at(self.offset + member.offset)
+    }
+}
+
+impl<T: Std140, const N: usize> Std140Field<[T; N]> {
+    pub const fn element(self, index: usize) -> Std140Field<T> {
+        assert!(index < N, "Array element out of bounds");
+        Std140Field::at(self.offset + index * array_stride(T::SIZE))
+    }
+}
+
+impl<T> Clone for Std140Field<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Std140Field<T> {}
+
+impl<T> fmt::Debug for Std140Field<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Std140Field").field("offset", &self.offset).finish()
+    }
+}
+
+/// Places the members of a block one after the other from their GLSL types, for blocks only known at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Std140Layout {
+    end:       usize,
+    alignment: usize,
+}
+
+impl Std140Layout {
+    pub fn new() -> Std140Layout {
+        Std140Layout {
+            end:       0,
+            alignment: 16,
+        }
+    }
+
+    /// Offset of the next member, `None` for samplers which can not be in blocks.
+    pub fn push(&mut self, kind: GlslType, array_size: Option<usize>) -> Option<usize> {
+        let (size, alignment) = match kind {
+            GlslType::Float | GlslType::Int | GlslType::UnsignedInt | GlslType::Bool => (4, 4),
+            GlslType::Vec2 | GlslType::IntVec2 | GlslType::UnsignedIntVec2 | GlslType::BoolVec2 => (8, 8),
+            GlslType::Vec3 | GlslType::IntVec3 | GlslType::UnsignedIntVec3 | GlslType::BoolVec3 => (12, 16),
+            GlslType::Vec4 | GlslType::IntVec4 | GlslType::UnsignedIntVec4 | GlslType::BoolVec4 => (16, 16),
+            // Columns are aligned like a vec4 whatever their size
+            GlslType::Mat2 | GlslType::Mat2x3 | GlslType::Mat2x4 => (32, 16),
+            GlslType::Mat3 | GlslType::Mat3x2 | GlslType::Mat3x4 => (48, 16),
+            GlslType::Mat4 | GlslType::Mat4x2 | GlslType::Mat4x3 => (64, 16),
+            _ => return None,
+        };
+
+        Some(self.push_member(size, alignment, array_size))
+    }
+
+    /// Offset of the next member, a struct with the members of `layout`.
+    pub fn push_struct(&mut self, layout: &Std140Layout, array_size: Option<usize>) -> usize {
+        self.push_member(layout.get_size(), layout.alignment, array_size)
+    }
+
+    fn push_member(&mut self, size: usize, alignment: usize, array_size: Option<usize>) -> usize {
+        let (size, alignment) = match array_size {
+            Some(array_size) => (array_stride(size) * array_size, align_to(alignment, 16)),
+            None => (size, alignment),
+        };
+
+        let offset = align_to(self.end, alignment);
+        self.end = offset + size;
+        self.alignment = self.alignment.max(alignment);
+        offset
+    }
+
+    /// Size with the padding at the end, as for a struct or the `UNIFORM_BLOCK_DATA_SIZE` of a block.
+    pub fn get_size(&self) -> usize {
+        align_to(self.end, self.alignment)
+    }
+}
+
+impl Default for Std140Layout {
+    fn default() -> Std140Layout {
+        Std140Layout::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Std140)]
+    struct Light {
+        color:     Vec3,
+        intensity: f32,
+    }
+
+    #[derive(Std140)]
+    struct Scene {
+        ambient:    Vec3,
+        normal:     Mat3,
+        exposure:   f32,
+        tint:       Vec2,
+        lights:     [Light; 2],
+        weights:    [f32; 3],
+        enabled:    bool,
+        view:       Mat4,
+        last_index: u32,
+    }
+
+    #[test]
+    fn lays_out_derived_structs_like_glsl() {
+        let fields = Scene::STD140_FIELDS;
+        let offsets = [
+            fields.ambient.offset(),
+            fields.normal.offset(),
+            fields.exposure.offset(),
+            fields.tint.offset(),
+            fields.lights.offset(),
+            fields.weights.offset(),
+            fields.enabled.offset(),
+            fields.view.offset(),
+            fields.last_index.offset(),
+        ];
+
+        assert_eq!(offsets, [0, 16, 64, 72, 80, 112, 160, 176, 240]);
+        assert_eq!(Scene::SIZE, 256);
+        assert_eq!(Light::SIZE, 16);
+
+        let mut layout = Std140Layout::new();
+        let mut light = Std140Layout::new();
+        light.push(GlslType::Vec3, None);
+        light.push(GlslType::Float, None);
+
+        let runtime_offsets = [
+            layout.push(GlslType::Vec3, None),
+            layout.push(GlslType::Mat3, None),
+            layout.push(GlslType::Float, None),
+            layout.push(GlslType::Vec2, None),
+            Some(layout.push_struct(&light, Some(2))),
+            layout.push(GlslType::Float, Some(3)),
+            layout.push(GlslType::Bool, None),
+            layout.push(GlslType::Mat4, None),
+            layout.push(GlslType::UnsignedInt, None),
+        ];
+
+        assert_eq!(runtime_offsets, offsets.map(Some));
+        assert_eq!(layout.get_size(), Scene::SIZE);
+        assert_eq!(layout.push(GlslType::Sampler2D, None), None);
+    }
+
+    #[test]
+    fn writes_padded_bytes() {
+        let scene = Scene {
+            ambient:    Vec3::new(1.0, 2.0, 3.0),
+            normal:     Mat3::from_cols(Vec3::X, Vec3::Y, Vec3::Z),
+            exposure:   0.5,
+            tint:       Vec2::ONE,
+            lights:     [
+                Light {
+                    color:     Vec3::ONE,
+                    intensity: 2.0,
+                },
+                Light {
+                    color:     Vec3::ZERO,
+                    intensity: 4.0,
+                },
+            ],
+            weights:    [1.0, 2.0, 3.0],
+            enabled:    true,
+            view:       Mat4::IDENTITY,
+            last_index: 7,
+        };
+
+        let bytes = scene.to_std140_bytes();
+        let float = |offset: usize| f32::from_ne_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        let uint = |offset: usize| u32::from_ne_bytes(bytes[offset..offset + 4].try_into().unwrap());
+
+        assert_eq!(bytes.len(), 256);
+        assert_eq!([float(0), float(4), float(8)], [1.0, 2.0, 3.0]);
+        // Columns of the mat3 are padded to 16 bytes
+        assert_eq!([float(16), float(32 + 4), float(48 + 8), float(28)], [1.0, 1.0, 1.0, 0.0]);
+        assert_eq!([float(64), float(72), float(76)], [0.5, 1.0, 1.0]);
+        assert_eq!([float(80), float(92), float(96 + 12)], [1.0, 2.0, 4.0]);
+        assert_eq!([float(112), float(128), float(144)], [1.0, 2.0, 3.0]);
+        assert_eq!([uint(160), uint(240)], [1, 7]);
+        assert_eq!(float(176 + 60), 1.0);
+
+        let light = Scene::STD140_FIELDS.lights.element(1).member(Light::STD140_FIELDS.intensity);
+        assert_eq!(light.offset(), 108);
+    }
+}
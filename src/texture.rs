@@ -1,55 +1,62 @@
+use std::{collections::HashMap, error::Error, fmt};
+
+#[cfg(feature = "web")]
 use wasm_bindgen::JsValue;
-use web_sys::{HtmlImageElement, WebGl2RenderingContext, WebGlTexture};
+#[cfg(feature = "web")]
+use web_sys::HtmlImageElement;
 
+use crate::backend::{Context as GL, SamplerHandle, TextureHandle};
+#[cfg(feature = "web")]
 use crate::utils::fetch_image;
 
 #[repr(u32)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum MinificationFilter {
-    Linear               = WebGl2RenderingContext::LINEAR,
-    Nearest              = WebGl2RenderingContext::NEAREST,
-    NearestMipmapNearest = WebGl2RenderingContext::NEAREST_MIPMAP_NEAREST,
-    LinearMipmapNearest  = WebGl2RenderingContext::LINEAR_MIPMAP_NEAREST,
-    NearestMipmapLinear  = WebGl2RenderingContext::NEAREST_MIPMAP_LINEAR,
-    LinearMipmapLinear   = WebGl2RenderingContext::LINEAR_MIPMAP_LINEAR,
+    Linear               = GL::LINEAR,
+    Nearest              = GL::NEAREST,
+    NearestMipmapNearest = GL::NEAREST_MIPMAP_NEAREST,
+    LinearMipmapNearest  = GL::LINEAR_MIPMAP_NEAREST,
+    NearestMipmapLinear  = GL::NEAREST_MIPMAP_LINEAR,
+    LinearMipmapLinear   = GL::LINEAR_MIPMAP_LINEAR,
 }
 
 #[repr(u32)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum MagnificationFilter {
-    Linear  = WebGl2RenderingContext::LINEAR,
-    Nearest = WebGl2RenderingContext::NEAREST,
+    Linear  = GL::LINEAR,
+    Nearest = GL::NEAREST,
 }
 
 #[repr(u32)]
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Wrap {
-    Repeat         = WebGl2RenderingContext::REPEAT,
-    ClampToEdge    = WebGl2RenderingContext::CLAMP_TO_EDGE,
-    MirroredRepeat = WebGl2RenderingContext::MIRRORED_REPEAT,
+    Repeat         = GL::REPEAT,
+    ClampToEdge    = GL::CLAMP_TO_EDGE,
+    MirroredRepeat = GL::MIRRORED_REPEAT,
 }
 
 #[repr(u32)]
 #[derive(Copy, Clone, Debug)]
 pub enum TextureFormat {
-    RGB            = WebGl2RenderingContext::RGB,
-    RGBA           = WebGl2RenderingContext::RGBA,
-    LuminanceAlpha = WebGl2RenderingContext::LUMINANCE_ALPHA,
-    Luminance      = WebGl2RenderingContext::LUMINANCE,
-    Alpha          = WebGl2RenderingContext::ALPHA,
+    RGB            = GL::RGB,
+    RGBA           = GL::RGBA,
+    LuminanceAlpha = GL::LUMINANCE_ALPHA,
+    Luminance      = GL::LUMINANCE,
+    Alpha          = GL::ALPHA,
 }
 
 #[repr(u32)]
 #[derive(Copy, Clone, Debug)]
 pub enum TextureDataType {
-    UnsignedByte      = WebGl2RenderingContext::UNSIGNED_BYTE,
-    UnsignedShort565  = WebGl2RenderingContext::UNSIGNED_SHORT_5_6_5,
-    UnsignedShort4444 = WebGl2RenderingContext::UNSIGNED_SHORT_4_4_4_4,
-    UnsignedShort5551 = WebGl2RenderingContext::UNSIGNED_SHORT_5_5_5_1,
+    UnsignedByte      = GL::UNSIGNED_BYTE,
+    UnsignedShort565  = GL::UNSIGNED_SHORT_5_6_5,
+    UnsignedShort4444 = GL::UNSIGNED_SHORT_4_4_4_4,
+    UnsignedShort5551 = GL::UNSIGNED_SHORT_5_5_5_1,
 }
 
 #[derive(Clone, Debug)]
 pub enum TextureData {
+    #[cfg(feature = "web")]
     HtmlImageElement(HtmlImageElement),
     ImagePixelData(ImagePixelData),
 }
@@ -65,8 +72,21 @@ pub struct ImagePixelData {
 pub enum TextureError {
     CreationFailed,
     DataUploadFailed,
+    SamplerCreationFailed,
 }
 
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TextureError::CreationFailed => write!(f, "Failed to create the texture"),
+            TextureError::DataUploadFailed => write!(f, "Failed to upload the texture data"),
+            TextureError::SamplerCreationFailed => write!(f, "Failed to create the sampler"),
+        }
+    }
+}
+
+impl Error for TextureError {}
+
 /// Extracted from:
 /// https://developer.mozilla.org/en-US/docs/Web/API/WebGLRenderingContext/texParameter#pname
 #[derive(Clone, Debug)]
@@ -79,7 +99,7 @@ pub struct Texture {
     pub format:               TextureFormat,
     pub internal_format:      TextureFormat,
     pub texture_data:         TextureData,
-    pub webgl_texture:        Option<WebGlTexture>,
+    pub webgl_texture:        Option<TextureHandle>,
 }
 
 impl Texture {
@@ -97,26 +117,28 @@ impl Texture {
         }
     }
 
-    pub fn get_webgl_texture(&mut self, gl: &WebGl2RenderingContext) -> Result<&WebGlTexture, TextureError> {
-        if self.webgl_texture.is_none() {
-            let webgl_texture = self.create_webgl_texture(gl)?;
-            self.webgl_texture = Some(webgl_texture);
+    pub fn get_webgl_texture(&mut self, gl: &GL) -> Result<TextureHandle, TextureError> {
+        if let Some(webgl_texture) = self.webgl_texture {
+            return Ok(webgl_texture);
         }
 
-        Ok(self.webgl_texture.as_ref().unwrap())
+        let webgl_texture = self.create_webgl_texture(gl)?;
+        self.webgl_texture = Some(webgl_texture);
+        Ok(webgl_texture)
     }
 
-    fn create_webgl_texture(&self, gl: &WebGl2RenderingContext) -> Result<WebGlTexture, TextureError> {
+    fn create_webgl_texture(&self, gl: &GL) -> Result<TextureHandle, TextureError> {
         let Some(webgl_texture) = gl.create_texture() else {
             return Err(TextureError::CreationFailed);
         };
 
-        gl.bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&webgl_texture));
+        gl.bind_texture(GL::TEXTURE_2D, Some(webgl_texture));
 
         match &self.texture_data {
+            #[cfg(feature = "web")]
             TextureData::HtmlImageElement(source) => {
-                gl.tex_image_2d_with_u32_and_u32_and_html_image_element(
-                    WebGl2RenderingContext::TEXTURE_2D,
+                gl.tex_image_2d_with_html_image_element(
+                    GL::TEXTURE_2D,
                     0,
                     self.internal_format as i32,
                     self.format as u32,
@@ -126,13 +148,12 @@ impl Texture {
                 .map_err(|_| TextureError::DataUploadFailed)?;
             }
             TextureData::ImagePixelData(data) => {
-                gl.tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
-                    WebGl2RenderingContext::TEXTURE_2D,
+                gl.tex_image_2d(
+                    GL::TEXTURE_2D,
                     0,
                     self.internal_format as i32,
                     data.width as i32,
                     data.height as i32,
-                    0,
                     self.format as u32,
                     self.data_type as u32,
                     Some(&data.bytes),
@@ -141,23 +162,157 @@ impl Texture {
             }
         }
 
-        gl.tex_parameteri(
-            WebGl2RenderingContext::TEXTURE_2D,
-            WebGl2RenderingContext::TEXTURE_MIN_FILTER,
-            self.minification_filter as i32,
-        );
-
-        gl.tex_parameteri(
-            WebGl2RenderingContext::TEXTURE_2D,
-            WebGl2RenderingContext::TEXTURE_MAG_FILTER,
-            self.magnification_filter as i32,
-        );
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MIN_FILTER, self.minification_filter as i32);
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_MAG_FILTER, self.magnification_filter as i32);
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_S, self.wrap_horizontal as i32);
+        gl.tex_parameteri(GL::TEXTURE_2D, GL::TEXTURE_WRAP_T, self.wrap_vertical as i32);
 
         Ok(webgl_texture)
     }
 
+    #[cfg(feature = "web")]
     pub async fn from_image_url(url: &str) -> Result<Texture, JsValue> {
         let html_image = fetch_image(url).await?;
         Ok(Texture::new(TextureData::HtmlImageElement(html_image)))
     }
 }
+
+/// Filtering and wrapping of a texture, used in place of the parameters of the [`Texture`] it is paired with
+/// in [`Uniform::SampledTexture`](crate::uniforms::Uniform::SampledTexture).
+///
+/// Sampler objects are created once for every combination of parameters and shared between textures.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Sampler {
+    pub minification_filter:  MinificationFilter,
+    pub magnification_filter: MagnificationFilter,
+    pub wrap_horizontal:      Wrap,
+    pub wrap_vertical:        Wrap,
+}
+
+impl Sampler {
+    pub fn new(minification_filter: MinificationFilter, magnification_filter: MagnificationFilter) -> Sampler {
+        Sampler {
+            minification_filter,
+            magnification_filter,
+            ..Sampler::default()
+        }
+    }
+
+    fn create_webgl_sampler(&self, gl: &GL) -> Result<SamplerHandle, TextureError> {
+        let Some(webgl_sampler) = gl.create_sampler() else {
+            return Err(TextureError::SamplerCreationFailed);
+        };
+
+        gl.sampler_parameteri(webgl_sampler, GL::TEXTURE_MIN_FILTER, self.minification_filter as i32);
+        gl.sampler_parameteri(webgl_sampler, GL::TEXTURE_MAG_FILTER, self.magnification_filter as i32);
+        gl.sampler_parameteri(webgl_sampler, GL::TEXTURE_WRAP_S, self.wrap_horizontal as i32);
+        gl.sampler_parameteri(webgl_sampler, GL::TEXTURE_WRAP_T, self.wrap_vertical as i32);
+
+        Ok(webgl_sampler)
+    }
+}
+
+/// Same parameters as [`Texture::new`].
+impl Default for Sampler {
+    fn default() -> Sampler {
+        Sampler {
+            minification_filter:  MinificationFilter::Nearest,
+            magnification_filter: MagnificationFilter::Nearest,
+            wrap_horizontal:      Wrap::Repeat,
+            wrap_vertical:        Wrap::Repeat,
+        }
+    }
+}
+
+/// Parameters of the texture itself, to start a sampler that only changes some of them.
+impl From<&Texture> for Sampler {
+    fn from(texture: &Texture) -> Sampler {
+        Sampler {
+            minification_filter:  texture.minification_filter,
+            magnification_filter: texture.magnification_filter,
+            wrap_horizontal:      texture.wrap_horizontal,
+            wrap_vertical:        texture.wrap_vertical,
+        }
+    }
+}
+
+/// Mirrors the texture bound to each texture unit so that binding the same texture again is skipped.
+///
+/// Textures bound directly through `gl` are not seen, [`TextureBindingCache::invalidate`] must be called after.
+#[derive(Debug, Default)]
+pub struct TextureBindingCache {
+    active_unit:    Option<u32>,
+    /// Texture bound to every `(unit, target)` pair.
+    bound:          HashMap<(u32, u32), TextureHandle>,
+    /// Sampler bound to every unit, `None` when the texture uses its own parameters.
+    bound_samplers: HashMap<u32, Option<SamplerHandle>>,
+    /// Kept when invalidating, sampler objects are never deleted.
+    samplers:       HashMap<Sampler, SamplerHandle>,
+}
+
+impl TextureBindingCache {
+    /// Binds `texture` to `target` on `unit`, uploading it first if needed.
+    ///
+    /// Without a `sampler` the texture is sampled with its own parameters.
+    pub fn bind(&mut self, gl: &GL, unit: u32, target: u32, texture: &mut Texture, sampler: Option<Sampler>) -> Result<(), TextureError> {
+        let webgl_texture = self.upload(gl, texture)?;
+
+        if self.bound.get(&(unit, target)) != Some(&webgl_texture) {
+            if self.active_unit != Some(unit) {
+                gl.active_texture(GL::TEXTURE0 + unit);
+                self.active_unit = Some(unit);
+            }
+
+            gl.bind_texture(target, Some(webgl_texture));
+            self.bound.insert((unit, target), webgl_texture);
+        }
+
+        let webgl_sampler = match sampler {
+            Some(sampler) => Some(self.get_webgl_sampler(gl, sampler)?),
+            None => None,
+        };
+
+        if self.bound_samplers.get(&unit) != Some(&webgl_sampler) {
+            gl.bind_sampler(unit, webgl_sampler);
+            self.bound_samplers.insert(unit, webgl_sampler);
+        }
+
+        Ok(())
+    }
+
+    /// Sampler object with the parameters of `sampler`, created the first time they are used.
+    pub fn get_webgl_sampler(&mut self, gl: &GL, sampler: Sampler) -> Result<SamplerHandle, TextureError> {
+        if let Some(webgl_sampler) = self.samplers.get(&sampler) {
+            return Ok(*webgl_sampler);
+        }
+
+        let webgl_sampler = sampler.create_webgl_sampler(gl)?;
+        self.samplers.insert(sampler, webgl_sampler);
+        Ok(webgl_sampler)
+    }
+
+    /// Uploads `texture` if it has not been yet, which binds it to the active unit.
+    pub fn upload(&mut self, gl: &GL, texture: &mut Texture) -> Result<TextureHandle, TextureError> {
+        if let Some(webgl_texture) = texture.webgl_texture {
+            return Ok(webgl_texture);
+        }
+
+        let webgl_texture = texture.get_webgl_texture(gl)?;
+
+        match self.active_unit {
+            Some(active_unit) => {
+                self.bound.insert((active_unit, GL::TEXTURE_2D), webgl_texture);
+            }
+            None => self.bound.clear(),
+        }
+
+        Ok(webgl_texture)
+    }
+
+    /// Forgets the cached bindings, forcing the next `bind` of every texture and sampler.
+    pub fn invalidate(&mut self) {
+        self.active_unit = None;
+        self.bound.clear();
+        self.bound_samplers.clear();
+    }
+}
@@ -1,40 +1,156 @@
-use web_sys::WebGl2RenderingContext;
+use std::{error::Error, fmt};
 
 use crate::{
+    backend::Context as GL,
     buffer_gpu::{BufferGPU, BufferKind, BufferUsage},
     renderer::Renderer,
+    std140::{Std140, Std140Field},
 };
 
+#[derive(Debug)]
+pub enum UniformBufferError {
+    NoBindingPointLeft { available: u32 },
+}
+
+impl fmt::Display for UniformBufferError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UniformBufferError::NoBindingPointLeft { available } => {
+                write!(f, "All {available} uniform buffer binding points are already used")
+            }
+        }
+    }
+}
+
+impl Error for UniformBufferError {}
+
 pub struct UniformBufferObject {
-    pub gl:            WebGl2RenderingContext,
+    pub gl:            GL,
     pub binding_point: Option<u32>,
     pub buffer:        BufferGPU,
 }
 
 impl UniformBufferObject {
     pub fn new(renderer: &Renderer, buffer_cpu: &[u8]) -> UniformBufferObject {
+        UniformBufferObject::with_context(&renderer.gl, buffer_cpu)
+    }
+
+    pub(crate) fn with_context(gl: &GL, buffer_cpu: &[u8]) -> UniformBufferObject {
         let mut buffer = BufferGPU::new(BufferKind::UniformBuffer, BufferUsage::DynamicDraw, buffer_cpu.to_vec());
-        buffer.on_before_render(&renderer.gl);
+        buffer.on_before_render(gl);
 
         UniformBufferObject {
-            gl: renderer.gl.clone(),
+            gl: gl.clone(),
             binding_point: None,
             buffer,
         }
     }
 
+    /// Buffer holding `value` in the std140 layout, see [`UniformBufferObject::set_field`] to change it.
+    pub fn from_std140<T: Std140>(renderer: &Renderer, value: &T) -> UniformBufferObject {
+        UniformBufferObject::new(renderer, &value.to_std140_bytes())
+    }
+
+    /// Binds the buffer to `binding_point`, prefer [`Renderer::register_uniform_buffer`] which picks a free one
+    /// and binds the matching block of every program.
     pub fn set_binding_point(&mut self, binding_point: u32) {
         self.binding_point = Some(binding_point);
 
-        self.gl.bind_buffer_base(
-            WebGl2RenderingContext::UNIFORM_BUFFER,
-            binding_point,
-            self.buffer.get_buffer_gpu(&self.gl),
-        );
+        self.gl
+            .bind_buffer_base(GL::UNIFORM_BUFFER, binding_point, self.buffer.get_buffer_gpu(&self.gl));
     }
 
     pub fn set_bytes(&mut self, byte_offset: usize, data: &[u8]) {
         self.buffer.set_bytes(byte_offset, data);
         self.buffer.on_before_render(&self.gl);
     }
+
+    /// Replaces the whole value, uploading only the bytes that changed.
+    pub fn set_std140<T: Std140>(&mut self, value: &T) {
+        self.set_field(Std140Field::at(0), value);
+    }
+
+    /// Sets one field, taken from the `STD140_FIELDS` of the struct the buffer was created from,
+    /// like `ubo.set_field(Camera::STD140_FIELDS.position, &position)`. Only the bytes that changed are uploaded.
+    pub fn set_field<T: Std140>(&mut self, field: Std140Field<T>, value: &T) {
+        let current = &self.buffer.data()[field.offset()..field.end()];
+        let mut bytes = current.to_vec();
+        value.write_std140(&mut bytes);
+
+        let changed = |(new, old): (&u8, &u8)| new != old;
+        let Some(first) = bytes.iter().zip(current).position(changed) else {
+            return;
+        };
+        let last = bytes.iter().zip(current).rposition(changed).unwrap_or(first);
+
+        self.set_bytes(field.offset() + first, &bytes[first..=last]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use glam::{Mat3, Vec3};
+
+    use super::*;
+    use crate::backend::mock::MockContext;
+
+    #[derive(Std140)]
+    struct Light {
+        color:     Vec3,
+        intensity: f32,
+    }
+
+    #[derive(Std140)]
+    struct Lighting {
+        normal_matrix: Mat3,
+        lights:        [Light; 2],
+        exposure:      f32,
+    }
+
+    fn lighting() -> Lighting {
+        Lighting {
+            normal_matrix: Mat3::IDENTITY,
+            lights:        [
+                Light {
+                    color:     Vec3::ONE,
+                    intensity: 1.0,
+                },
+                Light {
+                    color:     Vec3::ONE,
+                    intensity: 2.0,
+                },
+            ],
+            exposure:      1.0,
+        }
+    }
+
+    #[test]
+    fn uploads_only_changed_fields() {
+        let mock = Rc::new(MockContext::new(1, 1));
+        let renderer = Renderer::with_context(mock.context());
+
+        let mut ubo = UniformBufferObject::from_std140(&renderer, &lighting());
+        let handle = ubo.buffer.get_buffer_gpu(&renderer.gl).unwrap().0;
+        assert_eq!(ubo.buffer.size(), 96);
+
+        let fields = Lighting::STD140_FIELDS;
+        let intensity = fields.lights.element(1).member(Light::STD140_FIELDS.intensity);
+        ubo.set_field(intensity, &2.0);
+        ubo.set_field(fields.exposure, &1.0);
+        ubo.set_std140(&lighting());
+        assert_eq!(mock.state().call_count("buffer_sub_data"), 0);
+
+        ubo.set_field(intensity, &4.0);
+        ubo.set_field(fields.normal_matrix, &Mat3::from_diagonal(Vec3::new(1.0, 2.0, 1.0)));
+
+        let mut expected = lighting();
+        expected.lights[1].intensity = 4.0;
+        expected.normal_matrix.y_axis.y = 2.0;
+
+        let state = mock.state();
+        assert_eq!(state.call_count("buffer_sub_data"), 2);
+        assert_eq!(state.buffers[&handle].data, expected.to_std140_bytes());
+    }
 }
@@ -1,10 +1,92 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
 use glam::{Mat2, Mat3, Mat4};
 
 use crate::{
-    texture::Texture,
+    shader::reflection::GlslType,
+    texture::{Sampler, Texture},
     transform::{Transform2D, Transform3D},
+    utils::to_bytes,
 };
 
+/// Uniform name interned once, so setting it every frame neither allocates nor hashes the name.
+///
+/// The same name gives the same handle on every material of the thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct UniformHandle(u32);
+
+impl UniformHandle {
+    // Set by the renderer on every mesh
+    pub const TRANSFORM: UniformHandle = UniformHandle(0);
+    pub const PROJECTION_MATRIX: UniformHandle = UniformHandle(1);
+    pub const CAMERA_INVERSE_MATRIX: UniformHandle = UniformHandle(2);
+
+    pub fn new(name: &str) -> UniformHandle {
+        UNIFORM_NAMES.with_borrow_mut(|names| names.intern(name))
+    }
+
+    pub fn get_name(&self) -> Rc<str> {
+        UNIFORM_NAMES.with_borrow(|names| names.names[self.0 as usize].clone())
+    }
+
+    /// Dense index, handles are numbered from zero in the order they are created.
+    pub(crate) fn index(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl From<&str> for UniformHandle {
+    fn from(value: &str) -> UniformHandle {
+        UniformHandle::new(value)
+    }
+}
+
+impl From<&String> for UniformHandle {
+    fn from(value: &String) -> UniformHandle {
+        UniformHandle::new(value)
+    }
+}
+
+struct UniformNames {
+    handles: HashMap<Rc<str>, UniformHandle>,
+    names:   Vec<Rc<str>>,
+}
+
+impl UniformNames {
+    fn intern(&mut self, name: &str) -> UniformHandle {
+        if let Some(handle) = self.handles.get(name) {
+            return *handle;
+        }
+
+        let handle = UniformHandle(self.names.len() as u32);
+        let name: Rc<str> = Rc::from(name);
+        self.names.push(name.clone());
+        self.handles.insert(name, handle);
+
+        handle
+    }
+}
+
+impl Default for UniformNames {
+    fn default() -> Self {
+        let mut names = UniformNames {
+            handles: HashMap::new(),
+            names:   Vec::new(),
+        };
+
+        // In the order of the constants
+        for name in ["transform", "projection_matrix", "camera_inverse_matrix"] {
+            names.intern(name);
+        }
+
+        names
+    }
+}
+
+thread_local! {
+    static UNIFORM_NAMES: RefCell<UniformNames> = RefCell::new(UniformNames::default());
+}
+
 #[derive(Debug, Clone)]
 pub enum Uniform {
     Float(f32),
@@ -27,6 +109,141 @@ pub enum Uniform {
     Mat4([f32; 16]),
 
     Texture(Texture),
+    /// Texture read with the filtering and wrapping of the sampler instead of its own.
+    SampledTexture(Texture, Sampler),
+
+    // Arrays, set from the first element and shorter than the declared array if needed
+    FloatArray(Vec<f32>),
+    Vec2Array(Vec<[f32; 2]>),
+    Vec3Array(Vec<[f32; 3]>),
+    Vec4Array(Vec<[f32; 4]>),
+
+    IntArray(Vec<i32>),
+    IntVec2Array(Vec<[i32; 2]>),
+    IntVec3Array(Vec<[i32; 3]>),
+    IntVec4Array(Vec<[i32; 4]>),
+
+    UnsignedIntArray(Vec<u32>),
+    UnsignedIntVec2Array(Vec<[u32; 2]>),
+    UnsignedIntVec3Array(Vec<[u32; 3]>),
+    UnsignedIntVec4Array(Vec<[u32; 4]>),
+
+    Mat2Array(Vec<[f32; 4]>),
+    Mat3Array(Vec<[f32; 9]>),
+    Mat4Array(Vec<[f32; 16]>),
+
+    TextureArray(Vec<Texture>),
+}
+
+impl Uniform {
+    /// GLSL type the value is written for, of the elements for arrays, textures being `sampler2D`.
+    pub fn glsl_type(&self) -> GlslType {
+        match self {
+            Uniform::Float(_) | Uniform::FloatArray(_) => GlslType::Float,
+            Uniform::Vec2(_) | Uniform::Vec2Array(_) => GlslType::Vec2,
+            Uniform::Vec3(_) | Uniform::Vec3Array(_) => GlslType::Vec3,
+            Uniform::Vec4(_) | Uniform::Vec4Array(_) => GlslType::Vec4,
+
+            Uniform::Int(_) | Uniform::IntArray(_) => GlslType::Int,
+            Uniform::IntVec2(_) | Uniform::IntVec2Array(_) => GlslType::IntVec2,
+            Uniform::IntVec3(_) | Uniform::IntVec3Array(_) => GlslType::IntVec3,
+            Uniform::IntVec4(_) | Uniform::IntVec4Array(_) => GlslType::IntVec4,
+
+            Uniform::UnsignedInt(_) | Uniform::UnsignedIntArray(_) => GlslType::UnsignedInt,
+            Uniform::UnsignedIntVec2(_) | Uniform::UnsignedIntVec2Array(_) => GlslType::UnsignedIntVec2,
+            Uniform::UnsignedIntVec3(_) | Uniform::UnsignedIntVec3Array(_) => GlslType::UnsignedIntVec3,
+            Uniform::UnsignedIntVec4(_) | Uniform::UnsignedIntVec4Array(_) => GlslType::UnsignedIntVec4,
+
+            Uniform::Mat2(_) | Uniform::Mat2Array(_) => GlslType::Mat2,
+            Uniform::Mat3(_) | Uniform::Mat3Array(_) => GlslType::Mat3,
+            Uniform::Mat4(_) | Uniform::Mat4Array(_) => GlslType::Mat4,
+
+            Uniform::Texture(_) | Uniform::SampledTexture(..) | Uniform::TextureArray(_) => GlslType::Sampler2D,
+        }
+    }
+
+    /// Number of elements, `1` for single values.
+    pub fn element_count(&self) -> usize {
+        match self {
+            Uniform::FloatArray(v) => v.len(),
+            Uniform::Vec2Array(v) => v.len(),
+            Uniform::Vec3Array(v) => v.len(),
+            Uniform::Vec4Array(v) => v.len(),
+            Uniform::IntArray(v) => v.len(),
+            Uniform::IntVec2Array(v) => v.len(),
+            Uniform::IntVec3Array(v) => v.len(),
+            Uniform::IntVec4Array(v) => v.len(),
+            Uniform::UnsignedIntArray(v) => v.len(),
+            Uniform::UnsignedIntVec2Array(v) => v.len(),
+            Uniform::UnsignedIntVec3Array(v) => v.len(),
+            Uniform::UnsignedIntVec4Array(v) => v.len(),
+            Uniform::Mat2Array(v) => v.len(),
+            Uniform::Mat3Array(v) => v.len(),
+            Uniform::Mat4Array(v) => v.len(),
+            Uniform::TextureArray(v) => v.len(),
+            _ => 1,
+        }
+    }
+
+    /// Whether both values send the same data, textures are never considered equal.
+    pub fn is_same_value(&self, other: &Uniform) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other) && self.bytes().is_some() && self.bytes() == other.bytes()
+    }
+
+    fn bytes(&self) -> Option<&[u8]> {
+        let bytes = match self {
+            Uniform::Float(v) => to_bytes(std::slice::from_ref(v)),
+            Uniform::Vec2(v) => to_bytes(v),
+            Uniform::Vec3(v) => to_bytes(v),
+            Uniform::Vec4(v) => to_bytes(v),
+            Uniform::Int(v) => to_bytes(std::slice::from_ref(v)),
+            Uniform::IntVec2(v) => to_bytes(v),
+            Uniform::IntVec3(v) => to_bytes(v),
+            Uniform::IntVec4(v) => to_bytes(v),
+            Uniform::UnsignedInt(v) => to_bytes(std::slice::from_ref(v)),
+            Uniform::UnsignedIntVec2(v) => to_bytes(v),
+            Uniform::UnsignedIntVec3(v) => to_bytes(v),
+            Uniform::UnsignedIntVec4(v) => to_bytes(v),
+            Uniform::Mat2(v) => to_bytes(v),
+            Uniform::Mat3(v) => to_bytes(v),
+            Uniform::Mat4(v) => to_bytes(v),
+            Uniform::FloatArray(v) => to_bytes(v),
+            Uniform::Vec2Array(v) => to_bytes(v),
+            Uniform::Vec3Array(v) => to_bytes(v),
+            Uniform::Vec4Array(v) => to_bytes(v),
+            Uniform::IntArray(v) => to_bytes(v),
+            Uniform::IntVec2Array(v) => to_bytes(v),
+            Uniform::IntVec3Array(v) => to_bytes(v),
+            Uniform::IntVec4Array(v) => to_bytes(v),
+            Uniform::UnsignedIntArray(v) => to_bytes(v),
+            Uniform::UnsignedIntVec2Array(v) => to_bytes(v),
+            Uniform::UnsignedIntVec3Array(v) => to_bytes(v),
+            Uniform::UnsignedIntVec4Array(v) => to_bytes(v),
+            Uniform::Mat2Array(v) => to_bytes(v),
+            Uniform::Mat3Array(v) => to_bytes(v),
+            Uniform::Mat4Array(v) => to_bytes(v),
+            Uniform::Texture(_) | Uniform::SampledTexture(..) | Uniform::TextureArray(_) => return None,
+        };
+
+        Some(bytes)
+    }
+
+    /// Textures to bind, one per texture unit starting at the unit of the uniform.
+    pub fn textures_mut(&mut self) -> &mut [Texture] {
+        match self {
+            Uniform::Texture(texture) | Uniform::SampledTexture(texture, _) => std::slice::from_mut(texture),
+            Uniform::TextureArray(textures) => textures,
+            _ => &mut [],
+        }
+    }
+
+    /// Sampler used in place of the parameters of the textures.
+    pub fn get_sampler(&self) -> Option<Sampler> {
+        match self {
+            Uniform::SampledTexture(_, sampler) => Some(*sampler),
+            _ => None,
+        }
+    }
 }
 
 // f32
@@ -145,6 +362,49 @@ impl From<Texture> for Uniform {
     }
 }
 
+impl From<(Texture, Sampler)> for Uniform {
+    fn from((texture, sampler): (Texture, Sampler)) -> Uniform {
+        Uniform::SampledTexture(texture, sampler)
+    }
+}
+
+// arrays
+macro_rules! from_vec {
+    ($($element:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<Vec<$element>> for Uniform {
+                fn from(value: Vec<$element>) -> Uniform {
+                    Uniform::$variant(value)
+                }
+            }
+        )*
+    };
+}
+
+from_vec! {
+    f32 => FloatArray,
+    [f32; 2] => Vec2Array,
+    [f32; 3] => Vec3Array,
+    [f32; 4] => Vec4Array,
+    i32 => IntArray,
+    [i32; 2] => IntVec2Array,
+    [i32; 3] => IntVec3Array,
+    [i32; 4] => IntVec4Array,
+    u32 => UnsignedIntArray,
+    [u32; 2] => UnsignedIntVec2Array,
+    [u32; 3] => UnsignedIntVec3Array,
+    [u32; 4] => UnsignedIntVec4Array,
+    [f32; 9] => Mat3Array,
+    [f32; 16] => Mat4Array,
+    Texture => TextureArray,
+}
+
+impl From<&[Mat4]> for Uniform {
+    fn from(value: &[Mat4]) -> Uniform {
+        Uniform::Mat4Array(value.iter().map(Mat4::to_cols_array).collect())
+    }
+}
+
 // Transform
 impl From<&Transform3D> for Uniform {
     fn from(value: &Transform3D) -> Uniform {
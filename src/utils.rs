@@ -1,11 +1,14 @@
-use std::{
-    cell::RefCell,
-    rc::Rc,
-    sync::atomic::{AtomicU64, Ordering},
-};
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "web")]
+use std::{cell::RefCell, rc::Rc};
+
+#[cfg(feature = "web")]
 use wasm_bindgen::prelude::*;
+#[cfg(feature = "web")]
 use wasm_bindgen_futures::JsFuture;
+#[cfg(feature = "web")]
 use web_sys::js_sys::{Array, ArrayBuffer, Uint8Array, Uint32Array};
+#[cfg(feature = "web")]
 use web_sys::{HtmlImageElement, Response};
 
 static ID_COUNTER: AtomicU64 = AtomicU64::new(0);
@@ -22,6 +25,7 @@ pub fn to_bytes<T>(slice: &[T]) -> &[u8] {
     }
 }
 
+#[cfg(feature = "web")]
 pub async fn fetch_image(url: &str) -> Result<HtmlImageElement, JsValue> {
     let window = web_sys::window().unwrap();
     let response_value = JsFuture::from(window.fetch_with_str(&url)).await?;
@@ -37,6 +41,7 @@ pub async fn fetch_image(url: &str) -> Result<HtmlImageElement, JsValue> {
     Ok(image)
 }
 
+#[cfg(feature = "web")]
 pub async fn fetch_bytes(url: &str) -> Result<Vec<u8>, JsValue> {
     let window = web_sys::window().unwrap();
     let response_value = JsFuture::from(window.fetch_with_str(url)).await?;
@@ -52,6 +57,7 @@ pub async fn fetch_bytes(url: &str) -> Result<Vec<u8>, JsValue> {
     Ok(bytes)
 }
 
+#[cfg(feature = "web")]
 pub async fn fetch_text(url: &str) -> Result<String, JsValue> {
     let window = web_sys::window().unwrap();
     let response_value = JsFuture::from(window.fetch_with_str(url)).await?;
@@ -61,6 +67,7 @@ pub async fn fetch_text(url: &str) -> Result<String, JsValue> {
     Ok(text.as_string().unwrap())
 }
 
+#[cfg(feature = "web")]
 pub fn js_value_to_vec_u32(array: JsValue) -> Vec<u32> {
     let array = Uint32Array::new(&array);
     let mut output = vec![0; array.length() as usize];
@@ -68,11 +75,14 @@ pub fn js_value_to_vec_u32(array: JsValue) -> Vec<u32> {
     output
 }
 
+#[cfg(feature = "web")]
 pub fn js_array_to_vec_u32(array: JsValue) -> Vec<u32> {
     let array: Array = array.dyn_into().unwrap();
     array.iter().map(|v| v.as_f64().unwrap() as u32).collect()
 }
 
+/// Logs to the browser console, or to stdout outside of the browser.
+#[cfg(all(feature = "web", target_arch = "wasm32"))]
 #[macro_export]
 macro_rules! log {
     ($($arg:tt)*) => {
@@ -80,6 +90,15 @@ macro_rules! log {
     }
 }
 
+#[cfg(not(all(feature = "web", target_arch = "wasm32")))]
+#[macro_export]
+macro_rules! log {
+    ($($arg:tt)*) => {
+        println!($($arg)*);
+    }
+}
+
+#[cfg(feature = "web")]
 pub fn request_animation_frame(mut fun: Box<dyn FnMut() -> ()>) {
     let main_loop = Rc::new(RefCell::new(None));
     let main_loop_clone = main_loop.clone();
@@ -92,6 +111,7 @@ pub fn request_animation_frame(mut fun: Box<dyn FnMut() -> ()>) {
     request_animation_frame_internal(main_loop_clone.borrow().as_ref().unwrap());
 }
 
+#[cfg(feature = "web")]
 fn request_animation_frame_internal(f: &Closure<dyn FnMut()>) {
     web_sys::window()
         .unwrap()
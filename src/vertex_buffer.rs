@@ -1,7 +1,10 @@
-use glam::Vec3;
-use web_sys::WebGl2RenderingContext as GL;
+use std::{fmt, marker::PhantomData};
+
+use glam::{IVec2, IVec3, IVec4, Mat2, Mat3, Mat4, UVec2, UVec3, UVec4, Vec2, Vec3, Vec4};
+pub use suricato_derive::Vertex;
 
 use crate::{
+    backend::Context as GL,
     buffer_gpu::{BufferGPU, BufferKind, BufferUsage},
     utils::to_bytes,
 };
@@ -84,13 +87,34 @@ impl VertexLayout {
         vertex_layouts
     }
 
+    /// Reads a float attribute of up to three components as a `Vec3` from the CPU
+    /// copy of a buffer, filling missing components with zero.
+    ///
+    /// Returns `None` for non float attributes or out of range vertices.
+    pub fn read_vec3(&self, bytes: &[u8], vertex_index: usize) -> Option<Vec3> {
+        if !matches!(self.component_type, VertexComponentType::Float) || self.number_of_columns != 1 {
+            return None;
+        }
+
+        let start = vertex_index * self.stride + self.offset;
+        let mut value = Vec3::ZERO;
+
+        for component in 0..(self.component_count as usize).min(3) {
+            let component_start = start + component * 4;
+            let component_bytes = bytes.get(component_start..component_start + 4)?;
+            value[component] = f32::from_le_bytes(component_bytes.try_into().unwrap());
+        }
+
+        Some(value)
+    }
+
     /// Aligns a value to the specified alignment boundary.
     ///
     /// This ensures that data is placed at memory addresses that are multiples
     /// of the alignment requirement, which is necessary for optimal GPU access.
     ///
     /// # Examples
-    /// ```
+    /// ```ignore
     /// assert_eq!(VertexLayout::align_to(5, 4), 8);  // 5 aligned to 4-byte boundary = 8
     /// assert_eq!(VertexLayout::align_to(8, 4), 8);  // 8 is already aligned
     /// ```
@@ -109,6 +133,153 @@ impl VertexLayout {
     }
 }
 
+/// `#[repr(C)]` struct holding the attributes of one vertex, see [`InterleavedVertexBuffer::from_vertices`].
+///
+/// Derive it rather than implementing it, the derive also checks the struct has no padding.
+/// Fields skipped to fill the gaps must be padding free too:
+///
+/// ```compile_fail
+/// # use suricato::vertex_buffer::Vertex;
+/// #[derive(Vertex, Clone, Copy)]
+/// #[repr(C)]
+/// struct PaddedVertex {
+///     position: [f32; 3],
+///     #[vertex(skip)]
+///     padding:  (u8, u32),
+/// }
+/// ```
+///
+/// # Safety
+///
+/// Vertices are uploaded as bytes, so the type must have no padding, even inside its fields,
+/// and `layouts` must stay within `size_of::<Self>()`.
+pub unsafe trait Vertex {
+    /// Layout of every attribute, strided by the size of the struct.
+    fn layouts() -> Vec<VertexLayout>;
+}
+
+/// Type of a field of a struct deriving [`Vertex`].
+///
+/// # Safety
+///
+/// The type must have no padding and hold `COMPONENT_COUNT` values of `COMPONENT_TYPE`, column after column.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` can not be a field of a `Vertex`, it may have padding",
+    note = "fields with `#[vertex(skip)]` must also be padding free, like `u8` or `[u8; 3]`"
+)]
+pub unsafe trait VertexAttribute {
+    const COMPONENT_TYPE: VertexComponentType;
+    /// Components of all the columns together.
+    const COMPONENT_COUNT: u8;
+    const NUMBER_OF_COLUMNS: u8 = 1;
+}
+
+macro_rules! vertex_attributes {
+    ($($type:ty => $component_type:ident, $component_count:literal),* $(,)?) => {
+        $(
+            unsafe impl VertexAttribute for $type {
+                const COMPONENT_TYPE: VertexComponentType = VertexComponentType::$component_type;
+                const COMPONENT_COUNT: u8 = $component_count;
+            }
+        )*
+    };
+}
+
+vertex_attributes! {
+    i8 => Byte, 1,
+    [i8; 2] => Byte, 2,
+    [i8; 3] => Byte, 3,
+    [i8; 4] => Byte, 4,
+    u8 => UnsignedByte, 1,
+    [u8; 2] => UnsignedByte, 2,
+    [u8; 3] => UnsignedByte, 3,
+    [u8; 4] => UnsignedByte, 4,
+    i16 => Short, 1,
+    [i16; 2] => Short, 2,
+    [i16; 3] => Short, 3,
+    [i16; 4] => Short, 4,
+    u16 => UnsignedShort, 1,
+    [u16; 2] => UnsignedShort, 2,
+    [u16; 3] => UnsignedShort, 3,
+    [u16; 4] => UnsignedShort, 4,
+    i32 => Int, 1,
+    [i32; 2] => Int, 2,
+    [i32; 3] => Int, 3,
+    [i32; 4] => Int, 4,
+    IVec2 => Int, 2,
+    IVec3 => Int, 3,
+    IVec4 => Int, 4,
+    u32 => UnsignedInt, 1,
+    [u32; 2] => UnsignedInt, 2,
+    [u32; 3] => UnsignedInt, 3,
+    [u32; 4] => UnsignedInt, 4,
+    UVec2 => UnsignedInt, 2,
+    UVec3 => UnsignedInt, 3,
+    UVec4 => UnsignedInt, 4,
+    f32 => Float, 1,
+    [f32; 2] => Float, 2,
+    [f32; 3] => Float, 3,
+    [f32; 4] => Float, 4,
+    Vec2 => Float, 2,
+    Vec3 => Float, 3,
+    Vec4 => Float, 4,
+}
+
+// Matrices take one attribute location per column
+macro_rules! vertex_matrices {
+    ($($type:ty => $columns:literal),* $(,)?) => {
+        $(
+            unsafe impl VertexAttribute for $type {
+                const COMPONENT_TYPE: VertexComponentType = VertexComponentType::Float;
+                const COMPONENT_COUNT: u8 = $columns * $columns;
+                const NUMBER_OF_COLUMNS: u8 = $columns;
+            }
+        )*
+    };
+}
+
+vertex_matrices! {
+    Mat2 => 2,
+    Mat3 => 3,
+    Mat4 => 4,
+    [[f32; 2]; 2] => 2,
+    [[f32; 3]; 3] => 3,
+    [[f32; 4]; 4] => 4,
+}
+
+/// Offset of an attribute of type `T` in a vertex, found in the `VERTEX_FIELDS` of structs deriving [`Vertex`].
+pub struct VertexField<T> {
+    offset: usize,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: VertexAttribute> VertexField<T> {
+    pub const fn at(offset: usize) -> VertexField<T> {
+        VertexField {
+            offset,
+            marker: PhantomData,
+        }
+    }
+
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<T> Clone for VertexField<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for VertexField<T> {}
+
+impl<T> fmt::Debug for VertexField<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("VertexField").field("offset", &self.offset).finish()
+    }
+}
+
 impl From<&VertexData> for VertexLayout {
     fn from(vertex: &VertexData) -> VertexLayout {
         VertexLayout {
@@ -630,6 +801,14 @@ impl InterleavedVertexBuffer {
         }
     }
 
+    /// Buffer holding `vertices` as they are in memory, with the attributes of the struct deriving [`Vertex`].
+    pub fn from_vertices<V: Vertex>(usage: BufferUsage, vertices: &[V]) -> InterleavedVertexBuffer {
+        InterleavedVertexBuffer {
+            buffer:  BufferGPU::new(BufferKind::ArrayBuffer, usage, to_bytes(vertices).to_vec()),
+            layouts: V::layouts(),
+        }
+    }
+
     pub fn vertex_count(&self) -> usize {
         self.buffer.size() / self.stride()
     }
@@ -685,6 +864,23 @@ impl InterleavedVertexBuffer {
         false
     }
 
+    /// Replaces a whole vertex of a buffer created by [`InterleavedVertexBuffer::from_vertices`] with the same type.
+    pub fn set_vertex<V: Vertex>(&mut self, vertex_index: usize, vertex: &V) {
+        debug_assert_eq!(size_of::<V>(), self.stride(), "The vertex type does not match the buffer");
+        self.buffer.set_bytes(vertex_index * self.stride(), std::slice::from_ref(vertex));
+    }
+
+    /// Sets one attribute of a vertex, taken from the `VERTEX_FIELDS` of the struct the buffer was created from,
+    /// like `buffer.set_vertex_field(3, MyVertex::VERTEX_FIELDS.color, &[255, 0, 0, 255])`.
+    pub fn set_vertex_field<T: VertexAttribute>(&mut self, vertex_index: usize, field: VertexField<T>, value: &T) {
+        debug_assert!(
+            field.offset() + size_of::<T>() <= self.stride(),
+            "The field does not match the buffer"
+        );
+        self.buffer
+            .set_bytes(vertex_index * self.stride() + field.offset(), std::slice::from_ref(value));
+    }
+
     /// Calculates the byte offset inside the buffer for a specific vertex attribute.
     /// This is useful for interlaved VertexBuffers.
     #[inline]
@@ -700,3 +896,106 @@ impl InterleavedVertexBuffer {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aligns_offsets_to_the_next_multiple() {
+        assert_eq!(VertexLayout::align_to(5, 4), 8);
+        assert_eq!(VertexLayout::align_to(8, 4), 8);
+        assert_eq!(VertexLayout::align_to(0, 4), 0);
+        assert_eq!(VertexLayout::align_to(7, 0), 7);
+    }
+
+    #[derive(Vertex, Clone, Copy)]
+    #[repr(C)]
+    struct TestVertex {
+        position:           Vec3,
+        #[vertex(name = "uv")]
+        texture_coordinate: [f32; 2],
+        #[vertex(normalize)]
+        color:              [u8; 3],
+        #[vertex(skip)]
+        padding:            u8,
+        instance_matrix:    Mat3,
+    }
+
+    fn vertex(index: u8) -> TestVertex {
+        TestVertex {
+            position:           Vec3::splat(index as f32),
+            texture_coordinate: [0.5, index as f32],
+            color:              [index, 0, 255],
+            padding:            0,
+            instance_matrix:    Mat3::from_diagonal(Vec3::splat(index as f32)),
+        }
+    }
+
+    fn describe(layouts: &[VertexLayout]) -> Vec<(&str, u8, u32, bool, usize, usize, u8)> {
+        layouts
+            .iter()
+            .map(|layout| {
+                (
+                    layout.name.as_str(),
+                    layout.component_count,
+                    layout.component_type as u32,
+                    layout.normalize,
+                    layout.stride,
+                    layout.offset,
+                    layout.number_of_columns,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lays_out_derived_vertices_like_vertex_data() {
+        let vertices: Vec<TestVertex> = (0..3).map(vertex).collect();
+        let derived = InterleavedVertexBuffer::from_vertices(BufferUsage::StaticDraw, &vertices);
+
+        let mut color = VertexData::new(
+            "color",
+            vertices.iter().map(|vertex| [vertex.color[0], 0, 255, 0]).collect::<Vec<_>>(),
+        );
+        color.normalize = true;
+        let data = vec![
+            VertexData::new("position", vertices.iter().map(|vertex| vertex.position).collect::<Vec<_>>()),
+            VertexData::new("uv", vertices.iter().map(|vertex| vertex.texture_coordinate).collect::<Vec<_>>()),
+            color,
+            VertexData::new(
+                "instance_matrix",
+                vertices
+                    .iter()
+                    .map(|vertex| vertex.instance_matrix.to_cols_array_2d())
+                    .collect::<Vec<_>>(),
+            ),
+        ];
+        let expected = InterleavedVertexBuffer::new(BufferUsage::StaticDraw, data);
+
+        // The skipped padding byte holds the fourth color component of the expected buffer
+        let mut expected_layouts = describe(&expected.layouts);
+        expected_layouts[2].1 = 3;
+
+        assert_eq!(derived.stride(), 60);
+        assert_eq!(derived.vertex_count(), 3);
+        assert_eq!(describe(&derived.layouts), expected_layouts);
+        assert_eq!(derived.buffer.data(), expected.buffer.data());
+    }
+
+    #[test]
+    fn sets_single_vertices_and_attributes() {
+        let vertices: Vec<TestVertex> = (0..3).map(vertex).collect();
+        let mut buffer = InterleavedVertexBuffer::from_vertices(BufferUsage::DynamicDraw, &vertices);
+
+        buffer.set_vertex_field(1, TestVertex::VERTEX_FIELDS.color, &[9, 8, 7]);
+        buffer.set_vertex(2, &vertex(5));
+
+        let mut expected = vertices.clone();
+        expected[1].color = [9, 8, 7];
+        expected[2] = vertex(5);
+
+        assert_eq!(TestVertex::VERTEX_FIELDS.color.offset(), 20);
+        assert_eq!(buffer.buffer.data(), to_bytes(&expected));
+    }
+}
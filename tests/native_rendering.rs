@@ -0,0 +1,505 @@
+//! Draws through a headless EGL context, runs on Mesa llvmpipe without a GPU.
+//!
+//! Skipped when no EGL display is available.
+
+#![cfg(feature = "native")]
+
+use std::rc::Rc;
+
+use glam::{Mat3, Vec2, Vec3};
+use suricato::{
+    backend::{Context, native::GlowContext},
+    buffer_gpu::BufferUsage,
+    camera::PerspectiveCamera,
+    error::ErrorPolicy,
+    geometry::Geometry,
+    material::{Material, MaterialError},
+    mesh::Mesh,
+    render_target::{RenderTarget, RenderTargetFormat},
+    renderer::Renderer,
+    shader::{ShaderStage, reflection::GlslType},
+    std140::Std140,
+    texture::{ImagePixelData, MagnificationFilter, MinificationFilter, Sampler, Texture, TextureData},
+    ubo::UniformBufferObject,
+    uniforms::Uniform,
+    vertex_buffer::{InterleavedVertexBuffer, Vertex},
+};
+
+const SIZE: u32 = 64;
+
+const VERTEX_SHADER: &str = r#"#version 300 es
+in vec2 position;
+in vec3 color;
+uniform vec2 offset;
+out vec3 vertex_color;
+
+void main() {
+    vertex_color = color;
+    gl_Position = vec4(position + offset, 0.0, 1.0);
+}
+"#;
+
+const FRAGMENT_SHADER: &str = r#"#version 300 es
+precision mediump float;
+in vec3 vertex_color;
+out vec4 fragment_color;
+
+void main() {
+    fragment_color = vec4(vertex_color, 1.0);
+}
+"#;
+
+fn create_renderer() -> Option<Renderer> {
+    match GlowContext::headless(SIZE, SIZE) {
+        Ok(context) => Some(Renderer::with_context(Context::new(Rc::new(context)))),
+        Err(error) => {
+            eprintln!("Skipping, no headless context: {error:?}");
+            None
+        }
+    }
+}
+
+fn pixel(renderer: &Renderer, x: u32, y: u32) -> [u8; 4] {
+    let pixels = renderer.read_pixels(None, x, y, 1, 1).unwrap().to_rgba8();
+    pixels.bytes.try_into().unwrap()
+}
+
+fn quad(offset: Vec2) -> Mesh {
+    let mut material = Material::new(VERTEX_SHADER, FRAGMENT_SHADER);
+    material.set_uniform("offset", Uniform::Vec2(offset.to_array()));
+
+    Mesh::new(Geometry::quad(), material)
+}
+
+#[test]
+fn draws_into_offscreen_framebuffer() {
+    let Some(mut renderer) = create_renderer() else {
+        return;
+    };
+
+    // Quad covering the right half of the canvas
+    let mut mesh = quad(Vec2::new(0.5, 0.0));
+
+    renderer.clear();
+    renderer.render(&mut mesh);
+
+    assert_eq!(renderer.gl.drawing_buffer_width(), SIZE as i32);
+    assert_eq!(pixel(&renderer, 8, 32), [0, 0, 0, 255]);
+
+    let [red, green, blue, alpha] = pixel(&renderer, 48, 32);
+    assert!(red > 0 || green > 0 || blue > 0);
+    assert_eq!(alpha, 255);
+}
+
+#[test]
+fn covers_a_quarter_of_the_canvas() {
+    let Some(mut renderer) = create_renderer() else {
+        return;
+    };
+
+    let mut mesh = quad(Vec2::ZERO);
+
+    renderer.clear();
+    renderer.render(&mut mesh);
+
+    let pixels = renderer.read_pixels(None, 0, 0, SIZE, SIZE).unwrap().to_rgba8();
+    let covered = pixels.bytes.chunks_exact(4).filter(|pixel| pixel[..3] != [0, 0, 0]).count();
+
+    // The quad spans half of the canvas on each axis
+    assert_eq!(covered, 32 * 32);
+}
+
+#[test]
+fn renders_to_target_and_back() {
+    let Some(mut renderer) = create_renderer() else {
+        return;
+    };
+
+    let mut target = RenderTarget::new(16, 16, RenderTargetFormat::RGBA8);
+    let mut mesh = quad(Vec2::ZERO);
+
+    renderer.set_render_target(Some(&mut target)).unwrap();
+    renderer.clear();
+    renderer.render(&mut mesh);
+
+    let target_pixels = renderer.read_pixels(Some(&mut target), 0, 0, 16, 16).unwrap().to_rgba8();
+    assert!(target_pixels.bytes.chunks_exact(4).any(|pixel| pixel[..3] != [0, 0, 0]));
+
+    renderer.set_render_target(None).unwrap();
+    renderer.clear();
+
+    assert_eq!(pixel(&renderer, 32, 32), [0, 0, 0, 255]);
+}
+
+#[test]
+fn reports_compile_errors_with_source_lines() {
+    let Some(renderer) = create_renderer() else {
+        return;
+    };
+
+    let broken_fragment_shader = FRAGMENT_SHADER.replace("vec4(vertex_color, 1.0)", "vec4(vertex_colour, 1.0)");
+    let mut material = Material::new(VERTEX_SHADER, &broken_fragment_shader);
+
+    let Err(MaterialError::ShaderCompilationFailed(error)) = material.try_get_or_create_resources(&renderer.gl) else {
+        panic!("The fragment shader should not compile");
+    };
+
+    assert_eq!(error.stage, ShaderStage::Fragment);
+    assert_eq!(error.diagnostics[0].line, 7);
+    assert!(error.diagnostics[0].snippet.contains("vertex_colour"));
+    assert!(error.to_string().contains("error at line 7"));
+}
+
+#[test]
+fn compiles_built_in_chunks() {
+    let Some(renderer) = create_renderer() else {
+        return;
+    };
+
+    let vertex_shader = r#"#include "camera"
+#include "skinning"
+in vec3 position;
+in vec3 normal;
+out vec3 v_normal;
+out float v_depth;
+
+void main() {
+    vec4 view_position = camera.view_matrix * transform * skinning_matrix() * vec4(position, 1.0);
+    v_normal = view_normal(normal);
+    v_depth = -view_position.z;
+    gl_Position = camera.projection_matrix * view_position;
+}
+"#;
+
+    let fragment_shader = r#"#include "lights"
+#include "fog"
+#include "tone_mapping"
+in vec3 v_normal;
+in float v_depth;
+out vec4 fragment_color;
+
+void main() {
+    vec3 color = vec3(directional_light(v_normal, vec3(0.0, 1.0, 1.0)));
+    fragment_color = vec4(linear_to_srgb(aces_tone_mapping(apply_fog(color, v_depth))), 1.0);
+}
+"#;
+
+    let mut material = Material::new(vertex_shader, fragment_shader);
+    material.set_define("MAX_JOINTS", "32");
+
+    if let Err(error) = material.try_get_or_create_resources(&renderer.gl) {
+        panic!("{error}");
+    }
+}
+
+#[test]
+fn reflects_active_uniforms_and_blocks() {
+    let Some(renderer) = create_renderer() else {
+        return;
+    };
+
+    let fragment_shader = r#"#version 300 es
+precision highp float;
+layout(std140) uniform Material {
+    vec4 colors[3];
+    float roughness;
+} material;
+uniform sampler2D diffuse;
+in vec3 vertex_color;
+out vec4 fragment_color;
+
+void main() {
+    fragment_color = texture(diffuse, vertex_color.xy) * material.colors[2] * material.roughness;
+}
+"#;
+
+    let mut material = Material::new(VERTEX_SHADER, fragment_shader);
+    let reflection = match material.try_get_or_create_resources(&renderer.gl) {
+        Ok(resources) => resources.get_reflection().clone(),
+        Err(error) => panic!("{error}"),
+    };
+
+    let block = reflection.get_uniform_block("Material").unwrap();
+    assert_eq!(block.size, 64);
+    assert_eq!(block.binding, Some(0));
+
+    let members: Vec<(&str, GlslType, u32, Option<u32>)> = reflection
+        .get_uniform_block_members("Material")
+        .into_iter()
+        .map(|member| (member.name.as_str(), member.kind, member.array_size, member.offset))
+        .collect();
+    assert_eq!(
+        members,
+        [
+            ("Material.colors", GlslType::Vec4, 3, Some(0)),
+            ("Material.roughness", GlslType::Float, 1, Some(48)),
+        ]
+    );
+
+    assert_eq!(reflection.get_uniform("offset").map(|uniform| uniform.kind), Some(GlslType::Vec2));
+    assert_eq!(
+        reflection.get_uniform("diffuse").map(|uniform| uniform.kind),
+        Some(GlslType::Sampler2D)
+    );
+
+    let color = reflection.get_attribute("color").unwrap();
+    assert_eq!(color.kind, GlslType::Vec3);
+    assert!(color.location.is_some());
+}
+
+#[test]
+fn sets_struct_members_and_array_elements() {
+    let Some(mut renderer) = create_renderer() else {
+        return;
+    };
+    renderer.error_reporter.policy = ErrorPolicy::Panic;
+
+    let fragment_shader = r#"#version 300 es
+precision highp float;
+struct Light {
+    vec3 color;
+    float intensity;
+};
+uniform Light light;
+uniform Light lights[2];
+uniform float weights[4];
+out vec4 fragment_color;
+
+void main() {
+    fragment_color = vec4(light.color * light.intensity + lights[1].color + vec3(0.0, 0.0, weights[3]), 1.0);
+}
+"#;
+
+    let mut material = Material::new(VERTEX_SHADER, fragment_shader);
+    material.set_uniform("light.color", Uniform::Vec3([0.5, 0.0, 0.0]));
+    material.set_uniform("light.intensity", Uniform::Float(2.0));
+    material.set_uniform("lights[1].color", Uniform::Vec3([0.0, 1.0, 0.0]));
+    material.set_uniform("weights", Uniform::FloatArray(vec![0.0; 3]));
+    material.set_uniform("weights[3]", Uniform::Float(1.0));
+    let mut mesh = Mesh::new(Geometry::quad(), material);
+
+    renderer.clear();
+    renderer.render(&mut mesh);
+
+    assert_eq!(pixel(&renderer, 32, 32), [255, 255, 255, 255]);
+}
+
+#[test]
+fn samples_texture_through_a_sampler() {
+    let Some(mut renderer) = create_renderer() else {
+        return;
+    };
+    renderer.error_reporter.policy = ErrorPolicy::Panic;
+
+    let fragment_shader = r#"#version 300 es
+precision highp float;
+uniform sampler2D image;
+out vec4 fragment_color;
+
+void main() {
+    fragment_color = texture(image, vec2(0.5));
+}
+"#;
+
+    // Black and white texels, the center of the texture is on the edge between them
+    let texture = Texture::new(TextureData::ImagePixelData(ImagePixelData {
+        width:  2,
+        height: 1,
+        bytes:  vec![0, 0, 0, 255, 255, 255, 255, 255],
+    }));
+    let mut mesh = Mesh::new(Geometry::quad(), Material::new(VERTEX_SHADER, fragment_shader));
+
+    mesh.material.set_uniform("image", Uniform::Texture(texture.clone()));
+    renderer.clear();
+    renderer.render(&mut mesh);
+    assert_eq!(pixel(&renderer, 32, 32), [255, 255, 255, 255]);
+
+    let sampler = Sampler::new(MinificationFilter::Linear, MagnificationFilter::Linear);
+    mesh.material.set_uniform("image", Uniform::SampledTexture(texture, sampler));
+    renderer.clear();
+    renderer.render(&mut mesh);
+
+    let [red, ..] = pixel(&renderer, 32, 32);
+    assert!(red.abs_diff(128) <= 1, "{red}");
+}
+
+#[derive(Std140)]
+struct Light {
+    color:     Vec3,
+    intensity: f32,
+}
+
+#[derive(Std140)]
+struct Lighting {
+    normal_matrix: Mat3,
+    lights:        [Light; 2],
+    exposure:      f32,
+}
+
+#[test]
+fn lays_out_uniform_blocks_like_the_driver() {
+    let Some(mut renderer) = create_renderer() else {
+        return;
+    };
+    renderer.error_reporter.policy = ErrorPolicy::Panic;
+
+    let fragment_shader = r#"#version 300 es
+precision highp float;
+struct Light {
+    vec3 color;
+    float intensity;
+};
+layout(std140) uniform Lighting {
+    mat3 normal_matrix;
+    Light lights[2];
+    float exposure;
+};
+out vec4 fragment_color;
+
+void main() {
+    fragment_color = vec4(lights[1].color * lights[1].intensity * exposure, normal_matrix[1][1]);
+}
+"#;
+
+    let mut mesh = Mesh::new(Geometry::quad(), Material::new(VERTEX_SHADER, fragment_shader));
+    let resources = mesh.material.get_or_create_resources(&renderer.gl);
+    resources.set_uniform_block("Lighting", 0);
+
+    let fields = Lighting::STD140_FIELDS;
+    let light = |index: usize| fields.lights.element(index);
+    let expected_offsets = [
+        ("normal_matrix", fields.normal_matrix.offset()),
+        ("lights[0].color", light(0).member(Light::STD140_FIELDS.color).offset()),
+        ("lights[0].intensity", light(0).member(Light::STD140_FIELDS.intensity).offset()),
+        ("lights[1].color", light(1).member(Light::STD140_FIELDS.color).offset()),
+        ("lights[1].intensity", light(1).member(Light::STD140_FIELDS.intensity).offset()),
+        ("exposure", fields.exposure.offset()),
+    ];
+
+    let reflection = resources.get_reflection();
+    for (name, offset) in expected_offsets {
+        let member = reflection.get_uniform(name).unwrap_or_else(|| panic!("{name} is not reflected"));
+        assert_eq!(member.offset, Some(offset as u32), "{name}");
+    }
+    assert_eq!(
+        reflection.get_uniform_block("Lighting").map(|block| block.size as usize),
+        Some(Lighting::SIZE)
+    );
+
+    let lighting = Lighting {
+        normal_matrix: Mat3::IDENTITY,
+        lights:        [
+            Light {
+                color:     Vec3::ZERO,
+                intensity: 0.0,
+            },
+            Light {
+                color:     Vec3::new(0.5, 0.25, 0.0),
+                intensity: 2.0,
+            },
+        ],
+        exposure:      0.5,
+    };
+    let mut ubo = UniformBufferObject::from_std140(&renderer, &lighting);
+    ubo.set_binding_point(0);
+
+    renderer.clear();
+    renderer.render(&mut mesh);
+    let [red, green, blue, alpha] = pixel(&renderer, 32, 32);
+    assert!(red.abs_diff(128) <= 1 && green.abs_diff(64) <= 1 && blue == 0 && alpha == 255);
+
+    ubo.set_field(fields.exposure, &1.0);
+    renderer.clear();
+    renderer.render(&mut mesh);
+    let [red, green, ..] = pixel(&renderer, 32, 32);
+    assert!(red == 255 && green.abs_diff(128) <= 1);
+}
+
+#[test]
+fn renders_through_the_camera_block() {
+    let Some(mut renderer) = create_renderer() else {
+        return;
+    };
+    renderer.error_reporter.policy = ErrorPolicy::Panic;
+
+    let vertex_shader = r#"#include "camera"
+in vec3 position;
+
+void main() {
+    gl_Position = project(position);
+}
+"#;
+
+    let fragment_shader = r#"#version 300 es
+precision highp float;
+#include "camera"
+out vec4 fragment_color;
+
+void main() {
+    fragment_color = vec4(camera.time, camera.viewport / 128.0, 1.0);
+}
+"#;
+
+    let mut scene = [Mesh::new(Geometry::quad(), Material::new(vertex_shader, fragment_shader))];
+    let mut camera = PerspectiveCamera::new(1.0, 1.0, 0.1, 10.0);
+    camera.update_projection_matrix();
+    camera.transform.translation.z = 3.0;
+    renderer.time = 0.5;
+
+    renderer.render_scene(&mut scene, &mut camera);
+    let [red, green, blue, alpha] = pixel(&renderer, 32, 32);
+    assert!(red.abs_diff(128) <= 1 && green.abs_diff(128) <= 1 && blue.abs_diff(128) <= 1 && alpha == 255);
+    assert_eq!(pixel(&renderer, 0, 0), [0, 0, 0, 255]);
+
+    // Moving the camera away shrinks the quad
+    camera.transform.translation.z = 9.0;
+    renderer.render_scene(&mut scene, &mut camera);
+    assert!(pixel(&renderer, 32, 32)[0].abs_diff(128) <= 1);
+    assert_eq!(pixel(&renderer, 16, 16), [0, 0, 0, 255]);
+}
+
+#[derive(Vertex, Clone, Copy)]
+#[repr(C)]
+struct ColoredVertex {
+    position: [f32; 2],
+    #[vertex(normalize)]
+    color:    [u8; 3],
+    #[vertex(skip)]
+    padding:  u8,
+}
+
+#[test]
+fn draws_derived_vertices() {
+    let Some(mut renderer) = create_renderer() else {
+        return;
+    };
+    renderer.error_reporter.policy = ErrorPolicy::Panic;
+
+    // Two triangles covering the left half of the canvas
+    let vertices: Vec<ColoredVertex> = [[-1.0, -1.0], [0.0, -1.0], [0.0, 1.0], [-1.0, -1.0], [0.0, 1.0], [-1.0, 1.0]]
+        .into_iter()
+        .map(|position| {
+            ColoredVertex {
+                position,
+                color: [255, 0, 0],
+                padding: 0,
+            }
+        })
+        .collect();
+    let buffer = InterleavedVertexBuffer::from_vertices(BufferUsage::DynamicDraw, &vertices);
+    let mut mesh = Mesh::new(Geometry::from(buffer), Material::new(VERTEX_SHADER, FRAGMENT_SHADER));
+
+    renderer.clear();
+    renderer.render(&mut mesh);
+    assert_eq!(pixel(&renderer, 16, 32), [255, 0, 0, 255]);
+    assert_eq!(pixel(&renderer, 48, 32), [0, 0, 0, 255]);
+
+    let buffer = &mut mesh.geometry.interleaved_vertex_buffers[0];
+    for vertex_index in 0..buffer.vertex_count() {
+        buffer.set_vertex_field(vertex_index, ColoredVertex::VERTEX_FIELDS.color, &[0, 255, 0]);
+    }
+
+    renderer.clear();
+    renderer.render(&mut mesh);
+    assert_eq!(pixel(&renderer, 16, 32), [0, 255, 0, 255]);
+}
@@ -0,0 +1,347 @@
+//! Golden image tests drawing through the software rasterizer.
+//!
+//! Run with `UPDATE_GOLDEN=1` to write the current output as the new golden images.
+
+use std::{env, fs, path::PathBuf, rc::Rc};
+
+use glam::{Quat, Vec2, Vec3, Vec4, Vec4Swizzles};
+use suricato::{
+    backend::software::{FragmentInput, SoftwareContext, VertexInput, VertexOutput},
+    camera::PerspectiveCamera,
+    geometry::Geometry,
+    material::Material,
+    mesh::{Mesh, RenderPrimitive},
+    readback::encode_png,
+    render_state::Blend,
+    renderer::Renderer,
+    texture::{ImagePixelData, MagnificationFilter, MinificationFilter, Sampler, Texture, TextureData, Wrap},
+    uniforms::Uniform,
+};
+
+const SIZE: i32 = 64;
+
+/// Largest difference allowed per color component.
+const TOLERANCE: u8 = 1;
+
+const VERTEX_COLOR_VERTEX_SHADER: &str = r#"#version 300 es
+in vec2 position;
+in vec3 color;
+uniform vec2 offset;
+out vec3 vertex_color;
+
+void main() {
+    vertex_color = color;
+    gl_Position = vec4(position * 1.5 + offset, 0.0, 1.0);
+}
+"#;
+
+const VERTEX_COLOR_FRAGMENT_SHADER: &str = r#"#version 300 es
+precision mediump float;
+in vec3 vertex_color;
+uniform float opacity;
+out vec4 fragment_color;
+
+void main() {
+    fragment_color = vec4(vertex_color, opacity);
+}
+"#;
+
+const TEXTURE_VERTEX_SHADER: &str = r#"#version 300 es
+in vec2 position;
+in vec2 uv;
+out vec2 vertex_uv;
+
+void main() {
+    vertex_uv = uv;
+    gl_Position = vec4(position * 1.5, 0.0, 1.0);
+}
+"#;
+
+const TEXTURE_FRAGMENT_SHADER: &str = r#"#version 300 es
+precision mediump float;
+in vec2 vertex_uv;
+uniform sampler2D color_texture;
+out vec4 fragment_color;
+
+void main() {
+    fragment_color = texture(color_texture, vertex_uv * 2.0);
+}
+"#;
+
+const NORMAL_VERTEX_SHADER: &str = r#"#version 300 es
+in vec3 position;
+in vec3 normal;
+uniform mat4 transform;
+uniform mat4 camera_inverse_matrix;
+uniform mat4 projection_matrix;
+out vec3 vertex_normal;
+
+void main() {
+    vertex_normal = mat3(transform) * normal;
+    gl_Position = projection_matrix * camera_inverse_matrix * transform * vec4(position, 1.0);
+}
+"#;
+
+const NORMAL_FRAGMENT_SHADER: &str = r#"#version 300 es
+precision mediump float;
+in vec3 vertex_normal;
+out vec4 fragment_color;
+
+void main() {
+    fragment_color = vec4(normalize(vertex_normal) * 0.5 + 0.5, 1.0);
+}
+"#;
+
+fn create_renderer() -> (Rc<SoftwareContext>, Renderer) {
+    let software = Rc::new(SoftwareContext::new(SIZE, SIZE));
+
+    software.register_shaders(
+        VERTEX_COLOR_VERTEX_SHADER,
+        VERTEX_COLOR_FRAGMENT_SHADER,
+        |input: &VertexInput| {
+            let position = input.attribute("position").xy() * 1.5 + input.uniforms.vec2("offset");
+            VertexOutput::new(position.extend(0.0).extend(1.0), vec![input.attribute("color")])
+        },
+        |input: &FragmentInput| Some(input.varyings[0].xyz().extend(input.uniforms.float("opacity"))),
+    );
+
+    software.register_shaders(
+        TEXTURE_VERTEX_SHADER,
+        TEXTURE_FRAGMENT_SHADER,
+        |input: &VertexInput| {
+            let position = input.attribute("position").xy() * 1.5;
+            VertexOutput::new(position.extend(0.0).extend(1.0), vec![input.attribute("uv")])
+        },
+        |input: &FragmentInput| Some(input.uniforms.texture("color_texture", input.varyings[0].xy() * 2.0)),
+    );
+
+    software.register_shaders(
+        NORMAL_VERTEX_SHADER,
+        NORMAL_FRAGMENT_SHADER,
+        |input: &VertexInput| {
+            let transform = input.uniforms.mat4("transform");
+            let view_projection = input.uniforms.mat4("projection_matrix") * input.uniforms.mat4("camera_inverse_matrix");
+            let normal = transform.transform_vector3(input.attribute("normal").xyz());
+            let position = view_projection * transform * input.attribute("position").xyz().extend(1.0);
+
+            VertexOutput::new(position, vec![normal.extend(0.0)])
+        },
+        |input: &FragmentInput| Some((input.varyings[0].xyz().normalize() * 0.5 + 0.5).extend(1.0)),
+    );
+
+    let renderer = Renderer::with_context(software.context());
+    (software, renderer)
+}
+
+fn vertex_color_mesh(offset: Vec2, opacity: f32) -> Mesh {
+    let mut material = Material::new(VERTEX_COLOR_VERTEX_SHADER, VERTEX_COLOR_FRAGMENT_SHADER);
+    material.set_uniform("offset", Uniform::Vec2(offset.to_array()));
+    material.set_uniform("opacity", Uniform::Float(opacity));
+
+    Mesh::new(Geometry::quad(), material)
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.png"))
+}
+
+fn decode_png(bytes: &[u8]) -> ImagePixelData {
+    let decoder = png::Decoder::new(std::io::Cursor::new(bytes));
+    let mut reader = decoder.read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size().unwrap()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+
+    ImagePixelData {
+        width:  info.width,
+        height: info.height,
+        bytes:  pixels,
+    }
+}
+
+/// Compares the whole canvas with `tests/golden/{name}.png`, saving the output next to the test binaries on mismatch.
+fn assert_matches_golden(renderer: &Renderer, name: &str) {
+    let image = renderer.read_pixels(None, 0, 0, SIZE as u32, SIZE as u32).unwrap().to_rgba8();
+    let path = golden_path(name);
+
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, encode_png(&image).unwrap()).unwrap();
+        return;
+    }
+
+    let Ok(golden) = fs::read(&path) else {
+        panic!("Missing golden image {}, run with UPDATE_GOLDEN=1 to create it", path.display());
+    };
+
+    let golden = decode_png(&golden);
+    let mismatches = image
+        .bytes
+        .iter()
+        .zip(&golden.bytes)
+        .filter(|(actual, expected)| actual.abs_diff(**expected) > TOLERANCE)
+        .count();
+
+    if (golden.width, golden.height) != (image.width, image.height) || mismatches > 0 {
+        let actual_path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("{name}.actual.png"));
+        fs::write(&actual_path, encode_png(&image).unwrap()).unwrap();
+
+        panic!(
+            "{name} differs from its golden image in {mismatches} components, output saved to {}",
+            actual_path.display()
+        );
+    }
+}
+
+#[test]
+fn interpolates_vertex_colors() {
+    let (_, mut renderer) = create_renderer();
+    let mut mesh = vertex_color_mesh(Vec2::ZERO, 1.0);
+
+    renderer.clear();
+    renderer.render(&mut mesh);
+
+    assert_matches_golden(&renderer, "vertex_colors");
+}
+
+#[test]
+fn blends_transparent_quads() {
+    let (_, mut renderer) = create_renderer();
+    let mut opaque = vertex_color_mesh(Vec2::new(-0.3, -0.3), 1.0);
+    let mut transparent = vertex_color_mesh(Vec2::new(0.3, 0.3), 0.5);
+    transparent.material.render_state.blend = Some(Blend::alpha());
+
+    renderer.clear();
+    renderer.render(&mut opaque);
+    renderer.render(&mut transparent);
+
+    assert_matches_golden(&renderer, "blending");
+}
+
+fn checker_texture() -> Texture {
+    let mut bytes = Vec::new();
+    for y in 0..4 {
+        for x in 0..4 {
+            let value = if (x + y) % 2 == 0 { 255 } else { 40 };
+            bytes.extend_from_slice(&[value, x * 60, y * 60, 255]);
+        }
+    }
+
+    Texture::new(TextureData::ImagePixelData(ImagePixelData {
+        width: 4,
+        height: 4,
+        bytes,
+    }))
+}
+
+#[test]
+fn samples_repeating_texture() {
+    let (_, mut renderer) = create_renderer();
+
+    let mut material = Material::new(TEXTURE_VERTEX_SHADER, TEXTURE_FRAGMENT_SHADER);
+    material.set_uniform("color_texture", Uniform::Texture(checker_texture()));
+    let mut mesh = Mesh::new(Geometry::quad(), material);
+
+    renderer.clear();
+    renderer.render(&mut mesh);
+
+    assert_matches_golden(&renderer, "texture");
+}
+
+#[test]
+fn samples_texture_through_a_sampler() {
+    let (software, mut renderer) = create_renderer();
+
+    let mut material = Material::new(TEXTURE_VERTEX_SHADER, TEXTURE_FRAGMENT_SHADER);
+    material.set_uniform("color_texture", Uniform::Texture(checker_texture()));
+    let mut mesh = Mesh::new(Geometry::quad(), material);
+    renderer.render(&mut mesh);
+
+    // The uploaded texture is shared by its copies
+    let Some(Uniform::Texture(texture)) = mesh.material.get_uniform("color_texture").cloned() else {
+        panic!("color_texture is not set");
+    };
+    let sampler = Sampler {
+        wrap_horizontal: Wrap::ClampToEdge,
+        wrap_vertical: Wrap::ClampToEdge,
+        ..Sampler::new(MinificationFilter::Linear, MagnificationFilter::Linear)
+    };
+    let sampled_texture = Uniform::SampledTexture(texture.clone(), sampler);
+    mesh.material.set_uniform("color_texture", sampled_texture);
+
+    renderer.clear();
+    renderer.render(&mut mesh);
+
+    assert_eq!(software.state().textures.len(), 1);
+    assert_matches_golden(&renderer, "texture_sampler");
+
+    // Without the sampler the texture is sampled with its own parameters again
+    mesh.material.set_uniform("color_texture", Uniform::Texture(texture));
+    renderer.clear();
+    renderer.render(&mut mesh);
+
+    assert_matches_golden(&renderer, "texture");
+}
+
+#[test]
+fn depth_tests_intersecting_boxes() {
+    let (_, mut renderer) = create_renderer();
+
+    let mut camera = PerspectiveCamera::new(50_f32.to_radians(), 1.0, 0.1, 10.0);
+    camera.transform.translation = Vec3::new(0.0, 0.0, 3.0);
+    camera.update_projection_matrix();
+
+    let mut scene = [-0.3_f32, 0.3].map(|x| {
+        let mut mesh = Mesh::new(
+            Geometry::box_geometry(),
+            Material::new(NORMAL_VERTEX_SHADER, NORMAL_FRAGMENT_SHADER),
+        );
+        mesh.transform.translation = Vec3::new(x, 0.0, 0.0);
+        mesh.transform.rotation = Quat::from_euler(glam::EulerRot::XYZ, 0.5, x * 2.0, 0.2);
+        mesh
+    });
+
+    renderer.render_scene(&mut scene, &mut camera);
+
+    assert_matches_golden(&renderer, "intersecting_boxes");
+}
+
+#[test]
+fn draws_lines_and_points() {
+    let (_, mut renderer) = create_renderer();
+
+    let mut outline = vertex_color_mesh(Vec2::ZERO, 1.0);
+    outline.render_primitive = RenderPrimitive::LineLoop;
+
+    let mut corners = vertex_color_mesh(Vec2::new(0.1, 0.1), 1.0);
+    corners.render_primitive = RenderPrimitive::Points;
+
+    renderer.clear();
+    renderer.render(&mut outline);
+    renderer.render(&mut corners);
+
+    assert_matches_golden(&renderer, "lines_and_points");
+}
+
+#[test]
+fn only_fills_pixels_once_along_shared_edges() {
+    let (software, mut renderer) = create_renderer();
+    let mut mesh = vertex_color_mesh(Vec2::ZERO, 0.5);
+    mesh.material.render_state.blend = Some(Blend::additive());
+
+    renderer.clear();
+    renderer.render(&mut mesh);
+
+    // Additive blending would reveal pixels covered by both triangles of the quad
+    let pixels = renderer.read_pixels(None, 0, 0, SIZE as u32, SIZE as u32).unwrap().to_rgba8();
+    let covered: Vec<Vec4> = pixels
+        .bytes
+        .chunks_exact(4)
+        .map(|pixel| Vec4::new(pixel[0] as f32, pixel[1] as f32, pixel[2] as f32, pixel[3] as f32))
+        .filter(|pixel| pixel.xyz() != Vec3::ZERO)
+        .collect();
+
+    assert_eq!(covered.len(), 48 * 48);
+    assert!(covered.iter().all(|pixel| pixel.xyz().max_element() <= 128.0));
+    assert_eq!(software.state().draws.len(), 1);
+}